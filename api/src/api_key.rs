@@ -0,0 +1,79 @@
+use crate::AppState;
+use crate::error::AppError;
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys",
+    tag = "ApiKey",
+    operation_id = "issue_api_key",
+    request_body = usecase::api_key::IssueDto,
+    security(("api_key" = ["admin"])),
+    responses(
+        (status = 201, description = "API key issued. The secret is shown only in this response", body = usecase::api_key::IssuedDto),
+        (status = 400, description = "Invalid name, owner, scopes or expiry"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The admin scope is required")
+    )
+)]
+pub async fn issue(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<usecase::api_key::IssueDto>,
+) -> impl IntoResponse {
+    match state.api_key_usecase.issue(payload).await {
+        Ok(issued) => (StatusCode::CREATED, Json(issued)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/api-keys",
+    tag = "ApiKey",
+    operation_id = "get_all_api_keys",
+    security(("api_key" = ["admin"])),
+    responses(
+        (status = 200, description = "List all API keys including revoked ones", body = [usecase::api_key::ResponseDto]),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The admin scope is required")
+    )
+)]
+pub async fn get_all(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.api_key_usecase.get_all().await {
+        Ok(keys) => (StatusCode::OK, Json(keys)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/api-keys/{pub_id}",
+    tag = "ApiKey",
+    operation_id = "revoke_api_key",
+    security(("api_key" = ["admin"])),
+    params(
+        ("pub_id" = uuid::Uuid, Path, description = "API key pub_id")
+    ),
+    responses(
+        (status = 200, description = "API key revoked", body = usecase::api_key::ResponseDto),
+        (status = 400, description = "API key is already revoked"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The admin scope is required"),
+        (status = 404, description = "API key not found")
+    )
+)]
+pub async fn revoke(
+    State(state): State<Arc<AppState>>,
+    Path(pub_id): Path<uuid::Uuid>,
+) -> impl IntoResponse {
+    match state.api_key_usecase.revoke(pub_id).await {
+        Ok(key) => (StatusCode::OK, Json(key)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
@@ -0,0 +1,282 @@
+use crate::AppState;
+use crate::error::AppError;
+use axum::Json;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use std::sync::Arc;
+
+/// `Authorization: ApiKey {secret}` の scheme
+pub const API_KEY_SCHEME: &str = "ApiKey";
+/// API キーの管理に必要なスコープ。匿名のアクセスには与えない
+pub const ADMIN_SCOPE: &str = "admin";
+/// 匿名のアクセスを許可していても、認証済みの API キーにしか与えないスコープ。
+/// webhook の登録はサーバーから任意の URL へ送らせられるため、参照は送り先と配信の記録が見えるため含める
+pub const AUTHENTICATED_ONLY_SCOPES: [&str; 3] = [ADMIN_SCOPE, "webhooks:read", "webhooks:write"];
+
+/// 認証済みの利用者。ハンドラーは extensions から取り出せる
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    /// "api_key:{pub_id}" のように種類と ID を繋げたもの
+    pub subject: String,
+    pub scopes: Vec<String>,
+}
+
+impl Principal {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// リクエストの利用者。スコープの確認をルート単位でできないハンドラー (GraphQL) は
+/// extensions から取り出して操作ごとに authorize する
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub principal: Option<Principal>,
+    allow_anonymous: bool,
+}
+
+impl Caller {
+    pub fn new(principal: Option<Principal>, allow_anonymous: bool) -> Self {
+        Self {
+            principal,
+            allow_anonymous,
+        }
+    }
+
+    pub fn authorize(&self, scope: &str) -> Result<(), Denied> {
+        match &self.principal {
+            Some(principal) if !principal.has_scope(scope) => Err(Denied::Forbidden(format!(
+                "The {} scope is required",
+                scope
+            ))),
+            Some(_) => Ok(()),
+            // 匿名のアクセスは、許可していれば管理系と webhook の登録以外の全ての操作ができる
+            None if self.allow_anonymous && !AUTHENTICATED_ONLY_SCOPES.contains(&scope) => Ok(()),
+            None => Err(Denied::Unauthenticated(
+                "Authentication is required".to_string(),
+            )),
+        }
+    }
+
+    /// 論理削除済みのレコードを含めて読めるのは admin スコープを持つ利用者に限る
+    pub fn authorize_include_deleted(&self, include_deleted: bool) -> Result<(), Denied> {
+        if include_deleted {
+            self.authorize(ADMIN_SCOPE)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denied {
+    Unauthenticated(String),
+    Forbidden(String),
+}
+
+impl Denied {
+    pub fn message(&self) -> &str {
+        match self {
+            Denied::Unauthenticated(m) | Denied::Forbidden(m) => m,
+        }
+    }
+}
+
+impl IntoResponse for Denied {
+    fn into_response(self) -> Response {
+        match self {
+            Denied::Unauthenticated(message) => unauthorized(message),
+            Denied::Forbidden(message) => {
+                (StatusCode::FORBIDDEN, Json(json!({ "error": message }))).into_response()
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Auth {
+    pub(crate) state: Arc<AppState>,
+    pub(crate) allow_anonymous: bool,
+}
+
+/// 資格情報を検証し、認証できれば Principal を extensions に入れる。
+/// 検証に失敗しても断らずに Denied を入れて渡し、レート制限で数えてから handle が断る
+pub(crate) async fn identify(
+    State(auth): State<Auth>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let credentials = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .map(|v| v.to_str().unwrap_or_default().to_string());
+    match authenticate(&auth.state, credentials.as_deref()).await {
+        Ok(Some(principal)) => {
+            request.extensions_mut().insert(principal);
+        }
+        Ok(None) => {}
+        Err(usecase::error::UseCaseError::Unauthorized(msg)) => {
+            request
+                .extensions_mut()
+                .insert(Denied::Unauthenticated(msg));
+        }
+        Err(e) => return AppError(e).into_response(),
+    }
+    next.run(request).await
+}
+
+/// identify の結果を確認し、ルートに必要なスコープを持っているか確認する
+pub(crate) async fn handle(State(auth): State<Auth>, mut request: Request, next: Next) -> Response {
+    if let Some(denied) = request.extensions().get::<Denied>() {
+        return denied.clone().into_response();
+    }
+    let required = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| required_scope(request.method(), path.as_str()));
+
+    let principal = request.extensions().get::<Principal>().cloned();
+    let caller = Caller::new(principal, auth.allow_anonymous);
+    if let Some(scope) = required
+        && let Err(denied) = caller.authorize(scope)
+    {
+        return denied.into_response();
+    }
+
+    request.extensions_mut().insert(caller);
+    next.run(request).await
+}
+
+/// `ApiKey {secret}` 形式の資格情報を検証する。資格情報が無ければ匿名 (None) とする
+pub async fn authenticate(
+    state: &AppState,
+    credentials: Option<&str>,
+) -> Result<Option<Principal>, usecase::error::UseCaseError> {
+    let Some(value) = credentials else {
+        return Ok(None);
+    };
+    let secret = value
+        .split_once(' ')
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(API_KEY_SCHEME))
+        .map(|(_, secret)| secret.trim())
+        .ok_or_else(|| {
+            usecase::error::UseCaseError::Unauthorized(
+                "Unsupported authorization scheme".to_string(),
+            )
+        })?;
+    let key = state.api_key_usecase.authenticate(secret).await?;
+    Ok(Some(Principal {
+        subject: format!("api_key:{}", key.pub_id),
+        scopes: key.scopes,
+    }))
+}
+
+/// ルートのテンプレートとメソッドから、必要なスコープを決める
+pub(crate) fn required_scope(method: &Method, path: &str) -> Option<&'static str> {
+    let resource = path.trim_start_matches('/').split(['/', ':']).next()?;
+    let read = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    let scope = match (resource, read) {
+        // 変更フィードは書籍の一覧と同じ情報を流すため、書籍の読み取り権限で見られる
+        ("books" | "search" | "events", true) => "books:read",
+        ("books" | "imports", false) => "books:write",
+        ("publishers", true) => "publishers:read",
+        ("publishers", false) => "publishers:write",
+        ("shops", true) => "shops:read",
+        ("shops", false) => "shops:write",
+        ("webhooks", true) => "webhooks:read",
+        ("webhooks", false) => "webhooks:write",
+        ("admin", _) => ADMIN_SCOPE,
+        // GraphQL は 1 つのリクエストに複数の操作を含むため、リゾルバーが確認する
+        _ => return None,
+    };
+    Some(scope)
+}
+
+fn unauthorized(message: String) -> Response {
+    let mut response =
+        (StatusCode::UNAUTHORIZED, Json(json!({ "error": message }))).into_response();
+    response.headers_mut().insert(
+        header::WWW_AUTHENTICATE,
+        HeaderValue::from_static(API_KEY_SCHEME),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Method::GET, "/books", Some("books:read"))]
+    #[case(Method::GET, "/books/{pub_id}", Some("books:read"))]
+    #[case(Method::GET, "/search", Some("books:read"))]
+    #[case(Method::GET, "/events", Some("books:read"))]
+    #[case(Method::POST, "/books", Some("books:write"))]
+    #[case(Method::POST, "/books:batch", Some("books:write"))]
+    #[case(Method::PATCH, "/books/{pub_id}/applied_at", Some("books:write"))]
+    #[case(Method::POST, "/imports/books", Some("books:write"))]
+    #[case(Method::DELETE, "/publishers/{pub_id}", Some("publishers:write"))]
+    #[case(Method::GET, "/shops", Some("shops:read"))]
+    #[case(Method::POST, "/shops/{pub_id}/restore", Some("shops:write"))]
+    #[case(Method::GET, "/webhooks/{pub_id}/deliveries", Some("webhooks:read"))]
+    #[case(Method::POST, "/webhooks", Some("webhooks:write"))]
+    #[case(Method::GET, "/admin/api-keys", Some("admin"))]
+    #[case(Method::POST, "/admin/outbox/{id}/retry", Some("admin"))]
+    #[case(Method::POST, "/graphql", None)]
+    #[case(Method::GET, "/healthz", None)]
+    fn test_required_scope(
+        #[case] method: Method,
+        #[case] path: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(required_scope(&method, path), expected);
+    }
+
+    fn principal(scopes: &[&str]) -> Principal {
+        Principal {
+            subject: "api_key:test".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[rstest]
+    #[case(Some(principal(&["books:read"])), false, "books:read", true)]
+    #[case(Some(principal(&["books:read"])), true, "books:write", false)]
+    #[case(Some(principal(&["admin"])), false, "admin", true)]
+    #[case(None, true, "books:write", true)]
+    #[case(None, true, "admin", false)]
+    #[case(None, true, "webhooks:write", false)]
+    #[case(None, true, "webhooks:read", false)]
+    #[case(None, false, "books:read", false)]
+    fn test_authorize(
+        #[case] principal: Option<Principal>,
+        #[case] allow_anonymous: bool,
+        #[case] scope: &str,
+        #[case] allowed: bool,
+    ) {
+        let caller = Caller::new(principal, allow_anonymous);
+        assert_eq!(caller.authorize(scope).is_ok(), allowed);
+    }
+
+    #[rstest]
+    #[case(None, false, true)]
+    #[case(Some(principal(&["books:read"])), false, true)]
+    #[case(None, true, false)]
+    #[case(Some(principal(&["books:read"])), true, false)]
+    #[case(Some(principal(&["admin"])), true, true)]
+    fn test_authorize_include_deleted(
+        #[case] principal: Option<Principal>,
+        #[case] include_deleted: bool,
+        #[case] allowed: bool,
+    ) {
+        let caller = Caller::new(principal, true);
+        assert_eq!(
+            caller.authorize_include_deleted(include_deleted).is_ok(),
+            allowed
+        );
+    }
+}
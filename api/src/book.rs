@@ -1,24 +1,93 @@
 use crate::AppState;
+use crate::auth::Caller;
 use crate::error::AppError;
+use crate::export::{self, Format};
+use crate::query::{DeletedFilter, IdempotencyHeader, ImportOptions, SearchParams};
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::StatusCode,
+    Extension, Json,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
     response::IntoResponse,
 };
+use futures::TryStreamExt;
+use serde::Serialize;
 use std::sync::Arc;
 
+/// CSV エクスポートの 1 行。publisher / shop は取り込み (POST /imports/books) と同じ列名で pub_id を出す
+#[derive(Debug, Serialize)]
+pub struct CsvRecord {
+    pub pub_id: uuid::Uuid,
+    pub title: String,
+    pub author: String,
+    pub publisher: uuid::Uuid,
+    pub publisher_name: String,
+    pub shop: Option<uuid::Uuid>,
+    pub shop_name: Option<String>,
+    pub format: String,
+    pub price: i32,
+    pub applied_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<usecase::book::ResponseDto> for CsvRecord {
+    fn from(book: usecase::book::ResponseDto) -> Self {
+        Self {
+            pub_id: book.pub_id,
+            title: book.title,
+            author: book.author,
+            publisher: book.publisher.pub_id,
+            publisher_name: book.publisher.name,
+            shop: book.shop.as_ref().map(|s| s.pub_id),
+            shop_name: book.shop.map(|s| s.name),
+            format: book.format,
+            price: book.price,
+            applied_at: book.applied_at,
+            deleted_at: book.deleted_at,
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/books",
     tag = "Book",
     operation_id = "get_all_books",
+    params(DeletedFilter),
     responses(
-        (status = 200, description = "List all books", body = [usecase::book::ResponseDto])
+        (status = 200, description = "List all books. `text/csv` and `application/x-ndjson` are streamed.", content(
+            ([usecase::book::ResponseDto] = "application/json"),
+            (String = "text/csv"),
+            (String = "application/x-ndjson")
+        )),
+        (status = 401, description = "include_deleted was requested without an API key"),
+        (status = 403, description = "include_deleted requires the admin scope")
     )
 )]
-pub async fn get_all(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    match state.book_usecase.get_all().await {
+pub async fn get_all(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<DeletedFilter>,
+    Extension(caller): Extension<Caller>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(denied) = caller.authorize_include_deleted(filter.include_deleted) {
+        return denied.into_response();
+    }
+    match Format::from_headers(&headers) {
+        Format::Csv => {
+            let rows = state
+                .book_usecase
+                .stream_all(filter.include_deleted)
+                .map_ok(CsvRecord::from);
+            return export::csv(rows);
+        }
+        Format::NdJson => {
+            return export::ndjson(state.book_usecase.stream_all(filter.include_deleted));
+        }
+        Format::Json => {}
+    }
+
+    match state.book_usecase.get_all(filter.include_deleted).await {
         Ok(books) => (StatusCode::OK, Json(books)).into_response(),
         Err(e) => AppError(e).into_response(),
     }
@@ -31,17 +100,25 @@ pub async fn get_all(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     operation_id = "get_book",
     responses(
         (status = 200, description = "Get book by pub_id", body = usecase::book::ResponseDto),
-        (status = 404, description = "Book not found")
+        (status = 404, description = "Book not found"),
+        (status = 401, description = "include_deleted was requested without an API key"),
+        (status = 403, description = "include_deleted requires the admin scope")
     ),
     params(
-        ("pub_id" = uuid::Uuid, Path, description = "Book pub_id")
+        ("pub_id" = uuid::Uuid, Path, description = "Book pub_id"),
+        DeletedFilter
     )
 )]
 pub async fn get(
     State(state): State<Arc<AppState>>,
     Path(pub_id): Path<uuid::Uuid>,
+    Query(filter): Query<DeletedFilter>,
+    Extension(caller): Extension<Caller>,
 ) -> impl IntoResponse {
-    match state.book_usecase.get(pub_id).await {
+    if let Err(denied) = caller.authorize_include_deleted(filter.include_deleted) {
+        return denied.into_response();
+    }
+    match state.book_usecase.get(pub_id, filter.include_deleted).await {
         Ok(book) => (StatusCode::OK, Json(book)).into_response(),
         Err(e) => AppError(e).into_response(),
     }
@@ -53,8 +130,11 @@ pub async fn get(
     tag = "Book",
     operation_id = "create_book",
     request_body = usecase::book::CreateDto,
+    params(IdempotencyHeader),
     responses(
-        (status = 201, description = "Book created successfully", body = usecase::book::ResponseDto)
+        (status = 201, description = "Book created successfully", body = usecase::book::ResponseDto),
+        (status = 409, description = "A request with the same Idempotency-Key is in progress"),
+        (status = 422, description = "Idempotency-Key was reused with a different request")
     )
 )]
 pub async fn create(
@@ -115,6 +195,30 @@ pub async fn delete(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/books/{pub_id}/restore",
+    tag = "Book",
+    operation_id = "restore_book",
+    responses(
+        (status = 200, description = "Book restored successfully", body = usecase::book::ResponseDto),
+        (status = 400, description = "Book is not deleted, or its publisher or shop is deleted"),
+        (status = 404, description = "Book not found")
+    ),
+    params(
+        ("pub_id" = uuid::Uuid, Path, description = "Book pub_id")
+    )
+)]
+pub async fn restore(
+    State(state): State<Arc<AppState>>,
+    Path(pub_id): Path<uuid::Uuid>,
+) -> impl IntoResponse {
+    match state.book_usecase.restore(pub_id).await {
+        Ok(book) => (StatusCode::OK, Json(book)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
 #[utoipa::path(
     put,
     path = "/books/{pub_id}/applied_at",
@@ -139,3 +243,96 @@ pub async fn change_applied_at(
         Err(e) => AppError(e).into_response(),
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/books:batch",
+    tag = "Book",
+    operation_id = "batch_books",
+    request_body = usecase::book::BatchDto,
+    params(IdempotencyHeader),
+    responses(
+        (status = 200, description = "All operations succeeded", body = usecase::book::BatchResponseDto),
+        (status = 207, description = "Some operations failed or were skipped", body = usecase::book::BatchResponseDto),
+        (status = 400, description = "Too many operations"),
+        (status = 409, description = "A request with the same Idempotency-Key is in progress"),
+        (status = 422, description = "Idempotency-Key was reused with a different request")
+    )
+)]
+pub async fn batch(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<usecase::book::BatchDto>,
+) -> impl IntoResponse {
+    match state.book_usecase.batch(payload).await {
+        Ok(result) => {
+            let status = if result.all_succeeded() {
+                StatusCode::OK
+            } else {
+                StatusCode::MULTI_STATUS
+            };
+            (status, Json(result)).into_response()
+        }
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/imports/books",
+    tag = "Book",
+    operation_id = "import_books",
+    params(ImportOptions),
+    request_body(content = String, content_type = "text/csv", description = "Columns: title, author, publisher, shop, format, price. publisher / shop accept a name or pub_id."),
+    responses(
+        (status = 200, description = "Import report. Rows are committed in chunks of 500, so an import that stops partway keeps the earlier chunks and reports the line it stopped at as the last error", body = usecase::book::ImportReportDto),
+        (status = 400, description = "Invalid CSV header"),
+        (status = 415, description = "Content-Type is not text/csv")
+    )
+)]
+pub async fn import(
+    State(state): State<Arc<AppState>>,
+    Query(options): Query<ImportOptions>,
+    headers: HeaderMap,
+    body: Body,
+) -> impl IntoResponse {
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/csv"));
+    if !is_csv {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+    }
+
+    // リクエストボディはメモリに溜めずにそのまま CSV リーダーへ流す
+    let reader =
+        tokio_util::io::StreamReader::new(body.into_data_stream().map_err(std::io::Error::other));
+    match state.book_usecase.import(reader, options.dry_run).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "Book",
+    operation_id = "search_books",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "Books ranked by relevance", body = usecase::book::SearchResponseDto),
+        (status = 400, description = "Empty query or invalid paging")
+    )
+)]
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SearchParams>,
+) -> impl IntoResponse {
+    match state
+        .book_usecase
+        .search(&params.q, params.page, params.per_page)
+        .await
+    {
+        Ok(result) => (StatusCode::OK, Json(result)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
@@ -0,0 +1,178 @@
+use axum::http::{HeaderName, HeaderValue, Method};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tower_http::cors::{AllowMethods, AllowOrigin, Any, CorsLayer};
+
+/// HTTP 層の設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// CORS を許可するオリジン。空なら CORS ヘッダーを付けず、"*" なら全て許可する
+    pub cors_origins: Vec<String>,
+    /// CORS を許可するメソッド。"*" なら全て許可する
+    pub cors_methods: Vec<String>,
+    /// /swagger-ui を公開するか
+    pub swagger_ui: bool,
+    /// Accept-Encoding に応じて gzip / br で圧縮するか
+    pub compression: bool,
+    /// JSON などメモリに読み込むリクエストボディの上限 (バイト)。
+    /// CSV の取り込みはストリームで読むため対象外
+    pub max_body_bytes: usize,
+    /// レスポンスを返し始めるまでの上限。超えたら 408 を返す。
+    /// CSV の取り込み・書き出しと変更フィードには掛けない
+    pub request_timeout_secs: u64,
+    pub rate_limit: RateLimitConfig,
+    pub auth: AuthConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// 資格情報の無いリクエストにも管理系と webhook の登録・削除以外の操作を許す。
+    /// false にすると全ての API で `Authorization: ApiKey ...` が必要になる
+    pub allow_anonymous: bool,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            allow_anonymous: true,
+        }
+    }
+}
+
+/// 利用者ごとのレート制限。読み取り (GET / HEAD / OPTIONS) と書き込みで別々に数える
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub read: RateLimit,
+    pub write: RateLimit,
+    /// リバースプロキシの後ろで動かす場合に、X-Forwarded-For の末尾 (プロキシが付け足した値) を利用者の IP とみなす
+    pub trust_forwarded_for: bool,
+}
+
+/// トークンバケットの大きさと補充の速さ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// 連続して受け付けられる回数
+    pub burst: u32,
+    /// 1 分あたりに補充する回数
+    pub per_minute: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            read: RateLimit {
+                burst: 120,
+                per_minute: 600,
+            },
+            write: RateLimit {
+                burst: 30,
+                per_minute: 120,
+            },
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            cors_origins: Vec::new(),
+            cors_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .map(String::from)
+                .to_vec(),
+            swagger_ui: true,
+            compression: true,
+            max_body_bytes: 2 * 1024 * 1024,
+            request_timeout_secs: 30,
+            rate_limit: RateLimitConfig::default(),
+            auth: AuthConfig::default(),
+        }
+    }
+}
+
+impl HttpConfig {
+    /// 設定値の誤りを全て列挙する
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors: Vec<String> = self
+            .cors_origins
+            .iter()
+            .filter(|origin| {
+                origin.as_str() != "*"
+                    && (!(origin.starts_with("http://") || origin.starts_with("https://"))
+                        || origin.ends_with('/')
+                        || HeaderValue::from_str(origin).is_err())
+            })
+            .map(|origin| {
+                format!(
+                    "http.cors_origins: '{}' must be \"*\" or an origin like https://example.com",
+                    origin
+                )
+            })
+            .collect();
+        errors.extend(
+            self.cors_methods
+                .iter()
+                .filter(|m| m.as_str() != "*" && Method::from_bytes(m.as_bytes()).is_err())
+                .map(|m| format!("http.cors_methods: '{}' is not an HTTP method", m)),
+        );
+        if self.max_body_bytes == 0 {
+            errors.push("http.max_body_bytes must be 1 or greater".to_string());
+        }
+        if self.request_timeout_secs == 0 {
+            errors.push("http.request_timeout_secs must be 1 or greater".to_string());
+        }
+        for (group, limit) in [
+            ("read", self.rate_limit.read),
+            ("write", self.rate_limit.write),
+        ] {
+            if limit.burst == 0 || limit.per_minute == 0 {
+                errors.push(format!(
+                    "http.rate_limit.{}: burst and per_minute must be 1 or greater",
+                    group
+                ));
+            }
+        }
+        errors
+    }
+
+    pub(crate) fn cors_layer(&self) -> Option<CorsLayer> {
+        if self.cors_origins.is_empty() {
+            return None;
+        }
+        let origin = if self.cors_origins.iter().any(|o| o == "*") {
+            AllowOrigin::from(Any)
+        } else {
+            AllowOrigin::list(
+                self.cors_origins
+                    .iter()
+                    .filter_map(|o| HeaderValue::from_str(o).ok()),
+            )
+        };
+        let methods = if self.cors_methods.iter().any(|m| m == "*") {
+            AllowMethods::from(Any)
+        } else {
+            AllowMethods::list(
+                self.cors_methods
+                    .iter()
+                    .filter_map(|m| Method::from_bytes(m.as_bytes()).ok()),
+            )
+        };
+        Some(
+            CorsLayer::new()
+                .allow_origin(origin)
+                .allow_methods(methods)
+                .allow_headers(Any)
+                // ブラウザから問い合わせ用の ID を読めるようにする
+                .expose_headers([HeaderName::from_static("x-request-id")]),
+        )
+    }
+
+    pub(crate) fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+}
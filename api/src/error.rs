@@ -6,10 +6,13 @@ use usecase::error::UseCaseError;
 
 pub struct AppError(pub UseCaseError);
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, message) = match self.0 {
+impl AppError {
+    /// 入力の誤りを数えて、HTTP のステータスと利用者に見せるメッセージにする
+    pub(crate) fn into_parts(self) -> (StatusCode, String) {
+        usecase::metrics::validation_failed(&self.0);
+        match self.0 {
             UseCaseError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            UseCaseError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             UseCaseError::InternalServerError | UseCaseError::DatabaseError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal Server Error".to_string(),
@@ -18,8 +21,18 @@ impl IntoResponse for AppError {
             UseCaseError::BookDomainError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
             UseCaseError::PublisherDomainError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
             UseCaseError::ShopDomainError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
-        };
+            UseCaseError::IdempotencyDomainError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+            UseCaseError::ApiKeyDomainError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+            UseCaseError::ChangeEventDomainError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+            UseCaseError::OutboxDomainError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+            UseCaseError::WebhookDomainError(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+        }
+    }
+}
 
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, message) = self.into_parts();
         let body = Json(json!({
             "error": message,
         }));
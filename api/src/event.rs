@@ -0,0 +1,68 @@
+use crate::AppState;
+use crate::error::AppError;
+use crate::export::BoxError;
+use crate::query::{EventFilter, LastEventIdHeader};
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures::StreamExt;
+use std::sync::Arc;
+use usecase::error::UseCaseError;
+
+const LAST_EVENT_ID: &str = "last-event-id";
+
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "Event",
+    operation_id = "stream_events",
+    params(EventFilter, LastEventIdHeader),
+    responses(
+        (status = 200, description = "Server-Sent Events stream of catalog changes. Each event has `id`, `event` (e.g. `book.created`) and a JSON `data`", content(
+            (usecase::change_event::EventDto = "text/event-stream")
+        )),
+        (status = 400, description = "Unknown resource type or invalid Last-Event-ID")
+    )
+)]
+pub async fn stream(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<EventFilter>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let last_event_id = match headers.get(LAST_EVENT_ID) {
+        None => None,
+        Some(value) => match value.to_str().ok().and_then(|v| v.trim().parse().ok()) {
+            Some(id) => Some(id),
+            None => {
+                return AppError(UseCaseError::DomainRuleViolation(
+                    "Last-Event-ID must be an integer".to_string(),
+                ))
+                .into_response();
+            }
+        },
+    };
+
+    let events = match state
+        .change_event_usecase
+        .subscribe(last_event_id, &filter.resource_types())
+        .await
+    {
+        Ok(events) => events,
+        Err(e) => return AppError(e).into_response(),
+    };
+    let events = events.map(|e| -> Result<Event, BoxError> {
+        let e = e?;
+        Ok(Event::default()
+            .id(e.id.to_string())
+            .event(&e.event_type)
+            .json_data(&e)?)
+    });
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
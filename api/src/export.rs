@@ -0,0 +1,86 @@
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use usecase::error::UseCaseError;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Accept ヘッダーから決まる一覧のレスポンス形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    NdJson,
+}
+
+impl Format {
+    /// 先に書かれたメディアタイプを優先する (q 値は見ない)
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()) else {
+            return Format::Json;
+        };
+        accept
+            .split(',')
+            .filter_map(|v| match v.split(';').next().unwrap_or_default().trim() {
+                "text/csv" => Some(Format::Csv),
+                "application/x-ndjson" => Some(Format::NdJson),
+                "application/json" | "*/*" => Some(Format::Json),
+                _ => None,
+            })
+            .next()
+            .unwrap_or(Format::Json)
+    }
+}
+
+/// 1 行ずつ CSV に書き出す。ヘッダー行は最初のレコードから作る
+pub fn csv<S, T>(rows: S) -> Response
+where
+    S: Stream<Item = Result<T, UseCaseError>> + Send + 'static,
+    T: Serialize,
+{
+    let body = rows
+        .enumerate()
+        .map(|(index, row)| csv_line(&row?, index == 0));
+    streaming(body, "text/csv; charset=utf-8")
+}
+
+/// 1 行に 1 つの JSON を書き出す
+pub fn ndjson<S, T>(rows: S) -> Response
+where
+    S: Stream<Item = Result<T, UseCaseError>> + Send + 'static,
+    T: Serialize,
+{
+    let body = rows.map(|row| ndjson_line(&row?));
+    streaming(body, "application/x-ndjson")
+}
+
+/// CSV の 1 行。with_header なら先頭にヘッダー行を付ける
+pub fn csv_line<T: Serialize>(row: &T, with_header: bool) -> Result<Vec<u8>, BoxError> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(with_header)
+        .from_writer(Vec::new());
+    writer.serialize(row)?;
+    Ok(writer.into_inner().map_err(|e| e.into_error())?)
+}
+
+/// 改行で終わる JSON の 1 行
+pub fn ndjson_line<T: Serialize>(row: &T) -> Result<Vec<u8>, BoxError> {
+    let mut line = serde_json::to_vec(row)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn streaming<S>(body: S, content_type: &'static str) -> Response
+where
+    S: Stream<Item = Result<Vec<u8>, BoxError>> + Send + 'static,
+{
+    (
+        [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
@@ -0,0 +1,479 @@
+use crate::AppState;
+use crate::auth::{Caller, Denied};
+use crate::error::AppError;
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{
+    Context, EmptySubscription, ErrorExtensions, InputObject, Object, SimpleObject,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::Extension;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse};
+use axum::routing::{MethodRouter, post};
+use std::sync::Arc;
+use usecase::error::UseCaseError;
+
+mod loader;
+use loader::{HistoryLoader, PublisherLoader, ShopLoader};
+
+/// 入れ子を深くして負荷を掛けるクエリを断る
+const MAX_DEPTH: usize = 10;
+
+pub type Schema = async_graphql::Schema<Query, Mutation, EmptySubscription>;
+
+pub fn schema() -> Schema {
+    Schema::build(Query, Mutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+/// POST /graphql と、開発時のみ GET /graphql の GraphiQL
+pub(crate) fn route() -> MethodRouter<Arc<AppState>> {
+    let route = post(execute);
+    let route = if cfg!(debug_assertions) {
+        route.get(graphiql)
+    } else {
+        route
+    };
+    route.layer(Extension(schema()))
+}
+
+/// DataLoader はリクエストごとに作り、別のリクエストの結果を使い回さない
+async fn execute(
+    State(state): State<Arc<AppState>>,
+    Extension(schema): Extension<Schema>,
+    Extension(caller): Extension<Caller>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    let request = request
+        .into_inner()
+        .data(caller)
+        .data(DataLoader::new(
+            PublisherLoader(state.clone()),
+            tokio::spawn,
+        ))
+        .data(DataLoader::new(ShopLoader(state.clone()), tokio::spawn))
+        .data(DataLoader::new(HistoryLoader(state.clone()), tokio::spawn))
+        .data(state);
+    schema.execute(request).await.into()
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(
+        async_graphql::http::GraphiQLSource::build()
+            .endpoint("/graphql")
+            .finish(),
+    )
+}
+
+fn state<'a>(ctx: &Context<'a>) -> &'a Arc<AppState> {
+    ctx.data_unchecked::<Arc<AppState>>()
+}
+
+/// REST のルートと同じスコープを操作ごとに確かめる
+fn authorize(ctx: &Context<'_>, scope: &str) -> async_graphql::Result<()> {
+    ctx.data::<Caller>()?.authorize(scope).map_err(denied)
+}
+
+/// REST の include_deleted と同じく、論理削除済みを含めるには admin スコープが必要
+fn authorize_include_deleted(
+    ctx: &Context<'_>,
+    include_deleted: bool,
+) -> async_graphql::Result<()> {
+    ctx.data::<Caller>()?
+        .authorize_include_deleted(include_deleted)
+        .map_err(denied)
+}
+
+fn denied(denied: Denied) -> async_graphql::Error {
+    let code = match denied {
+        Denied::Unauthenticated(_) => "UNAUTHENTICATED",
+        Denied::Forbidden(_) => "FORBIDDEN",
+    };
+    async_graphql::Error::new(denied.message()).extend_with(|_, e| e.set("code", code))
+}
+
+/// REST と同じメッセージにし、HTTP のステータスを code に入れる
+fn error(e: UseCaseError) -> async_graphql::Error {
+    let (status, message) = AppError(e).into_parts();
+    let code = status
+        .canonical_reason()
+        .unwrap_or_default()
+        .to_uppercase()
+        .replace(' ', "_");
+    async_graphql::Error::new(message).extend_with(|_, e| e.set("code", code))
+}
+
+/// 見つからない時は null を返す
+fn optional<T>(result: Result<T, UseCaseError>) -> async_graphql::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(UseCaseError::NotFound(_)) => Ok(None),
+        Err(e) => Err(error(e)),
+    }
+}
+
+pub struct Book(usecase::book::ResponseDto);
+
+/// publisher / shop / history は同じクエリ内の Book の分をまとめて読む。
+/// REST で Book に publisher と shop を含めて返すのと同じく、books:read だけで見られる
+#[Object]
+impl Book {
+    async fn pub_id(&self) -> uuid::Uuid {
+        self.0.pub_id
+    }
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+    async fn author(&self) -> &str {
+        &self.0.author
+    }
+    async fn format(&self) -> &str {
+        &self.0.format
+    }
+    async fn price(&self) -> i32 {
+        self.0.price
+    }
+    async fn applied_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.0.applied_at
+    }
+    async fn deleted_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.0.deleted_at
+    }
+
+    async fn publisher(&self, ctx: &Context<'_>) -> async_graphql::Result<Publisher> {
+        let pub_id = self.0.publisher.pub_id;
+        ctx.data::<DataLoader<PublisherLoader>>()?
+            .load_one(pub_id)
+            .await?
+            .ok_or_else(|| {
+                async_graphql::Error::new(format!("Publisher with pub_id = {} not found", pub_id))
+            })
+    }
+
+    async fn shop(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Shop>> {
+        let Some(shop) = &self.0.shop else {
+            return Ok(None);
+        };
+        ctx.data::<DataLoader<ShopLoader>>()?
+            .load_one(shop.pub_id)
+            .await
+    }
+
+    /// 古い順
+    async fn history(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<BookHistory>> {
+        Ok(ctx
+            .data::<DataLoader<HistoryLoader>>()?
+            .load_one(self.0.pub_id)
+            .await?
+            .unwrap_or_default())
+    }
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct Publisher {
+    pub_id: uuid::Uuid,
+    name: String,
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<usecase::publisher::ResponseDto> for Publisher {
+    fn from(dto: usecase::publisher::ResponseDto) -> Self {
+        Self {
+            pub_id: dto.pub_id,
+            name: dto.name,
+            deleted_at: dto.deleted_at,
+        }
+    }
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct Shop {
+    pub_id: uuid::Uuid,
+    name: String,
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<usecase::shop::ResponseDto> for Shop {
+    fn from(dto: usecase::shop::ResponseDto) -> Self {
+        Self {
+            pub_id: dto.pub_id,
+            name: dto.name,
+            deleted_at: dto.deleted_at,
+        }
+    }
+}
+
+/// 変更後の Book の値
+#[derive(SimpleObject, Clone)]
+pub struct BookHistory {
+    /// INSERT / UPDATE / DELETE
+    operation: String,
+    operation_at: chrono::DateTime<chrono::Utc>,
+    title: String,
+    author: String,
+    format: String,
+    price: i32,
+    applied_at: Option<chrono::DateTime<chrono::Utc>>,
+    updated_by: String,
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<usecase::book::HistoryDto> for BookHistory {
+    fn from(dto: usecase::book::HistoryDto) -> Self {
+        Self {
+            operation: dto.operation,
+            operation_at: dto.operation_at,
+            title: dto.title,
+            author: dto.author,
+            format: dto.format,
+            price: dto.price,
+            applied_at: dto.applied_at,
+            updated_by: dto.updated_by,
+            deleted_at: dto.deleted_at,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct BookInput {
+    title: String,
+    author: String,
+    publisher_id: uuid::Uuid,
+    shop_id: Option<uuid::Uuid>,
+    /// Real / EBook。省略すると Real
+    format: Option<String>,
+    price: i32,
+}
+
+impl From<BookInput> for usecase::book::CreateDto {
+    fn from(input: BookInput) -> Self {
+        Self {
+            title: input.title,
+            author: input.author,
+            publisher_id: input.publisher_id,
+            shop_id: input.shop_id,
+            format: input.format,
+            price: input.price,
+        }
+    }
+}
+
+impl From<BookInput> for usecase::book::UpdateDto {
+    fn from(input: BookInput) -> Self {
+        Self {
+            title: input.title,
+            author: input.author,
+            publisher_id: input.publisher_id,
+            shop_id: input.shop_id,
+            format: input.format,
+            price: input.price,
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct PublisherInput {
+    name: String,
+}
+
+#[derive(InputObject)]
+pub struct ShopInput {
+    name: String,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    async fn book(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+        #[graphql(default)] include_deleted: bool,
+    ) -> async_graphql::Result<Option<Book>> {
+        authorize(ctx, "books:read")?;
+        authorize_include_deleted(ctx, include_deleted)?;
+        let book = state(ctx).book_usecase.get(pub_id, include_deleted).await;
+        Ok(optional(book)?.map(Book))
+    }
+
+    async fn books(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] include_deleted: bool,
+    ) -> async_graphql::Result<Vec<Book>> {
+        authorize(ctx, "books:read")?;
+        authorize_include_deleted(ctx, include_deleted)?;
+        let books = state(ctx)
+            .book_usecase
+            .get_all(include_deleted)
+            .await
+            .map_err(error)?;
+        Ok(books.into_iter().map(Book).collect())
+    }
+
+    async fn publisher(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+        #[graphql(default)] include_deleted: bool,
+    ) -> async_graphql::Result<Option<Publisher>> {
+        authorize(ctx, "publishers:read")?;
+        authorize_include_deleted(ctx, include_deleted)?;
+        let publisher = state(ctx)
+            .publisher_usecase
+            .get(pub_id, include_deleted)
+            .await;
+        Ok(optional(publisher)?.map(Publisher::from))
+    }
+
+    async fn shop(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+        #[graphql(default)] include_deleted: bool,
+    ) -> async_graphql::Result<Option<Shop>> {
+        authorize(ctx, "shops:read")?;
+        authorize_include_deleted(ctx, include_deleted)?;
+        let shop = state(ctx).shop_usecase.get(pub_id, include_deleted).await;
+        Ok(optional(shop)?.map(Shop::from))
+    }
+}
+
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    async fn create_book(
+        &self,
+        ctx: &Context<'_>,
+        input: BookInput,
+    ) -> async_graphql::Result<Book> {
+        authorize(ctx, "books:write")?;
+        let book = state(ctx)
+            .book_usecase
+            .create(input.into())
+            .await
+            .map_err(error)?;
+        Ok(Book(book))
+    }
+
+    async fn update_book(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+        input: BookInput,
+    ) -> async_graphql::Result<Book> {
+        authorize(ctx, "books:write")?;
+        let book = state(ctx)
+            .book_usecase
+            .update(pub_id, input.into())
+            .await
+            .map_err(error)?;
+        Ok(Book(book))
+    }
+
+    /// 論理削除する
+    async fn delete_book(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+    ) -> async_graphql::Result<bool> {
+        authorize(ctx, "books:write")?;
+        state(ctx)
+            .book_usecase
+            .delete(pub_id)
+            .await
+            .map_err(error)?;
+        Ok(true)
+    }
+
+    async fn create_publisher(
+        &self,
+        ctx: &Context<'_>,
+        input: PublisherInput,
+    ) -> async_graphql::Result<Publisher> {
+        authorize(ctx, "publishers:write")?;
+        let publisher = state(ctx)
+            .publisher_usecase
+            .create(usecase::publisher::CreateDto { name: input.name })
+            .await
+            .map_err(error)?;
+        Ok(publisher.into())
+    }
+
+    async fn update_publisher(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+        input: PublisherInput,
+    ) -> async_graphql::Result<Publisher> {
+        authorize(ctx, "publishers:write")?;
+        let publisher = state(ctx)
+            .publisher_usecase
+            .update(pub_id, usecase::publisher::UpdateDto { name: input.name })
+            .await
+            .map_err(error)?;
+        Ok(publisher.into())
+    }
+
+    /// 論理削除する
+    async fn delete_publisher(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+    ) -> async_graphql::Result<bool> {
+        authorize(ctx, "publishers:write")?;
+        state(ctx)
+            .publisher_usecase
+            .delete(pub_id)
+            .await
+            .map_err(error)?;
+        Ok(true)
+    }
+
+    async fn create_shop(
+        &self,
+        ctx: &Context<'_>,
+        input: ShopInput,
+    ) -> async_graphql::Result<Shop> {
+        authorize(ctx, "shops:write")?;
+        let shop = state(ctx)
+            .shop_usecase
+            .create(usecase::shop::CreateDto { name: input.name })
+            .await
+            .map_err(error)?;
+        Ok(shop.into())
+    }
+
+    async fn update_shop(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+        input: ShopInput,
+    ) -> async_graphql::Result<Shop> {
+        authorize(ctx, "shops:write")?;
+        let shop = state(ctx)
+            .shop_usecase
+            .update(pub_id, usecase::shop::UpdateDto { name: input.name })
+            .await
+            .map_err(error)?;
+        Ok(shop.into())
+    }
+
+    /// 論理削除する
+    async fn delete_shop(
+        &self,
+        ctx: &Context<'_>,
+        pub_id: uuid::Uuid,
+    ) -> async_graphql::Result<bool> {
+        authorize(ctx, "shops:write")?;
+        state(ctx)
+            .shop_usecase
+            .delete(pub_id)
+            .await
+            .map_err(error)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,73 @@
+use super::{BookHistory, Publisher, Shop, error};
+use crate::AppState;
+use async_graphql::dataloader::Loader;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 1 回のクエリで参照された publisher をまとめて読む。
+/// 論理削除済みの publisher を参照している Book もあるため、削除済みも返す
+pub(crate) struct PublisherLoader(pub(crate) Arc<AppState>);
+
+impl Loader<uuid::Uuid> for PublisherLoader {
+    type Value = Publisher;
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        keys: &[uuid::Uuid],
+    ) -> Result<HashMap<uuid::Uuid, Publisher>, Self::Error> {
+        let publishers = self
+            .0
+            .publisher_usecase
+            .get_by_pub_ids(keys, true)
+            .await
+            .map_err(error)?;
+        Ok(publishers
+            .into_iter()
+            .map(|p| (p.pub_id, Publisher::from(p)))
+            .collect())
+    }
+}
+
+pub(crate) struct ShopLoader(pub(crate) Arc<AppState>);
+
+impl Loader<uuid::Uuid> for ShopLoader {
+    type Value = Shop;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[uuid::Uuid]) -> Result<HashMap<uuid::Uuid, Shop>, Self::Error> {
+        let shops = self
+            .0
+            .shop_usecase
+            .get_by_pub_ids(keys, true)
+            .await
+            .map_err(error)?;
+        Ok(shops
+            .into_iter()
+            .map(|s| (s.pub_id, Shop::from(s)))
+            .collect())
+    }
+}
+
+/// Book ごとの変更履歴。履歴の無い Book は空の一覧にする
+pub(crate) struct HistoryLoader(pub(crate) Arc<AppState>);
+
+impl Loader<uuid::Uuid> for HistoryLoader {
+    type Value = Vec<BookHistory>;
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        keys: &[uuid::Uuid],
+    ) -> Result<HashMap<uuid::Uuid, Vec<BookHistory>>, Self::Error> {
+        let history = self.0.book_usecase.get_history(keys).await.map_err(error)?;
+        let mut by_book: HashMap<_, _> = keys.iter().map(|k| (*k, Vec::new())).collect();
+        for h in history {
+            by_book
+                .entry(h.pub_id)
+                .or_default()
+                .push(BookHistory::from(h));
+        }
+        Ok(by_book)
+    }
+}
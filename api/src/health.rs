@@ -0,0 +1,97 @@
+use crate::AppState;
+use async_trait::async_trait;
+use axum::{Json, Router, extract::State, http::StatusCode, response::IntoResponse, routing::get};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 1 つのチェックにかけてよい時間。これを超えたら劣化とみなす
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+/// 劣化したチェックの error。/readyz は認証を掛けないので、理由はログにだけ出す
+const UNAVAILABLE: &str = "unavailable";
+
+/// /readyz で確認する依存先 (DB やバックグラウンドワーカーなど)
+#[async_trait]
+pub trait Check: Send + Sync {
+    fn name(&self) -> &str;
+    /// 問題があれば理由を返す (ログに出し、応答には載せない)
+    async fn check(&self) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Degraded,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckResult {
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub status: Status,
+    pub checks: BTreeMap<String, CheckResult>,
+}
+
+/// OpenAPI には載せず、認証も掛けないルート
+pub(crate) fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+/// プロセスが応答できるか
+async fn healthz() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": Status::Ok }))
+}
+
+/// 全てのチェックが通ればトラフィックを受けてよい
+async fn readyz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let report = readiness(&state.readiness_checks).await;
+    let status = match report.status {
+        Status::Ok => StatusCode::OK,
+        Status::Degraded => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(report))
+}
+
+pub async fn readiness(checks: &[Arc<dyn Check>]) -> Report {
+    let results = futures::future::join_all(checks.iter().map(|c| async move {
+        let error = match tokio::time::timeout(CHECK_TIMEOUT, c.check()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e),
+            Err(_) => Some(format!("timed out after {:?}", CHECK_TIMEOUT)),
+        };
+        let result = match error {
+            None => CheckResult {
+                status: Status::Ok,
+                error: None,
+            },
+            Some(e) => {
+                tracing::warn!(check = c.name(), error = %e, "readiness check failed");
+                CheckResult {
+                    status: Status::Degraded,
+                    error: Some(UNAVAILABLE.to_string()),
+                }
+            }
+        };
+        (c.name().to_string(), result)
+    }))
+    .await;
+
+    let status = if results.iter().all(|(_, r)| r.status == Status::Ok) {
+        Status::Ok
+    } else {
+        Status::Degraded
+    };
+    Report {
+        status,
+        checks: results.into_iter().collect(),
+    }
+}
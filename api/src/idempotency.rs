@@ -0,0 +1,135 @@
+use crate::AppState;
+use crate::auth::Principal;
+use crate::error::AppError;
+use axum::Json;
+use axum::body::{Body, Bytes};
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use std::sync::Arc;
+use usecase::idempotency::{Outcome, ResponseDto};
+
+pub(crate) const HEADER: &str = "idempotency-key";
+/// 保存したレスポンスを返した時に付けるヘッダー
+pub(crate) const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Idempotency-Key を受け付ける作成系のルート。
+/// CSV の取り込みはストリームで処理するため、本文を溜めて比べる対象から外す
+const CREATE_ROUTES: [&str; 5] = [
+    "/books",
+    "/books:batch",
+    "/publishers",
+    "/shops",
+    "/webhooks",
+];
+
+#[derive(Clone)]
+pub(crate) struct Layer {
+    pub(crate) state: Arc<AppState>,
+    pub(crate) max_body_bytes: usize,
+}
+
+/// Idempotency-Key 付きの作成リクエストは、同じキーの再試行に最初のレスポンスを返す
+pub(crate) async fn handle(State(layer): State<Layer>, request: Request, next: Next) -> Response {
+    let is_create = request.method() == Method::POST
+        && request
+            .extensions()
+            .get::<MatchedPath>()
+            .is_some_and(|path| CREATE_ROUTES.contains(&path.as_str()));
+    let Some(key) = request.headers().get(HEADER).filter(|_| is_create) else {
+        return next.run(request).await;
+    };
+    let Ok(key) = key.to_str().map(str::to_string) else {
+        return error(StatusCode::BAD_REQUEST, "Idempotency-Key must be ASCII");
+    };
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, layer.max_body_bytes).await else {
+        return error(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large");
+    };
+    // クエリも結果を変えるので、本文と合わせて同じリクエストかを比べる
+    let mut fingerprint = parts.uri.query().unwrap_or_default().as_bytes().to_vec();
+    fingerprint.push(b'\n');
+    fingerprint.extend_from_slice(&body);
+    // 別の利用者が同じキーを選んでも、他人のレスポンスを返さないよう利用者ごとに分ける
+    let subject = parts
+        .extensions
+        .get::<Principal>()
+        .map_or("anonymous", |principal| principal.subject.as_str());
+    let scope = format!("{} {} {}", subject, parts.method, parts.uri.path());
+
+    let usecase = &layer.state.idempotency_usecase;
+    let claim = match usecase.begin(key, scope, &fingerprint).await {
+        Ok(Outcome::Proceed(claim)) => claim,
+        Ok(Outcome::Replay(stored)) => return replay(stored),
+        Ok(Outcome::Mismatch) => {
+            return error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used for a different request",
+            );
+        }
+        Ok(Outcome::InProgress) => {
+            return error(
+                StatusCode::CONFLICT,
+                "A request with this Idempotency-Key is still in progress",
+            );
+        }
+        Err(e) => return AppError(e).into_response(),
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    // サーバー側の失敗は再試行で結果が変わりうるので保存しない
+    if response.status().is_server_error() {
+        if let Err(e) = usecase.abandon(claim).await {
+            tracing::warn!(error = %e, "failed to release idempotency key");
+        }
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to read response body");
+            if let Err(e) = usecase.abandon(claim).await {
+                tracing::warn!(error = %e, "failed to release idempotency key");
+            }
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let stored = ResponseDto {
+        status: parts.status.as_u16(),
+        content_type: parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+        body: body.to_vec(),
+    };
+    // 保存に失敗しても処理自体は済んでいるので、レスポンスはそのまま返す
+    if let Err(e) = usecase.complete(claim, stored).await {
+        tracing::warn!(error = %e, "failed to store idempotent response");
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn replay(stored: ResponseDto) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, Bytes::from(stored.body)).into_response();
+    let headers = response.headers_mut();
+    headers.remove(header::CONTENT_TYPE);
+    if let Some(value) = stored
+        .content_type
+        .and_then(|v| HeaderValue::from_str(&v).ok())
+    {
+        headers.insert(header::CONTENT_TYPE, value);
+    }
+    headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
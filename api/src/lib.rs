@@ -1,54 +1,162 @@
+pub mod api_key;
+pub mod auth;
 pub mod book;
+pub mod config;
 pub mod error;
+pub mod event;
+pub mod export;
+pub mod graphql;
+pub mod health;
+mod idempotency;
+pub mod metrics;
+mod middleware;
+pub mod outbox;
 pub mod publisher;
+pub mod query;
+pub mod rate_limit;
 pub mod shop;
+mod telemetry;
+pub mod webhook;
 
 use axum::Router;
+pub use config::HttpConfig;
 use std::sync::Arc;
-use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 use utoipa_axum::{router::OpenApiRouter, routes};
 use utoipa_swagger_ui::SwaggerUi;
 
 #[derive(OpenApi)]
-#[openapi(info(title = "Rust Web App", version = "0.1.0"))]
+#[openapi(
+    info(title = "Rust Web App", version = "0.1.0"),
+    modifiers(&SecurityAddon)
+)]
 pub struct ApiDoc;
 
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "Authorization",
+                "ApiKey {secret}",
+            ))),
+        );
+    }
+}
+
 pub struct AppState {
+    pub api_key_usecase: usecase::api_key::Service,
     pub book_usecase: usecase::book::Service,
     pub publisher_usecase: usecase::publisher::Service,
     pub shop_usecase: usecase::shop::Service,
+    pub idempotency_usecase: usecase::idempotency::Service,
+    /// book / publisher / shop の各サービスと共有する
+    pub change_event_usecase: Arc<usecase::change_event::Service>,
+    pub outbox_usecase: usecase::outbox::Service,
+    /// outbox の中継先 (Fanout) と共有する
+    pub webhook_usecase: Arc<usecase::webhook::Service>,
+    /// /readyz で確認する依存先
+    pub readiness_checks: Vec<Arc<dyn health::Check>>,
 }
 
-pub fn create_router(state: Arc<AppState>) -> Router {
-    let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
+pub fn create_router(state: Arc<AppState>, config: &HttpConfig) -> Router {
+    create_router_with_store(state, config, Arc::new(rate_limit::MemoryStore::new()))
+}
+
+/// レート制限のバケットを rate_limit_store に持たせる
+pub fn create_router_with_store(
+    state: Arc<AppState>,
+    config: &HttpConfig,
+    rate_limit_store: Arc<dyn rate_limit::Store>,
+) -> Router {
+    metrics::init();
+    let (mut router, api) = api_router().split_for_parts();
+    router = router.route_layer(axum::middleware::from_fn_with_state(
+        idempotency::Layer {
+            state: state.clone(),
+            max_body_bytes: config.max_body_bytes,
+        },
+        idempotency::handle,
+    ));
+    router = router.route_layer(axum::middleware::from_fn_with_state(
+        auth::Auth {
+            state: state.clone(),
+            allow_anonymous: config.auth.allow_anonymous,
+        },
+        auth::handle,
+    ));
+    // 制限を超えたリクエストはスコープの確認や Idempotency-Key の記録より前に断る
+    if config.rate_limit.enabled {
+        router = router.route_layer(axum::middleware::from_fn_with_state(
+            rate_limit::RateLimiter {
+                store: rate_limit_store,
+                config: config.rate_limit.clone(),
+            },
+            rate_limit::handle,
+        ));
+    }
+    // 認証済みの利用者ごとに数えるため、レート制限より先に資格情報を検証する
+    router = router.route_layer(axum::middleware::from_fn_with_state(
+        auth::Auth {
+            state: state.clone(),
+            allow_anonymous: config.auth.allow_anonymous,
+        },
+        auth::identify,
+    ));
+
+    if config.swagger_ui {
+        router = router.merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api));
+    }
+    router = router
+        .layer(axum::middleware::from_fn(metrics::track))
+        // ヘルスチェックとメトリクスは API の認証などの対象外にするため最後に足す
+        .merge(health::router())
+        .merge(metrics::router());
+    middleware::apply(router, config).with_state(state)
+}
+
+/// 全エンドポイントの OpenAPI 定義
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    api_router().split_for_parts().1
+}
+
+fn api_router() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::with_openapi(ApiDoc::openapi())
         // routes!はPath毎に分ける必要あり
         .routes(routes!(book::get_all, book::create))
         .routes(routes!(book::get, book::update, book::delete))
         .routes(routes!(book::change_applied_at))
+        .routes(routes!(book::restore))
+        .routes(routes!(book::batch))
+        .routes(routes!(book::import))
+        .routes(routes!(book::search))
         .routes(routes!(publisher::get_all, publisher::create))
         .routes(routes!(
             publisher::get,
             publisher::update,
             publisher::delete
         ))
+        .routes(routes!(publisher::restore))
         .routes(routes!(shop::get_all_shops, shop::create_shop))
         .routes(routes!(
             shop::get_shop,
             shop::update_shop,
             shop::delete_shop
         ))
-        .split_for_parts();
-
-    #[cfg(debug_assertions)]
-    {
-        let openapi_json = api
-            .to_pretty_json()
-            .expect("Failed to generate OpenAPI JSON");
-        std::fs::write("openapi.json", openapi_json).expect("Failed to write openapi.json");
-    }
-
-    router
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api))
-        .layer(tower_http::trace::TraceLayer::new_for_http())
-        .with_state(state)
+        .routes(routes!(shop::restore_shop))
+        .routes(routes!(webhook::get_all, webhook::create))
+        .routes(routes!(webhook::get, webhook::delete))
+        .routes(routes!(webhook::deliveries))
+        .routes(routes!(api_key::get_all, api_key::issue))
+        .routes(routes!(api_key::revoke))
+        .routes(routes!(event::stream))
+        .routes(routes!(outbox::get_all))
+        .routes(routes!(outbox::get))
+        .routes(routes!(outbox::retry))
+        // GraphQL は OpenAPI の定義に含めない
+        .route("/graphql", graphql::route())
 }
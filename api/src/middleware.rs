@@ -0,0 +1,284 @@
+use crate::HttpConfig;
+use crate::export::Format;
+use crate::telemetry;
+use axum::Router;
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::time::Duration;
+use tower::ServiceBuilder;
+use tower_http::compression::CompressionLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+
+/// 全てのルートに共通のミドルウェアを掛ける。上に書いたものほど外側で動く
+pub(crate) fn apply<S>(router: Router<S>, config: &HttpConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router.layer(
+        ServiceBuilder::new()
+            // 受け取った X-Request-Id はそのまま使い、無ければ作る
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(TraceLayer::new_for_http().make_span_with(telemetry::make_span))
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .option_layer(config.cors_layer())
+            .layer(axum::middleware::from_fn_with_state(
+                config.request_timeout(),
+                timeout,
+            ))
+            // 無効の時もレスポンスの型を揃えるため、層は残して全ての方式を切る
+            .layer(
+                CompressionLayer::new()
+                    .gzip(config.compression)
+                    .br(config.compression),
+            )
+            .layer(DefaultBodyLimit::max(config.max_body_bytes)),
+    )
+}
+
+/// 時間内に応答できなければ 408 を返す。流し続けるリクエストは対象外
+async fn timeout(State(duration): State<Duration>, request: Request, next: Next) -> Response {
+    if is_streaming(&request) {
+        return next.run(request).await;
+    }
+    match tokio::time::timeout(duration, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => StatusCode::REQUEST_TIMEOUT.into_response(),
+    }
+}
+
+/// CSV の取り込み、一覧の書き出し、変更フィードは大きさや接続時間に上限がない
+fn is_streaming(request: &Request) -> bool {
+    let path = request.uri().path();
+    match *request.method() {
+        Method::POST => path == "/imports/books",
+        Method::GET => {
+            path == "/events"
+                || (path == "/books" && Format::from_headers(request.headers()) != Format::Json)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, Bytes};
+    use axum::http::{Request, StatusCode, header};
+    use axum::routing::{get, post};
+    use http_body_util::BodyExt;
+    use rstest::rstest;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn slow() -> &'static str {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        "done"
+    }
+
+    fn router(config: &HttpConfig) -> Router {
+        let routes = Router::new()
+            .route("/text", get(|| async { "a".repeat(4096) }))
+            .route("/echo", post(|body: Bytes| async move { body }))
+            .route("/slow", get(slow))
+            .route("/books", get(slow))
+            .route("/events", get(slow))
+            .route("/imports/books", post(slow));
+        apply(routes, config)
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_generated_and_propagated() {
+        let router = router(&HttpConfig::default());
+
+        let response = router
+            .clone()
+            .oneshot(Request::get("/text").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let generated = response.headers()["x-request-id"].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(generated).is_ok());
+
+        let response = router
+            .oneshot(
+                Request::get("/text")
+                    .header("x-request-id", "abc-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["x-request-id"], "abc-123");
+    }
+
+    #[rstest]
+    #[case("https://allowed.example", "PUT", true)]
+    #[case("https://allowed.example", "DELETE", false)]
+    #[case("https://other.example", "PUT", false)]
+    #[tokio::test]
+    async fn test_cors_allow_list(
+        #[case] origin: &str,
+        #[case] method: &str,
+        #[case] allowed: bool,
+    ) {
+        let router = router(&HttpConfig {
+            cors_origins: vec!["https://allowed.example".to_string()],
+            cors_methods: vec!["GET".to_string(), "PUT".to_string()],
+            ..Default::default()
+        });
+
+        let response = router
+            .oneshot(
+                Request::options("/text")
+                    .header(header::ORIGIN, origin)
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, method)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let headers = response.headers();
+        let origin_allowed = headers
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_some_and(|v| v == origin);
+        let method_allowed = headers
+            .get(header::ACCESS_CONTROL_ALLOW_METHODS)
+            .is_some_and(|v| v.to_str().unwrap().split(',').any(|m| m.trim() == method));
+        assert_eq!(origin_allowed && method_allowed, allowed);
+    }
+
+    #[tokio::test]
+    async fn test_cors_is_disabled_without_origins() {
+        let response = router(&HttpConfig::default())
+            .oneshot(
+                Request::get("/text")
+                    .header(header::ORIGIN, "https://allowed.example")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(
+            !response
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+
+    #[rstest]
+    #[case(true, "br", Some("br"))]
+    #[case(true, "gzip", Some("gzip"))]
+    #[case(true, "identity", None)]
+    #[case(false, "gzip", None)]
+    #[tokio::test]
+    async fn test_compression(
+        #[case] compression: bool,
+        #[case] accept: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let router = router(&HttpConfig {
+            compression,
+            ..Default::default()
+        });
+        let response = router
+            .oneshot(
+                Request::get("/text")
+                    .header(header::ACCEPT_ENCODING, accept)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .map(|v| v.to_str().unwrap()),
+            expected
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body.len() < 4096, expected.is_some());
+    }
+
+    #[rstest]
+    #[case(16, StatusCode::OK)]
+    #[case(17, StatusCode::PAYLOAD_TOO_LARGE)]
+    #[tokio::test]
+    async fn test_body_limit(#[case] size: usize, #[case] expected: StatusCode) {
+        let router = router(&HttpConfig {
+            max_body_bytes: 16,
+            ..Default::default()
+        });
+        let response = router
+            .oneshot(
+                Request::post("/echo")
+                    .body(Body::from(vec![b'a'; size]))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_request_timeout() {
+        let router = router(&HttpConfig {
+            request_timeout_secs: 1,
+            ..Default::default()
+        });
+        let response = router
+            .oneshot(Request::get("/slow").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+        // タイムアウトしても X-Request-Id は返す
+        assert!(response.headers().contains_key("x-request-id"));
+    }
+
+    #[rstest]
+    #[case("GET", "/books", "application/json", StatusCode::REQUEST_TIMEOUT)]
+    #[case("GET", "/books", "text/csv", StatusCode::OK)]
+    #[case("GET", "/books", "application/x-ndjson", StatusCode::OK)]
+    #[case("GET", "/events", "text/event-stream", StatusCode::OK)]
+    #[case("POST", "/imports/books", "application/json", StatusCode::OK)]
+    #[tokio::test(start_paused = true)]
+    async fn test_streaming_routes_are_not_timed_out(
+        #[case] method: &str,
+        #[case] path: &str,
+        #[case] accept: &str,
+        #[case] expected: StatusCode,
+    ) {
+        let router = router(&HttpConfig {
+            request_timeout_secs: 1,
+            ..Default::default()
+        });
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(path)
+                    .header(header::ACCEPT, accept)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = HttpConfig {
+            cors_origins: vec!["example.com".to_string(), "*".to_string()],
+            cors_methods: vec!["GET".to_string(), "NOT A METHOD".to_string()],
+            max_body_bytes: 0,
+            request_timeout_secs: 0,
+            ..Default::default()
+        };
+        let errors = config.validate();
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(HttpConfig::default().validate().is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::AppState;
+use crate::error::AppError;
+use crate::query::OutboxFilter;
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+
+#[utoipa::path(
+    get,
+    path = "/admin/outbox",
+    tag = "Outbox",
+    operation_id = "get_all_outbox_messages",
+    security(("api_key" = ["admin"])),
+    params(OutboxFilter),
+    responses(
+        (status = 200, description = "List outbox messages, newest first", body = [usecase::outbox::MessageDto]),
+        (status = 400, description = "Unknown status"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The admin scope is required")
+    )
+)]
+pub async fn get_all(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<OutboxFilter>,
+) -> impl IntoResponse {
+    match state
+        .outbox_usecase
+        .get_all(filter.status.as_deref(), filter.limit)
+        .await
+    {
+        Ok(messages) => (StatusCode::OK, Json(messages)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/outbox/{id}",
+    tag = "Outbox",
+    operation_id = "get_outbox_message",
+    security(("api_key" = ["admin"])),
+    params(
+        ("id" = i64, Path, description = "Outbox message id")
+    ),
+    responses(
+        (status = 200, description = "Outbox message with its delivery state", body = usecase::outbox::MessageDto),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The admin scope is required"),
+        (status = 404, description = "Outbox message not found")
+    )
+)]
+pub async fn get(State(state): State<Arc<AppState>>, Path(id): Path<i64>) -> impl IntoResponse {
+    match state.outbox_usecase.get(id).await {
+        Ok(message) => (StatusCode::OK, Json(message)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/outbox/{id}/retry",
+    tag = "Outbox",
+    operation_id = "retry_outbox_message",
+    security(("api_key" = ["admin"])),
+    params(
+        ("id" = i64, Path, description = "Outbox message id")
+    ),
+    responses(
+        (status = 200, description = "Message scheduled for immediate delivery with its attempts reset", body = usecase::outbox::MessageDto),
+        (status = 400, description = "Message is already delivered"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The admin scope is required"),
+        (status = 404, description = "Outbox message not found")
+    )
+)]
+pub async fn retry(State(state): State<Arc<AppState>>, Path(id): Path<i64>) -> impl IntoResponse {
+    match state.outbox_usecase.retry(id).await {
+        Ok(message) => (StatusCode::OK, Json(message)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
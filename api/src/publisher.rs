@@ -1,8 +1,10 @@
 use crate::AppState;
+use crate::auth::Caller;
 use crate::error::AppError;
+use crate::query::{DeletedFilter, IdempotencyHeader};
 use axum::{
-    Json,
-    extract::{Path, State},
+    Extension, Json,
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
 };
@@ -13,12 +15,26 @@ use std::sync::Arc;
     path = "/publishers",
     tag = "Publisher",
     operation_id = "get_all_publishers",
+    params(DeletedFilter),
     responses(
-        (status = 200, description = "List all publishers", body = [usecase::publisher::ResponseDto])
+        (status = 200, description = "List all publishers", body = [usecase::publisher::ResponseDto]),
+        (status = 401, description = "include_deleted was requested without an API key"),
+        (status = 403, description = "include_deleted requires the admin scope")
     )
 )]
-pub async fn get_all(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    match state.publisher_usecase.get_all().await {
+pub async fn get_all(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<DeletedFilter>,
+    Extension(caller): Extension<Caller>,
+) -> impl IntoResponse {
+    if let Err(denied) = caller.authorize_include_deleted(filter.include_deleted) {
+        return denied.into_response();
+    }
+    match state
+        .publisher_usecase
+        .get_all(filter.include_deleted)
+        .await
+    {
         Ok(publishers) => (StatusCode::OK, Json(publishers)).into_response(),
         Err(e) => AppError(e).into_response(),
     }
@@ -31,17 +47,29 @@ pub async fn get_all(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     operation_id = "get_publisher",
     responses(
         (status = 200, description = "Get publisher by pub_id", body = usecase::publisher::ResponseDto),
-        (status = 404, description = "Publisher not found")
+        (status = 404, description = "Publisher not found"),
+        (status = 401, description = "include_deleted was requested without an API key"),
+        (status = 403, description = "include_deleted requires the admin scope")
     ),
     params(
-        ("pub_id" = uuid::Uuid, Path, description = "Publisher pub_id")
+        ("pub_id" = uuid::Uuid, Path, description = "Publisher pub_id"),
+        DeletedFilter
     )
 )]
 pub async fn get(
     State(state): State<Arc<AppState>>,
     Path(pub_id): Path<uuid::Uuid>,
+    Query(filter): Query<DeletedFilter>,
+    Extension(caller): Extension<Caller>,
 ) -> impl IntoResponse {
-    match state.publisher_usecase.get(pub_id).await {
+    if let Err(denied) = caller.authorize_include_deleted(filter.include_deleted) {
+        return denied.into_response();
+    }
+    match state
+        .publisher_usecase
+        .get(pub_id, filter.include_deleted)
+        .await
+    {
         Ok(publisher) => (StatusCode::OK, Json(publisher)).into_response(),
         Err(e) => AppError(e).into_response(),
     }
@@ -53,8 +81,11 @@ pub async fn get(
     tag = "Publisher",
     operation_id = "create_publisher",
     request_body =  usecase::publisher::CreateDto,
+    params(IdempotencyHeader),
     responses(
-        (status = 201, description = "Publisher created successfully", body = usecase::publisher::ResponseDto)
+        (status = 201, description = "Publisher created successfully", body = usecase::publisher::ResponseDto),
+        (status = 409, description = "A request with the same Idempotency-Key is in progress"),
+        (status = 422, description = "Idempotency-Key was reused with a different request")
     )
 )]
 pub async fn create(
@@ -99,6 +130,7 @@ pub async fn update(
     operation_id = "delete_publisher",
     responses(
         (status = 204, description = "Publisher deleted successfully"),
+        (status = 400, description = "Publisher still has books"),
         (status = 404, description = "Publisher not found")
     ),
     params(
@@ -114,3 +146,27 @@ pub async fn delete(
         Err(e) => AppError(e).into_response(),
     }
 }
+
+#[utoipa::path(
+    post,
+    path = "/publishers/{pub_id}/restore",
+    tag = "Publisher",
+    operation_id = "restore_publisher",
+    responses(
+        (status = 200, description = "Publisher restored successfully", body = usecase::publisher::ResponseDto),
+        (status = 400, description = "Publisher is not deleted"),
+        (status = 404, description = "Publisher not found")
+    ),
+    params(
+        ("pub_id" = uuid::Uuid, Path, description = "Publisher pub_id")
+    )
+)]
+pub async fn restore(
+    State(state): State<Arc<AppState>>,
+    Path(pub_id): Path<uuid::Uuid>,
+) -> impl IntoResponse {
+    match state.publisher_usecase.restore(pub_id).await {
+        Ok(publisher) => (StatusCode::OK, Json(publisher)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeletedFilter {
+    /// 論理削除済みのレコードも含める。admin スコープが必要
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportOptions {
+    /// 検証のみ行い、書き込みはしない
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchParams {
+    /// 検索語。空白 (全角スペースを含む) で区切ると AND 検索になる
+    pub q: String,
+    /// 1 始まり
+    #[serde(default = "default_page")]
+    #[param(default = 1)]
+    pub page: u64,
+    #[serde(default = "default_per_page")]
+    #[param(default = 20, maximum = 100)]
+    pub per_page: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_per_page() -> u64 {
+    20
+}
+
+/// 作成系エンドポイント共通のヘッダー。実際の処理は idempotency ミドルウェアが行う
+#[derive(Debug, Default, IntoParams)]
+#[into_params(parameter_in = Header)]
+pub struct IdempotencyHeader {
+    /// 同じ値と内容で再試行すると、保存した最初のレスポンスを返す
+    #[param(rename = "Idempotency-Key", max_length = 255)]
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventFilter {
+    /// 流すリソースの種類をカンマ区切りで指定する (book / publisher / shop)。省略すると全て
+    #[param(example = "book,publisher")]
+    pub types: Option<String>,
+}
+
+impl EventFilter {
+    pub fn resource_types(&self) -> Vec<String> {
+        self.types
+            .iter()
+            .flat_map(|t| t.split(','))
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// 変更フィードの再開位置。ブラウザの EventSource は再接続時に自動で送る
+#[derive(Debug, Default, IntoParams)]
+#[into_params(parameter_in = Header)]
+pub struct LastEventIdHeader {
+    /// 最後に受け取ったイベントの id。これより後のイベントから流す
+    #[param(rename = "Last-Event-ID")]
+    pub last_event_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OutboxFilter {
+    /// pending / delivered / dead のいずれか。省略すると全て
+    #[param(example = "dead")]
+    pub status: Option<String>,
+    #[serde(default = "default_outbox_limit")]
+    #[param(default = 100, maximum = 500)]
+    pub limit: u64,
+}
+
+fn default_outbox_limit() -> u64 {
+    100
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveryFilter {
+    /// pending / delivered / dead のいずれか。省略すると全て
+    #[param(example = "dead")]
+    pub status: Option<String>,
+    #[serde(default = "default_delivery_limit")]
+    #[param(default = 100, maximum = 500)]
+    pub limit: u64,
+}
+
+fn default_delivery_limit() -> u64 {
+    100
+}
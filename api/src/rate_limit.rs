@@ -0,0 +1,362 @@
+use crate::auth::Principal;
+use crate::config::{RateLimit, RateLimitConfig};
+use async_trait::async_trait;
+use axum::Json;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// この数を超えたら、満タンに戻ったバケットを捨てる
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// バケットを保持する場所。複数のプロセスで共有する場合は Redis などで実装する
+#[async_trait]
+pub trait Store: Send + Sync {
+    /// key のバケットからトークンを 1 つ取り出す
+    async fn acquire(
+        &self,
+        key: &str,
+        limit: RateLimit,
+    ) -> Result<Decision, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// バケットが満タンに戻るまで
+    pub reset: Duration,
+    /// 次のトークンが補充されるまで。受け付けた場合は 0
+    pub retry_after: Duration,
+}
+
+/// プロセス内でバケットを持つ Store
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    full_at: Instant,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Store for MemoryStore {
+    async fn acquire(
+        &self,
+        key: &str,
+        limit: RateLimit,
+    ) -> Result<Decision, Box<dyn std::error::Error + Send + Sync>> {
+        let now = Instant::now();
+        let capacity = f64::from(limit.burst);
+        let rate = f64::from(limit.per_minute) / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_IDLE_BUCKETS && !buckets.contains_key(key) {
+            buckets.retain(|_, b| b.full_at > now);
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+            full_at: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let reset = Duration::from_secs_f64((capacity - bucket.tokens) / rate);
+        bucket.full_at = now + reset;
+        let retry_after = if allowed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / rate)
+        };
+        Ok(Decision {
+            allowed,
+            limit: limit.burst,
+            remaining: bucket.tokens.floor() as u32,
+            reset,
+            retry_after,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RateLimiter {
+    pub(crate) store: Arc<dyn Store>,
+    pub(crate) config: RateLimitConfig,
+}
+
+/// 利用者ごと・読み書きごとにトークンバケットで制限し、RateLimit-* ヘッダーで残りを知らせる
+pub(crate) async fn handle(
+    State(limiter): State<RateLimiter>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (group, limit) = match *request.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => ("read", limiter.config.read),
+        _ => ("write", limiter.config.write),
+    };
+    let key = format!(
+        "{}:{}",
+        group,
+        client(&request, limiter.config.trust_forwarded_for)
+    );
+
+    // 制限の判定に失敗しても API 自体は止めない
+    let decision = match limiter.store.acquire(&key, limit).await {
+        Ok(decision) => decision,
+        Err(e) => {
+            tracing::warn!(error = %e, "rate limit store failed");
+            return next.run(request).await;
+        }
+    };
+    if !decision.allowed {
+        tracing::debug!(key, "rate limited");
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({ "error": "Too many requests" })),
+        )
+            .into_response();
+        set_headers(response.headers_mut(), &decision);
+        response.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(ceil_secs(decision.retry_after)),
+        );
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    set_headers(response.headers_mut(), &decision);
+    response
+}
+
+/// 認証済みの利用者、IP の順に、数える単位を決める。
+/// 資格情報が無い、または検証に失敗したリクエストは IP で数える
+fn client(request: &Request, trust_forwarded_for: bool) -> String {
+    if let Some(principal) = request.extensions().get::<Principal>() {
+        return format!("principal:{}", principal.subject);
+    }
+    let forwarded = request
+        .headers()
+        .get("x-forwarded-for")
+        .filter(|_| trust_forwarded_for)
+        .and_then(|v| v.to_str().ok())
+        // 先頭側は利用者が自由に書けるので、手前のプロキシが付け足した末尾を使う
+        .and_then(|v| v.rsplit(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty());
+    let connected = || {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+    };
+    match forwarded.or_else(connected) {
+        Some(ip) => format!("ip:{}", ip),
+        None => "unknown".to_string(),
+    }
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert(
+        "ratelimit-reset",
+        HeaderValue::from(ceil_secs(decision.reset)),
+    );
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs_f64().ceil() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::routing::get;
+    use rstest::rstest;
+    use tower::ServiceExt;
+
+    const LIMIT: RateLimit = RateLimit {
+        burst: 2,
+        per_minute: 60,
+    };
+
+    fn router(trust_forwarded_for: bool) -> Router {
+        let limiter = RateLimiter {
+            store: Arc::new(MemoryStore::new()),
+            config: RateLimitConfig {
+                read: LIMIT,
+                write: RateLimit {
+                    burst: 1,
+                    per_minute: 60,
+                },
+                trust_forwarded_for,
+                ..Default::default()
+            },
+        };
+        Router::new()
+            .route("/books", get(|| async { "ok" }).post(|| async { "ok" }))
+            .route_layer(axum::middleware::from_fn_with_state(limiter, handle))
+            .route_layer(axum::middleware::from_fn(identify))
+    }
+
+    /// auth::identify の代わりに `ApiKey {name}` だけを認証済みとして扱う
+    async fn identify(mut request: Request, next: Next) -> Response {
+        let subject = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("ApiKey "))
+            .map(|name| format!("api_key:{}", name));
+        if let Some(subject) = subject {
+            request.extensions_mut().insert(Principal {
+                subject,
+                scopes: vec![],
+            });
+        }
+        next.run(request).await
+    }
+
+    fn request(method: Method, headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder().method(method).uri("/books");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_memory_store_refills() {
+        let store = MemoryStore::new();
+        let first = store.acquire("a", LIMIT).await.unwrap();
+        assert!(first.allowed);
+        assert_eq!(first.remaining, 1);
+        assert_eq!(first.reset, Duration::from_secs(1));
+
+        assert!(store.acquire("a", LIMIT).await.unwrap().allowed);
+        let denied = store.acquire("a", LIMIT).await.unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(denied.remaining, 0);
+        assert_eq!(denied.retry_after, Duration::from_secs(1));
+        // 別のキーは別のバケット
+        assert!(store.acquire("b", LIMIT).await.unwrap().allowed);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(store.acquire("a", LIMIT).await.unwrap().allowed);
+        assert!(!store.acquire("a", LIMIT).await.unwrap().allowed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rejects_with_429_and_headers() {
+        let router = router(false);
+        let send = || router.clone().oneshot(request(Method::GET, &[]));
+
+        let response = send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["ratelimit-limit"], "2");
+        assert_eq!(response.headers()["ratelimit-remaining"], "1");
+        assert_eq!(response.headers()["ratelimit-reset"], "1");
+        assert_eq!(send().await.unwrap().status(), StatusCode::OK);
+
+        let response = send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["ratelimit-remaining"], "0");
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(send().await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reads_and_writes_are_limited_separately() {
+        let router = router(false);
+        let send = |method: Method| router.clone().oneshot(request(method, &[]));
+
+        assert_eq!(send(Method::POST).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            send(Method::POST).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(send(Method::GET).await.unwrap().status(), StatusCode::OK);
+    }
+
+    // 信頼しない設定では X-Forwarded-For を無視するので、同じ利用者として数える。
+    // 信頼する設定でも、利用者が書ける先頭の値を変えただけでは別の利用者にならない。
+    // 検証に失敗する資格情報は毎回変えても IP で数える
+    #[rstest]
+    #[case(&[("authorization", "ApiKey a")], &[("authorization", "ApiKey b")], false, false)]
+    #[case(&[("authorization", "ApiKey a")], &[("authorization", "ApiKey a")], false, true)]
+    #[case(&[("authorization", "Bogus a")], &[("authorization", "Bogus b")], false, true)]
+    #[case(&[("x-forwarded-for", "10.0.0.1")], &[("x-forwarded-for", "10.0.0.2")], false, true)]
+    #[case(&[("x-forwarded-for", "10.0.0.1")], &[("x-forwarded-for", "10.0.0.2")], true, false)]
+    #[case(
+        &[("x-forwarded-for", "1.1.1.1, 10.0.0.1")],
+        &[("x-forwarded-for", "2.2.2.2, 10.0.0.1")],
+        true,
+        true
+    )]
+    #[tokio::test(start_paused = true)]
+    async fn test_clients_are_limited_separately(
+        #[case] first: &[(&str, &str)],
+        #[case] second: &[(&str, &str)],
+        #[case] trust_forwarded_for: bool,
+        #[case] shared: bool,
+    ) {
+        let router = router(trust_forwarded_for);
+        // 書き込みは 1 回で使い切る
+        let response = router
+            .clone()
+            .oneshot(request(Method::POST, first))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.oneshot(request(Method::POST, second)).await.unwrap();
+        assert_eq!(response.status() == StatusCode::TOO_MANY_REQUESTS, shared);
+    }
+
+    #[test]
+    fn test_client_key() {
+        let mut request = request(Method::GET, &[("x-forwarded-for", "10.0.0.1, 10.0.0.2")]);
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([192, 168, 0, 1], 5000))));
+        // X-Forwarded-For は信頼する設定の時だけ使う
+        assert_eq!(client(&request, false), "ip:192.168.0.1");
+        assert_eq!(client(&request, true), "ip:10.0.0.2");
+
+        // 資格情報そのものではなく、認証済みの利用者で区別する
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, HeaderValue::from_static("ApiKey a"));
+        assert_eq!(client(&request, true), "ip:10.0.0.2");
+        request.extensions_mut().insert(Principal {
+            subject: "api_key:a".to_string(),
+            scopes: vec![],
+        });
+        assert_eq!(client(&request, true), "principal:api_key:a");
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
-    Json,
-    extract::{Path, State},
+    Extension, Json,
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
 };
@@ -8,47 +8,57 @@ use std::sync::Arc;
 use usecase::shop::{CreateDto, ResponseDto, UpdateDto};
 
 use crate::AppState;
+use crate::auth::Caller;
+use crate::error::AppError;
+use crate::query::{DeletedFilter, IdempotencyHeader};
 
 #[utoipa::path(
     post,
     path = "/shops",
     tag = "Shop",
     request_body = CreateDto,
+    params(IdempotencyHeader),
     responses(
         (status = 201, description = "Shop created successfully", body = ResponseDto),
+        (status = 409, description = "A request with the same Idempotency-Key is in progress"),
+        (status = 422, description = "Idempotency-Key was reused with a different request"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn create_shop(
     State(state): State<Arc<AppState>>,
     Json(dto): Json<CreateDto>,
-) -> Result<impl IntoResponse, StatusCode> {
-    state
-        .shop_usecase
-        .create(dto)
-        .await
-        .map(|dto| (StatusCode::CREATED, Json(dto)))
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+) -> impl IntoResponse {
+    match state.shop_usecase.create(dto).await {
+        Ok(shop) => (StatusCode::CREATED, Json(shop)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
 }
 
 #[utoipa::path(
     get,
     path = "/shops",
     tag = "Shop",
+    params(DeletedFilter),
     responses(
         (status = 200, description = "List of all shops", body = [ResponseDto]),
+        (status = 401, description = "include_deleted was requested without an API key"),
+        (status = 403, description = "include_deleted requires the admin scope"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn get_all_shops(
     State(state): State<Arc<AppState>>,
-) -> Result<impl IntoResponse, StatusCode> {
-    state
-        .shop_usecase
-        .get_all()
-        .await
-        .map(Json)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    Query(filter): Query<DeletedFilter>,
+    Extension(caller): Extension<Caller>,
+) -> impl IntoResponse {
+    if let Err(denied) = caller.authorize_include_deleted(filter.include_deleted) {
+        return denied.into_response();
+    }
+    match state.shop_usecase.get_all(filter.include_deleted).await {
+        Ok(shops) => (StatusCode::OK, Json(shops)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
 }
 
 #[utoipa::path(
@@ -56,27 +66,30 @@ pub async fn get_all_shops(
     path = "/shops/{pub_id}",
     tag = "Shop",
     params(
-        ("pub_id" = Uuid, Path, description = "Shop ID")
+        ("pub_id" = Uuid, Path, description = "Shop ID"),
+        DeletedFilter
     ),
     responses(
         (status = 200, description = "Shop found", body = ResponseDto),
         (status = 404, description = "Shop not found"),
+        (status = 401, description = "include_deleted was requested without an API key"),
+        (status = 403, description = "include_deleted requires the admin scope"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn get_shop(
     State(state): State<Arc<AppState>>,
     Path(pub_id): Path<uuid::Uuid>,
-) -> Result<impl IntoResponse, StatusCode> {
-    state
-        .shop_usecase
-        .get(pub_id)
-        .await
-        .map(Json)
-        .map_err(|e| match e {
-            usecase::error::UseCaseError::NotFound(_) => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        })
+    Query(filter): Query<DeletedFilter>,
+    Extension(caller): Extension<Caller>,
+) -> impl IntoResponse {
+    if let Err(denied) = caller.authorize_include_deleted(filter.include_deleted) {
+        return denied.into_response();
+    }
+    match state.shop_usecase.get(pub_id, filter.include_deleted).await {
+        Ok(shop) => (StatusCode::OK, Json(shop)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
 }
 
 #[utoipa::path(
@@ -97,16 +110,11 @@ pub async fn update_shop(
     State(state): State<Arc<AppState>>,
     Path(pub_id): Path<uuid::Uuid>,
     Json(dto): Json<UpdateDto>,
-) -> Result<impl IntoResponse, StatusCode> {
-    state
-        .shop_usecase
-        .update(pub_id, dto)
-        .await
-        .map(|dto| (StatusCode::OK, Json(dto)))
-        .map_err(|e| match e {
-            usecase::error::UseCaseError::NotFound(_) => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        })
+) -> impl IntoResponse {
+    match state.shop_usecase.update(pub_id, dto).await {
+        Ok(shop) => (StatusCode::OK, Json(shop)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
 }
 
 #[utoipa::path(
@@ -118,6 +126,7 @@ pub async fn update_shop(
     ),
     responses(
         (status = 204, description = "Shop deleted successfully"),
+        (status = 400, description = "Shop still has books"),
         (status = 404, description = "Shop not found"),
         (status = 500, description = "Internal server error")
     )
@@ -125,14 +134,33 @@ pub async fn update_shop(
 pub async fn delete_shop(
     State(state): State<Arc<AppState>>,
     Path(pub_id): Path<uuid::Uuid>,
-) -> Result<impl IntoResponse, StatusCode> {
-    state
-        .shop_usecase
-        .delete(pub_id)
-        .await
-        .map(|_| StatusCode::NO_CONTENT)
-        .map_err(|e| match e {
-            usecase::error::UseCaseError::NotFound(_) => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        })
+) -> impl IntoResponse {
+    match state.shop_usecase.delete(pub_id).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/shops/{pub_id}/restore",
+    tag = "Shop",
+    params(
+        ("pub_id" = Uuid, Path, description = "Shop ID")
+    ),
+    responses(
+        (status = 200, description = "Shop restored successfully", body = ResponseDto),
+        (status = 400, description = "Shop is not deleted"),
+        (status = 404, description = "Shop not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn restore_shop(
+    State(state): State<Arc<AppState>>,
+    Path(pub_id): Path<uuid::Uuid>,
+) -> impl IntoResponse {
+    match state.shop_usecase.restore(pub_id).await {
+        Ok(shop) => (StatusCode::OK, Json(shop)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
 }
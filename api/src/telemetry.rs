@@ -0,0 +1,25 @@
+use axum::extract::Request;
+use opentelemetry_http::HeaderExtractor;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// リクエストのスパンを作る。W3C traceparent ヘッダーがあれば呼び出し元のトレースに繋げる
+pub(crate) fn make_span(request: &Request) -> Span {
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        request_id = request
+            .headers()
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default(),
+    );
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    // OpenTelemetry のレイヤーが無い (OTLP を設定していない) 場合は失敗するが、その時は繋ぐ先も無い
+    let _ = span.set_parent(parent);
+    span
+}
@@ -0,0 +1,141 @@
+use crate::AppState;
+use crate::error::AppError;
+use crate::query::{DeliveryFilter, IdempotencyHeader};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "Webhook",
+    operation_id = "create_webhook",
+    request_body = usecase::webhook::CreateDto,
+    security(("api_key" = ["webhooks:write"])),
+    params(IdempotencyHeader),
+    responses(
+        (status = 201, description = "Webhook subscribed. Deliveries are signed with the secret in the X-Webhook-Signature header", body = usecase::webhook::ResponseDto),
+        (status = 400, description = "Invalid URL, secret or event types. URLs pointing to loopback, private or link-local addresses are rejected"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The webhooks:write scope is required"),
+        (status = 409, description = "A request with the same Idempotency-Key is in progress"),
+        (status = 422, description = "Idempotency-Key was reused with a different request")
+    )
+)]
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<usecase::webhook::CreateDto>,
+) -> impl IntoResponse {
+    match state.webhook_usecase.create(payload).await {
+        Ok(subscription) => (StatusCode::CREATED, Json(subscription)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "Webhook",
+    operation_id = "get_all_webhooks",
+    security(("api_key" = ["webhooks:read"])),
+    responses(
+        (status = 200, description = "List all webhooks", body = [usecase::webhook::ResponseDto]),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The webhooks:read scope is required")
+    )
+)]
+pub async fn get_all(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.webhook_usecase.get_all().await {
+        Ok(subscriptions) => (StatusCode::OK, Json(subscriptions)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{pub_id}",
+    tag = "Webhook",
+    operation_id = "get_webhook",
+    security(("api_key" = ["webhooks:read"])),
+    params(
+        ("pub_id" = uuid::Uuid, Path, description = "Webhook pub_id")
+    ),
+    responses(
+        (status = 200, description = "Get webhook by pub_id", body = usecase::webhook::ResponseDto),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The webhooks:read scope is required"),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn get(
+    State(state): State<Arc<AppState>>,
+    Path(pub_id): Path<uuid::Uuid>,
+) -> impl IntoResponse {
+    match state.webhook_usecase.get(pub_id).await {
+        Ok(subscription) => (StatusCode::OK, Json(subscription)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{pub_id}",
+    tag = "Webhook",
+    operation_id = "delete_webhook",
+    security(("api_key" = ["webhooks:write"])),
+    params(
+        ("pub_id" = uuid::Uuid, Path, description = "Webhook pub_id")
+    ),
+    responses(
+        (status = 204, description = "Webhook and its delivery log deleted"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The webhooks:write scope is required"),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn delete(
+    State(state): State<Arc<AppState>>,
+    Path(pub_id): Path<uuid::Uuid>,
+) -> impl IntoResponse {
+    match state.webhook_usecase.delete(pub_id).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{pub_id}/deliveries",
+    tag = "Webhook",
+    operation_id = "get_webhook_deliveries",
+    security(("api_key" = ["webhooks:read"])),
+    params(
+        ("pub_id" = uuid::Uuid, Path, description = "Webhook pub_id"),
+        DeliveryFilter
+    ),
+    responses(
+        (status = 200, description = "Delivery log of the webhook, newest first", body = [usecase::webhook::DeliveryDto]),
+        (status = 400, description = "Unknown status"),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 403, description = "The webhooks:read scope is required"),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn deliveries(
+    State(state): State<Arc<AppState>>,
+    Path(pub_id): Path<uuid::Uuid>,
+    Query(filter): Query<DeliveryFilter>,
+) -> impl IntoResponse {
+    match state
+        .webhook_usecase
+        .deliveries(pub_id, filter.status.as_deref(), filter.limit)
+        .await
+    {
+        Ok(deliveries) => (StatusCode::OK, Json(deliveries)).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
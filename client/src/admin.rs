@@ -0,0 +1,43 @@
+//! admin スコープが必要なルート
+use crate::dto::{ApiKeyIssueDto, ApiKeyIssuedDto, ApiKeyResponseDto, OutboxMessageDto};
+use crate::{Client, Result, json};
+use reqwest::Method;
+use uuid::Uuid;
+
+impl Client {
+    pub async fn get_api_keys(&self) -> Result<Vec<ApiKeyResponseDto>> {
+        json(self.request(Method::GET, "/admin/api-keys")).await
+    }
+
+    /// secret はこのレスポンスでしか受け取れない
+    pub async fn issue_api_key(&self, dto: &ApiKeyIssueDto) -> Result<ApiKeyIssuedDto> {
+        json(self.request(Method::POST, "/admin/api-keys").json(dto)).await
+    }
+
+    pub async fn revoke_api_key(&self, pub_id: Uuid) -> Result<ApiKeyResponseDto> {
+        json(self.request(Method::DELETE, &format!("/admin/api-keys/{}", pub_id))).await
+    }
+
+    /// status は pending / delivered / dead のいずれか。None なら全て
+    pub async fn get_outbox_messages(
+        &self,
+        status: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<OutboxMessageDto>> {
+        let mut request = self
+            .request(Method::GET, "/admin/outbox")
+            .query(&[("limit", limit)]);
+        if let Some(status) = status {
+            request = request.query(&[("status", status)]);
+        }
+        json(request).await
+    }
+
+    pub async fn get_outbox_message(&self, id: i64) -> Result<OutboxMessageDto> {
+        json(self.request(Method::GET, &format!("/admin/outbox/{}", id))).await
+    }
+
+    pub async fn retry_outbox_message(&self, id: i64) -> Result<OutboxMessageDto> {
+        json(self.request(Method::POST, &format!("/admin/outbox/{}/retry", id))).await
+    }
+}
@@ -0,0 +1,123 @@
+use crate::dto::{
+    BookBatchDto, BookBatchResponseDto, BookChangeAppliedAtDto, BookCreateDto, BookImportReportDto,
+    BookResponseDto, BookSearchResponseDto, BookUpdateDto,
+};
+use crate::{Client, Result, empty, json, send};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use reqwest::Method;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use uuid::Uuid;
+
+/// GET /books をストリームで受け取る時の形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    NdJson,
+}
+
+impl ExportFormat {
+    fn mime(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::NdJson => "application/x-ndjson",
+        }
+    }
+}
+
+impl Client {
+    pub async fn get_books(&self, include_deleted: bool) -> Result<Vec<BookResponseDto>> {
+        json(
+            self.request(Method::GET, "/books")
+                .query(&[("include_deleted", include_deleted)]),
+        )
+        .await
+    }
+
+    /// 全件を読み込まずに受け取る。本文はサーバーが書いた順のまま流す
+    pub async fn export_books(
+        &self,
+        format: ExportFormat,
+        include_deleted: bool,
+    ) -> Result<BoxStream<'static, Result<bytes::Bytes>>> {
+        let response = send(
+            self.request(Method::GET, "/books")
+                .query(&[("include_deleted", include_deleted)])
+                .header(ACCEPT, format.mime()),
+        )
+        .await?;
+        Ok(response.bytes_stream().err_into().boxed())
+    }
+
+    pub async fn get_book(&self, pub_id: Uuid, include_deleted: bool) -> Result<BookResponseDto> {
+        json(
+            self.request(Method::GET, &format!("/books/{}", pub_id))
+                .query(&[("include_deleted", include_deleted)]),
+        )
+        .await
+    }
+
+    pub async fn create_book(&self, dto: &BookCreateDto) -> Result<BookResponseDto> {
+        json(self.create_request("/books").json(dto)).await
+    }
+
+    pub async fn update_book(&self, pub_id: Uuid, dto: &BookUpdateDto) -> Result<BookResponseDto> {
+        json(
+            self.request(Method::PUT, &format!("/books/{}", pub_id))
+                .json(dto),
+        )
+        .await
+    }
+
+    pub async fn delete_book(&self, pub_id: Uuid) -> Result<()> {
+        empty(self.request(Method::DELETE, &format!("/books/{}", pub_id))).await
+    }
+
+    pub async fn restore_book(&self, pub_id: Uuid) -> Result<BookResponseDto> {
+        json(self.request(Method::POST, &format!("/books/{}/restore", pub_id))).await
+    }
+
+    pub async fn change_book_applied_at(
+        &self,
+        pub_id: Uuid,
+        dto: &BookChangeAppliedAtDto,
+    ) -> Result<BookResponseDto> {
+        json(
+            self.request(Method::PUT, &format!("/books/{}/applied_at", pub_id))
+                .json(dto),
+        )
+        .await
+    }
+
+    /// 一部だけ失敗した場合 (207) も Ok で返す。結果は items で確認する
+    pub async fn batch_books(&self, dto: &BookBatchDto) -> Result<BookBatchResponseDto> {
+        json(self.create_request("/books:batch").json(dto)).await
+    }
+
+    pub async fn import_books(
+        &self,
+        csv: impl Into<reqwest::Body>,
+        dry_run: bool,
+    ) -> Result<BookImportReportDto> {
+        json(
+            self.request(Method::POST, "/imports/books")
+                .query(&[("dry_run", dry_run)])
+                .header(CONTENT_TYPE, "text/csv")
+                .body(csv),
+        )
+        .await
+    }
+
+    pub async fn search_books(
+        &self,
+        q: &str,
+        page: u64,
+        per_page: u64,
+    ) -> Result<BookSearchResponseDto> {
+        json(self.request(Method::GET, "/search").query(&[
+            ("q", q),
+            ("page", &page.to_string()),
+            ("per_page", &per_page.to_string()),
+        ]))
+        .await
+    }
+}
@@ -0,0 +1,92 @@
+use crate::dto::ChangeEventDto;
+use crate::{Client, Error, Result, send};
+use futures::stream::{self, BoxStream, StreamExt};
+use reqwest::Method;
+use reqwest::header::ACCEPT;
+
+impl Client {
+    /// GET /events を購読する。types が空なら全ての種類を流す。
+    /// 切断後は最後に受け取った id を last_event_id に渡すと続きから受け取れる
+    pub async fn events(
+        &self,
+        types: &[&str],
+        last_event_id: Option<i64>,
+    ) -> Result<BoxStream<'static, Result<ChangeEventDto>>> {
+        let mut request = self
+            .request(Method::GET, "/events")
+            .header(ACCEPT, "text/event-stream");
+        if !types.is_empty() {
+            request = request.query(&[("types", types.join(","))]);
+        }
+        if let Some(id) = last_event_id {
+            request = request.header("last-event-id", id.to_string());
+        }
+        let body = send(request).await?.bytes_stream().boxed();
+        let events = stream::try_unfold((body, Vec::new()), |(mut body, mut buffer)| async move {
+            loop {
+                if let Some(event) = next_event(&mut buffer)? {
+                    return Ok(Some((event, (body, buffer))));
+                }
+                // 文字の途中で切れることがあるので、バイト列のまま溜める
+                match body.next().await {
+                    Some(chunk) => buffer.extend_from_slice(&chunk?),
+                    None => return Ok(None),
+                }
+            }
+        });
+        Ok(events.boxed())
+    }
+}
+
+/// buffer の先頭から空行までを 1 イベントとして取り出し、揃ってから UTF-8 として読む。
+/// data のないもの (keep-alive のコメントなど) は読み飛ばす
+fn next_event(buffer: &mut Vec<u8>) -> Result<Option<ChangeEventDto>> {
+    while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+        let block: Vec<u8> = buffer.drain(..end + 2).collect();
+        let block = String::from_utf8(block).map_err(|e| Error::InvalidEvent(e.to_string()))?;
+        let data = block
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|data| data.strip_prefix(' ').unwrap_or(data))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if data.is_empty() {
+            continue;
+        }
+        return serde_json::from_str(&data)
+            .map(Some)
+            .map_err(|e| Error::InvalidEvent(e.to_string()));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_event_waits_for_split_character() {
+        let event = concat!(
+            ": keep-alive\n\n",
+            "id: 1\nevent: book.created\ndata: {\"id\":1,\"event_type\":\"book.created\",",
+            "\"resource_type\":\"book\",",
+            "\"resource_id\":\"00000000-0000-0000-0000-000000000001\",",
+            "\"occurred_at\":\"2024-01-01T00:00:00Z\",\"data\":{\"title\":\"吾輩は猫である\"}}\n\n",
+        )
+        .as_bytes();
+        // 「猫」の途中で分ける
+        let split = event
+            .windows("猫".len())
+            .position(|w| w == "猫".as_bytes())
+            .unwrap()
+            + 1;
+
+        let mut buffer = event[..split].to_vec();
+        assert!(next_event(&mut buffer).unwrap().is_none());
+        buffer.extend_from_slice(&event[split..]);
+        let event = next_event(&mut buffer).unwrap().unwrap();
+        assert_eq!(event.id, 1);
+        assert_eq!(event.data["title"], "吾輩は猫である");
+        assert!(buffer.is_empty());
+    }
+}
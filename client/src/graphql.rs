@@ -0,0 +1,18 @@
+use crate::{Client, Result, json};
+use reqwest::Method;
+use serde_json::json;
+
+impl Client {
+    /// POST /graphql。GraphQL のエラーは HTTP 200 の本文 (errors) で返るため、そのまま返す
+    pub async fn graphql(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        json(
+            self.request(Method::POST, "/graphql")
+                .json(&json!({ "query": query, "variables": variables })),
+        )
+        .await
+    }
+}
@@ -0,0 +1,50 @@
+//! 認証の掛からない運用向けのルート
+use crate::{Client, Result, check, send};
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Degraded,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CheckResult {
+    pub status: Status,
+    pub error: Option<String>,
+}
+
+/// GET /readyz の本文
+#[derive(Debug, Clone, Deserialize)]
+pub struct Readiness {
+    pub status: Status,
+    pub checks: BTreeMap<String, CheckResult>,
+}
+
+impl Client {
+    /// プロセスが応答できるか
+    pub async fn healthz(&self) -> Result<()> {
+        send(self.request(Method::GET, "/healthz")).await?;
+        Ok(())
+    }
+
+    /// 劣化していても (503) エラーにせず、チェックごとの結果を返す
+    pub async fn readyz(&self) -> Result<Readiness> {
+        let response = self.request(Method::GET, "/readyz").send().await?;
+        if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+            return Ok(response.json().await?);
+        }
+        Ok(check(response).await?.json().await?)
+    }
+
+    /// Prometheus のテキスト形式
+    pub async fn metrics(&self) -> Result<String> {
+        Ok(send(self.request(Method::GET, "/metrics"))
+            .await?
+            .text()
+            .await?)
+    }
+}
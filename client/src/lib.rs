@@ -0,0 +1,161 @@
+pub use reqwest::StatusCode;
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub mod admin;
+pub mod book;
+pub mod event;
+pub mod graphql;
+pub mod health;
+pub mod publisher;
+pub mod shop;
+pub mod webhook;
+
+/// リクエストとレスポンスの型。サーバーと同じ usecase の DTO を OpenAPI のスキーマ名で公開する
+pub mod dto {
+    pub use usecase::api_key::{
+        IssueDto as ApiKeyIssueDto, IssuedDto as ApiKeyIssuedDto, ResponseDto as ApiKeyResponseDto,
+    };
+    pub use usecase::book::{
+        BatchDto as BookBatchDto, BatchItemResultDto as BookBatchItemResultDto,
+        BatchItemStatus as BookBatchItemStatus, BatchMode as BookBatchMode,
+        BatchOperation as BookBatchOperation, BatchResponseDto as BookBatchResponseDto,
+        BookPublisherDto, BookShopDto, ChangeAppliedAtDto as BookChangeAppliedAtDto,
+        CreateDto as BookCreateDto, HighlightDto as BookHighlightDto,
+        ImportLineErrorDto as BookImportLineErrorDto, ImportReportDto as BookImportReportDto,
+        ResponseDto as BookResponseDto, SearchHitDto as BookSearchHitDto,
+        SearchResponseDto as BookSearchResponseDto, UpdateDto as BookUpdateDto,
+    };
+    pub use usecase::change_event::EventDto as ChangeEventDto;
+    pub use usecase::outbox::MessageDto as OutboxMessageDto;
+    pub use usecase::publisher::{
+        CreateDto as PublisherCreateDto, ResponseDto as PublisherResponseDto,
+        UpdateDto as PublisherUpdateDto,
+    };
+    pub use usecase::shop::{
+        CreateDto as ShopCreateDto, ResponseDto as ShopResponseDto, UpdateDto as ShopUpdateDto,
+    };
+    pub use usecase::webhook::{
+        CreateDto as WebhookCreateDto, DeliveryDto as WebhookDeliveryDto,
+        ResponseDto as WebhookResponseDto,
+    };
+}
+
+/// API が 4xx / 5xx と一緒に返す本文
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// API がエラーを返した
+    #[error("{status}: {}", body.error)]
+    Api { status: StatusCode, body: ErrorBody },
+    /// 接続できない、レスポンスを読めないなど
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// 変更フィードに解釈できないイベントが流れてきた
+    #[error("Invalid event: {0}")]
+    InvalidEvent(String),
+}
+
+impl Error {
+    /// API が返したステータス
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Api { status, .. } => Some(*status),
+            Error::Http(e) => e.status(),
+            Error::InvalidEvent(_) => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// 全ルートの非同期クライアント。Clone は安く、接続プールを共有する
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    idempotency_key: Option<String>,
+}
+
+impl Client {
+    /// base_url は `http://localhost:3000` のようにパスを含めない
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            idempotency_key: None,
+        }
+    }
+
+    /// タイムアウトやプロキシなどを設定した reqwest::Client を使う
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// `Authorization: ApiKey {secret}` を付ける
+    pub fn with_api_key(mut self, secret: impl Into<String>) -> Self {
+        self.api_key = Some(secret.into());
+        self
+    }
+
+    /// 作成系のリクエストに Idempotency-Key を付けたクライアントを返す。
+    /// 同じキーは同じ内容の再試行にだけ使う
+    pub fn with_idempotency_key(&self, key: impl Into<String>) -> Self {
+        Self {
+            idempotency_key: Some(key.into()),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        if let Some(secret) = &self.api_key {
+            request = request.header(reqwest::header::AUTHORIZATION, format!("ApiKey {}", secret));
+        }
+        request
+    }
+
+    /// Idempotency-Key を受け付けるルートへのリクエスト
+    pub(crate) fn create_request(&self, path: &str) -> RequestBuilder {
+        let request = self.request(Method::POST, path);
+        match &self.idempotency_key {
+            Some(key) => request.header("idempotency-key", key),
+            None => request,
+        }
+    }
+}
+
+pub(crate) async fn send(request: RequestBuilder) -> Result<Response> {
+    check(request.send().await?).await
+}
+
+/// 2xx 以外を Error::Api にする
+pub(crate) async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let text = response.text().await?;
+    // JSON でない本文 (プロキシのエラーページなど) はそのまま error に入れる
+    let body = serde_json::from_str(&text).unwrap_or(ErrorBody { error: text });
+    Err(Error::Api { status, body })
+}
+
+pub(crate) async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+    Ok(send(request).await?.json().await?)
+}
+
+pub(crate) async fn empty(request: RequestBuilder) -> Result<()> {
+    send(request).await?;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::dto::{PublisherCreateDto, PublisherResponseDto, PublisherUpdateDto};
+use crate::{Client, Result, empty, json};
+use reqwest::Method;
+use uuid::Uuid;
+
+impl Client {
+    pub async fn get_publishers(&self, include_deleted: bool) -> Result<Vec<PublisherResponseDto>> {
+        json(
+            self.request(Method::GET, "/publishers")
+                .query(&[("include_deleted", include_deleted)]),
+        )
+        .await
+    }
+
+    pub async fn get_publisher(
+        &self,
+        pub_id: Uuid,
+        include_deleted: bool,
+    ) -> Result<PublisherResponseDto> {
+        json(
+            self.request(Method::GET, &format!("/publishers/{}", pub_id))
+                .query(&[("include_deleted", include_deleted)]),
+        )
+        .await
+    }
+
+    pub async fn create_publisher(&self, dto: &PublisherCreateDto) -> Result<PublisherResponseDto> {
+        json(self.create_request("/publishers").json(dto)).await
+    }
+
+    pub async fn update_publisher(
+        &self,
+        pub_id: Uuid,
+        dto: &PublisherUpdateDto,
+    ) -> Result<PublisherResponseDto> {
+        json(
+            self.request(Method::PUT, &format!("/publishers/{}", pub_id))
+                .json(dto),
+        )
+        .await
+    }
+
+    pub async fn delete_publisher(&self, pub_id: Uuid) -> Result<()> {
+        empty(self.request(Method::DELETE, &format!("/publishers/{}", pub_id))).await
+    }
+
+    pub async fn restore_publisher(&self, pub_id: Uuid) -> Result<PublisherResponseDto> {
+        json(self.request(Method::POST, &format!("/publishers/{}/restore", pub_id))).await
+    }
+}
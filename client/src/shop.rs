@@ -0,0 +1,42 @@
+use crate::dto::{ShopCreateDto, ShopResponseDto, ShopUpdateDto};
+use crate::{Client, Result, empty, json};
+use reqwest::Method;
+use uuid::Uuid;
+
+impl Client {
+    pub async fn get_shops(&self, include_deleted: bool) -> Result<Vec<ShopResponseDto>> {
+        json(
+            self.request(Method::GET, "/shops")
+                .query(&[("include_deleted", include_deleted)]),
+        )
+        .await
+    }
+
+    pub async fn get_shop(&self, pub_id: Uuid, include_deleted: bool) -> Result<ShopResponseDto> {
+        json(
+            self.request(Method::GET, &format!("/shops/{}", pub_id))
+                .query(&[("include_deleted", include_deleted)]),
+        )
+        .await
+    }
+
+    pub async fn create_shop(&self, dto: &ShopCreateDto) -> Result<ShopResponseDto> {
+        json(self.create_request("/shops").json(dto)).await
+    }
+
+    pub async fn update_shop(&self, pub_id: Uuid, dto: &ShopUpdateDto) -> Result<ShopResponseDto> {
+        json(
+            self.request(Method::PUT, &format!("/shops/{}", pub_id))
+                .json(dto),
+        )
+        .await
+    }
+
+    pub async fn delete_shop(&self, pub_id: Uuid) -> Result<()> {
+        empty(self.request(Method::DELETE, &format!("/shops/{}", pub_id))).await
+    }
+
+    pub async fn restore_shop(&self, pub_id: Uuid) -> Result<ShopResponseDto> {
+        json(self.request(Method::POST, &format!("/shops/{}/restore", pub_id))).await
+    }
+}
@@ -0,0 +1,38 @@
+use crate::dto::{WebhookCreateDto, WebhookDeliveryDto, WebhookResponseDto};
+use crate::{Client, Result, empty, json};
+use reqwest::Method;
+use uuid::Uuid;
+
+impl Client {
+    pub async fn get_webhooks(&self) -> Result<Vec<WebhookResponseDto>> {
+        json(self.request(Method::GET, "/webhooks")).await
+    }
+
+    pub async fn get_webhook(&self, pub_id: Uuid) -> Result<WebhookResponseDto> {
+        json(self.request(Method::GET, &format!("/webhooks/{}", pub_id))).await
+    }
+
+    pub async fn create_webhook(&self, dto: &WebhookCreateDto) -> Result<WebhookResponseDto> {
+        json(self.create_request("/webhooks").json(dto)).await
+    }
+
+    pub async fn delete_webhook(&self, pub_id: Uuid) -> Result<()> {
+        empty(self.request(Method::DELETE, &format!("/webhooks/{}", pub_id))).await
+    }
+
+    /// status は pending / delivered / dead のいずれか。None なら全て
+    pub async fn get_webhook_deliveries(
+        &self,
+        pub_id: Uuid,
+        status: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<WebhookDeliveryDto>> {
+        let mut request = self
+            .request(Method::GET, &format!("/webhooks/{}/deliveries", pub_id))
+            .query(&[("limit", limit)]);
+        if let Some(status) = status {
+            request = request.query(&[("status", status)]);
+        }
+        json(request).await
+    }
+}
@@ -0,0 +1,167 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod vo;
+
+#[async_trait]
+pub trait Repository: Sync + Send {
+    async fn find_all(&self) -> anyhow::Result<Vec<ApiKey>>;
+    async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<ApiKey>>;
+    async fn find_by_prefix(&self, prefix: &str) -> anyhow::Result<Option<ApiKey>>;
+    async fn create(&self, item: ApiKey) -> anyhow::Result<ApiKey>;
+    async fn update(&self, item: ApiKey) -> anyhow::Result<ApiKey>;
+    /// 失効していなければ last_used_at だけを記録する。
+    /// 認証の度に呼ぶため、並行して失効させた変更を上書きしないよう他の列には触れない
+    async fn touch(&self, id: i32, at: chrono::DateTime<chrono::Utc>) -> anyhow::Result<()>;
+}
+
+/// 機械同士の連携に使う API キー。秘密の値そのものは持たず、ハッシュだけを保存する
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    id: i32,
+    pub_id: uuid::Uuid,
+    name: vo::ApiKeyName,
+    owner: vo::Owner,
+    scopes: Vec<vo::Scope>,
+    /// キーを探すための先頭部分。秘密ではない
+    prefix: String,
+    key_hash: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ApiKey {
+    pub fn new(
+        pub_id: uuid::Uuid,
+        name: vo::ApiKeyName,
+        owner: vo::Owner,
+        scopes: Vec<vo::Scope>,
+        prefix: String,
+        key_hash: String,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Self, DomainError> {
+        if scopes.is_empty() {
+            return Err(DomainError::DomainRuleViolation(
+                "At least one scope is required.".to_string(),
+            ));
+        }
+        let now = chrono::Utc::now();
+        if expires_at.is_some_and(|at| at <= now) {
+            return Err(DomainError::DomainRuleViolation(
+                "Expiry must be in the future.".to_string(),
+            ));
+        }
+        let mut scopes = scopes;
+        scopes.sort();
+        scopes.dedup();
+        Ok(Self {
+            id: 0,
+            pub_id,
+            name,
+            owner,
+            scopes,
+            prefix,
+            key_hash,
+            created_at: now,
+            last_used_at: None,
+            expires_at,
+            revoked_at: None,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn reconstruct(
+        id: i32,
+        pub_id: uuid::Uuid,
+        name: vo::ApiKeyName,
+        owner: vo::Owner,
+        scopes: Vec<vo::Scope>,
+        prefix: String,
+        key_hash: String,
+        created_at: chrono::DateTime<chrono::Utc>,
+        last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+        revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Self {
+        Self {
+            id,
+            pub_id,
+            name,
+            owner,
+            scopes,
+            prefix,
+            key_hash,
+            created_at,
+            last_used_at,
+            expires_at,
+            revoked_at,
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+    pub fn pub_id(&self) -> uuid::Uuid {
+        self.pub_id
+    }
+    pub fn name(&self) -> String {
+        self.name.value().to_string()
+    }
+    pub fn owner(&self) -> String {
+        self.owner.value().to_string()
+    }
+    pub fn scopes(&self) -> &[vo::Scope] {
+        &self.scopes
+    }
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+    pub fn key_hash(&self) -> &str {
+        &self.key_hash
+    }
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+    }
+    pub fn last_used_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.last_used_at
+    }
+    pub fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.expires_at
+    }
+    pub fn revoked_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.revoked_at
+    }
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    /// 失効も期限切れもしていなければ使える
+    pub fn is_active(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        !self.is_revoked() && self.expires_at.is_none_or(|at| at > now)
+    }
+
+    pub fn touch(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        self.last_used_at = Some(now);
+    }
+
+    pub fn revoke(&mut self) -> Result<(), DomainError> {
+        if self.is_revoked() {
+            return Err(DomainError::DomainRuleViolation(
+                "API key is already revoked.".to_string(),
+            ));
+        }
+        self.revoked_at = Some(chrono::Utc::now());
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DomainError {
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+    #[error("Domain rule violation: {0}")]
+    DomainRuleViolation(String),
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::DomainError;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct ApiKeyName(String);
+
+impl ApiKeyName {
+    pub fn new(name: String) -> Result<Self, DomainError> {
+        if name.trim().is_empty() {
+            return Err(DomainError::InvalidFormat(
+                "Name must not be empty".to_string(),
+            ));
+        }
+        if name.chars().count() > 64 {
+            return Err(DomainError::InvalidFormat(
+                "Name must be 64 chars or less".to_string(),
+            ));
+        }
+        Ok(Self(name))
+    }
+
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// キーを管理する担当者やチーム
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct Owner(String);
+
+impl Owner {
+    pub fn new(owner: String) -> Result<Self, DomainError> {
+        if owner.trim().is_empty() {
+            return Err(DomainError::InvalidFormat(
+                "Owner must not be empty".to_string(),
+            ));
+        }
+        if owner.chars().count() > 64 {
+            return Err(DomainError::InvalidFormat(
+                "Owner must be 64 chars or less".to_string(),
+            ));
+        }
+        Ok(Self(owner))
+    }
+
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// 操作の権限。リソースごとに読み取りと書き込みを分ける
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Scope {
+    #[serde(rename = "books:read")]
+    BooksRead,
+    #[serde(rename = "books:write")]
+    BooksWrite,
+    #[serde(rename = "publishers:read")]
+    PublishersRead,
+    #[serde(rename = "publishers:write")]
+    PublishersWrite,
+    #[serde(rename = "shops:read")]
+    ShopsRead,
+    #[serde(rename = "shops:write")]
+    ShopsWrite,
+    #[serde(rename = "webhooks:read")]
+    WebhooksRead,
+    #[serde(rename = "webhooks:write")]
+    WebhooksWrite,
+    /// API キーの発行・失効
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 9] = [
+        Scope::BooksRead,
+        Scope::BooksWrite,
+        Scope::PublishersRead,
+        Scope::PublishersWrite,
+        Scope::ShopsRead,
+        Scope::ShopsWrite,
+        Scope::WebhooksRead,
+        Scope::WebhooksWrite,
+        Scope::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::BooksRead => "books:read",
+            Scope::BooksWrite => "books:write",
+            Scope::PublishersRead => "publishers:read",
+            Scope::PublishersWrite => "publishers:write",
+            Scope::ShopsRead => "shops:read",
+            Scope::ShopsWrite => "shops:write",
+            Scope::WebhooksRead => "webhooks:read",
+            Scope::WebhooksWrite => "webhooks:write",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| DomainError::InvalidFormat(format!("Unknown scope: {}", s)))
+    }
+}
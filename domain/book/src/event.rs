@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use crate::vo;
+
+/// Book の状態変化。集約が記録し、永続化の後にユースケース層が配信する
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Created,
+    TitleChanged {
+        old: String,
+        new: String,
+    },
+    AuthorChanged {
+        old: String,
+        new: String,
+    },
+    PublisherChanged {
+        old: uuid::Uuid,
+        new: uuid::Uuid,
+    },
+    ShopChanged {
+        old: Option<uuid::Uuid>,
+        new: Option<uuid::Uuid>,
+    },
+    FormatChanged {
+        old: vo::BookFormat,
+        new: vo::BookFormat,
+    },
+    PriceChanged {
+        old: i32,
+        new: i32,
+    },
+    Applied {
+        applied_at: chrono::DateTime<chrono::Utc>,
+    },
+    Unapplied,
+    Deleted,
+    Restored,
+}
+
+impl Event {
+    /// name() が返しうる全てのイベント名
+    pub const NAMES: [&'static str; 11] = [
+        "BookCreated",
+        "BookTitleChanged",
+        "BookAuthorChanged",
+        "BookPublisherChanged",
+        "BookShopChanged",
+        "BookFormatChanged",
+        "BookPriceChanged",
+        "BookApplied",
+        "BookUnapplied",
+        "BookDeleted",
+        "BookRestored",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Created => "BookCreated",
+            Event::TitleChanged { .. } => "BookTitleChanged",
+            Event::AuthorChanged { .. } => "BookAuthorChanged",
+            Event::PublisherChanged { .. } => "BookPublisherChanged",
+            Event::ShopChanged { .. } => "BookShopChanged",
+            Event::FormatChanged { .. } => "BookFormatChanged",
+            Event::PriceChanged { .. } => "BookPriceChanged",
+            Event::Applied { .. } => "BookApplied",
+            Event::Unapplied => "BookUnapplied",
+            Event::Deleted => "BookDeleted",
+            Event::Restored => "BookRestored",
+        }
+    }
+}
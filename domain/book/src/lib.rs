@@ -2,15 +2,75 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod event;
 pub mod vo;
 
 #[async_trait]
 pub trait Repository: Sync + Send {
-    async fn find_all(&self) -> anyhow::Result<Vec<Book>>;
-    async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<Book>>;
+    async fn find_all(&self, include_deleted: bool) -> anyhow::Result<Vec<Book>>;
+    async fn find_by_pub_id(
+        &self,
+        pub_id: uuid::Uuid,
+        include_deleted: bool,
+    ) -> anyhow::Result<Option<Book>>;
+    async fn find_by_pub_ids(&self, pub_ids: &[uuid::Uuid]) -> anyhow::Result<Vec<Book>>;
     async fn create(&self, item: Book) -> anyhow::Result<Book>;
     async fn update(&self, item: Book) -> anyhow::Result<Book>;
-    async fn delete(&self, item: Book) -> anyhow::Result<()>;
+    /// id 順に page_size 件ずつ読み込みながら全件を流す
+    fn stream_all(
+        &self,
+        include_deleted: bool,
+        page_size: u64,
+    ) -> futures::stream::BoxStream<'static, anyhow::Result<Book>>;
+    /// title / author / publisher 名 / shop 名を関連度順に検索する (論理削除済みは除く)
+    async fn search(&self, query: &SearchQuery) -> anyhow::Result<SearchPage>;
+    /// 指定した Book の変更履歴を記録順に返す
+    async fn find_history(&self, pub_ids: &[uuid::Uuid]) -> anyhow::Result<Vec<History>>;
+    /// 全ての変更を 1 トランザクションで保存する
+    async fn save_all(&self, changes: Vec<Change>) -> anyhow::Result<Vec<Book>>;
+    /// 変更を 1 件ずつ保存し、それぞれの結果を返す。失敗した変更だけを取り消す
+    async fn save_each(&self, changes: Vec<Change>) -> anyhow::Result<Vec<anyhow::Result<Book>>>;
+    /// deleted_at が指定日時より前の論理削除済みレコードを物理削除し、件数を返す
+    async fn purge_deleted(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<u64>;
+}
+
+/// 全ての語を含む Book を探す。語は空白で区切られたもの
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    pub books: Vec<Book>,
+    pub total: u64,
+}
+
+/// book_history に記録された変更後の Book。参照専用のため値はそのまま持つ
+#[derive(Debug, Clone)]
+pub struct History {
+    pub pub_id: uuid::Uuid,
+    /// INSERT / UPDATE / DELETE
+    pub operation: String,
+    pub operation_at: chrono::DateTime<chrono::Utc>,
+    pub title: String,
+    pub author: String,
+    pub format: String,
+    pub price: i32,
+    pub applied_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub updated_by: String,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    Create(Book),
+    Update(Book),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +88,11 @@ pub struct Book {
     updated_at: chrono::DateTime<chrono::Utc>,
     created_by: String,
     updated_by: String,
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    deleted_by: Option<String>,
+    /// 永続化前に取り出されるまで保持する未配信のイベント
+    #[serde(skip)]
+    events: Vec<event::Event>,
 }
 
 impl Book {
@@ -57,6 +122,9 @@ impl Book {
             updated_at: now,
             created_by: created_by.clone(),
             updated_by: created_by,
+            deleted_at: None,
+            deleted_by: None,
+            events: vec![event::Event::Created],
         }
     }
 
@@ -75,6 +143,8 @@ impl Book {
         updated_at: chrono::DateTime<chrono::Utc>,
         created_by: String,
         updated_by: String,
+        deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+        deleted_by: Option<String>,
     ) -> Self {
         Self {
             id,
@@ -90,6 +160,9 @@ impl Book {
             updated_at,
             created_by,
             updated_by,
+            deleted_at,
+            deleted_by,
+            events: Vec::new(),
         }
     }
 
@@ -132,6 +205,25 @@ impl Book {
     pub fn updated_by(&self) -> String {
         self.updated_by.clone()
     }
+    pub fn deleted_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.deleted_at
+    }
+    pub fn deleted_by(&self) -> Option<String> {
+        self.deleted_by.clone()
+    }
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// まだ保存していないイベント
+    pub fn events(&self) -> &[event::Event] {
+        &self.events
+    }
+
+    /// 記録済みのイベントを取り出す。リポジトリが保存と同じトランザクションで outbox に書き込む
+    pub fn take_events(&mut self) -> Vec<event::Event> {
+        std::mem::take(&mut self.events)
+    }
 
     fn update_audit(&mut self, updated_by: String) {
         self.updated_at = chrono::Utc::now();
@@ -155,6 +247,45 @@ impl Book {
             ));
         }
 
+        if self.title != title {
+            self.events.push(event::Event::TitleChanged {
+                old: self.title.value().to_string(),
+                new: title.value().to_string(),
+            });
+        }
+        if self.author != author {
+            self.events.push(event::Event::AuthorChanged {
+                old: self.author.value().to_string(),
+                new: author.value().to_string(),
+            });
+        }
+        if self.publisher.pub_id() != publisher.pub_id() {
+            self.events.push(event::Event::PublisherChanged {
+                old: self.publisher.pub_id(),
+                new: publisher.pub_id(),
+            });
+        }
+        let old_shop = self.shop.as_ref().map(|s| s.pub_id());
+        let new_shop = shop.as_ref().map(|s| s.pub_id());
+        if old_shop != new_shop {
+            self.events.push(event::Event::ShopChanged {
+                old: old_shop,
+                new: new_shop,
+            });
+        }
+        if self.format != format {
+            self.events.push(event::Event::FormatChanged {
+                old: self.format,
+                new: format,
+            });
+        }
+        if self.price != price {
+            self.events.push(event::Event::PriceChanged {
+                old: self.price.value(),
+                new: price.value(),
+            });
+        }
+
         self.title = title;
         self.author = author;
         self.publisher = publisher;
@@ -170,10 +301,55 @@ impl Book {
         applied_at: Option<chrono::DateTime<chrono::Utc>>,
         updated_by: String,
     ) -> Result<(), DomainError> {
+        match (self.applied_at, applied_at) {
+            (old, Some(new)) if old != Some(new) => {
+                self.events.push(event::Event::Applied { applied_at: new });
+            }
+            (Some(_), None) => self.events.push(event::Event::Unapplied),
+            _ => {}
+        }
         self.applied_at = applied_at;
         self.update_audit(updated_by);
         Ok(())
     }
+
+    pub fn delete(&mut self, deleted_by: String) -> Result<(), DomainError> {
+        if self.is_deleted() {
+            return Err(DomainError::DomainRuleViolation(
+                "Book is already deleted.".to_string(),
+            ));
+        }
+
+        self.deleted_at = Some(chrono::Utc::now());
+        self.deleted_by = Some(deleted_by.clone());
+        self.update_audit(deleted_by);
+        self.events.push(event::Event::Deleted);
+        Ok(())
+    }
+
+    pub fn restore(&mut self, restored_by: String) -> Result<(), DomainError> {
+        if !self.is_deleted() {
+            return Err(DomainError::DomainRuleViolation(
+                "Book is not deleted.".to_string(),
+            ));
+        }
+        if self.publisher.is_deleted() {
+            return Err(DomainError::DomainRuleViolation(
+                "Cannot restore a book whose publisher is deleted.".to_string(),
+            ));
+        }
+        if self.shop.as_ref().is_some_and(|s| s.is_deleted()) {
+            return Err(DomainError::DomainRuleViolation(
+                "Cannot restore a book whose shop is deleted.".to_string(),
+            ));
+        }
+
+        self.deleted_at = None;
+        self.deleted_by = None;
+        self.update_audit(restored_by);
+        self.events.push(event::Event::Restored);
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
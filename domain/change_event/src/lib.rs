@@ -0,0 +1,101 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod vo;
+
+#[async_trait]
+pub trait Repository: Sync + Send {
+    /// 記録して、採番した id を持つイベントを返す。
+    /// id の小さいイベントから順に読めるようにならなければならない (find_after は id だけで続きを読む)
+    async fn append(&self, event: ChangeEvent) -> anyhow::Result<ChangeEvent>;
+    /// id が after より大きいイベントを id 順に最大 limit 件返す。
+    /// resource_types が空なら全ての種類を返す
+    async fn find_after(
+        &self,
+        after: i64,
+        resource_types: &[vo::ResourceType],
+        limit: u64,
+    ) -> anyhow::Result<Vec<ChangeEvent>>;
+    /// 最後に記録したイベントの id。1 件も無ければ 0
+    async fn last_id(&self) -> anyhow::Result<i64>;
+    /// occurred_at が指定日時より前のイベントを削除し、件数を返す
+    async fn purge_before(&self, before: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64>;
+}
+
+/// カタログへの変更 1 件。id は記録順に増え、フィードの再開位置 (Last-Event-ID) になる
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    id: i64,
+    resource_type: vo::ResourceType,
+    action: vo::Action,
+    resource_id: uuid::Uuid,
+    /// 変更後のリソースを JSON にしたもの
+    data: String,
+    occurred_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ChangeEvent {
+    pub fn new(
+        resource_type: vo::ResourceType,
+        action: vo::Action,
+        resource_id: uuid::Uuid,
+        data: String,
+    ) -> Self {
+        Self {
+            id: 0,
+            resource_type,
+            action,
+            resource_id,
+            data,
+            occurred_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn reconstruct(
+        id: i64,
+        resource_type: vo::ResourceType,
+        action: vo::Action,
+        resource_id: uuid::Uuid,
+        data: String,
+        occurred_at: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            id,
+            resource_type,
+            action,
+            resource_id,
+            data,
+            occurred_at,
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+    pub fn resource_type(&self) -> vo::ResourceType {
+        self.resource_type
+    }
+    pub fn action(&self) -> vo::Action {
+        self.action
+    }
+    /// "book.created" のようにリソースの種類と操作を繋げたもの
+    pub fn event_type(&self) -> String {
+        format!("{}.{}", self.resource_type, self.action)
+    }
+    pub fn resource_id(&self) -> uuid::Uuid {
+        self.resource_id
+    }
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+    pub fn occurred_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.occurred_at
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DomainError {
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+}
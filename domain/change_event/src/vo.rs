@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::DomainError;
+
+/// 変更されたリソースの種類
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceType {
+    Book,
+    Publisher,
+    Shop,
+}
+
+impl ResourceType {
+    pub const ALL: [ResourceType; 3] = [
+        ResourceType::Book,
+        ResourceType::Publisher,
+        ResourceType::Shop,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceType::Book => "book",
+            ResourceType::Publisher => "publisher",
+            ResourceType::Shop => "shop",
+        }
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ResourceType {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ResourceType::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| DomainError::InvalidFormat(format!("Unknown resource type: {}", s)))
+    }
+}
+
+/// リソースに対して行われた操作
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+    /// Book の applied_at が設定された
+    Published,
+    /// Book の applied_at が取り消された
+    Unpublished,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Created,
+        Action::Updated,
+        Action::Deleted,
+        Action::Restored,
+        Action::Published,
+        Action::Unpublished,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Created => "created",
+            Action::Updated => "updated",
+            Action::Deleted => "deleted",
+            Action::Restored => "restored",
+            Action::Published => "published",
+            Action::Unpublished => "unpublished",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Action {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|a| a.as_str() == s)
+            .ok_or_else(|| DomainError::InvalidFormat(format!("Unknown action: {}", s)))
+    }
+}
@@ -0,0 +1,118 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod vo;
+
+#[async_trait]
+pub trait Repository: Sync + Send {
+    /// 同じ key・scope の有効なレコードが無ければ登録して None を、あればそれを返す。
+    /// 期限切れのレコードと、処理中のまま locked_until を過ぎたレコードは無いものとして置き換える
+    async fn insert_or_get(&self, record: Record) -> anyhow::Result<Option<Record>>;
+    async fn save_response(
+        &self,
+        key: &vo::IdempotencyKey,
+        scope: &str,
+        response: StoredResponse,
+    ) -> anyhow::Result<()>;
+    async fn delete(&self, key: &vo::IdempotencyKey, scope: &str) -> anyhow::Result<()>;
+    /// 期限切れのレコードを削除し、件数を返す
+    async fn purge_expired(&self, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64>;
+}
+
+/// 1 つの Idempotency-Key で受け付けたリクエストと、その結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    key: vo::IdempotencyKey,
+    scope: String,
+    request_hash: String,
+    response: Option<StoredResponse>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+    /// 処理中のリクエストがキーを押さえておく期限。タイムアウトや切断で処理が中断され、
+    /// レスポンスが保存されないまま残っても、これを過ぎれば再試行が引き継げる
+    locked_until: chrono::DateTime<chrono::Utc>,
+}
+
+/// 再試行に返すレスポンス
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Record {
+    /// scope はキーを使い回せる範囲 (利用者、メソッドとパス)
+    pub fn new(
+        key: vo::IdempotencyKey,
+        scope: String,
+        request_hash: String,
+        ttl: chrono::Duration,
+        lease: chrono::Duration,
+    ) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            key,
+            scope,
+            request_hash,
+            response: None,
+            created_at: now,
+            expires_at: now + ttl,
+            locked_until: now + lease,
+        }
+    }
+
+    pub fn reconstruct(
+        key: vo::IdempotencyKey,
+        scope: String,
+        request_hash: String,
+        response: Option<StoredResponse>,
+        created_at: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+        locked_until: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            key,
+            scope,
+            request_hash,
+            response,
+            created_at,
+            expires_at,
+            locked_until,
+        }
+    }
+
+    pub fn key(&self) -> &vo::IdempotencyKey {
+        &self.key
+    }
+    pub fn scope(&self) -> &str {
+        &self.scope
+    }
+    pub fn request_hash(&self) -> &str {
+        &self.request_hash
+    }
+    /// 最初のリクエストを処理中なら None
+    pub fn response(&self) -> Option<&StoredResponse> {
+        self.response.as_ref()
+    }
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+    }
+    pub fn expires_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.expires_at
+    }
+    pub fn locked_until(&self) -> chrono::DateTime<chrono::Utc> {
+        self.locked_until
+    }
+    /// now の時点で置き換えてよいか
+    pub fn is_stale(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.expires_at <= now || (self.response.is_none() && self.locked_until <= now)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DomainError {
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+}
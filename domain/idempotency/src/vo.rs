@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::DomainError;
+
+/// クライアントが再試行のたびに同じ値を送るキー
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    pub fn new(key: String) -> Result<Self, DomainError> {
+        if key.is_empty() || key.len() > 255 {
+            return Err(DomainError::InvalidFormat(
+                "Idempotency-Key must be 1 to 255 chars".to_string(),
+            ));
+        }
+        if !key.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(DomainError::InvalidFormat(
+                "Idempotency-Key must be printable ASCII without spaces".to_string(),
+            ));
+        }
+        Ok(Self(key))
+    }
+
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,181 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod vo;
+
+/// メッセージの追加は集約の保存と同じトランザクションで各リポジトリが行う
+#[async_trait]
+pub trait Repository: Sync + Send {
+    /// 新しい順に返す。status があればその状態のものだけを返す
+    async fn find_all(
+        &self,
+        status: Option<vo::Status>,
+        limit: u64,
+    ) -> anyhow::Result<Vec<Message>>;
+    async fn find_by_id(&self, id: i64) -> anyhow::Result<Option<Message>>;
+    /// 配信予定時刻を過ぎた pending のメッセージを古い順に返す
+    async fn find_due(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: u64,
+    ) -> anyhow::Result<Vec<Message>>;
+    async fn update(&self, item: Message) -> anyhow::Result<Message>;
+    /// delivered_at が指定日時より前の配信済みメッセージを削除し、件数を返す
+    async fn purge_delivered(&self, before: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64>;
+}
+
+/// メッセージの配信先。少なくとも 1 回は届けるため、同じメッセージを重ねて受け取ることがある
+#[async_trait]
+pub trait Sink: Sync + Send {
+    fn name(&self) -> &str;
+    async fn send(&self, message: &Message) -> anyhow::Result<()>;
+}
+
+/// 集約の変更と一緒に保存し、後から配信するイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    id: i64,
+    aggregate_type: String,
+    aggregate_id: uuid::Uuid,
+    event_type: String,
+    /// イベントを JSON にしたもの
+    payload: String,
+    status: vo::Status,
+    attempts: u32,
+    next_attempt_at: chrono::DateTime<chrono::Utc>,
+    last_error: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Message {
+    pub fn new(
+        aggregate_type: String,
+        aggregate_id: uuid::Uuid,
+        event_type: String,
+        payload: String,
+    ) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: 0,
+            aggregate_type,
+            aggregate_id,
+            event_type,
+            payload,
+            status: vo::Status::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            created_at: now,
+            delivered_at: None,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn reconstruct(
+        id: i64,
+        aggregate_type: String,
+        aggregate_id: uuid::Uuid,
+        event_type: String,
+        payload: String,
+        status: vo::Status,
+        attempts: u32,
+        next_attempt_at: chrono::DateTime<chrono::Utc>,
+        last_error: Option<String>,
+        created_at: chrono::DateTime<chrono::Utc>,
+        delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Self {
+        Self {
+            id,
+            aggregate_type,
+            aggregate_id,
+            event_type,
+            payload,
+            status,
+            attempts,
+            next_attempt_at,
+            last_error,
+            created_at,
+            delivered_at,
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+    pub fn aggregate_type(&self) -> &str {
+        &self.aggregate_type
+    }
+    pub fn aggregate_id(&self) -> uuid::Uuid {
+        self.aggregate_id
+    }
+    pub fn event_type(&self) -> &str {
+        &self.event_type
+    }
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+    pub fn status(&self) -> vo::Status {
+        self.status
+    }
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+    pub fn next_attempt_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.next_attempt_at
+    }
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+    }
+    pub fn delivered_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.delivered_at
+    }
+
+    pub fn delivered(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        self.attempts += 1;
+        self.status = vo::Status::Delivered;
+        self.delivered_at = Some(now);
+        self.last_error = None;
+    }
+
+    /// 失敗を記録して次の配信予定を決める。再試行の上限に達したら dead にする
+    pub fn failed(
+        &mut self,
+        error: String,
+        now: chrono::DateTime<chrono::Utc>,
+        policy: &vo::RetryPolicy,
+    ) {
+        self.attempts += 1;
+        self.last_error = Some(error);
+        if self.attempts >= policy.max_attempts() {
+            self.status = vo::Status::Dead;
+        } else {
+            self.next_attempt_at = now + policy.backoff(self.attempts);
+        }
+    }
+
+    /// 配信済みでなければ回数を数え直してすぐに配信し直す
+    pub fn retry(&mut self, now: chrono::DateTime<chrono::Utc>) -> Result<(), DomainError> {
+        if self.status == vo::Status::Delivered {
+            return Err(DomainError::DomainRuleViolation(
+                "Message is already delivered.".to_string(),
+            ));
+        }
+        self.status = vo::Status::Pending;
+        self.attempts = 0;
+        self.next_attempt_at = now;
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DomainError {
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+    #[error("Domain rule violation: {0}")]
+    DomainRuleViolation(String),
+}
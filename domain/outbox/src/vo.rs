@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::DomainError;
+
+/// メッセージの配信状況
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// 配信待ち。失敗した場合も再試行するまではこの状態に戻る
+    Pending,
+    Delivered,
+    /// 再試行の上限に達したもの。手動で再試行するまで配信しない
+    Dead,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Pending, Status::Delivered, Status::Dead];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Pending => "pending",
+            Status::Delivered => "delivered",
+            Status::Dead => "dead",
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Status {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Status::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| DomainError::InvalidFormat(format!("Unknown outbox status: {}", s)))
+    }
+}
+
+/// 配信に失敗したときの再試行の間隔と回数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base: chrono::Duration,
+    max: chrono::Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(
+        max_attempts: u32,
+        base: chrono::Duration,
+        max: chrono::Duration,
+    ) -> Result<Self, DomainError> {
+        if max_attempts == 0 {
+            return Err(DomainError::InvalidFormat(
+                "Max attempts must be 1 or more".to_string(),
+            ));
+        }
+        if base <= chrono::Duration::zero() || max < base {
+            return Err(DomainError::InvalidFormat(
+                "Backoff must be positive and not exceed its maximum".to_string(),
+            ));
+        }
+        Ok(Self {
+            max_attempts,
+            base,
+            max,
+        })
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// attempts 回目の失敗の後に待つ時間。失敗のたびに倍にし、max で頭打ちにする
+    pub fn backoff(&self, attempts: u32) -> chrono::Duration {
+        let exponent = attempts.saturating_sub(1).min(30);
+        self.base
+            .checked_mul(1 << exponent)
+            .map_or(self.max, |delay| delay.min(self.max))
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+/// Publisher に起きた変更
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Created,
+    Renamed { old: String, new: String },
+    Deleted,
+    Restored,
+}
+
+impl Event {
+    /// name() が返しうる全てのイベント名
+    pub const NAMES: [&'static str; 4] = [
+        "PublisherCreated",
+        "PublisherRenamed",
+        "PublisherDeleted",
+        "PublisherRestored",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Created => "PublisherCreated",
+            Event::Renamed { .. } => "PublisherRenamed",
+            Event::Deleted => "PublisherDeleted",
+            Event::Restored => "PublisherRestored",
+        }
+    }
+}
@@ -2,15 +2,32 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub mod event;
 pub mod vo;
 
 #[async_trait]
 pub trait Repository: Sync + Send {
-    async fn find_all(&self) -> anyhow::Result<Vec<Publisher>>;
-    async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<Publisher>>;
+    async fn find_all(&self, include_deleted: bool) -> anyhow::Result<Vec<Publisher>>;
+    async fn find_by_pub_id(
+        &self,
+        pub_id: uuid::Uuid,
+        include_deleted: bool,
+    ) -> anyhow::Result<Option<Publisher>>;
+    async fn find_by_pub_ids(
+        &self,
+        pub_ids: &[uuid::Uuid],
+        include_deleted: bool,
+    ) -> anyhow::Result<Vec<Publisher>>;
+    async fn find_by_names(&self, names: &[String]) -> anyhow::Result<Vec<Publisher>>;
     async fn create(&self, item: Publisher) -> anyhow::Result<Publisher>;
     async fn update(&self, item: Publisher) -> anyhow::Result<Publisher>;
-    async fn delete(&self, item: Publisher) -> anyhow::Result<()>;
+    /// 論理削除されていない Book から参照されているか
+    async fn has_books(&self, id: i32) -> anyhow::Result<bool>;
+    /// deleted_at が指定日時より前の論理削除済みレコードを物理削除し、件数を返す
+    async fn purge_deleted(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<u64>;
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +39,10 @@ pub struct Publisher {
     updated_at: chrono::DateTime<chrono::Utc>,
     created_by: String,
     updated_by: String,
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    deleted_by: Option<String>,
+    #[serde(skip)]
+    events: Vec<event::Event>,
 }
 
 impl Publisher {
@@ -35,9 +56,13 @@ impl Publisher {
             updated_at: now,
             created_by: created_by.clone(),
             updated_by: created_by,
+            deleted_at: None,
+            deleted_by: None,
+            events: vec![event::Event::Created],
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn reconstruct(
         id: i32,
         pub_id: uuid::Uuid,
@@ -46,6 +71,8 @@ impl Publisher {
         updated_at: chrono::DateTime<chrono::Utc>,
         created_by: String,
         updated_by: String,
+        deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+        deleted_by: Option<String>,
     ) -> Self {
         Self {
             id,
@@ -55,6 +82,9 @@ impl Publisher {
             updated_at,
             created_by,
             updated_by,
+            deleted_at,
+            deleted_by,
+            events: Vec::new(),
         }
     }
 
@@ -79,6 +109,24 @@ impl Publisher {
     pub fn updated_by(&self) -> String {
         self.updated_by.clone()
     }
+    pub fn deleted_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.deleted_at
+    }
+    pub fn deleted_by(&self) -> Option<String> {
+        self.deleted_by.clone()
+    }
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn events(&self) -> &[event::Event] {
+        &self.events
+    }
+
+    /// 記録済みのイベントを取り出す
+    pub fn take_events(&mut self) -> Vec<event::Event> {
+        std::mem::take(&mut self.events)
+    }
 
     fn update_audit(&mut self, updated_by: String) {
         self.updated_at = chrono::Utc::now();
@@ -90,10 +138,44 @@ impl Publisher {
         name: vo::PublisherName,
         updated_by: String,
     ) -> Result<(), DomainError> {
+        if self.name != name {
+            self.events.push(event::Event::Renamed {
+                old: self.name.value().to_string(),
+                new: name.value().to_string(),
+            });
+        }
         self.name = name;
         self.update_audit(updated_by);
         Ok(())
     }
+
+    pub fn delete(&mut self, deleted_by: String) -> Result<(), DomainError> {
+        if self.is_deleted() {
+            return Err(DomainError::DomainRuleViolation(
+                "Publisher is already deleted.".to_string(),
+            ));
+        }
+
+        self.deleted_at = Some(chrono::Utc::now());
+        self.deleted_by = Some(deleted_by.clone());
+        self.update_audit(deleted_by);
+        self.events.push(event::Event::Deleted);
+        Ok(())
+    }
+
+    pub fn restore(&mut self, restored_by: String) -> Result<(), DomainError> {
+        if !self.is_deleted() {
+            return Err(DomainError::DomainRuleViolation(
+                "Publisher is not deleted.".to_string(),
+            ));
+        }
+
+        self.deleted_at = None;
+        self.deleted_by = None;
+        self.update_audit(restored_by);
+        self.events.push(event::Event::Restored);
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
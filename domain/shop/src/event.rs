@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Shop に起きた変更
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Created,
+    Renamed { old: String, new: String },
+    Deleted,
+    Restored,
+}
+
+impl Event {
+    /// name() が返しうる全てのイベント名
+    pub const NAMES: [&'static str; 4] =
+        ["ShopCreated", "ShopRenamed", "ShopDeleted", "ShopRestored"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Created => "ShopCreated",
+            Event::Renamed { .. } => "ShopRenamed",
+            Event::Deleted => "ShopDeleted",
+            Event::Restored => "ShopRestored",
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod vo;
+
+#[async_trait]
+pub trait SubscriptionRepository: Sync + Send {
+    async fn find_all(&self) -> anyhow::Result<Vec<Subscription>>;
+    async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<Subscription>>;
+    async fn create(&self, item: Subscription) -> anyhow::Result<Subscription>;
+    /// 配信の記録もまとめて削除する
+    async fn delete(&self, item: Subscription) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+pub trait DeliveryRepository: Sync + Send {
+    /// 同じ購読とメッセージの組み合わせが既にあれば追加しない。追加した件数を返す
+    async fn enqueue(&self, items: Vec<Delivery>) -> anyhow::Result<u64>;
+    /// 配信予定時刻を過ぎた pending の配信を古い順に返す
+    async fn find_due(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: u64,
+    ) -> anyhow::Result<Vec<Delivery>>;
+    /// 新しい順に返す。status があればその状態のものだけを返す
+    async fn find_by_subscription(
+        &self,
+        subscription_id: i32,
+        status: Option<vo::DeliveryStatus>,
+        limit: u64,
+    ) -> anyhow::Result<Vec<Delivery>>;
+    async fn update(&self, item: Delivery) -> anyhow::Result<Delivery>;
+    /// delivered_at が指定日時より前の配信済みの記録を削除し、件数を返す
+    async fn purge_delivered(&self, before: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64>;
+}
+
+/// 署名済みの本文を送る HTTP クライアント
+#[async_trait]
+pub trait Sender: Sync + Send {
+    /// 応答のステータスコードを返す。接続できなかったときやタイムアウトはエラーにする
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &str,
+    ) -> anyhow::Result<u16>;
+}
+
+/// 外部のシステムが受け取りたいイベントの種類と配信先
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    id: i32,
+    pub_id: uuid::Uuid,
+    url: vo::WebhookUrl,
+    /// BookPriceChanged のようなイベント名
+    event_types: Vec<String>,
+    secret: vo::Secret,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Subscription {
+    pub fn new(
+        pub_id: uuid::Uuid,
+        url: vo::WebhookUrl,
+        event_types: Vec<String>,
+        secret: vo::Secret,
+    ) -> Result<Self, DomainError> {
+        if event_types.is_empty() {
+            return Err(DomainError::DomainRuleViolation(
+                "At least one event type is required.".to_string(),
+            ));
+        }
+        let mut event_types = event_types;
+        event_types.sort();
+        event_types.dedup();
+        Ok(Self {
+            id: 0,
+            pub_id,
+            url,
+            event_types,
+            secret,
+            created_at: chrono::Utc::now(),
+        })
+    }
+
+    pub fn reconstruct(
+        id: i32,
+        pub_id: uuid::Uuid,
+        url: vo::WebhookUrl,
+        event_types: Vec<String>,
+        secret: vo::Secret,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            id,
+            pub_id,
+            url,
+            event_types,
+            secret,
+            created_at,
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+    pub fn pub_id(&self) -> uuid::Uuid {
+        self.pub_id
+    }
+    pub fn url(&self) -> &str {
+        self.url.value()
+    }
+    pub fn event_types(&self) -> &[String] {
+        &self.event_types
+    }
+    pub fn secret(&self) -> &str {
+        self.secret.value()
+    }
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+    }
+
+    pub fn subscribes(&self, event_type: &str) -> bool {
+        self.event_types.iter().any(|t| t == event_type)
+    }
+}
+
+/// 1 つの購読に 1 つのイベントを届ける記録。再試行しても本文は変えない
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    id: i64,
+    subscription_id: i32,
+    /// 元になった outbox のメッセージ
+    message_id: i64,
+    event_type: String,
+    payload: String,
+    status: vo::DeliveryStatus,
+    attempts: u32,
+    next_attempt_at: chrono::DateTime<chrono::Utc>,
+    /// 最後に受け取った応答のステータスコード
+    response_status: Option<u16>,
+    last_error: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Delivery {
+    pub fn new(subscription_id: i32, message_id: i64, event_type: String, payload: String) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            id: 0,
+            subscription_id,
+            message_id,
+            event_type,
+            payload,
+            status: vo::DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            response_status: None,
+            last_error: None,
+            created_at: now,
+            delivered_at: None,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn reconstruct(
+        id: i64,
+        subscription_id: i32,
+        message_id: i64,
+        event_type: String,
+        payload: String,
+        status: vo::DeliveryStatus,
+        attempts: u32,
+        next_attempt_at: chrono::DateTime<chrono::Utc>,
+        response_status: Option<u16>,
+        last_error: Option<String>,
+        created_at: chrono::DateTime<chrono::Utc>,
+        delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Self {
+        Self {
+            id,
+            subscription_id,
+            message_id,
+            event_type,
+            payload,
+            status,
+            attempts,
+            next_attempt_at,
+            response_status,
+            last_error,
+            created_at,
+            delivered_at,
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+    pub fn subscription_id(&self) -> i32 {
+        self.subscription_id
+    }
+    pub fn message_id(&self) -> i64 {
+        self.message_id
+    }
+    pub fn event_type(&self) -> &str {
+        &self.event_type
+    }
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+    pub fn status(&self) -> vo::DeliveryStatus {
+        self.status
+    }
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+    pub fn next_attempt_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.next_attempt_at
+    }
+    pub fn response_status(&self) -> Option<u16> {
+        self.response_status
+    }
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+    }
+    pub fn delivered_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.delivered_at
+    }
+
+    pub fn delivered(&mut self, response_status: u16, now: chrono::DateTime<chrono::Utc>) {
+        self.attempts += 1;
+        self.status = vo::DeliveryStatus::Delivered;
+        self.response_status = Some(response_status);
+        self.last_error = None;
+        self.delivered_at = Some(now);
+    }
+
+    /// 失敗を記録して次の配信予定を決める。再試行の上限に達したら dead にする
+    pub fn failed(
+        &mut self,
+        error: String,
+        response_status: Option<u16>,
+        now: chrono::DateTime<chrono::Utc>,
+        policy: &outbox::vo::RetryPolicy,
+    ) {
+        self.attempts += 1;
+        self.response_status = response_status;
+        self.last_error = Some(error);
+        if self.attempts >= policy.max_attempts() {
+            self.status = vo::DeliveryStatus::Dead;
+        } else {
+            self.next_attempt_at = now + policy.backoff(self.attempts);
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DomainError {
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+    #[error("Domain rule violation: {0}")]
+    DomainRuleViolation(String),
+}
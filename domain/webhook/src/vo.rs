@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use crate::DomainError;
+
+/// 配信先の URL。http(s) のみ受け付け、内部のネットワークを指すホストは断る
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct WebhookUrl(String);
+
+impl WebhookUrl {
+    pub fn new(url: String) -> Result<Self, DomainError> {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(DomainError::InvalidFormat(
+                "URL must start with http:// or https://".to_string(),
+            ));
+        }
+        if url.len() > 2048 {
+            return Err(DomainError::InvalidFormat(
+                "URL must be 2048 chars or less".to_string(),
+            ));
+        }
+        let host = host(&url);
+        let internal = match host.parse::<IpAddr>() {
+            Ok(ip) => !is_public(&ip),
+            Err(_) => {
+                let name = host.trim_end_matches('.').to_ascii_lowercase();
+                name.is_empty() || name == "localhost" || name.ends_with(".localhost")
+            }
+        };
+        if internal {
+            return Err(DomainError::InvalidFormat(
+                "URL must not point to a loopback, private or link-local address".to_string(),
+            ));
+        }
+        Ok(Self(url))
+    }
+
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// scheme の後ろから、ユーザー情報とポートを除いたホストを取り出す。IPv6 の [] も外す
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    match host_port.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    }
+}
+
+/// 配信してよいアドレスか。ループバック、プライベート、リンクローカル、未指定などを除く。
+/// 配信時に名前解決した結果もこれで確かめる
+pub fn is_public(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                // 100.64.0.0/10 (CGNAT)
+                || (v4.octets()[0] == 100 && v4.octets()[1] & 0xc0 == 64))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public(&IpAddr::V4(v4)),
+            None => {
+                !(v6.is_loopback()
+                    || v6.is_unspecified()
+                    || v6.is_unique_local()
+                    || v6.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// 署名に使う共有の秘密。推測されないよう最低の長さを決める
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: String) -> Result<Self, DomainError> {
+        let len = secret.chars().count();
+        if !(16..=128).contains(&len) {
+            return Err(DomainError::InvalidFormat(
+                "Secret must be between 16 and 128 chars".to_string(),
+            ));
+        }
+        Ok(Self(secret))
+    }
+
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// ログに秘密を出さない
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+/// 配信の状況
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// 配信待ち。失敗して再試行を待っているものも含む
+    Pending,
+    Delivered,
+    /// 再試行の上限に達したもの
+    Dead,
+}
+
+impl DeliveryStatus {
+    pub const ALL: [DeliveryStatus; 3] = [
+        DeliveryStatus::Pending,
+        DeliveryStatus::Delivered,
+        DeliveryStatus::Dead,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Dead => "dead",
+        }
+    }
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DeliveryStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| DomainError::InvalidFormat(format!("Unknown delivery status: {}", s)))
+    }
+}
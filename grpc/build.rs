@@ -0,0 +1,7 @@
+// protoc を入れずにビルドできるよう、protox で記述子を作ってからコードを生成する
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    let descriptors = protox::compile(["catalog/v1/catalog.proto"], ["proto"])?;
+    tonic_prost_build::configure().compile_fds(descriptors)?;
+    Ok(())
+}
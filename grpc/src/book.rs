@@ -0,0 +1,171 @@
+use crate::proto::{
+    Book, BookPublisher, BookShop, ChangeAppliedAtRequest, CreateBookRequest, GetRequest,
+    ListBooksResponse, ListRequest, PubIdRequest, UpdateBookRequest,
+    book_service_server::BookService,
+};
+use crate::{Auth, datetime, pub_id, status, timestamp};
+use tonic::{Request, Response, Status};
+
+pub struct Service(pub(crate) Auth);
+
+impl From<usecase::book::ResponseDto> for Book {
+    fn from(dto: usecase::book::ResponseDto) -> Self {
+        Self {
+            pub_id: dto.pub_id.to_string(),
+            title: dto.title,
+            author: dto.author,
+            publisher: Some(BookPublisher {
+                pub_id: dto.publisher.pub_id.to_string(),
+                name: dto.publisher.name,
+            }),
+            shop: dto.shop.map(|s| BookShop {
+                pub_id: s.pub_id.to_string(),
+                name: s.name,
+            }),
+            applied_at: dto.applied_at.map(timestamp),
+            format: dto.format,
+            price: dto.price,
+            deleted_at: dto.deleted_at.map(timestamp),
+        }
+    }
+}
+
+impl TryFrom<CreateBookRequest> for usecase::book::CreateDto {
+    type Error = Status;
+
+    fn try_from(request: CreateBookRequest) -> Result<Self, Status> {
+        Ok(Self {
+            title: request.title,
+            author: request.author,
+            publisher_id: pub_id("publisher_id", &request.publisher_id)?,
+            shop_id: request
+                .shop_id
+                .map(|id| pub_id("shop_id", &id))
+                .transpose()?,
+            format: request.format,
+            price: request.price,
+        })
+    }
+}
+
+impl TryFrom<UpdateBookRequest> for usecase::book::UpdateDto {
+    type Error = Status;
+
+    fn try_from(request: UpdateBookRequest) -> Result<Self, Status> {
+        Ok(Self {
+            title: request.title,
+            author: request.author,
+            publisher_id: pub_id("publisher_id", &request.publisher_id)?,
+            shop_id: request
+                .shop_id
+                .map(|id| pub_id("shop_id", &id))
+                .transpose()?,
+            format: request.format,
+            price: request.price,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl BookService for Service {
+    async fn list_books(
+        &self,
+        request: Request<ListRequest>,
+    ) -> Result<Response<ListBooksResponse>, Status> {
+        let state = self
+            .0
+            .authorize_include_deleted(&request, "books:read", request.get_ref().include_deleted)
+            .await?;
+        let books = state
+            .book_usecase
+            .get_all(request.get_ref().include_deleted)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(ListBooksResponse {
+            books: books.into_iter().map(Book::from).collect(),
+        }))
+    }
+
+    async fn get_book(&self, request: Request<GetRequest>) -> Result<Response<Book>, Status> {
+        let state = self
+            .0
+            .authorize_include_deleted(&request, "books:read", request.get_ref().include_deleted)
+            .await?;
+        let request = request.get_ref();
+        let book = state
+            .book_usecase
+            .get(pub_id("pub_id", &request.pub_id)?, request.include_deleted)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(book.into()))
+    }
+
+    async fn create_book(
+        &self,
+        request: Request<CreateBookRequest>,
+    ) -> Result<Response<Book>, Status> {
+        let state = self.0.authorize(&request, "books:write").await?;
+        let book = state
+            .book_usecase
+            .create(request.into_inner().try_into()?)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(book.into()))
+    }
+
+    async fn update_book(
+        &self,
+        request: Request<UpdateBookRequest>,
+    ) -> Result<Response<Book>, Status> {
+        let state = self.0.authorize(&request, "books:write").await?;
+        let request = request.into_inner();
+        let id = pub_id("pub_id", &request.pub_id)?;
+        let book = state
+            .book_usecase
+            .update(id, request.try_into()?)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(book.into()))
+    }
+
+    async fn delete_book(&self, request: Request<PubIdRequest>) -> Result<Response<()>, Status> {
+        let state = self.0.authorize(&request, "books:write").await?;
+        state
+            .book_usecase
+            .delete(pub_id("pub_id", &request.get_ref().pub_id)?)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(()))
+    }
+
+    async fn restore_book(&self, request: Request<PubIdRequest>) -> Result<Response<Book>, Status> {
+        let state = self.0.authorize(&request, "books:write").await?;
+        let book = state
+            .book_usecase
+            .restore(pub_id("pub_id", &request.get_ref().pub_id)?)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(book.into()))
+    }
+
+    async fn change_applied_at(
+        &self,
+        request: Request<ChangeAppliedAtRequest>,
+    ) -> Result<Response<Book>, Status> {
+        let state = self.0.authorize(&request, "books:write").await?;
+        let request = request.into_inner();
+        let applied_at = request
+            .applied_at
+            .map(|at| datetime("applied_at", at))
+            .transpose()?;
+        let book = state
+            .book_usecase
+            .change_applied_at(
+                pub_id("pub_id", &request.pub_id)?,
+                usecase::book::ChangeAppliedAtDto { applied_at },
+            )
+            .await
+            .map_err(status)?;
+        Ok(Response::new(book.into()))
+    }
+}
@@ -0,0 +1,142 @@
+use api::AppState;
+use api::auth::{Caller, Denied};
+use std::future::Future;
+use std::sync::Arc;
+use tonic::{Request, Status};
+use usecase::error::UseCaseError;
+
+pub mod book;
+pub mod publisher;
+pub mod shop;
+mod test;
+
+pub mod proto {
+    tonic::include_proto!("catalog.v1");
+}
+
+use proto::book_service_server::BookServiceServer;
+use proto::publisher_service_server::PublisherServiceServer;
+use proto::shop_service_server::ShopServiceServer;
+
+/// 全てのサービスを載せたルーティング。生成したクライアントにそのまま渡すとプロセス内で呼べる
+pub fn routes(state: Arc<AppState>, allow_anonymous: bool) -> tonic::service::Routes {
+    let auth = Auth {
+        state,
+        allow_anonymous,
+    };
+    tonic::service::Routes::new(BookServiceServer::new(book::Service(auth.clone())))
+        .add_service(PublisherServiceServer::new(publisher::Service(
+            auth.clone(),
+        )))
+        .add_service(ShopServiceServer::new(shop::Service(auth)))
+}
+
+/// shutdown が完了したら新しい呼び出しを断り、処理中のものを終えてから戻る
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    routes: tonic::service::Routes,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_routes(routes)
+        .serve_with_incoming_shutdown(
+            tokio_stream::wrappers::TcpListenerStream::new(listener),
+            shutdown,
+        )
+        .await
+}
+
+#[derive(Clone)]
+pub(crate) struct Auth {
+    state: Arc<AppState>,
+    allow_anonymous: bool,
+}
+
+impl Auth {
+    /// metadata の authorization を REST と同じく検証し、操作に必要なスコープを確かめる
+    pub(crate) async fn authorize<T>(
+        &self,
+        request: &Request<T>,
+        scope: &str,
+    ) -> Result<&AppState, Status> {
+        self.caller(request, scope).await?;
+        Ok(&self.state)
+    }
+
+    /// authorize に加え、論理削除済みを含める場合は REST と同じく admin スコープを確かめる
+    pub(crate) async fn authorize_include_deleted<T>(
+        &self,
+        request: &Request<T>,
+        scope: &str,
+        include_deleted: bool,
+    ) -> Result<&AppState, Status> {
+        self.caller(request, scope)
+            .await?
+            .authorize_include_deleted(include_deleted)
+            .map_err(denied)?;
+        Ok(&self.state)
+    }
+
+    async fn caller<T>(&self, request: &Request<T>, scope: &str) -> Result<Caller, Status> {
+        let credentials = request
+            .metadata()
+            .get("authorization")
+            .map(|v| v.to_str().unwrap_or_default());
+        let principal = api::auth::authenticate(&self.state, credentials)
+            .await
+            .map_err(status)?;
+        let caller = Caller::new(principal, self.allow_anonymous);
+        caller.authorize(scope).map_err(denied)?;
+        Ok(caller)
+    }
+}
+
+fn denied(denied: Denied) -> Status {
+    match denied {
+        Denied::Unauthenticated(m) => Status::unauthenticated(m),
+        Denied::Forbidden(m) => Status::permission_denied(m),
+    }
+}
+
+/// REST で 4xx / 5xx にするものを、対応する gRPC のステータスにする
+pub(crate) fn status(e: UseCaseError) -> Status {
+    usecase::metrics::validation_failed(&e);
+    match e {
+        UseCaseError::NotFound(m) => Status::not_found(m),
+        UseCaseError::Unauthorized(m) => Status::unauthenticated(m),
+        UseCaseError::InternalServerError | UseCaseError::DatabaseError => {
+            Status::internal("Internal Server Error")
+        }
+        UseCaseError::DomainRuleViolation(m) => Status::invalid_argument(m),
+        UseCaseError::BookDomainError(e) => Status::invalid_argument(e.to_string()),
+        UseCaseError::PublisherDomainError(e) => Status::invalid_argument(e.to_string()),
+        UseCaseError::ShopDomainError(e) => Status::invalid_argument(e.to_string()),
+        UseCaseError::IdempotencyDomainError(e) => Status::invalid_argument(e.to_string()),
+        UseCaseError::ApiKeyDomainError(e) => Status::invalid_argument(e.to_string()),
+        UseCaseError::ChangeEventDomainError(e) => Status::invalid_argument(e.to_string()),
+        UseCaseError::OutboxDomainError(e) => Status::invalid_argument(e.to_string()),
+        UseCaseError::WebhookDomainError(e) => Status::invalid_argument(e.to_string()),
+    }
+}
+
+pub(crate) fn pub_id(field: &str, value: &str) -> Result<uuid::Uuid, Status> {
+    uuid::Uuid::parse_str(value)
+        .map_err(|_| Status::invalid_argument(format!("{} must be a UUID", field)))
+}
+
+pub(crate) fn timestamp(at: chrono::DateTime<chrono::Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_nanos() as i32,
+    }
+}
+
+pub(crate) fn datetime(
+    field: &str,
+    at: prost_types::Timestamp,
+) -> Result<chrono::DateTime<chrono::Utc>, Status> {
+    u32::try_from(at.nanos)
+        .ok()
+        .and_then(|nanos| chrono::DateTime::from_timestamp(at.seconds, nanos))
+        .ok_or_else(|| Status::invalid_argument(format!("{} is out of range", field)))
+}
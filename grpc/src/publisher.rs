@@ -0,0 +1,122 @@
+use crate::proto::{
+    CreatePublisherRequest, GetRequest, ListPublishersResponse, ListRequest, PubIdRequest,
+    Publisher, UpdatePublisherRequest, publisher_service_server::PublisherService,
+};
+use crate::{Auth, pub_id, status, timestamp};
+use tonic::{Request, Response, Status};
+
+pub struct Service(pub(crate) Auth);
+
+impl From<usecase::publisher::ResponseDto> for Publisher {
+    fn from(dto: usecase::publisher::ResponseDto) -> Self {
+        Self {
+            pub_id: dto.pub_id.to_string(),
+            name: dto.name,
+            deleted_at: dto.deleted_at.map(timestamp),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl PublisherService for Service {
+    async fn list_publishers(
+        &self,
+        request: Request<ListRequest>,
+    ) -> Result<Response<ListPublishersResponse>, Status> {
+        let state = self
+            .0
+            .authorize_include_deleted(
+                &request,
+                "publishers:read",
+                request.get_ref().include_deleted,
+            )
+            .await?;
+        let publishers = state
+            .publisher_usecase
+            .get_all(request.get_ref().include_deleted)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(ListPublishersResponse {
+            publishers: publishers.into_iter().map(Publisher::from).collect(),
+        }))
+    }
+
+    async fn get_publisher(
+        &self,
+        request: Request<GetRequest>,
+    ) -> Result<Response<Publisher>, Status> {
+        let state = self
+            .0
+            .authorize_include_deleted(
+                &request,
+                "publishers:read",
+                request.get_ref().include_deleted,
+            )
+            .await?;
+        let request = request.get_ref();
+        let publisher = state
+            .publisher_usecase
+            .get(pub_id("pub_id", &request.pub_id)?, request.include_deleted)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(publisher.into()))
+    }
+
+    async fn create_publisher(
+        &self,
+        request: Request<CreatePublisherRequest>,
+    ) -> Result<Response<Publisher>, Status> {
+        let state = self.0.authorize(&request, "publishers:write").await?;
+        let publisher = state
+            .publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: request.into_inner().name,
+            })
+            .await
+            .map_err(status)?;
+        Ok(Response::new(publisher.into()))
+    }
+
+    async fn update_publisher(
+        &self,
+        request: Request<UpdatePublisherRequest>,
+    ) -> Result<Response<Publisher>, Status> {
+        let state = self.0.authorize(&request, "publishers:write").await?;
+        let request = request.into_inner();
+        let publisher = state
+            .publisher_usecase
+            .update(
+                pub_id("pub_id", &request.pub_id)?,
+                usecase::publisher::UpdateDto { name: request.name },
+            )
+            .await
+            .map_err(status)?;
+        Ok(Response::new(publisher.into()))
+    }
+
+    async fn delete_publisher(
+        &self,
+        request: Request<PubIdRequest>,
+    ) -> Result<Response<()>, Status> {
+        let state = self.0.authorize(&request, "publishers:write").await?;
+        state
+            .publisher_usecase
+            .delete(pub_id("pub_id", &request.get_ref().pub_id)?)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(()))
+    }
+
+    async fn restore_publisher(
+        &self,
+        request: Request<PubIdRequest>,
+    ) -> Result<Response<Publisher>, Status> {
+        let state = self.0.authorize(&request, "publishers:write").await?;
+        let publisher = state
+            .publisher_usecase
+            .restore(pub_id("pub_id", &request.get_ref().pub_id)?)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(publisher.into()))
+    }
+}
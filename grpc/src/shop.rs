@@ -0,0 +1,105 @@
+use crate::proto::{
+    CreateShopRequest, GetRequest, ListRequest, ListShopsResponse, PubIdRequest, Shop,
+    UpdateShopRequest, shop_service_server::ShopService,
+};
+use crate::{Auth, pub_id, status, timestamp};
+use tonic::{Request, Response, Status};
+
+pub struct Service(pub(crate) Auth);
+
+impl From<usecase::shop::ResponseDto> for Shop {
+    fn from(dto: usecase::shop::ResponseDto) -> Self {
+        Self {
+            pub_id: dto.pub_id.to_string(),
+            name: dto.name,
+            deleted_at: dto.deleted_at.map(timestamp),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ShopService for Service {
+    async fn list_shops(
+        &self,
+        request: Request<ListRequest>,
+    ) -> Result<Response<ListShopsResponse>, Status> {
+        let state = self
+            .0
+            .authorize_include_deleted(&request, "shops:read", request.get_ref().include_deleted)
+            .await?;
+        let shops = state
+            .shop_usecase
+            .get_all(request.get_ref().include_deleted)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(ListShopsResponse {
+            shops: shops.into_iter().map(Shop::from).collect(),
+        }))
+    }
+
+    async fn get_shop(&self, request: Request<GetRequest>) -> Result<Response<Shop>, Status> {
+        let state = self
+            .0
+            .authorize_include_deleted(&request, "shops:read", request.get_ref().include_deleted)
+            .await?;
+        let request = request.get_ref();
+        let shop = state
+            .shop_usecase
+            .get(pub_id("pub_id", &request.pub_id)?, request.include_deleted)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(shop.into()))
+    }
+
+    async fn create_shop(
+        &self,
+        request: Request<CreateShopRequest>,
+    ) -> Result<Response<Shop>, Status> {
+        let state = self.0.authorize(&request, "shops:write").await?;
+        let shop = state
+            .shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: request.into_inner().name,
+            })
+            .await
+            .map_err(status)?;
+        Ok(Response::new(shop.into()))
+    }
+
+    async fn update_shop(
+        &self,
+        request: Request<UpdateShopRequest>,
+    ) -> Result<Response<Shop>, Status> {
+        let state = self.0.authorize(&request, "shops:write").await?;
+        let request = request.into_inner();
+        let shop = state
+            .shop_usecase
+            .update(
+                pub_id("pub_id", &request.pub_id)?,
+                usecase::shop::UpdateDto { name: request.name },
+            )
+            .await
+            .map_err(status)?;
+        Ok(Response::new(shop.into()))
+    }
+
+    async fn delete_shop(&self, request: Request<PubIdRequest>) -> Result<Response<()>, Status> {
+        let state = self.0.authorize(&request, "shops:write").await?;
+        state
+            .shop_usecase
+            .delete(pub_id("pub_id", &request.get_ref().pub_id)?)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(()))
+    }
+
+    async fn restore_shop(&self, request: Request<PubIdRequest>) -> Result<Response<Shop>, Status> {
+        let state = self.0.authorize(&request, "shops:write").await?;
+        let shop = state
+            .shop_usecase
+            .restore(pub_id("pub_id", &request.get_ref().pub_id)?)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(shop.into()))
+    }
+}
@@ -0,0 +1,212 @@
+#[cfg(test)]
+mod tests {
+    // SQLite 上の usecase をプロセス内のクライアントから呼び出して検証する
+    use crate::proto::book_service_client::BookServiceClient;
+    use crate::proto::publisher_service_client::PublisherServiceClient;
+    use crate::proto::shop_service_client::ShopServiceClient;
+    use crate::proto::*;
+    use migration::{Migrator, MigratorTrait};
+    use rstest::*;
+    use sea_orm::{Database, DatabaseConnection};
+    use std::sync::Arc;
+    use tonic::Code;
+
+    #[fixture]
+    async fn db() -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to SQLite");
+        Migrator::up(&db, None)
+            .await
+            .expect("Failed to run migrations");
+        db
+    }
+
+    fn state(db: &DatabaseConnection) -> Arc<api::AppState> {
+        Arc::new(infra::test::state(db))
+    }
+
+    fn with_key<T>(message: T, key: &str) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        request
+            .metadata_mut()
+            .insert("authorization", key.parse().unwrap());
+        request
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_book_lifecycle_over_in_process_client(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let routes = crate::routes(state(&db), true);
+        let mut books = BookServiceClient::new(routes.clone());
+        let mut publishers = PublisherServiceClient::new(routes.clone());
+        let mut shops = ShopServiceClient::new(routes);
+
+        let publisher = publishers
+            .create_publisher(CreatePublisherRequest {
+                name: "gRPC Press".to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let shop = shops
+            .create_shop(CreateShopRequest {
+                name: "gRPC Books".to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        let created = books
+            .create_book(CreateBookRequest {
+                title: "Protobuf".to_string(),
+                author: "Author".to_string(),
+                publisher_id: publisher.pub_id.clone(),
+                shop_id: Some(shop.pub_id.clone()),
+                format: None,
+                price: 1000,
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(created.format, "Real");
+        assert_eq!(created.publisher.as_ref().unwrap().name, "gRPC Press");
+        assert_eq!(created.shop.as_ref().unwrap().name, "gRPC Books");
+        assert!(created.applied_at.is_none());
+
+        let update = |price| UpdateBookRequest {
+            pub_id: created.pub_id.clone(),
+            title: "Protobuf".to_string(),
+            author: "Author".to_string(),
+            publisher_id: publisher.pub_id.clone(),
+            shop_id: None,
+            format: Some("EBook".to_string()),
+            price,
+        };
+        let updated = books.update_book(update(1200)).await.unwrap().into_inner();
+        assert_eq!((updated.price, updated.shop), (1200, None));
+
+        let applied_at = chrono::DateTime::parse_from_rfc3339("2026-01-01T00:00:00.5Z")
+            .unwrap()
+            .to_utc();
+        let applied = books
+            .change_applied_at(ChangeAppliedAtRequest {
+                pub_id: created.pub_id.clone(),
+                applied_at: Some(crate::timestamp(applied_at)),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            crate::datetime("applied_at", applied.applied_at.unwrap()).unwrap(),
+            applied_at
+        );
+        // 適用済みの Book は変更できない
+        let error = books.update_book(update(1500)).await.unwrap_err();
+        assert_eq!(error.code(), Code::InvalidArgument);
+        assert!(
+            error.message().contains("already applied"),
+            "{}",
+            error.message()
+        );
+
+        books
+            .delete_book(PubIdRequest {
+                pub_id: created.pub_id.clone(),
+            })
+            .await
+            .unwrap();
+        let listed = books
+            .list_books(ListRequest {
+                include_deleted: false,
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(listed.books.is_empty());
+        let error = books
+            .get_book(GetRequest {
+                pub_id: created.pub_id.clone(),
+                include_deleted: false,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::NotFound);
+        let restored = books
+            .restore_book(PubIdRequest {
+                pub_id: created.pub_id.clone(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(restored.deleted_at.is_none());
+
+        let error = books
+            .get_book(GetRequest {
+                pub_id: "not-a-uuid".to_string(),
+                include_deleted: false,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::InvalidArgument);
+        let error = publishers
+            .create_publisher(CreatePublisherRequest {
+                name: "x".repeat(33),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::InvalidArgument);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_api_key_scopes_are_enforced(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let state = state(&db);
+        let key = state
+            .api_key_usecase
+            .issue(usecase::api_key::IssueDto {
+                name: "Inventory".to_string(),
+                owner: "Backend".to_string(),
+                scopes: vec!["shops:read".to_string()],
+                expires_at: None,
+            })
+            .await
+            .expect("Failed to issue key");
+        let key = format!("ApiKey {}", key.secret);
+        let mut shops = ShopServiceClient::new(crate::routes(state, false));
+
+        let error = shops.list_shops(ListRequest::default()).await.unwrap_err();
+        assert_eq!(error.code(), Code::Unauthenticated);
+        let error = shops
+            .list_shops(with_key(ListRequest::default(), "ApiKey wrong.secret"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::Unauthenticated);
+        shops
+            .list_shops(with_key(ListRequest::default(), &key))
+            .await
+            .unwrap();
+        let error = shops
+            .create_shop(with_key(
+                CreateShopRequest {
+                    name: "Denied".to_string(),
+                },
+                &key,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::PermissionDenied);
+        // 論理削除済みを含めて読めるのは admin スコープだけ
+        let include_deleted = ListRequest {
+            include_deleted: true,
+        };
+        let error = shops
+            .list_shops(with_key(include_deleted, &key))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::PermissionDenied);
+        assert!(error.message().contains("admin"), "{}", error.message());
+    }
+}
@@ -0,0 +1,145 @@
+use async_trait::async_trait;
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::StringLen;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, QueryOrder, Set};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "api_key")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pub_id: uuid::Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(64))")]
+    pub name: String,
+    #[sea_orm(column_type = "String(StringLen::N(64))")]
+    pub owner: String,
+    /// 空白区切りのスコープ
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub scopes: String,
+    #[sea_orm(unique, column_type = "String(StringLen::N(16))")]
+    pub prefix: String,
+    #[sea_orm(column_type = "String(StringLen::N(64))")]
+    pub key_hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub struct SqlRepository {
+    pub(crate) db: DatabaseConnection,
+}
+
+impl SqlRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    fn to_domain(model: Model) -> anyhow::Result<api_key::ApiKey> {
+        let name = api_key::vo::ApiKeyName::new(model.name)
+            .map_err(|e| anyhow::anyhow!("Invalid name in DB: {}", e))?;
+        let owner = api_key::vo::Owner::new(model.owner)
+            .map_err(|e| anyhow::anyhow!("Invalid owner in DB: {}", e))?;
+        let scopes = model
+            .scopes
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<api_key::vo::Scope>, _>>()
+            .map_err(|e| anyhow::anyhow!("Invalid scopes in DB: {}", e))?;
+        Ok(api_key::ApiKey::reconstruct(
+            model.id,
+            model.pub_id,
+            name,
+            owner,
+            scopes,
+            model.prefix,
+            model.key_hash,
+            model.created_at,
+            model.last_used_at,
+            model.expires_at,
+            model.revoked_at,
+        ))
+    }
+
+    fn to_active_model(item: &api_key::ApiKey) -> ActiveModel {
+        ActiveModel {
+            id: Set(item.id()),
+            pub_id: Set(item.pub_id()),
+            name: Set(item.name()),
+            owner: Set(item.owner()),
+            scopes: Set(item
+                .scopes()
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")),
+            prefix: Set(item.prefix().to_string()),
+            key_hash: Set(item.key_hash().to_string()),
+            created_at: Set(item.created_at()),
+            last_used_at: Set(item.last_used_at()),
+            expires_at: Set(item.expires_at()),
+            revoked_at: Set(item.revoked_at()),
+        }
+    }
+}
+
+#[async_trait]
+impl api_key::Repository for SqlRepository {
+    #[tracing::instrument(skip(self))]
+    async fn find_all(&self) -> anyhow::Result<Vec<api_key::ApiKey>> {
+        let keys = Entity::find()
+            .order_by_asc(Column::Id)
+            .all(&self.db)
+            .await?;
+        keys.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<api_key::ApiKey>> {
+        let key = Entity::find()
+            .filter(Column::PubId.eq(pub_id))
+            .one(&self.db)
+            .await?;
+        key.map(Self::to_domain).transpose()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_by_prefix(&self, prefix: &str) -> anyhow::Result<Option<api_key::ApiKey>> {
+        let key = Entity::find()
+            .filter(Column::Prefix.eq(prefix))
+            .one(&self.db)
+            .await?;
+        key.map(Self::to_domain).transpose()
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = %item.pub_id()))]
+    async fn create(&self, item: api_key::ApiKey) -> anyhow::Result<api_key::ApiKey> {
+        let mut active_model = Self::to_active_model(&item);
+        active_model.id = sea_orm::ActiveValue::NotSet;
+        let result = active_model.insert(&self.db).await?;
+        Self::to_domain(result)
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = %item.pub_id()))]
+    async fn update(&self, item: api_key::ApiKey) -> anyhow::Result<api_key::ApiKey> {
+        let result = Self::to_active_model(&item).update(&self.db).await?;
+        Self::to_domain(result)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn touch(&self, id: i32, at: chrono::DateTime<chrono::Utc>) -> anyhow::Result<()> {
+        Entity::update_many()
+            .col_expr(Column::LastUsedAt, Expr::value(at))
+            .filter(Column::Id.eq(id))
+            .filter(Column::RevokedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,7 +1,15 @@
 use async_trait::async_trait;
 use sea_orm::entity::prelude::*;
-use sea_orm::sea_query::StringLen;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::sea_query::extension::postgres::PgExpr;
+use sea_orm::sea_query::{Alias, Condition, ExprTrait, LikeExpr, Order, Query, StringLen};
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, Set, TransactionTrait, TryIntoModel,
+};
+
+use crate::history;
+use futures::{StreamExt, TryStreamExt};
+use std::collections::HashMap;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "book")]
@@ -26,6 +34,9 @@ pub struct Model {
     pub created_by: String,
     #[sea_orm(column_type = "String(StringLen::N(32))")]
     pub updated_by: String,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[sea_orm(column_type = "String(StringLen::N(32))")]
+    pub deleted_by: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -56,7 +67,35 @@ impl Related<super::shop::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn after_save<C>(model: Model, db: &C, insert: bool) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let operation = if insert {
+            history::Operation::Insert
+        } else {
+            history::Operation::Update
+        };
+        history::record::<_, super::book_history::ActiveModel, _>(&model, operation, db).await?;
+        Ok(model)
+    }
+
+    async fn before_delete<C>(self, db: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let model = self.clone().try_into_model()?;
+        history::record::<_, super::book_history::ActiveModel, _>(
+            &model,
+            history::Operation::Delete,
+            db,
+        )
+        .await?;
+        Ok(self)
+    }
+}
 
 pub struct SqlRepository {
     pub(crate) db: DatabaseConnection,
@@ -94,6 +133,8 @@ impl SqlRepository {
                 p.updated_at,
                 p.created_by,
                 p.updated_by,
+                p.deleted_at,
+                p.deleted_by,
             )
         } else {
             return Err(anyhow::anyhow!("Publisher not found for book {}", model.id));
@@ -109,6 +150,8 @@ impl SqlRepository {
                 s.updated_at,
                 s.created_by,
                 s.updated_by,
+                s.deleted_at,
+                s.deleted_by,
             ))
         } else {
             None
@@ -128,14 +171,186 @@ impl SqlRepository {
             model.updated_at,
             model.created_by,
             model.updated_by,
+            model.deleted_at,
+            model.deleted_by,
         ))
     }
+
+    /// Shop はまとめて 1 クエリで引き当てる
+    async fn with_shops<C: ConnectionTrait>(
+        db: &C,
+        books_with_publishers: Vec<(Model, Option<super::publisher::Model>)>,
+    ) -> anyhow::Result<Vec<book::Book>> {
+        let shop_ids: Vec<i32> = books_with_publishers
+            .iter()
+            .filter_map(|(b, _)| b.shop_id)
+            .collect();
+        let shops: HashMap<i32, super::shop::Model> = super::shop::Entity::find()
+            .filter(super::shop::Column::Id.is_in(shop_ids))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|s| (s.id, s))
+            .collect();
+
+        books_with_publishers
+            .into_iter()
+            .map(|(b, p)| {
+                let shop = b.shop_id.and_then(|id| shops.get(&id).cloned());
+                Self::to_domain(b, p, shop)
+            })
+            .collect()
+    }
+
+    /// 1 語ぶんの条件。どれかのカラムに部分一致すればよい
+    /// 変更が参照する Publisher / Shop をまとめて 1 クエリずつで解決する
+    async fn resolve(
+        &self,
+        changes: &[book::Change],
+    ) -> anyhow::Result<(
+        HashMap<uuid::Uuid, super::publisher::Model>,
+        HashMap<uuid::Uuid, super::shop::Model>,
+    )> {
+        let items: Vec<&book::Book> = changes
+            .iter()
+            .map(|c| match c {
+                book::Change::Create(b) | book::Change::Update(b) => b,
+            })
+            .collect();
+        let publisher_ids: Vec<uuid::Uuid> = items.iter().map(|b| b.publisher().pub_id()).collect();
+        let publishers = super::publisher::Entity::find()
+            .filter(super::publisher::Column::PubId.is_in(publisher_ids))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|p| (p.pub_id, p))
+            .collect();
+        let shop_ids: Vec<uuid::Uuid> = items
+            .iter()
+            .filter_map(|b| b.shop().map(|s| s.pub_id()))
+            .collect();
+        let shops = super::shop::Entity::find()
+            .filter(super::shop::Column::PubId.is_in(shop_ids))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|s| (s.pub_id, s))
+            .collect();
+        Ok((publishers, shops))
+    }
+
+    /// 解決済みの Publisher / Shop を使って 1 件の変更を保存する
+    async fn save_change<C: ConnectionTrait>(
+        conn: &C,
+        change: book::Change,
+        publishers: &HashMap<uuid::Uuid, super::publisher::Model>,
+        shops: &HashMap<uuid::Uuid, super::shop::Model>,
+    ) -> anyhow::Result<book::Book> {
+        let (mut item, insert) = match change {
+            book::Change::Create(b) => (b, true),
+            book::Change::Update(b) => (b, false),
+        };
+        let publisher_model = publishers
+            .get(&item.publisher().pub_id())
+            .cloned()
+            .ok_or(anyhow::anyhow!("Publisher not found"))?;
+        let shop_model = match item.shop() {
+            Some(s) => Some(
+                shops
+                    .get(&s.pub_id())
+                    .cloned()
+                    .ok_or(anyhow::anyhow!("Shop not found"))?,
+            ),
+            None => None,
+        };
+
+        let mut active_model =
+            Self::to_active_model(&item, publisher_model.id, shop_model.as_ref().map(|s| s.id));
+        let result = if insert {
+            active_model.insert(conn).await?
+        } else {
+            active_model.id = Set(item.id());
+            active_model.update(conn).await?
+        };
+        crate::outbox::append(conn, "book", item.pub_id(), &item.take_events()).await?;
+        Self::to_domain(result, Some(publisher_model), shop_model)
+    }
+
+    fn search_condition(backend: DbBackend, term: &str) -> Condition {
+        let pattern = like_pattern(term);
+        let mut condition = Condition::any();
+        for column in search_columns() {
+            let like = LikeExpr::new(pattern.clone()).escape('\\');
+            condition = condition.add(match backend {
+                // pg_trgm の GIN インデックスが使われる
+                DbBackend::Postgres => column.ilike(like),
+                _ => column.like(like),
+            });
+        }
+        if backend == DbBackend::Postgres {
+            condition = condition.add(Expr::cust_with_values(
+                r#""book"."search_vector" @@ plainto_tsquery('simple', $1)"#,
+                [term],
+            ));
+        }
+        condition
+    }
+
+    /// 関連度。title > author > publisher / shop の順に重みを付ける
+    fn search_score(backend: DbBackend, terms: &[String]) -> Expr {
+        if backend == DbBackend::Postgres {
+            let q = terms.join(" ");
+            return Expr::cust_with_values(
+                r#"ts_rank("book"."search_vector", plainto_tsquery('simple', $1))
+                    + 2 * word_similarity($2, "book"."title")
+                    + word_similarity($3, "book"."author")
+                    + 0.5 * word_similarity($4, "publisher"."name")
+                    + 0.5 * word_similarity($5, coalesce("shop"."name", ''))"#,
+                [q.clone(), q.clone(), q.clone(), q.clone(), q],
+            );
+        }
+
+        let mut score = Expr::val(0);
+        for term in terms {
+            let pattern = like_pattern(term);
+            for (column, weight) in search_columns().into_iter().zip([4, 2, 1, 1]) {
+                let like = LikeExpr::new(pattern.clone()).escape('\\');
+                score = score.add(Expr::case(column.like(like), weight).finally(0));
+            }
+        }
+        score
+    }
+
+    fn to_active_model(item: &book::Book, publisher_id: i32, shop_id: Option<i32>) -> ActiveModel {
+        ActiveModel {
+            pub_id: Set(item.pub_id()),
+            publisher_id: Set(publisher_id),
+            shop_id: Set(shop_id),
+            title: Set(item.title()),
+            author: Set(item.author()),
+            price: Set(item.price()),
+            applied_at: Set(item.applied_at()),
+            format: Set(item.format().to_string()),
+            created_at: Set(item.created_at()),
+            updated_at: Set(item.updated_at()),
+            created_by: Set(item.created_by()),
+            updated_by: Set(item.updated_by()),
+            deleted_at: Set(item.deleted_at()),
+            deleted_by: Set(item.deleted_by()),
+            ..Default::default()
+        }
+    }
 }
 
 #[async_trait]
 impl book::Repository for SqlRepository {
-    async fn find_all(&self) -> anyhow::Result<Vec<book::Book>> {
-        let books_with_publishers = Entity::find()
+    #[tracing::instrument(skip(self))]
+    async fn find_all(&self, include_deleted: bool) -> anyhow::Result<Vec<book::Book>> {
+        let mut query = Entity::find();
+        if !include_deleted {
+            query = query.filter(Column::DeletedAt.is_null());
+        }
+        let books_with_publishers = query
             .find_also_related(super::publisher::Entity)
             .all(&self.db)
             .await?;
@@ -155,9 +370,17 @@ impl book::Repository for SqlRepository {
         Ok(books)
     }
 
-    async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<book::Book>> {
-        let result = Entity::find()
-            .filter(Column::PubId.eq(pub_id))
+    #[tracing::instrument(skip(self))]
+    async fn find_by_pub_id(
+        &self,
+        pub_id: uuid::Uuid,
+        include_deleted: bool,
+    ) -> anyhow::Result<Option<book::Book>> {
+        let mut query = Entity::find().filter(Column::PubId.eq(pub_id));
+        if !include_deleted {
+            query = query.filter(Column::DeletedAt.is_null());
+        }
+        let result = query
             .find_also_related(super::publisher::Entity)
             .one(&self.db)
             .await?;
@@ -177,7 +400,132 @@ impl book::Repository for SqlRepository {
         }
     }
 
-    async fn create(&self, item: book::Book) -> anyhow::Result<book::Book> {
+    #[tracing::instrument(skip_all, fields(count = pub_ids.len()))]
+    async fn find_by_pub_ids(&self, pub_ids: &[uuid::Uuid]) -> anyhow::Result<Vec<book::Book>> {
+        let books_with_publishers = Entity::find()
+            .filter(Column::PubId.is_in(pub_ids.iter().copied()))
+            .filter(Column::DeletedAt.is_null())
+            .find_also_related(super::publisher::Entity)
+            .all(&self.db)
+            .await?;
+
+        Self::with_shops(&self.db, books_with_publishers).await
+    }
+
+    fn stream_all(
+        &self,
+        include_deleted: bool,
+        page_size: u64,
+    ) -> futures::stream::BoxStream<'static, anyhow::Result<book::Book>> {
+        let db = self.db.clone();
+        // 1 ページ読むごとに接続を返すため、ストリーム消費中も他のクエリを妨げない
+        futures::stream::try_unfold(Some(0), move |after| {
+            let db = db.clone();
+            async move {
+                let Some(after) = after else {
+                    return Ok(None);
+                };
+                let mut query = Entity::find();
+                if !include_deleted {
+                    query = query.filter(Column::DeletedAt.is_null());
+                }
+                let page = query
+                    .find_also_related(super::publisher::Entity)
+                    .cursor_by(Column::Id)
+                    .after(after)
+                    .first(page_size)
+                    .all(&db)
+                    .await?;
+                if page.is_empty() {
+                    return Ok(None);
+                }
+
+                let next = if (page.len() as u64) < page_size {
+                    None
+                } else {
+                    page.last().map(|(b, _)| b.id)
+                };
+                let books = Self::with_shops(&db, page).await?;
+                Ok::<_, anyhow::Error>(Some((
+                    futures::stream::iter(books.into_iter().map(Ok)),
+                    next,
+                )))
+            }
+        })
+        .try_flatten()
+        .boxed()
+    }
+
+    #[tracing::instrument(skip_all, fields(terms = query.terms.len(), offset = query.offset, limit = query.limit))]
+    async fn search(&self, query: &book::SearchQuery) -> anyhow::Result<book::SearchPage> {
+        let backend = self.db.get_database_backend();
+
+        let mut condition = Condition::all().add(Expr::col((Entity, Column::DeletedAt)).is_null());
+        for term in &query.terms {
+            condition = condition.add(Self::search_condition(backend, term));
+        }
+        let mut select = Query::select();
+        select
+            .from(Entity)
+            .inner_join(
+                super::publisher::Entity,
+                Expr::col((Entity, Column::PublisherId))
+                    .equals((super::publisher::Entity, super::publisher::Column::Id)),
+            )
+            .left_join(
+                super::shop::Entity,
+                Expr::col((Entity, Column::ShopId))
+                    .equals((super::shop::Entity, super::shop::Column::Id)),
+            )
+            .cond_where(condition);
+
+        let count = select
+            .clone()
+            .expr(Expr::col((Entity, Column::Id)).count())
+            .to_owned();
+        let total = match self.db.query_one(&count).await? {
+            Some(row) => row.try_get_by_index::<i64>(0)? as u64,
+            None => 0,
+        };
+
+        select
+            .column((Entity, Column::Id))
+            .expr_as(
+                Self::search_score(backend, &query.terms),
+                Alias::new("score"),
+            )
+            .order_by(Alias::new("score"), Order::Desc)
+            .order_by((Entity, Column::Id), Order::Asc)
+            .limit(query.limit)
+            .offset(query.offset);
+        let ids = self
+            .db
+            .query_all(&select)
+            .await?
+            .iter()
+            .map(|row| row.try_get::<i32>("", "id"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let books_with_publishers = Entity::find()
+            .filter(Column::Id.is_in(ids.clone()))
+            .find_also_related(super::publisher::Entity)
+            .all(&self.db)
+            .await?;
+        let mut books: HashMap<i32, book::Book> = Self::with_shops(&self.db, books_with_publishers)
+            .await?
+            .into_iter()
+            .map(|b| (b.id(), b))
+            .collect();
+
+        Ok(book::SearchPage {
+            books: ids.iter().filter_map(|id| books.remove(id)).collect(),
+            total,
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = %item.pub_id()))]
+    async fn create(&self, mut item: book::Book) -> anyhow::Result<book::Book> {
+        let events = item.take_events();
         let publisher_model = super::publisher::Entity::find()
             .filter(super::publisher::Column::PubId.eq(item.publisher().pub_id()))
             .one(&self.db)
@@ -196,26 +544,18 @@ impl book::Repository for SqlRepository {
             None
         };
 
-        let active_model = ActiveModel {
-            pub_id: Set(item.pub_id()),
-            publisher_id: Set(publisher_model.id),
-            shop_id: Set(shop_model.as_ref().map(|s| s.id)),
-            title: Set(item.title()),
-            author: Set(item.author()),
-            price: Set(item.price()),
-            applied_at: Set(item.applied_at()),
-            format: Set(item.format().to_string()),
-            created_at: Set(item.created_at()),
-            updated_at: Set(item.updated_at()),
-            created_by: Set(item.created_by()),
-            updated_by: Set(item.updated_by()),
-            ..Default::default()
-        };
-        let result = active_model.insert(&self.db).await?;
+        let active_model =
+            Self::to_active_model(&item, publisher_model.id, shop_model.as_ref().map(|s| s.id));
+        let txn = self.db.begin().await?;
+        let result = active_model.insert(&txn).await?;
+        crate::outbox::append(&txn, "book", item.pub_id(), &events).await?;
+        txn.commit().await?;
         Ok(Self::to_domain(result, Some(publisher_model), shop_model)?)
     }
 
-    async fn update(&self, item: book::Book) -> anyhow::Result<book::Book> {
+    #[tracing::instrument(skip_all, fields(pub_id = %item.pub_id()))]
+    async fn update(&self, mut item: book::Book) -> anyhow::Result<book::Book> {
+        let events = item.take_events();
         let publisher_model = super::publisher::Entity::find()
             .filter(super::publisher::Column::PubId.eq(item.publisher().pub_id()))
             .one(&self.db)
@@ -234,28 +574,114 @@ impl book::Repository for SqlRepository {
             None
         };
 
-        let active_model = ActiveModel {
-            id: Set(item.id()),
-            pub_id: Set(item.pub_id()),
-            publisher_id: Set(publisher_model.id),
-            shop_id: Set(shop_model.as_ref().map(|s| s.id)),
-            title: Set(item.title()),
-            author: Set(item.author()),
-            price: Set(item.price()),
-            applied_at: Set(item.applied_at()),
-            format: Set(item.format().to_string()),
-            created_at: Set(item.created_at()),
-            updated_at: Set(item.updated_at()),
-            created_by: Set(item.created_by()),
-            updated_by: Set(item.updated_by()),
-        };
-        let result = active_model.update(&self.db).await?;
+        let mut active_model =
+            Self::to_active_model(&item, publisher_model.id, shop_model.as_ref().map(|s| s.id));
+        active_model.id = Set(item.id());
+        let txn = self.db.begin().await?;
+        let result = active_model.update(&txn).await?;
+        crate::outbox::append(&txn, "book", item.pub_id(), &events).await?;
+        txn.commit().await?;
 
         Ok(Self::to_domain(result, Some(publisher_model), shop_model)?)
     }
 
-    async fn delete(&self, item: book::Book) -> anyhow::Result<()> {
-        Entity::delete_by_id(item.id()).exec(&self.db).await?;
-        Ok(())
+    #[tracing::instrument(skip_all, fields(count = changes.len()))]
+    async fn save_all(&self, changes: Vec<book::Change>) -> anyhow::Result<Vec<book::Book>> {
+        let (publishers, shops) = self.resolve(&changes).await?;
+        let txn = self.db.begin().await?;
+        let mut saved = Vec::with_capacity(changes.len());
+        for change in changes {
+            saved.push(Self::save_change(&txn, change, &publishers, &shops).await?);
+        }
+        txn.commit().await?;
+
+        Ok(saved)
+    }
+
+    #[tracing::instrument(skip_all, fields(count = changes.len()))]
+    async fn save_each(
+        &self,
+        changes: Vec<book::Change>,
+    ) -> anyhow::Result<Vec<anyhow::Result<book::Book>>> {
+        let (publishers, shops) = self.resolve(&changes).await?;
+        let txn = self.db.begin().await?;
+        let mut saved = Vec::with_capacity(changes.len());
+        for change in changes {
+            // 失敗した 1 件だけを取り消せるよう、セーブポイントで区切る
+            let savepoint = txn.begin().await?;
+            match Self::save_change(&savepoint, change, &publishers, &shops).await {
+                Ok(book) => {
+                    savepoint.commit().await?;
+                    saved.push(Ok(book));
+                }
+                Err(e) => {
+                    savepoint.rollback().await?;
+                    saved.push(Err(e));
+                }
+            }
+        }
+        txn.commit().await?;
+
+        Ok(saved)
+    }
+
+    #[tracing::instrument(skip_all, fields(count = pub_ids.len()))]
+    async fn find_history(&self, pub_ids: &[uuid::Uuid]) -> anyhow::Result<Vec<book::History>> {
+        let models = super::book_history::Entity::find()
+            .filter(super::book_history::Column::PubId.is_in(pub_ids.iter().copied()))
+            .order_by_asc(super::book_history::Column::HistoryId)
+            .all(&self.db)
+            .await?;
+        Ok(models
+            .into_iter()
+            .map(|m| book::History {
+                pub_id: m.pub_id,
+                operation: m.operation_type,
+                operation_at: m.operation_at,
+                title: m.title,
+                author: m.author,
+                format: m.format,
+                price: m.price,
+                applied_at: m.applied_at,
+                updated_by: m.updated_by,
+                deleted_at: m.deleted_at,
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn purge_deleted(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<u64> {
+        let txn = self.db.begin().await?;
+        let models = Entity::find()
+            .filter(Column::DeletedAt.lt(deleted_before))
+            .all(&txn)
+            .await?;
+        let count = models.len() as u64;
+        for model in models {
+            model.into_active_model().delete(&txn).await?;
+        }
+        txn.commit().await?;
+        Ok(count)
     }
 }
+
+fn search_columns() -> [Expr; 4] {
+    [
+        Expr::col((Entity, Column::Title)),
+        Expr::col((Entity, Column::Author)),
+        Expr::col((super::publisher::Entity, super::publisher::Column::Name)),
+        Expr::col((super::shop::Entity, super::shop::Column::Name)),
+    ]
+}
+
+/// LIKE の特殊文字をエスケープして部分一致のパターンにする
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
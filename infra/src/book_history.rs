@@ -29,6 +29,9 @@ pub struct Model {
     pub created_by: String,
     #[sea_orm(column_type = "String(StringLen::N(32))")]
     pub updated_by: String,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[sea_orm(column_type = "String(StringLen::N(32))")]
+    pub deleted_by: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -59,6 +62,8 @@ mod tests {
                 updated_at,
                 created_by,
                 updated_by,
+                deleted_at,
+                deleted_by,
             } = book;
 
             let Model {
@@ -78,6 +83,8 @@ mod tests {
                 updated_at: _,
                 created_by: _,
                 updated_by: _,
+                deleted_at: _,
+                deleted_by: _,
             } = history;
 
             // コンパイルエラーを防ぐために変数を使用
@@ -95,6 +102,8 @@ mod tests {
                 updated_at,
                 created_by,
                 updated_by,
+                deleted_at,
+                deleted_by,
             );
         }
     }
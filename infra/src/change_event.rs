@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::StringLen;
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, QueryOrder,
+    QuerySelect, Set, Statement, TransactionTrait,
+};
+
+/// 記録を直列にするための advisory lock のキー
+const APPEND_LOCK_KEY: i64 = 0x6368_616e_6765;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "change_event")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(indexed, column_type = "String(StringLen::N(16))")]
+    pub resource_type: String,
+    #[sea_orm(column_type = "String(StringLen::N(16))")]
+    pub action: String,
+    pub resource_id: uuid::Uuid,
+    #[sea_orm(column_type = "Text")]
+    pub data: String,
+    #[sea_orm(indexed)]
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub struct SqlRepository {
+    pub(crate) db: DatabaseConnection,
+}
+
+impl SqlRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    fn to_domain(model: Model) -> anyhow::Result<change_event::ChangeEvent> {
+        let resource_type = model
+            .resource_type
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid resource type in DB: {}", e))?;
+        let action = model
+            .action
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid action in DB: {}", e))?;
+        Ok(change_event::ChangeEvent::reconstruct(
+            model.id,
+            resource_type,
+            action,
+            model.resource_id,
+            model.data,
+            model.occurred_at,
+        ))
+    }
+}
+
+#[async_trait]
+impl change_event::Repository for SqlRepository {
+    #[tracing::instrument(skip_all, fields(event_type = %event.event_type()))]
+    async fn append(
+        &self,
+        event: change_event::ChangeEvent,
+    ) -> anyhow::Result<change_event::ChangeEvent> {
+        let active_model = ActiveModel {
+            resource_type: Set(event.resource_type().to_string()),
+            action: Set(event.action().to_string()),
+            resource_id: Set(event.resource_id()),
+            data: Set(event.data().to_string()),
+            occurred_at: Set(event.occurred_at()),
+            ..Default::default()
+        };
+        // Postgres では並行した INSERT が採番と逆の順にコミットされることがあり、
+        // 後の id を読んだフィードが先の id を読み飛ばす。採番からコミットまでを直列にする
+        // (SQLite は書き込みが元々直列)
+        let txn = self.db.begin().await?;
+        if txn.get_database_backend() == DbBackend::Postgres {
+            txn.execute_raw(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT pg_advisory_xact_lock($1)",
+                [APPEND_LOCK_KEY.into()],
+            ))
+            .await?;
+        }
+        let result = active_model.insert(&txn).await?;
+        txn.commit().await?;
+        Self::to_domain(result)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_after(
+        &self,
+        after: i64,
+        resource_types: &[change_event::vo::ResourceType],
+        limit: u64,
+    ) -> anyhow::Result<Vec<change_event::ChangeEvent>> {
+        let mut query = Entity::find().filter(Column::Id.gt(after));
+        if !resource_types.is_empty() {
+            query =
+                query.filter(Column::ResourceType.is_in(resource_types.iter().map(|t| t.as_str())));
+        }
+        let events = query
+            .order_by_asc(Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        events.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn last_id(&self) -> anyhow::Result<i64> {
+        let last = Entity::find()
+            .order_by_desc(Column::Id)
+            .one(&self.db)
+            .await?;
+        Ok(last.map(|e| e.id).unwrap_or(0))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn purge_before(&self, before: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64> {
+        let result = Entity::delete_many()
+            .filter(Column::OccurredAt.lt(before))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
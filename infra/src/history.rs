@@ -0,0 +1,58 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, IntoActiveModel, Iterable};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Operation::Insert => "INSERT",
+            Operation::Update => "UPDATE",
+            Operation::Delete => "DELETE",
+        }
+    }
+}
+
+/// 元テーブルの全カラムを履歴テーブルの同名カラムへコピーして INSERT する。
+/// カラムは Entity 定義から列挙するため、片方にだけカラムを追加するとここでエラーになる。
+pub async fn record<M, H, C>(model: &M, operation: Operation, db: &C) -> Result<(), DbErr>
+where
+    M: ModelTrait,
+    H: ActiveModelTrait + ActiveModelBehavior + Send,
+    <H::Entity as EntityTrait>::Model: IntoActiveModel<H>,
+    C: ConnectionTrait,
+{
+    let mut history = H::default();
+    for column in <M::Entity as EntityTrait>::Column::iter() {
+        history.try_set(history_column::<H>(column.as_str())?, model.get(column))?;
+    }
+    history.try_set(
+        history_column::<H>("operation_type")?,
+        operation.as_str().into(),
+    )?;
+    history.try_set(
+        history_column::<H>("operation_at")?,
+        chrono::Utc::now().into(),
+    )?;
+    history.insert(db).await?;
+    Ok(())
+}
+
+fn history_column<H>(name: &str) -> Result<<H::Entity as EntityTrait>::Column, DbErr>
+where
+    H: ActiveModelTrait,
+{
+    <H::Entity as EntityTrait>::Column::from_str(name).map_err(|_| {
+        DbErr::Custom(format!(
+            "History table {} has no column {}",
+            H::Entity::default().table_name(),
+            name
+        ))
+    })
+}
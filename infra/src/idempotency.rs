@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::StringLen;
+use sea_orm::{Condition, DatabaseConnection, EntityTrait, Set, TransactionTrait, TryInsertResult};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "idempotency_key")]
+pub struct Model {
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "String(StringLen::N(255))"
+    )]
+    pub key: String,
+    #[sea_orm(
+        primary_key,
+        auto_increment = false,
+        column_type = "String(StringLen::N(255))"
+    )]
+    pub scope: String,
+    #[sea_orm(column_type = "String(StringLen::N(64))")]
+    pub request_hash: String,
+    pub status: Option<i16>,
+    #[sea_orm(column_type = "String(StringLen::N(255))")]
+    pub content_type: Option<String>,
+    pub body: Option<Vec<u8>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[sea_orm(indexed)]
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// 処理中のキーを押さえておく期限。追加前のレコードは NULL で、期限切れとして扱う
+    pub locked_until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub struct SqlRepository {
+    pub(crate) db: DatabaseConnection,
+}
+
+impl SqlRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    fn to_domain(model: Model) -> anyhow::Result<idempotency::Record> {
+        let key = idempotency::vo::IdempotencyKey::new(model.key)
+            .map_err(|e| anyhow::anyhow!("Invalid key in DB: {}", e))?;
+        let response = match (model.status, model.body) {
+            (Some(status), Some(body)) => Some(idempotency::StoredResponse {
+                status: u16::try_from(status)
+                    .map_err(|_| anyhow::anyhow!("Invalid status in DB: {}", status))?,
+                content_type: model.content_type,
+                body,
+            }),
+            _ => None,
+        };
+        Ok(idempotency::Record::reconstruct(
+            key,
+            model.scope,
+            model.request_hash,
+            response,
+            model.created_at,
+            model.expires_at,
+            model.locked_until.unwrap_or(model.created_at),
+        ))
+    }
+}
+
+#[async_trait]
+impl idempotency::Repository for SqlRepository {
+    #[tracing::instrument(skip_all, fields(scope = %record.scope()))]
+    async fn insert_or_get(
+        &self,
+        record: idempotency::Record,
+    ) -> anyhow::Result<Option<idempotency::Record>> {
+        let key = record.key().value().to_string();
+        let scope = record.scope().to_string();
+        let active_model = ActiveModel {
+            key: Set(key.clone()),
+            scope: Set(scope.clone()),
+            request_hash: Set(record.request_hash().to_string()),
+            status: Set(None),
+            content_type: Set(None),
+            body: Set(None),
+            created_at: Set(record.created_at()),
+            expires_at: Set(record.expires_at()),
+            locked_until: Set(Some(record.locked_until())),
+        };
+
+        let txn = self.db.begin().await?;
+        // 期限切れのキーと、処理が中断されたまま lease を過ぎたキーは新しいリクエストとして受け付け直す
+        let now = record.created_at();
+        let abandoned = Condition::all().add(Column::Status.is_null()).add(
+            Condition::any()
+                .add(Column::LockedUntil.is_null())
+                .add(Column::LockedUntil.lte(now)),
+        );
+        Entity::delete_many()
+            .filter(Column::Key.eq(key.as_str()))
+            .filter(Column::Scope.eq(scope.as_str()))
+            .filter(
+                Condition::any()
+                    .add(Column::ExpiresAt.lte(now))
+                    .add(abandoned),
+            )
+            .exec(&txn)
+            .await?;
+        // 同時に来た再試行とは主キーの一意制約で競合させる
+        let inserted = Entity::insert(active_model)
+            .on_conflict_do_nothing()
+            .exec_without_returning(&txn)
+            .await?;
+        let existing = match inserted {
+            TryInsertResult::Inserted(n) if n > 0 => None,
+            _ => Entity::find_by_id((key, scope)).one(&txn).await?,
+        };
+        txn.commit().await?;
+        existing.map(Self::to_domain).transpose()
+    }
+
+    #[tracing::instrument(skip_all, fields(scope = %scope, status = response.status))]
+    async fn save_response(
+        &self,
+        key: &idempotency::vo::IdempotencyKey,
+        scope: &str,
+        response: idempotency::StoredResponse,
+    ) -> anyhow::Result<()> {
+        let active_model = ActiveModel {
+            key: Set(key.value().to_string()),
+            scope: Set(scope.to_string()),
+            status: Set(Some(i16::try_from(response.status)?)),
+            content_type: Set(response.content_type),
+            body: Set(Some(response.body)),
+            ..Default::default()
+        };
+        Entity::update(active_model).exec(&self.db).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, key))]
+    async fn delete(
+        &self,
+        key: &idempotency::vo::IdempotencyKey,
+        scope: &str,
+    ) -> anyhow::Result<()> {
+        Entity::delete_by_id((key.value().to_string(), scope.to_string()))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn purge_expired(&self, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64> {
+        let result = Entity::delete_many()
+            .filter(Column::ExpiresAt.lte(now))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
@@ -1,5 +1,18 @@
+pub mod api_key;
 pub mod book;
 pub mod book_history;
+pub mod change_event;
+pub mod history;
+pub mod idempotency;
+pub mod metrics;
+pub mod outbox;
+pub mod outbox_sink;
 pub mod publisher;
 pub mod publisher_history;
 pub mod shop;
+pub mod shop_history;
+#[cfg(feature = "test")]
+pub mod test;
+pub mod webhook_delivery;
+pub mod webhook_sender;
+pub mod webhook_subscription;
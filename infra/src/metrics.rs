@@ -0,0 +1,103 @@
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::MetricFamily;
+use prometheus::{HistogramVec, IntGaugeVec, Opts, register_histogram_vec};
+use sea_orm::{DatabaseBackend, DatabaseConnection};
+use std::sync::LazyLock;
+
+static QUERY_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "db_query_duration_seconds",
+        "Database query latency by statement kind",
+        &["operation", "status"],
+        vec![
+            0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5
+        ]
+    )
+    .unwrap()
+});
+
+/// クエリの所要時間を記録し、スクレイプ時に接続プールの使用状況を出力するよう登録する。
+/// コールバックは clone 済みの接続には反映されないため、接続直後に呼ぶこと
+pub fn register(db: &mut DatabaseConnection) -> prometheus::Result<()> {
+    db.set_metric_callback(|info| {
+        let status = if info.failed { "error" } else { "ok" };
+        QUERY_DURATION
+            .with_label_values(&[operation(&info.statement.sql), status])
+            .observe(info.elapsed.as_secs_f64());
+    });
+    LazyLock::force(&QUERY_DURATION);
+    prometheus::register(Box::new(PoolCollector::new(db.clone())?))
+}
+
+/// SQL の先頭のキーワード。WITH などそれ以外は other にまとめる
+fn operation(sql: &str) -> &'static str {
+    let keyword = sql.split_whitespace().next().unwrap_or_default();
+    ["select", "insert", "update", "delete"]
+        .into_iter()
+        .find(|k| keyword.eq_ignore_ascii_case(k))
+        .unwrap_or("other")
+}
+
+struct PoolCollector {
+    db: DatabaseConnection,
+    connections: IntGaugeVec,
+}
+
+impl PoolCollector {
+    fn new(db: DatabaseConnection) -> prometheus::Result<Self> {
+        let connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_connections",
+                "Number of pooled database connections by state",
+            ),
+            &["state"],
+        )?;
+        Ok(Self { db, connections })
+    }
+
+    /// (開いている接続数, そのうち待機中の数)
+    fn usage(&self) -> Option<(u32, usize)> {
+        match self.db.get_database_backend() {
+            DatabaseBackend::Postgres => {
+                let pool = self.db.get_postgres_connection_pool();
+                Some((pool.size(), pool.num_idle()))
+            }
+            #[cfg(feature = "test")]
+            DatabaseBackend::Sqlite => {
+                let pool = self.db.get_sqlite_connection_pool();
+                Some((pool.size(), pool.num_idle()))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Collector for PoolCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.connections.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        if let Some((size, idle)) = self.usage() {
+            let idle = idle as i64;
+            self.connections.with_label_values(&["idle"]).set(idle);
+            self.connections
+                .with_label_values(&["in_use"])
+                .set(size as i64 - idle);
+        }
+        self.connections.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation() {
+        assert_eq!(operation("SELECT \"book\".\"id\" FROM \"book\""), "select");
+        assert_eq!(operation("  insert INTO book VALUES (1)"), "insert");
+        assert_eq!(operation("WITH t AS (SELECT 1) SELECT * FROM t"), "other");
+        assert_eq!(operation(""), "other");
+    }
+}
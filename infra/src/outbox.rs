@@ -0,0 +1,189 @@
+use async_trait::async_trait;
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::StringLen;
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryOrder, QuerySelect,
+    Set,
+};
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(column_type = "String(StringLen::N(16))")]
+    pub aggregate_type: String,
+    pub aggregate_id: uuid::Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(32))")]
+    pub event_type: String,
+    #[sea_orm(column_type = "Text")]
+    pub payload: String,
+    #[sea_orm(indexed, column_type = "String(StringLen::N(16))")]
+    pub status: String,
+    pub attempts: i32,
+    #[sea_orm(indexed)]
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// outbox に書き込むドメインイベント
+pub(crate) trait Event: Serialize {
+    fn name(&self) -> &'static str;
+}
+
+impl Event for book::event::Event {
+    fn name(&self) -> &'static str {
+        self.name()
+    }
+}
+
+impl Event for publisher::event::Event {
+    fn name(&self) -> &'static str {
+        self.name()
+    }
+}
+
+impl Event for shop::event::Event {
+    fn name(&self) -> &'static str {
+        self.name()
+    }
+}
+
+/// 集約を保存するトランザクションの中で呼び、未配信のイベントを書き込む
+pub(crate) async fn append<C, E>(
+    db: &C,
+    aggregate_type: &str,
+    aggregate_id: uuid::Uuid,
+    events: &[E],
+) -> anyhow::Result<()>
+where
+    C: ConnectionTrait,
+    E: Event,
+{
+    for event in events {
+        let message = outbox::Message::new(
+            aggregate_type.to_string(),
+            aggregate_id,
+            event.name().to_string(),
+            serde_json::to_string(event)?,
+        );
+        let mut active_model = SqlRepository::to_active_model(&message);
+        active_model.id = Default::default();
+        active_model.insert(db).await?;
+    }
+    Ok(())
+}
+
+pub struct SqlRepository {
+    pub(crate) db: DatabaseConnection,
+}
+
+impl SqlRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    fn to_active_model(message: &outbox::Message) -> ActiveModel {
+        ActiveModel {
+            id: Set(message.id()),
+            aggregate_type: Set(message.aggregate_type().to_string()),
+            aggregate_id: Set(message.aggregate_id()),
+            event_type: Set(message.event_type().to_string()),
+            payload: Set(message.payload().to_string()),
+            status: Set(message.status().to_string()),
+            attempts: Set(message.attempts() as i32),
+            next_attempt_at: Set(message.next_attempt_at()),
+            last_error: Set(message.last_error().map(str::to_string)),
+            created_at: Set(message.created_at()),
+            delivered_at: Set(message.delivered_at()),
+        }
+    }
+
+    fn to_domain(model: Model) -> anyhow::Result<outbox::Message> {
+        let status = model
+            .status
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid status in DB: {}", e))?;
+        Ok(outbox::Message::reconstruct(
+            model.id,
+            model.aggregate_type,
+            model.aggregate_id,
+            model.event_type,
+            model.payload,
+            status,
+            model.attempts.max(0) as u32,
+            model.next_attempt_at,
+            model.last_error,
+            model.created_at,
+            model.delivered_at,
+        ))
+    }
+}
+
+#[async_trait]
+impl outbox::Repository for SqlRepository {
+    #[tracing::instrument(skip(self))]
+    async fn find_all(
+        &self,
+        status: Option<outbox::vo::Status>,
+        limit: u64,
+    ) -> anyhow::Result<Vec<outbox::Message>> {
+        let mut query = Entity::find();
+        if let Some(status) = status {
+            query = query.filter(Column::Status.eq(status.as_str()));
+        }
+        let messages = query
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        messages.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_by_id(&self, id: i64) -> anyhow::Result<Option<outbox::Message>> {
+        let message = Entity::find_by_id(id).one(&self.db).await?;
+        message.map(Self::to_domain).transpose()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_due(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: u64,
+    ) -> anyhow::Result<Vec<outbox::Message>> {
+        let messages = Entity::find()
+            .filter(Column::Status.eq(outbox::vo::Status::Pending.as_str()))
+            .filter(Column::NextAttemptAt.lte(now))
+            .order_by_asc(Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        messages.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(id = item.id()))]
+    async fn update(&self, item: outbox::Message) -> anyhow::Result<outbox::Message> {
+        let result = Self::to_active_model(&item).update(&self.db).await?;
+        Self::to_domain(result)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn purge_delivered(&self, before: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64> {
+        let result = Entity::delete_many()
+            .filter(Column::Status.eq(outbox::vo::Status::Delivered.as_str()))
+            .filter(Column::DeliveredAt.lt(before))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
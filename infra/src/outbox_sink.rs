@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, mpsc};
+
+/// webhook とファイルに書き出す JSON。受け取る側は id で重複を除く
+#[derive(Debug, Serialize)]
+struct Envelope<'a> {
+    id: i64,
+    event_type: &'a str,
+    aggregate_type: &'a str,
+    aggregate_id: uuid::Uuid,
+    occurred_at: chrono::DateTime<chrono::Utc>,
+    data: serde_json::Value,
+}
+
+impl<'a> Envelope<'a> {
+    fn new(message: &'a outbox::Message) -> anyhow::Result<Self> {
+        Ok(Self {
+            id: message.id(),
+            event_type: message.event_type(),
+            aggregate_type: message.aggregate_type(),
+            aggregate_id: message.aggregate_id(),
+            occurred_at: message.created_at(),
+            data: serde_json::from_str(message.payload())?,
+        })
+    }
+}
+
+/// 同じプロセス内の受信側へメッセージを渡す
+pub struct ChannelSink {
+    sender: mpsc::Sender<outbox::Message>,
+}
+
+impl ChannelSink {
+    /// 受信側が capacity 件溜めている間は送信を待つ
+    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<outbox::Message>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (Self { sender }, receiver)
+    }
+}
+
+#[async_trait]
+impl outbox::Sink for ChannelSink {
+    fn name(&self) -> &str {
+        "channel"
+    }
+
+    async fn send(&self, message: &outbox::Message) -> anyhow::Result<()> {
+        self.sender
+            .send(message.clone())
+            .await
+            .map_err(|_| anyhow::anyhow!("receiver is closed"))
+    }
+}
+
+/// URL へ JSON を POST し、2xx 以外は失敗として扱う
+pub struct WebhookSink {
+    name: String,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: String, timeout: std::time::Duration) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(Self {
+            name: format!("webhook:{}", url),
+            url,
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl outbox::Sink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[tracing::instrument(skip_all, fields(url = %self.url, id = message.id()))]
+    async fn send(&self, message: &outbox::Message) -> anyhow::Result<()> {
+        self.client
+            .post(&self.url)
+            .json(&Envelope::new(message)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// JSON Lines 形式でファイルの末尾に追記する
+pub struct FileSink {
+    name: String,
+    path: PathBuf,
+    /// 1 行が他の書き込みと混ざらないようにする
+    lock: Mutex<()>,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            name: format!("file:{}", path.display()),
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl outbox::Sink for FileSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, message: &outbox::Message) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(&Envelope::new(message)?)?;
+        line.push(b'\n');
+        let _guard = self.lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
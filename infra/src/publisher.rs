@@ -1,7 +1,12 @@
 use async_trait::async_trait;
 use sea_orm::entity::prelude::*;
-use sea_orm::sea_query::StringLen;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use sea_orm::sea_query::{Query, StringLen};
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QuerySelect, Set,
+    TransactionTrait, TryIntoModel,
+};
+
+use crate::history;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "publisher")]
@@ -18,6 +23,9 @@ pub struct Model {
     pub created_by: String,
     #[sea_orm(column_type = "String(StringLen::N(32))")]
     pub updated_by: String,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[sea_orm(column_type = "String(StringLen::N(32))")]
+    pub deleted_by: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -32,7 +40,36 @@ impl Related<super::book::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn after_save<C>(model: Model, db: &C, insert: bool) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let operation = if insert {
+            history::Operation::Insert
+        } else {
+            history::Operation::Update
+        };
+        history::record::<_, super::publisher_history::ActiveModel, _>(&model, operation, db)
+            .await?;
+        Ok(model)
+    }
+
+    async fn before_delete<C>(self, db: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let model = self.clone().try_into_model()?;
+        history::record::<_, super::publisher_history::ActiveModel, _>(
+            &model,
+            history::Operation::Delete,
+            db,
+        )
+        .await?;
+        Ok(self)
+    }
+}
 
 pub struct SqlRepository {
     pub(crate) db: DatabaseConnection,
@@ -54,32 +91,68 @@ impl SqlRepository {
             model.updated_at,
             model.created_by,
             model.updated_by,
+            model.deleted_at,
+            model.deleted_by,
         ))
     }
 }
 
 #[async_trait]
 impl publisher::Repository for SqlRepository {
-    async fn find_all(&self) -> anyhow::Result<Vec<publisher::Publisher>> {
-        let publishers = Entity::find().all(&self.db).await?;
+    #[tracing::instrument(skip(self))]
+    async fn find_all(&self, include_deleted: bool) -> anyhow::Result<Vec<publisher::Publisher>> {
+        let mut query = Entity::find();
+        if !include_deleted {
+            query = query.filter(Column::DeletedAt.is_null());
+        }
+        let publishers = query.all(&self.db).await?;
         publishers.into_iter().map(Self::to_domain).collect()
     }
 
+    #[tracing::instrument(skip(self))]
     async fn find_by_pub_id(
         &self,
         pub_id: uuid::Uuid,
+        include_deleted: bool,
     ) -> anyhow::Result<Option<publisher::Publisher>> {
-        let publisher = Entity::find()
-            .filter(Column::PubId.eq(pub_id))
-            .one(&self.db)
-            .await?;
+        let mut query = Entity::find().filter(Column::PubId.eq(pub_id));
+        if !include_deleted {
+            query = query.filter(Column::DeletedAt.is_null());
+        }
+        let publisher = query.one(&self.db).await?;
         match publisher {
             Some(p) => Ok(Some(Self::to_domain(p)?)),
             None => Ok(None),
         }
     }
 
-    async fn create(&self, item: publisher::Publisher) -> anyhow::Result<publisher::Publisher> {
+    #[tracing::instrument(skip_all, fields(count = pub_ids.len()))]
+    async fn find_by_pub_ids(
+        &self,
+        pub_ids: &[uuid::Uuid],
+        include_deleted: bool,
+    ) -> anyhow::Result<Vec<publisher::Publisher>> {
+        let mut query = Entity::find().filter(Column::PubId.is_in(pub_ids.iter().copied()));
+        if !include_deleted {
+            query = query.filter(Column::DeletedAt.is_null());
+        }
+        let publishers = query.all(&self.db).await?;
+        publishers.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(count = names.len()))]
+    async fn find_by_names(&self, names: &[String]) -> anyhow::Result<Vec<publisher::Publisher>> {
+        let publishers = Entity::find()
+            .filter(Column::Name.is_in(names.iter().cloned()))
+            .filter(Column::DeletedAt.is_null())
+            .all(&self.db)
+            .await?;
+        publishers.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = %item.pub_id()))]
+    async fn create(&self, mut item: publisher::Publisher) -> anyhow::Result<publisher::Publisher> {
+        let events = item.take_events();
         let active_model = ActiveModel {
             pub_id: Set(item.pub_id()),
             name: Set(item.name()),
@@ -87,14 +160,21 @@ impl publisher::Repository for SqlRepository {
             updated_at: Set(item.updated_at()),
             created_by: Set(item.created_by()),
             updated_by: Set(item.updated_by()),
+            deleted_at: Set(item.deleted_at()),
+            deleted_by: Set(item.deleted_by()),
             ..Default::default()
         };
 
-        let result = active_model.insert(&self.db).await?;
+        let txn = self.db.begin().await?;
+        let result = active_model.insert(&txn).await?;
+        crate::outbox::append(&txn, "publisher", item.pub_id(), &events).await?;
+        txn.commit().await?;
         Ok(Self::to_domain(result)?)
     }
 
-    async fn update(&self, item: publisher::Publisher) -> anyhow::Result<publisher::Publisher> {
+    #[tracing::instrument(skip_all, fields(pub_id = %item.pub_id()))]
+    async fn update(&self, mut item: publisher::Publisher) -> anyhow::Result<publisher::Publisher> {
+        let events = item.take_events();
         let active_model = ActiveModel {
             id: Set(item.id()),
             pub_id: Set(item.pub_id()),
@@ -103,14 +183,52 @@ impl publisher::Repository for SqlRepository {
             updated_at: Set(item.updated_at()),
             created_by: Set(item.created_by()),
             updated_by: Set(item.updated_by()),
+            deleted_at: Set(item.deleted_at()),
+            deleted_by: Set(item.deleted_by()),
         };
 
-        let result = active_model.update(&self.db).await?;
+        let txn = self.db.begin().await?;
+        let result = active_model.update(&txn).await?;
+        crate::outbox::append(&txn, "publisher", item.pub_id(), &events).await?;
+        txn.commit().await?;
         Ok(Self::to_domain(result)?)
     }
 
-    async fn delete(&self, item: publisher::Publisher) -> anyhow::Result<()> {
-        Entity::delete_by_id(item.id()).exec(&self.db).await?;
-        Ok(())
+    #[tracing::instrument(skip(self))]
+    async fn has_books(&self, id: i32) -> anyhow::Result<bool> {
+        let book = super::book::Entity::find()
+            .select_only()
+            .column(super::book::Column::Id)
+            .filter(super::book::Column::PublisherId.eq(id))
+            .filter(super::book::Column::DeletedAt.is_null())
+            .into_tuple::<i32>()
+            .one(&self.db)
+            .await?;
+        Ok(book.is_some())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn purge_deleted(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<u64> {
+        // 論理削除済みでも Book から参照されている行は残す
+        let referenced = Query::select()
+            .column(super::book::Column::PublisherId)
+            .from(super::book::Entity)
+            .to_owned();
+
+        let txn = self.db.begin().await?;
+        let models = Entity::find()
+            .filter(Column::DeletedAt.lt(deleted_before))
+            .filter(Column::Id.not_in_subquery(referenced))
+            .all(&txn)
+            .await?;
+        let count = models.len() as u64;
+        for model in models {
+            model.into_active_model().delete(&txn).await?;
+        }
+        txn.commit().await?;
+        Ok(count)
     }
 }
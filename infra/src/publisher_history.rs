@@ -21,6 +21,9 @@ pub struct Model {
     pub created_by: String,
     #[sea_orm(column_type = "String(StringLen::N(32))")]
     pub updated_by: String,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[sea_orm(column_type = "String(StringLen::N(32))")]
+    pub deleted_by: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -43,6 +46,8 @@ mod tests {
                 updated_at,
                 created_by,
                 updated_by,
+                deleted_at,
+                deleted_by,
             } = pub_orig;
 
             let Model {
@@ -56,10 +61,13 @@ mod tests {
                 updated_at: _,
                 created_by: _,
                 updated_by: _,
+                deleted_at: _,
+                deleted_by: _,
             } = history;
 
             let _ = (
-                id, pub_id, name, created_at, updated_at, created_by, updated_by,
+                id, pub_id, name, created_at, updated_at, created_by, updated_by, deleted_at,
+                deleted_by,
             );
         }
     }
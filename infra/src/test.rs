@@ -0,0 +1,66 @@
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+
+/// テスト用に、db のリポジトリで全ての usecase を繋いだ AppState を作る。
+/// 差し替えたいものは構造体更新構文で上書きする
+pub fn state(db: &DatabaseConnection) -> api::AppState {
+    let book_repo =
+        Arc::new(crate::book::SqlRepository::new(db.clone())) as Arc<dyn book::Repository>;
+    let publisher_repo = Arc::new(crate::publisher::SqlRepository::new(db.clone()))
+        as Arc<dyn publisher::Repository>;
+    let shop_repo =
+        Arc::new(crate::shop::SqlRepository::new(db.clone())) as Arc<dyn shop::Repository>;
+    let events = Arc::new(usecase::change_event::Service::new(
+        Arc::new(crate::change_event::SqlRepository::new(db.clone())),
+        chrono::Duration::days(7),
+    ));
+    let dispatcher = Arc::new(usecase::domain_event::Dispatcher::new());
+    let policy = outbox::vo::RetryPolicy::new(
+        3,
+        chrono::Duration::seconds(1),
+        chrono::Duration::seconds(60),
+    )
+    .expect("Invalid retry policy");
+
+    api::AppState {
+        api_key_usecase: usecase::api_key::Service::new(Arc::new(
+            crate::api_key::SqlRepository::new(db.clone()),
+        )),
+        book_usecase: usecase::book::Service::new(
+            book_repo,
+            publisher_repo.clone(),
+            shop_repo.clone(),
+            events.clone(),
+            dispatcher.clone(),
+        ),
+        publisher_usecase: usecase::publisher::Service::new(
+            publisher_repo,
+            events.clone(),
+            dispatcher.clone(),
+        ),
+        shop_usecase: usecase::shop::Service::new(shop_repo, events.clone(), dispatcher),
+        idempotency_usecase: usecase::idempotency::Service::new(
+            Arc::new(crate::idempotency::SqlRepository::new(db.clone())),
+            chrono::Duration::hours(1),
+            chrono::Duration::minutes(1),
+        ),
+        change_event_usecase: events,
+        outbox_usecase: usecase::outbox::Service::new(
+            Arc::new(crate::outbox::SqlRepository::new(db.clone())),
+            Vec::new(),
+            policy,
+            chrono::Duration::days(7),
+        ),
+        webhook_usecase: Arc::new(usecase::webhook::Service::new(
+            Arc::new(crate::webhook_subscription::SqlRepository::new(db.clone())),
+            Arc::new(crate::webhook_delivery::SqlRepository::new(db.clone())),
+            Arc::new(
+                crate::webhook_sender::HttpSender::new(std::time::Duration::from_secs(5))
+                    .expect("Failed to build webhook sender"),
+            ),
+            policy,
+            chrono::Duration::days(7),
+        )),
+        readiness_checks: Vec::new(),
+    }
+}
@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::StringLen;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, QueryOrder, QuerySelect, Set,
+    TryInsertResult,
+};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "webhook_delivery")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub subscription_id: i32,
+    /// 元になった outbox のメッセージ。subscription_id との組み合わせで一意にする
+    pub message_id: i64,
+    #[sea_orm(column_type = "String(StringLen::N(32))")]
+    pub event_type: String,
+    #[sea_orm(column_type = "Text")]
+    pub payload: String,
+    #[sea_orm(indexed, column_type = "String(StringLen::N(16))")]
+    pub status: String,
+    pub attempts: i32,
+    #[sea_orm(indexed)]
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    pub response_status: Option<i32>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub struct SqlRepository {
+    pub(crate) db: DatabaseConnection,
+}
+
+impl SqlRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    fn to_active_model(item: &webhook::Delivery) -> ActiveModel {
+        ActiveModel {
+            id: Set(item.id()),
+            subscription_id: Set(item.subscription_id()),
+            message_id: Set(item.message_id()),
+            event_type: Set(item.event_type().to_string()),
+            payload: Set(item.payload().to_string()),
+            status: Set(item.status().to_string()),
+            attempts: Set(item.attempts() as i32),
+            next_attempt_at: Set(item.next_attempt_at()),
+            response_status: Set(item.response_status().map(i32::from)),
+            last_error: Set(item.last_error().map(str::to_string)),
+            created_at: Set(item.created_at()),
+            delivered_at: Set(item.delivered_at()),
+        }
+    }
+
+    fn to_domain(model: Model) -> anyhow::Result<webhook::Delivery> {
+        let status = model
+            .status
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid status in DB: {}", e))?;
+        Ok(webhook::Delivery::reconstruct(
+            model.id,
+            model.subscription_id,
+            model.message_id,
+            model.event_type,
+            model.payload,
+            status,
+            model.attempts.max(0) as u32,
+            model.next_attempt_at,
+            model.response_status.and_then(|s| u16::try_from(s).ok()),
+            model.last_error,
+            model.created_at,
+            model.delivered_at,
+        ))
+    }
+}
+
+#[async_trait]
+impl webhook::DeliveryRepository for SqlRepository {
+    #[tracing::instrument(skip_all, fields(count = items.len()))]
+    async fn enqueue(&self, items: Vec<webhook::Delivery>) -> anyhow::Result<u64> {
+        if items.is_empty() {
+            return Ok(0);
+        }
+        let active_models = items.iter().map(|item| {
+            let mut active_model = Self::to_active_model(item);
+            active_model.id = sea_orm::ActiveValue::NotSet;
+            active_model
+        });
+        // outbox の中継が再送したときは (subscription_id, message_id) の一意制約で弾く
+        let inserted = Entity::insert_many(active_models)
+            .on_conflict_do_nothing()
+            .exec_without_returning(&self.db)
+            .await?;
+        Ok(match inserted {
+            TryInsertResult::Inserted(n) => n,
+            _ => 0,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_due(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: u64,
+    ) -> anyhow::Result<Vec<webhook::Delivery>> {
+        let deliveries = Entity::find()
+            .filter(Column::Status.eq(webhook::vo::DeliveryStatus::Pending.as_str()))
+            .filter(Column::NextAttemptAt.lte(now))
+            .order_by_asc(Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        deliveries.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_by_subscription(
+        &self,
+        subscription_id: i32,
+        status: Option<webhook::vo::DeliveryStatus>,
+        limit: u64,
+    ) -> anyhow::Result<Vec<webhook::Delivery>> {
+        let mut query = Entity::find().filter(Column::SubscriptionId.eq(subscription_id));
+        if let Some(status) = status {
+            query = query.filter(Column::Status.eq(status.as_str()));
+        }
+        let deliveries = query
+            .order_by_desc(Column::Id)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        deliveries.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(id = item.id()))]
+    async fn update(&self, item: webhook::Delivery) -> anyhow::Result<webhook::Delivery> {
+        let result = Self::to_active_model(&item).update(&self.db).await?;
+        Self::to_domain(result)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn purge_delivered(&self, before: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64> {
+        let result = Entity::delete_many()
+            .filter(Column::Status.eq(webhook::vo::DeliveryStatus::Delivered.as_str()))
+            .filter(Column::DeliveredAt.lt(before))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
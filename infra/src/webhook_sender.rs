@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// reqwest で webhook を POST する。内部のネットワークには接続しない
+pub struct HttpSender {
+    client: reqwest::Client,
+}
+
+impl HttpSender {
+    pub fn new(timeout: std::time::Duration) -> anyhow::Result<Self> {
+        Self::build(reqwest::Client::builder().timeout(timeout))
+    }
+
+    /// domain への接続を addr に向ける。ループバックで待ち受ける受信側にテストから配信する
+    #[cfg(feature = "test")]
+    pub fn with_override(
+        timeout: std::time::Duration,
+        domain: &str,
+        addr: SocketAddr,
+    ) -> anyhow::Result<Self> {
+        Self::build(
+            reqwest::Client::builder()
+                .timeout(timeout)
+                .resolve(domain, addr),
+        )
+    }
+
+    fn build(builder: reqwest::ClientBuilder) -> anyhow::Result<Self> {
+        let client = builder
+            // リダイレクト先で内部のアドレスに誘導されないよう、追わずに 3xx をそのまま返す
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()?;
+        Ok(Self { client })
+    }
+}
+
+/// 名前解決の結果から内部のアドレスを除く。全て除かれたら接続しない。
+/// 登録時に確かめたホストが、後から内部のアドレスを指すように変えられても防げる
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| webhook::vo::is_public(&addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} does not resolve to a public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[async_trait]
+impl webhook::Sender for HttpSender {
+    #[tracing::instrument(skip_all, fields(url = %url))]
+    async fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &str,
+    ) -> anyhow::Result<u16> {
+        // IP を直接書いた URL は名前解決を通らないので、ここで確かめる
+        let parsed = reqwest::Url::parse(url)?;
+        let ip = parsed
+            .host_str()
+            .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+            .and_then(|host| host.parse::<std::net::IpAddr>().ok());
+        if let Some(ip) = ip
+            && !webhook::vo::is_public(&ip)
+        {
+            anyhow::bail!("{} is not a public address", ip);
+        }
+
+        let mut request = self
+            .client
+            .post(parsed)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        Ok(request.send().await?.status().as_u16())
+    }
+}
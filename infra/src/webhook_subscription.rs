@@ -0,0 +1,112 @@
+use async_trait::async_trait;
+use sea_orm::entity::prelude::*;
+use sea_orm::sea_query::StringLen;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, QueryOrder, Set, TransactionTrait,
+};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "webhook_subscription")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub pub_id: uuid::Uuid,
+    #[sea_orm(column_type = "String(StringLen::N(2048))")]
+    pub url: String,
+    /// 空白区切りのイベント名
+    #[sea_orm(column_type = "Text")]
+    pub event_types: String,
+    #[sea_orm(column_type = "String(StringLen::N(128))")]
+    pub secret: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub struct SqlRepository {
+    pub(crate) db: DatabaseConnection,
+}
+
+impl SqlRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    fn to_domain(model: Model) -> anyhow::Result<webhook::Subscription> {
+        let url = webhook::vo::WebhookUrl::new(model.url)
+            .map_err(|e| anyhow::anyhow!("Invalid url in DB: {}", e))?;
+        let secret = webhook::vo::Secret::new(model.secret)
+            .map_err(|e| anyhow::anyhow!("Invalid secret in DB: {}", e))?;
+        Ok(webhook::Subscription::reconstruct(
+            model.id,
+            model.pub_id,
+            url,
+            model
+                .event_types
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            secret,
+            model.created_at,
+        ))
+    }
+
+    fn to_active_model(item: &webhook::Subscription) -> ActiveModel {
+        ActiveModel {
+            id: Set(item.id()),
+            pub_id: Set(item.pub_id()),
+            url: Set(item.url().to_string()),
+            event_types: Set(item.event_types().join(" ")),
+            secret: Set(item.secret().to_string()),
+            created_at: Set(item.created_at()),
+        }
+    }
+}
+
+#[async_trait]
+impl webhook::SubscriptionRepository for SqlRepository {
+    #[tracing::instrument(skip(self))]
+    async fn find_all(&self) -> anyhow::Result<Vec<webhook::Subscription>> {
+        let subscriptions = Entity::find()
+            .order_by_asc(Column::Id)
+            .all(&self.db)
+            .await?;
+        subscriptions.into_iter().map(Self::to_domain).collect()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn find_by_pub_id(
+        &self,
+        pub_id: uuid::Uuid,
+    ) -> anyhow::Result<Option<webhook::Subscription>> {
+        let subscription = Entity::find()
+            .filter(Column::PubId.eq(pub_id))
+            .one(&self.db)
+            .await?;
+        subscription.map(Self::to_domain).transpose()
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = %item.pub_id()))]
+    async fn create(&self, item: webhook::Subscription) -> anyhow::Result<webhook::Subscription> {
+        let mut active_model = Self::to_active_model(&item);
+        active_model.id = sea_orm::ActiveValue::NotSet;
+        let result = active_model.insert(&self.db).await?;
+        Self::to_domain(result)
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = %item.pub_id()))]
+    async fn delete(&self, item: webhook::Subscription) -> anyhow::Result<()> {
+        let txn = self.db.begin().await?;
+        crate::webhook_delivery::Entity::delete_many()
+            .filter(crate::webhook_delivery::Column::SubscriptionId.eq(item.id()))
+            .exec(&txn)
+            .await?;
+        Entity::delete_by_id(item.id()).exec(&txn).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+}
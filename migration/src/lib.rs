@@ -1,7 +1,17 @@
 pub use sea_orm_migration::prelude::*;
+pub use sea_orm_migration::MigrationStatus;
 
 mod m20220101_000001_create_table;
 mod m20260101_000002_add_audit_and_history;
+mod m20260201_000003_app_level_history;
+mod m20260301_000004_add_soft_delete;
+mod m20260401_000005_book_search;
+mod m20260501_000006_idempotency_key;
+mod m20260601_000007_api_key;
+mod m20260701_000008_change_event;
+mod m20260801_000009_outbox;
+mod m20260901_000010_webhook;
+mod m20261001_000011_idempotency_lease;
 
 pub struct Migrator;
 
@@ -11,6 +21,15 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20220101_000001_create_table::Migration),
             Box::new(m20260101_000002_add_audit_and_history::Migration),
+            Box::new(m20260201_000003_app_level_history::Migration),
+            Box::new(m20260301_000004_add_soft_delete::Migration),
+            Box::new(m20260401_000005_book_search::Migration),
+            Box::new(m20260501_000006_idempotency_key::Migration),
+            Box::new(m20260601_000007_api_key::Migration),
+            Box::new(m20260701_000008_change_event::Migration),
+            Box::new(m20260801_000009_outbox::Migration),
+            Box::new(m20260901_000010_webhook::Migration),
+            Box::new(m20261001_000011_idempotency_lease::Migration),
         ]
     }
 }
@@ -1,5 +1,6 @@
 use sea_orm_migration::prelude::*;
 use sea_orm_migration::sea_orm::schema::Schema;
+use sea_orm_migration::sea_orm::DatabaseBackend;
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
@@ -19,6 +20,11 @@ impl MigrationTrait for Migration {
             .await?;
 
         // 2. Create Trigger Functions and Triggers
+        // plpgsql のトリガーは Postgres 専用。履歴の記録は m20260201_000003 以降アプリ側で行う。
+        if manager.get_database_backend() != DatabaseBackend::Postgres {
+            return Ok(());
+        }
+
         // Book - Trigger Function
         db.execute_unprepared(
             r#"
@@ -138,14 +144,16 @@ impl MigrationTrait for Migration {
         let db = manager.get_connection();
 
         // Drop Triggers and Functions
-        db.execute_unprepared("DROP TRIGGER IF EXISTS trigger_publisher_history ON publisher")
-            .await?;
-        db.execute_unprepared("DROP FUNCTION IF EXISTS save_history_publisher")
-            .await?;
-        db.execute_unprepared("DROP TRIGGER IF EXISTS trigger_book_history ON book")
-            .await?;
-        db.execute_unprepared("DROP FUNCTION IF EXISTS save_history_book")
-            .await?;
+        if manager.get_database_backend() == DatabaseBackend::Postgres {
+            db.execute_unprepared("DROP TRIGGER IF EXISTS trigger_publisher_history ON publisher")
+                .await?;
+            db.execute_unprepared("DROP FUNCTION IF EXISTS save_history_publisher")
+                .await?;
+            db.execute_unprepared("DROP TRIGGER IF EXISTS trigger_book_history ON book")
+                .await?;
+            db.execute_unprepared("DROP FUNCTION IF EXISTS save_history_book")
+                .await?;
+        }
 
         // Drop History Tables
         manager
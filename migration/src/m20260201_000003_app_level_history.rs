@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::schema::Schema;
+use sea_orm_migration::sea_orm::DatabaseBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let schema = Schema::new(manager.get_database_backend());
+
+        // 1. 履歴は infra の ActiveModelBehavior で記録するため、トリガーを削除する
+        if manager.get_database_backend() == DatabaseBackend::Postgres {
+            db.execute_unprepared("DROP TRIGGER IF EXISTS trigger_publisher_history ON publisher")
+                .await?;
+            db.execute_unprepared("DROP FUNCTION IF EXISTS save_history_publisher")
+                .await?;
+            db.execute_unprepared("DROP TRIGGER IF EXISTS trigger_book_history ON book")
+                .await?;
+            db.execute_unprepared("DROP FUNCTION IF EXISTS save_history_book")
+                .await?;
+        }
+
+        // 2. Create shop history table from Entity definition
+        manager
+            .create_table(schema.create_table_from_entity(infra::shop_history::Entity))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // トリガーは再作成しない (履歴の記録はアプリ側の責務のまま)
+        manager
+            .drop_table(Table::drop().table(infra::shop_history::Entity).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
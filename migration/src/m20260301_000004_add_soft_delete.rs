@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::EntityName;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum SoftDelete {
+    DeletedAt,
+    DeletedBy,
+}
+
+fn tables() -> Vec<&'static str> {
+    vec![
+        infra::book::Entity.table_name(),
+        infra::publisher::Entity.table_name(),
+        infra::shop::Entity.table_name(),
+        infra::book_history::Entity.table_name(),
+        infra::publisher_history::Entity.table_name(),
+        infra::shop_history::Entity.table_name(),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 以前のマイグレーションは Entity 定義からテーブルを作成するため、
+        // 新規 DB では既にカラムが存在する。既存 DB のみカラムを追加する。
+        // また SQLite の ALTER TABLE は 1 文につき 1 カラムしか追加できないため、カラム毎に発行する。
+        for table in tables() {
+            if !manager.has_column(table, "deleted_at").await? {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(Alias::new(table))
+                            .add_column(
+                                ColumnDef::new(SoftDelete::DeletedAt)
+                                    .timestamp_with_time_zone()
+                                    .null(),
+                            )
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+            if !manager.has_column(table, "deleted_by").await? {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(Alias::new(table))
+                            .add_column(ColumnDef::new(SoftDelete::DeletedBy).string_len(32).null())
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .drop_column(SoftDelete::DeletedBy)
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .drop_column(SoftDelete::DeletedAt)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
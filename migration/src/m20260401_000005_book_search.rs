@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::DatabaseBackend;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite では LIKE で検索するため追加するものはない
+        if manager.get_database_backend() != DatabaseBackend::Postgres {
+            return Ok(());
+        }
+        let db = manager.get_connection();
+
+        // 1. 英字などの単語検索用。'simple' は日本語を分かち書きしないので trigram と併用する
+        db.execute_unprepared(
+            r#"
+            ALTER TABLE book ADD COLUMN IF NOT EXISTS search_vector tsvector
+                GENERATED ALWAYS AS (
+                    setweight(to_tsvector('simple', coalesce(title, '')), 'A') ||
+                    setweight(to_tsvector('simple', coalesce(author, '')), 'B')
+                ) STORED
+            "#,
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE INDEX IF NOT EXISTS idx_book_search_vector ON book USING GIN (search_vector)",
+        )
+        .await?;
+
+        // 2. 日本語の部分一致 (ILIKE) 用の trigram インデックス
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+        for (index, table, column) in TRIGRAM_INDEXES {
+            db.execute_unprepared(&format!(
+                "CREATE INDEX IF NOT EXISTS {index} ON {table} USING GIN ({column} gin_trgm_ops)"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() != DatabaseBackend::Postgres {
+            return Ok(());
+        }
+        let db = manager.get_connection();
+
+        for (index, _, _) in TRIGRAM_INDEXES {
+            db.execute_unprepared(&format!("DROP INDEX IF EXISTS {index}"))
+                .await?;
+        }
+        db.execute_unprepared("DROP INDEX IF EXISTS idx_book_search_vector")
+            .await?;
+        db.execute_unprepared("ALTER TABLE book DROP COLUMN IF EXISTS search_vector")
+            .await?;
+
+        Ok(())
+    }
+}
+
+const TRIGRAM_INDEXES: [(&str, &str, &str); 4] = [
+    ("idx_book_title_trgm", "book", "title"),
+    ("idx_book_author_trgm", "book", "author"),
+    ("idx_publisher_name_trgm", "publisher", "name"),
+    ("idx_shop_name_trgm", "shop", "name"),
+];
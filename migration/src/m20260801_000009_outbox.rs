@@ -0,0 +1,30 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::schema::Schema;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let schema = Schema::new(manager.get_database_backend());
+
+        manager
+            .create_table(schema.create_table_from_entity(infra::outbox::Entity))
+            .await?;
+        // 中継のたびに status と next_attempt_at で配信待ちを探す
+        for index in schema.create_index_from_entity(infra::outbox::Entity) {
+            manager.create_index(index).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(infra::outbox::Entity).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::schema::Schema;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let schema = Schema::new(manager.get_database_backend());
+
+        manager
+            .create_table(schema.create_table_from_entity(infra::webhook_subscription::Entity))
+            .await?;
+        manager
+            .create_table(schema.create_table_from_entity(infra::webhook_delivery::Entity))
+            .await?;
+        for index in schema.create_index_from_entity(infra::webhook_delivery::Entity) {
+            manager.create_index(index).await?;
+        }
+        // outbox の中継が再送しても、同じ購読に同じメッセージを重ねて積まない
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-webhook_delivery-subscription_id-message_id")
+                    .table(infra::webhook_delivery::Entity)
+                    .col(infra::webhook_delivery::Column::SubscriptionId)
+                    .col(infra::webhook_delivery::Column::MessageId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(infra::webhook_delivery::Entity)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(infra::webhook_subscription::Entity)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
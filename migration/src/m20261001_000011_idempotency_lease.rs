@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::EntityName;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum IdempotencyKey {
+    LockedUntil,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 以前のマイグレーションは Entity 定義からテーブルを作成するため、
+        // 新規 DB では既にカラムが存在する。既存 DB のみカラムを追加する。
+        // 既存の処理中のレコードは NULL のままにし、期限切れとして引き継がせる
+        let table = infra::idempotency::Entity.table_name();
+        if !manager.has_column(table, "locked_until").await? {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .add_column(
+                            ColumnDef::new(IdempotencyKey::LockedUntil)
+                                .timestamp_with_time_zone()
+                                .null(),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alias::new(infra::idempotency::Entity.table_name()))
+                    .drop_column(IdempotencyKey::LockedUntil)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::config::{Config, DatabaseConfig, OutboxConfig, SinkConfig};
+use crate::health::{DatabaseCheck, MigrationCheck, WorkerStatus};
+use api::AppState;
+use sea_orm::{Database, DatabaseConnection};
+use std::sync::Arc;
+
+/// サーバーと各サブコマンドで共通の依存関係
+pub struct App {
+    pub db: DatabaseConnection,
+    pub state: Arc<AppState>,
+    pub purge_status: Arc<WorkerStatus>,
+    pub outbox_status: Arc<WorkerStatus>,
+    pub webhook_status: Arc<WorkerStatus>,
+}
+
+impl App {
+    pub async fn connect(config: &DatabaseConfig) -> anyhow::Result<DatabaseConnection> {
+        let mut db = Database::connect(config.connect_options()).await?;
+        infra::metrics::register(&mut db)?;
+        Ok(db)
+    }
+
+    pub fn new(db: DatabaseConnection, config: &Config) -> anyhow::Result<Self> {
+        let book_repo =
+            Arc::new(infra::book::SqlRepository::new(db.clone())) as Arc<dyn book::Repository>;
+        let publisher_repo = Arc::new(infra::publisher::SqlRepository::new(db.clone()))
+            as Arc<dyn publisher::Repository>;
+        let shop_repo =
+            Arc::new(infra::shop::SqlRepository::new(db.clone())) as Arc<dyn shop::Repository>;
+        let api_key_repo = Arc::new(infra::api_key::SqlRepository::new(db.clone()))
+            as Arc<dyn api_key::Repository>;
+        let idempotency_repo = Arc::new(infra::idempotency::SqlRepository::new(db.clone()))
+            as Arc<dyn idempotency::Repository>;
+        let change_event_repo = Arc::new(infra::change_event::SqlRepository::new(db.clone()))
+            as Arc<dyn change_event::Repository>;
+        let outbox_repo =
+            Arc::new(infra::outbox::SqlRepository::new(db.clone())) as Arc<dyn outbox::Repository>;
+
+        let change_event_usecase = Arc::new(usecase::change_event::Service::new(
+            change_event_repo,
+            chrono::Duration::days(config.events.retention_days),
+        ));
+        // 保存に成功した変更のドメインイベントを受け取る処理はここで登録する
+        let domain_events = Arc::new(usecase::domain_event::Dispatcher::new());
+        domain_events.register(Arc::new(usecase::metrics::DomainEventCounter));
+        let book_usecase = usecase::book::Service::new(
+            book_repo,
+            publisher_repo.clone(),
+            shop_repo.clone(),
+            change_event_usecase.clone(),
+            domain_events.clone(),
+        );
+        let publisher_usecase = usecase::publisher::Service::new(
+            publisher_repo,
+            change_event_usecase.clone(),
+            domain_events.clone(),
+        );
+        let shop_usecase = usecase::shop::Service::new(
+            shop_repo,
+            change_event_usecase.clone(),
+            domain_events.clone(),
+        );
+        let api_key_usecase = usecase::api_key::Service::new(api_key_repo);
+        let idempotency_usecase = usecase::idempotency::Service::new(
+            idempotency_repo,
+            chrono::Duration::seconds(config.idempotency.ttl_secs as i64),
+            config.idempotency_lease(),
+        );
+
+        let webhook_usecase = Arc::new(usecase::webhook::Service::new(
+            Arc::new(infra::webhook_subscription::SqlRepository::new(db.clone())),
+            Arc::new(infra::webhook_delivery::SqlRepository::new(db.clone())),
+            Arc::new(infra::webhook_sender::HttpSender::new(
+                std::time::Duration::from_secs(config.webhook.timeout_secs),
+            )?),
+            config.webhook.retry_policy()?,
+            chrono::Duration::days(config.webhook.retention_days),
+        ));
+        let mut sinks = Self::outbox_sinks(&config.outbox)?;
+        sinks.push(Arc::new(webhook_usecase.fanout()));
+        let outbox_usecase = usecase::outbox::Service::new(
+            outbox_repo,
+            sinks,
+            config.outbox.retry_policy()?,
+            chrono::Duration::days(config.outbox.retention_days),
+        );
+
+        let purge_status = Arc::new(WorkerStatus::new("purge_worker"));
+        let outbox_status = Arc::new(WorkerStatus::new("outbox_relay"));
+        let webhook_status = Arc::new(WorkerStatus::new("webhook_delivery"));
+        let state = Arc::new(AppState {
+            api_key_usecase,
+            book_usecase,
+            publisher_usecase,
+            shop_usecase,
+            idempotency_usecase,
+            change_event_usecase,
+            outbox_usecase,
+            webhook_usecase,
+            readiness_checks: vec![
+                Arc::new(DatabaseCheck(db.clone())),
+                Arc::new(MigrationCheck(db.clone())),
+                purge_status.clone(),
+                outbox_status.clone(),
+                webhook_status.clone(),
+            ],
+        });
+
+        Ok(Self {
+            db,
+            state,
+            purge_status,
+            outbox_status,
+            webhook_status,
+        })
+    }
+
+    fn outbox_sinks(config: &OutboxConfig) -> anyhow::Result<Vec<Arc<dyn outbox::Sink>>> {
+        config
+            .sinks
+            .iter()
+            .map(|sink| {
+                let sink: Arc<dyn outbox::Sink> = match sink {
+                    SinkConfig::Webhook { url, timeout_secs } => {
+                        Arc::new(infra::outbox_sink::WebhookSink::new(
+                            url.clone(),
+                            std::time::Duration::from_secs(*timeout_secs),
+                        )?)
+                    }
+                    SinkConfig::File { path } => {
+                        Arc::new(infra::outbox_sink::FileSink::new(path.clone()))
+                    }
+                };
+                Ok(sink)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,183 @@
+use crate::config::Overrides;
+use api::export::{csv_line, ndjson_line};
+use clap::{Parser, Subcommand, ValueEnum};
+use futures::TryStreamExt;
+use migration::{MigrationStatus, Migrator, MigratorTrait};
+use sea_orm::DatabaseConnection;
+use std::path::PathBuf;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use usecase::book::Service;
+
+/// 書籍管理 API のサーバーと運用コマンド
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// 省略時は HTTP サーバーを起動する
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub overrides: Overrides,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// HTTP サーバーを起動する
+    Serve,
+    /// OpenAPI 定義を出力する (DB には接続しない)
+    Openapi {
+        /// 出力先。省略時は標準出力
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// マイグレーションを適用・取り消し・確認する (database.auto_migrate は無視する)
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// YAML / JSON のフィクスチャを登録する。同じ名前のものは登録済みとして読み飛ばす
+    Seed { path: PathBuf },
+    /// Book を全件書き出す
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// 出力先。省略時は標準出力
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// 論理削除済みも含める
+        #[arg(long)]
+        include_deleted: bool,
+    },
+    /// API キーを発行・一覧・失効する。最初の admin キーはここで発行する
+    #[command(name = "api-key")]
+    ApiKey {
+        #[command(subcommand)]
+        action: ApiKeyAction,
+    },
+    /// CSV から Book を取り込む (列: title, author, publisher, shop, format, price)
+    #[command(alias = "import-books")]
+    Import {
+        path: PathBuf,
+        /// 検証のみ行い、書き込みはしない
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateAction {
+    /// 未適用のマイグレーションを適用する
+    Up {
+        /// 適用する数。省略時は全て
+        #[arg(long)]
+        steps: Option<u32>,
+    },
+    /// 適用済みのマイグレーションを新しい順に取り消す
+    Down {
+        #[arg(long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// 各マイグレーションの適用状況を表示する
+    Status,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ApiKeyAction {
+    /// 新しいキーを発行し、秘密の値を一度だけ表示する
+    Issue {
+        #[arg(long)]
+        name: String,
+        /// キーを管理する担当者やチーム
+        #[arg(long)]
+        owner: String,
+        /// 例: --scope books:read --scope books:write (admin で管理 API も使える)
+        #[arg(long = "scope", required = true)]
+        scopes: Vec<String>,
+        /// 有効期限 (日数)。省略すると無期限
+        #[arg(long)]
+        expires_in_days: Option<i64>,
+    },
+    /// 失効済みも含めて一覧する
+    List,
+    /// キーを失効させる
+    Revoke { pub_id: uuid::Uuid },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+}
+
+pub async fn migrate(db: &DatabaseConnection, action: MigrateAction) -> anyhow::Result<()> {
+    match action {
+        MigrateAction::Up { steps } => Migrator::up(db, steps).await?,
+        MigrateAction::Down { steps } => Migrator::down(db, Some(steps)).await?,
+        MigrateAction::Status => {
+            for migration in Migrator::get_migration_with_status(db).await? {
+                let status = match migration.status() {
+                    MigrationStatus::Applied => "applied",
+                    MigrationStatus::Pending => "pending",
+                };
+                println!("{:<8} {}", status, migration.name());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 結果は HTTP の管理 API と同じ JSON で表示する
+pub async fn api_key(
+    api_key_usecase: &usecase::api_key::Service,
+    action: ApiKeyAction,
+) -> anyhow::Result<()> {
+    let output = match action {
+        ApiKeyAction::Issue {
+            name,
+            owner,
+            scopes,
+            expires_in_days,
+        } => {
+            let issued = api_key_usecase
+                .issue(usecase::api_key::IssueDto {
+                    name,
+                    owner,
+                    scopes,
+                    expires_at: expires_in_days
+                        .map(|days| chrono::Utc::now() + chrono::Duration::days(days)),
+                })
+                .await?;
+            serde_json::to_string_pretty(&issued)?
+        }
+        ApiKeyAction::List => serde_json::to_string_pretty(&api_key_usecase.get_all().await?)?,
+        ApiKeyAction::Revoke { pub_id } => {
+            serde_json::to_string_pretty(&api_key_usecase.revoke(pub_id).await?)?
+        }
+    };
+    println!("{}", output);
+    Ok(())
+}
+
+/// HTTP の GET /books と同じ形式で 1 行ずつ書き出す
+pub async fn export<W>(
+    book_usecase: &Service,
+    format: ExportFormat,
+    include_deleted: bool,
+    mut writer: W,
+) -> anyhow::Result<u64>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut rows = book_usecase.stream_all(include_deleted);
+    let mut count = 0;
+    while let Some(book) = rows.try_next().await? {
+        let line = match format {
+            ExportFormat::Csv => csv_line(&api::book::CsvRecord::from(book), count == 0),
+            ExportFormat::Ndjson => ndjson_line(&book),
+        }
+        .map_err(|e| anyhow::anyhow!(e))?;
+        writer.write_all(&line).await?;
+        count += 1;
+    }
+    writer.flush().await?;
+    Ok(count)
+}
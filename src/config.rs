@@ -0,0 +1,515 @@
+use clap::{Args, ValueEnum};
+use figment::Figment;
+use figment::providers::{Env, Format, Serialized, Toml};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// --config を指定しない場合に読む設定ファイル (無ければ読まない)
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// 既定値 < 設定ファイル (TOML) < 環境変数 < CLI フラグ の順に上書きする。
+/// 環境変数は `APP_SERVER__BIND` のように `APP_` + セクション + `__` + キーで指定する。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub log: LogConfig,
+    pub http: api::HttpConfig,
+    pub purge: PurgeConfig,
+    pub telemetry: TelemetryConfig,
+    pub idempotency: IdempotencyConfig,
+    pub events: EventsConfig,
+    pub outbox: OutboxConfig,
+    pub webhook: WebhookConfig,
+    pub grpc: GrpcConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind: SocketAddr,
+    /// 終了シグナルを受けてから処理中のリクエストを待つ秒数
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout_secs: u64,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+    /// 起動時に Migrator::up を実行するか
+    pub auto_migrate: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 10,
+            min_connections: 0,
+            connect_timeout_secs: 8,
+            acquire_timeout_secs: 8,
+            idle_timeout_secs: 600,
+            auto_migrate: true,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn connect_options(&self) -> sea_orm::ConnectOptions {
+        let mut options = sea_orm::ConnectOptions::new(self.url.clone());
+        options
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+            .idle_timeout(Duration::from_secs(self.idle_timeout_secs));
+        options
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Full,
+    Compact,
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    /// tracing の EnvFilter 形式 (例: "info,sqlx=warn")
+    pub filter: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Full,
+            filter: "info".to_string(),
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn layer<S>(&self) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        // 標準出力はサブコマンドの出力 (export など) に使うため、ログは標準エラーに出す
+        let layer = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+        match self.format {
+            LogFormat::Full => layer.boxed(),
+            LogFormat::Compact => layer.compact().boxed(),
+            LogFormat::Pretty => layer.pretty().boxed(),
+            LogFormat::Json => layer.json().with_span_list(true).boxed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PurgeConfig {
+    /// 論理削除してから物理削除するまでの日数
+    pub retention_days: i64,
+    pub interval_secs: u64,
+}
+
+impl Default for PurgeConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            interval_secs: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP のコレクタ (例: "http://localhost:4318")。未指定ならスパンを送らない
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    /// traceparent を持たないリクエストのうちトレースする割合 (0.0 - 1.0)
+    pub sample_ratio: f64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: env!("CARGO_PKG_NAME").to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+/// タイムアウト直前に終わった処理がレスポンスを保存し終えるまでの余裕
+const IDEMPOTENCY_LEASE_MARGIN_SECS: u64 = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdempotencyConfig {
+    /// Idempotency-Key と保存したレスポンスを保持する秒数
+    pub ttl_secs: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self { ttl_secs: 86400 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    /// 変更フィード (GET /events) のイベントログを保持する日数。これより前からは再開できない
+    pub retention_days: i64,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self { retention_days: 7 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutboxConfig {
+    /// 配信待ちのメッセージを探す間隔
+    pub interval_secs: u64,
+    /// この回数失敗したら dead にし、管理 API から再試行するまで配信しない
+    pub max_attempts: u32,
+    /// 再試行までの待ち時間。失敗するたびに倍にし、backoff_max_secs で頭打ちにする
+    pub backoff_base_secs: u64,
+    pub backoff_max_secs: u64,
+    /// 配信済みのメッセージを残す日数
+    pub retention_days: i64,
+    /// 全ての配信先に届いたものを配信済みにする。webhook の購読への振り分けは常に行う
+    pub sinks: Vec<SinkConfig>,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 5,
+            max_attempts: 10,
+            backoff_base_secs: 5,
+            backoff_max_secs: 3600,
+            retention_days: 7,
+            sinks: Vec::new(),
+        }
+    }
+}
+
+impl OutboxConfig {
+    pub fn retry_policy(&self) -> Result<outbox::vo::RetryPolicy, outbox::DomainError> {
+        outbox::vo::RetryPolicy::new(
+            self.max_attempts,
+            chrono::Duration::seconds(self.backoff_base_secs as i64),
+            chrono::Duration::seconds(self.backoff_max_secs as i64),
+        )
+    }
+}
+
+/// POST /webhooks で購読された配信先への送信
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// 配信待ちを探す間隔
+    pub interval_secs: u64,
+    /// 1 回の送信で応答を待つ秒数
+    pub timeout_secs: u64,
+    /// この回数失敗したら dead にして送るのをやめる
+    pub max_attempts: u32,
+    /// 再試行までの待ち時間。失敗するたびに倍にし、backoff_max_secs で頭打ちにする
+    pub backoff_base_secs: u64,
+    pub backoff_max_secs: u64,
+    /// 配信済みの記録を残す日数
+    pub retention_days: i64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 5,
+            timeout_secs: 10,
+            max_attempts: 8,
+            backoff_base_secs: 10,
+            backoff_max_secs: 3600,
+            retention_days: 30,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn retry_policy(&self) -> Result<outbox::vo::RetryPolicy, outbox::DomainError> {
+        outbox::vo::RetryPolicy::new(
+            self.max_attempts,
+            chrono::Duration::seconds(self.backoff_base_secs as i64),
+            chrono::Duration::seconds(self.backoff_max_secs as i64),
+        )
+    }
+}
+
+/// grpc feature を有効にしてビルドした時だけ使う。認証は [http.auth] に従う
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcConfig {
+    pub bind: SocketAddr,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 50051)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SinkConfig {
+    /// JSON を POST する
+    Webhook {
+        url: String,
+        #[serde(default = "default_webhook_timeout_secs")]
+        timeout_secs: u64,
+    },
+    /// JSON Lines で追記する
+    File { path: PathBuf },
+}
+
+fn default_webhook_timeout_secs() -> u64 {
+    10
+}
+
+/// CLI フラグによる上書き。全てのサブコマンドで使える
+#[derive(Debug, Clone, Default, Args)]
+pub struct Overrides {
+    /// 設定ファイルのパス (既定: ./config.toml があれば読む)
+    #[arg(long, global = true, env = "APP_CONFIG")]
+    pub config: Option<PathBuf>,
+    #[arg(long, global = true)]
+    pub bind: Option<SocketAddr>,
+    #[arg(long, global = true)]
+    pub database_url: Option<String>,
+    #[arg(long, global = true)]
+    pub auto_migrate: Option<bool>,
+    #[arg(long, global = true)]
+    pub log_format: Option<LogFormat>,
+    #[arg(long, global = true)]
+    pub swagger_ui: Option<bool>,
+    #[arg(long, global = true)]
+    pub otlp_endpoint: Option<String>,
+}
+
+impl Config {
+    pub fn load(overrides: &Overrides) -> anyhow::Result<Self> {
+        let mut figment = Figment::from(Serialized::defaults(Config::default()));
+        figment = match &overrides.config {
+            Some(path) if !path.exists() => {
+                anyhow::bail!("Config file {} not found", path.display())
+            }
+            Some(path) => figment.merge(Toml::file_exact(path)),
+            None => figment.merge(Toml::file(DEFAULT_CONFIG_PATH)),
+        };
+        let mut config: Config = figment
+            .merge(legacy_env())
+            .merge(Env::prefixed("APP_").split("__"))
+            .extract()
+            .map_err(|e| anyhow::anyhow!("Invalid configuration: {}", e))?;
+
+        if let Some(bind) = overrides.bind {
+            config.server.bind = bind;
+        }
+        if let Some(url) = &overrides.database_url {
+            config.database.url = url.clone();
+        }
+        if let Some(auto_migrate) = overrides.auto_migrate {
+            config.database.auto_migrate = auto_migrate;
+        }
+        if let Some(format) = overrides.log_format {
+            config.log.format = format;
+        }
+        if let Some(swagger_ui) = overrides.swagger_ui {
+            config.http.swagger_ui = swagger_ui;
+        }
+        if let Some(endpoint) = &overrides.otlp_endpoint {
+            config.telemetry.otlp_endpoint = Some(endpoint.clone());
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 処理中の Idempotency-Key を押さえておく時間。リクエストのタイムアウトで打ち切られた
+    /// 処理のキーを、再試行がこれだけ待てば引き継げる
+    pub fn idempotency_lease(&self) -> chrono::Duration {
+        chrono::Duration::seconds(
+            (self.http.request_timeout_secs + IDEMPOTENCY_LEASE_MARGIN_SECS) as i64,
+        )
+    }
+
+    /// 全ての誤りをまとめて報告する
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+
+        let db = &self.database;
+        if db.url.is_empty() {
+            errors.push(
+                "database.url is required (set DATABASE_URL or APP_DATABASE__URL)".to_string(),
+            );
+        }
+        if db.max_connections == 0 {
+            errors.push("database.max_connections must be 1 or greater".to_string());
+        }
+        if db.min_connections > db.max_connections {
+            errors.push(format!(
+                "database.min_connections ({}) must not exceed database.max_connections ({})",
+                db.min_connections, db.max_connections
+            ));
+        }
+        for (name, secs) in [
+            ("connect_timeout_secs", db.connect_timeout_secs),
+            ("acquire_timeout_secs", db.acquire_timeout_secs),
+            ("idle_timeout_secs", db.idle_timeout_secs),
+        ] {
+            if secs == 0 {
+                errors.push(format!("database.{} must be 1 or greater", name));
+            }
+        }
+
+        if let Err(e) = EnvFilter::try_new(&self.log.filter) {
+            errors.push(format!(
+                "log.filter '{}' is invalid: {}",
+                self.log.filter, e
+            ));
+        }
+        errors.extend(self.http.validate());
+        if self.purge.retention_days < 0 {
+            errors.push("purge.retention_days must not be negative".to_string());
+        }
+        if self.purge.interval_secs == 0 {
+            errors.push("purge.interval_secs must be 1 or greater".to_string());
+        }
+        if self.idempotency.ttl_secs == 0 {
+            errors.push("idempotency.ttl_secs must be 1 or greater".to_string());
+        }
+        if self.events.retention_days < 1 {
+            errors.push("events.retention_days must be 1 or greater".to_string());
+        }
+        let outbox = &self.outbox;
+        if outbox.interval_secs == 0 {
+            errors.push("outbox.interval_secs must be 1 or greater".to_string());
+        }
+        if let Err(e) = outbox.retry_policy() {
+            errors.push(format!(
+                "outbox.max_attempts / backoff_base_secs / backoff_max_secs are invalid: {}",
+                e
+            ));
+        }
+        if outbox.retention_days < 1 {
+            errors.push("outbox.retention_days must be 1 or greater".to_string());
+        }
+        for sink in &outbox.sinks {
+            if let SinkConfig::Webhook { url, timeout_secs } = sink {
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    errors.push(format!(
+                        "outbox webhook url '{}' must be an http(s) URL",
+                        url
+                    ));
+                }
+                if *timeout_secs == 0 {
+                    errors.push("outbox webhook timeout_secs must be 1 or greater".to_string());
+                }
+            }
+        }
+        let webhook = &self.webhook;
+        if webhook.interval_secs == 0 {
+            errors.push("webhook.interval_secs must be 1 or greater".to_string());
+        }
+        if webhook.timeout_secs == 0 {
+            errors.push("webhook.timeout_secs must be 1 or greater".to_string());
+        }
+        if let Err(e) = webhook.retry_policy() {
+            errors.push(format!(
+                "webhook.max_attempts / backoff_base_secs / backoff_max_secs are invalid: {}",
+                e
+            ));
+        }
+        if webhook.retention_days < 1 {
+            errors.push("webhook.retention_days must be 1 or greater".to_string());
+        }
+        if self.grpc.bind == self.server.bind {
+            errors.push("grpc.bind must differ from server.bind".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.telemetry.sample_ratio) {
+            errors.push("telemetry.sample_ratio must be between 0.0 and 1.0".to_string());
+        }
+        if let Some(endpoint) = &self.telemetry.otlp_endpoint
+            && !(endpoint.starts_with("http://") || endpoint.starts_with("https://"))
+        {
+            errors.push(format!(
+                "telemetry.otlp_endpoint '{}' must be an http(s) URL",
+                endpoint
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Invalid configuration:\n  - {}", errors.join("\n  - "))
+        }
+    }
+}
+
+/// 以前から使っている環境変数も引き続き読む
+fn legacy_env() -> Env {
+    Env::raw()
+        .only(&[
+            "DATABASE_URL",
+            "RUST_LOG",
+            "SOFT_DELETE_RETENTION_DAYS",
+            "PURGE_INTERVAL_SECS",
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+            "OTEL_SERVICE_NAME",
+        ])
+        .map(|key| {
+            if key == "DATABASE_URL" {
+                "database.url".into()
+            } else if key == "RUST_LOG" {
+                "log.filter".into()
+            } else if key == "SOFT_DELETE_RETENTION_DAYS" {
+                "purge.retention_days".into()
+            } else if key == "PURGE_INTERVAL_SECS" {
+                "purge.interval_secs".into()
+            } else if key == "OTEL_EXPORTER_OTLP_ENDPOINT" {
+                "telemetry.otlp_endpoint".into()
+            } else {
+                "telemetry.service_name".into()
+            }
+        })
+}
@@ -0,0 +1,93 @@
+use api::health::Check;
+use async_trait::async_trait;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::DatabaseConnection;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// DB に接続できるか
+pub struct DatabaseCheck(pub DatabaseConnection);
+
+#[async_trait]
+impl Check for DatabaseCheck {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        self.0.ping().await.map_err(|e| e.to_string())
+    }
+}
+
+/// 未適用のマイグレーションが無いか
+pub struct MigrationCheck(pub DatabaseConnection);
+
+#[async_trait]
+impl Check for MigrationCheck {
+    fn name(&self) -> &str {
+        "migrations"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        let pending = Migrator::get_pending_migrations(&self.0)
+            .await
+            .map_err(|e| e.to_string())?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let names: Vec<_> = pending.iter().map(|m| m.name().to_string()).collect();
+        Err(format!("pending migrations: {}", names.join(", ")))
+    }
+}
+
+/// バックグラウンドワーカーの稼働状況。ワーカー側が更新し、/readyz で参照する
+pub struct WorkerStatus {
+    name: String,
+    alive: AtomicBool,
+    last_error: Mutex<Option<String>>,
+}
+
+impl WorkerStatus {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            alive: AtomicBool::new(false),
+            last_error: Mutex::new(None),
+        }
+    }
+
+    /// 返した値が drop されるまで (panic を含む) 稼働中とみなす
+    pub fn start(&self) -> AliveGuard<'_> {
+        self.alive.store(true, Ordering::SeqCst);
+        AliveGuard(self)
+    }
+
+    pub fn record(&self, result: Result<(), String>) {
+        *self.last_error.lock().unwrap() = result.err();
+    }
+}
+
+pub struct AliveGuard<'a>(&'a WorkerStatus);
+
+impl Drop for AliveGuard<'_> {
+    fn drop(&mut self) {
+        self.0.alive.store(false, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Check for WorkerStatus {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        if !self.alive.load(Ordering::SeqCst) {
+            return Err("worker is not running".to_string());
+        }
+        match self.last_error.lock().unwrap().as_ref() {
+            Some(e) => Err(format!("last run failed: {}", e)),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,46 +1,158 @@
-use api::{AppState, create_router};
+use api::create_router;
+use app::App;
+use clap::Parser;
+use cli::{Cli, Command};
+use config::Config;
 use migration::{Migrator, MigratorTrait};
-use sea_orm::Database;
-use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+mod app;
+mod cli;
+mod config;
+mod health;
+mod outbox;
+mod purge;
+mod seed;
+mod shutdown;
+mod telemetry;
 mod test;
+mod webhook;
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // 0. Load .env
+    // 0. Load .env and Config
     dotenvy::dotenv().ok();
-    tracing_subscriber::fmt::init();
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Serve);
+    if let Command::Openapi { output } = &command {
+        let spec = api::openapi().to_pretty_json()?;
+        match output {
+            Some(path) => std::fs::write(path, spec)?,
+            None => println!("{}", spec),
+        }
+        return Ok(());
+    }
+    let config = Config::load(&cli.overrides)?;
+    let _telemetry = telemetry::init(&config.log, &config.telemetry)?;
 
     // 1. Database Connection
-    let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let db = Database::connect(db_url).await?;
+    let db = App::connect(&config.database).await?;
 
     // 2. Run Migrations
-    Migrator::up(&db, None).await?;
+    if let Command::Migrate { action } = command {
+        cli::migrate(&db, action).await?;
+        db.close().await?;
+        return Ok(());
+    }
+    if config.database.auto_migrate {
+        Migrator::up(&db, None).await?;
+    }
 
     // 3. Dependency Injection
-    let book_repo =
-        Arc::new(infra::book::SqlRepository::new(db.clone())) as Arc<dyn book::Repository>;
-    let publisher_repo = Arc::new(infra::publisher::SqlRepository::new(db.clone()))
-        as Arc<dyn publisher::Repository>;
-    let shop_repo =
-        Arc::new(infra::shop::SqlRepository::new(db.clone())) as Arc<dyn shop::Repository>;
-
-    let book_usecase =
-        usecase::book::Service::new(book_repo, publisher_repo.clone(), shop_repo.clone());
-    let publisher_usecase = usecase::publisher::Service::new(publisher_repo);
-    let shop_usecase = usecase::shop::Service::new(shop_repo);
-
-    let state = Arc::new(AppState {
-        book_usecase,
-        publisher_usecase,
-        shop_usecase,
-    });
-
-    // 4. Start Server
-    let router = create_router(state);
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
-    println!("Server running on http://localhost:3000");
-
-    axum::serve(listener, router).await?;
+    let app = App::new(db, &config)?;
 
+    match command {
+        Command::Serve => serve(&app, &config).await?,
+        Command::Seed { path } => {
+            let fixtures = seed::Fixtures::load(&path)?;
+            let report = seed::seed(&app.state, fixtures).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        Command::Export {
+            format,
+            output,
+            include_deleted,
+        } => {
+            let count = match output {
+                Some(path) => {
+                    let file = tokio::fs::File::create(path).await?;
+                    cli::export(&app.state.book_usecase, format, include_deleted, file).await?
+                }
+                None => {
+                    let stdout = tokio::io::stdout();
+                    cli::export(&app.state.book_usecase, format, include_deleted, stdout).await?
+                }
+            };
+            tracing::info!(count, "exported books");
+        }
+        Command::ApiKey { action } => cli::api_key(&app.state.api_key_usecase, action).await?,
+        Command::Import { path, dry_run } => {
+            let file = tokio::fs::File::open(path).await?;
+            let report = app.state.book_usecase.import(file, dry_run).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if !report.errors.is_empty() {
+                anyhow::bail!("{} rows failed validation", report.errors.len());
+            }
+        }
+        Command::Openapi { .. } | Command::Migrate { .. } => unreachable!(),
+    }
+
+    app.db.close().await?;
+    Ok(())
+}
+
+async fn serve(app: &App, config: &Config) -> anyhow::Result<()> {
+    // 4. Start Background Tasks
+    let background = CancellationToken::new();
+    let mut tasks = tokio::task::JoinSet::new();
+    tasks.spawn(purge::run(
+        app.state.clone(),
+        chrono::Duration::days(config.purge.retention_days),
+        std::time::Duration::from_secs(config.purge.interval_secs),
+        app.purge_status.clone(),
+        background.clone(),
+    ));
+    tasks.spawn(outbox::run(
+        app.state.clone(),
+        std::time::Duration::from_secs(config.outbox.interval_secs),
+        app.outbox_status.clone(),
+        background.clone(),
+    ));
+    tasks.spawn(webhook::run(
+        app.state.clone(),
+        std::time::Duration::from_secs(config.webhook.interval_secs),
+        app.webhook_status.clone(),
+        background.clone(),
+    ));
+
+    // gRPC は HTTP の drain が済んでバックグラウンド処理を止める時に合わせて止める
+    #[cfg(feature = "grpc")]
+    {
+        let listener = tokio::net::TcpListener::bind(config.grpc.bind).await?;
+        println!("gRPC server running on {}", config.grpc.bind);
+        let routes = grpc::routes(app.state.clone(), config.http.auth.allow_anonymous);
+        let shutdown = background.clone();
+        tasks.spawn(async move {
+            if let Err(e) = grpc::serve(listener, routes, shutdown.cancelled_owned()).await {
+                tracing::error!(error = %e, "gRPC server failed");
+            }
+        });
+    }
+
+    // 5. Start Server
+    let router = create_router(app.state.clone(), &config.http);
+    let listener = tokio::net::TcpListener::bind(config.server.bind).await?;
+    println!("Server running on http://{}", config.server.bind);
+
+    let state = app.state.clone();
+    shutdown::serve(
+        listener,
+        router,
+        async move {
+            shutdown::signal().await;
+            // 終わらない変更フィードの接続を閉じ、drain を待たせないようにする
+            state.change_event_usecase.close();
+        },
+        std::time::Duration::from_secs(config.server.shutdown_timeout_secs),
+    )
+    .await?;
+
+    // 6. Shutdown
+    // リクエストを捌き終えてからバックグラウンド処理を止める。接続は呼び出し元で閉じる
+    background.cancel();
+    while let Some(result) = tasks.join_next().await {
+        if let Err(e) = result {
+            tracing::error!(error = %e, "background task failed");
+        }
+    }
+    tracing::info!("shutdown complete");
     Ok(())
 }
@@ -0,0 +1,39 @@
+use crate::health::WorkerStatus;
+use api::AppState;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+/// outbox に溜まったメッセージを一定間隔で Sink へ中継する
+pub async fn run(
+    state: Arc<AppState>,
+    interval: std::time::Duration,
+    status: Arc<WorkerStatus>,
+    shutdown: CancellationToken,
+) {
+    let _alive = status.start();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+        // Sink への配信の失敗は再試行に回るため、DB に触れなかったときだけ異常とする
+        match state.outbox_usecase.relay().await {
+            Ok(report) => {
+                if report.delivered + report.retrying + report.dead > 0 {
+                    tracing::info!(
+                        delivered = report.delivered,
+                        retrying = report.retrying,
+                        dead = report.dead,
+                        "relayed outbox messages"
+                    );
+                }
+                status.record(Ok(()));
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "failed to relay outbox messages");
+                status.record(Err(e.to_string()));
+            }
+        }
+    }
+}
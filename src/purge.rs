@@ -0,0 +1,55 @@
+use crate::health::WorkerStatus;
+use api::AppState;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use usecase::error::UseCaseError;
+
+/// 論理削除から retention 以上経過したレコードと、期限切れの Idempotency-Key・変更イベント・配信済みの outbox と webhook の配信記録を物理削除する
+pub async fn purge(state: &AppState, retention: chrono::Duration) -> Result<u64, UseCaseError> {
+    let deleted_before = chrono::Utc::now() - retention;
+
+    // Book が Publisher / Shop を参照しているため Book から削除する
+    let books = state.book_usecase.purge_deleted(deleted_before).await?;
+    let publishers = state
+        .publisher_usecase
+        .purge_deleted(deleted_before)
+        .await?;
+    let shops = state.shop_usecase.purge_deleted(deleted_before).await?;
+    // 期限切れの Idempotency-Key も合わせて掃除する
+    let keys = state.idempotency_usecase.purge_expired().await?;
+    let events = state.change_event_usecase.purge_expired().await?;
+    let messages = state.outbox_usecase.purge_delivered().await?;
+    let deliveries = state.webhook_usecase.purge_delivered().await?;
+
+    Ok(books + publishers + shops + keys + events + messages + deliveries)
+}
+
+pub async fn run(
+    state: Arc<AppState>,
+    retention: chrono::Duration,
+    interval: std::time::Duration,
+    status: Arc<WorkerStatus>,
+    shutdown: CancellationToken,
+) {
+    let _alive = status.start();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        // 実行中の削除は中断せず、次の実行を待っている間だけ止める
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+        match purge(&state, retention).await {
+            Ok(count) => {
+                if count > 0 {
+                    tracing::info!(count, "purged soft-deleted rows");
+                }
+                status.record(Ok(()));
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "failed to purge soft-deleted rows");
+                status.record(Err(e.to_string()));
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::Context;
+use api::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// seed コマンドで読み込むフィクスチャ
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fixtures {
+    pub publishers: Vec<usecase::publisher::CreateDto>,
+    pub shops: Vec<usecase::shop::CreateDto>,
+    pub books: Vec<BookFixture>,
+}
+
+/// publisher / shop には名前か pub_id を指定する
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BookFixture {
+    pub title: String,
+    pub author: String,
+    pub publisher: String,
+    pub shop: Option<String>,
+    pub format: Option<String>,
+    pub price: i32,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SeedReport {
+    pub created: Counts,
+    pub skipped: Counts,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Counts {
+    pub publishers: u64,
+    pub shops: u64,
+    pub books: u64,
+}
+
+impl Fixtures {
+    /// 拡張子が .json なら JSON、それ以外は YAML として読む
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let fixtures = if path.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&content)?
+        } else {
+            serde_yaml::from_str(&content)?
+        };
+        Ok(fixtures)
+    }
+}
+
+/// ドメインのルールを通すためユースケース経由で登録する。
+/// 同じ名前の Publisher / Shop と、同じ title・author・publisher の Book は登録済みとみなすので、何度実行してもよい
+pub async fn seed(state: &AppState, fixtures: Fixtures) -> anyhow::Result<SeedReport> {
+    let mut report = SeedReport::default();
+
+    let mut publishers: HashMap<String, uuid::Uuid> = HashMap::new();
+    for p in state.publisher_usecase.get_all(false).await? {
+        publishers.entry(p.name).or_insert(p.pub_id);
+    }
+    for (i, dto) in fixtures.publishers.into_iter().enumerate() {
+        if publishers.contains_key(&dto.name) {
+            report.skipped.publishers += 1;
+            continue;
+        }
+        let created = state
+            .publisher_usecase
+            .create(dto)
+            .await
+            .with_context(|| format!("publishers[{}]", i))?;
+        publishers.insert(created.name, created.pub_id);
+        report.created.publishers += 1;
+    }
+
+    let mut shops: HashMap<String, uuid::Uuid> = HashMap::new();
+    for s in state.shop_usecase.get_all(false).await? {
+        shops.entry(s.name).or_insert(s.pub_id);
+    }
+    for (i, dto) in fixtures.shops.into_iter().enumerate() {
+        if shops.contains_key(&dto.name) {
+            report.skipped.shops += 1;
+            continue;
+        }
+        let created = state
+            .shop_usecase
+            .create(dto)
+            .await
+            .with_context(|| format!("shops[{}]", i))?;
+        shops.insert(created.name, created.pub_id);
+        report.created.shops += 1;
+    }
+
+    let mut books: Vec<(String, String, uuid::Uuid)> = state
+        .book_usecase
+        .get_all(false)
+        .await?
+        .into_iter()
+        .map(|b| (b.title, b.author, b.publisher.pub_id))
+        .collect();
+    for (i, fixture) in fixtures.books.into_iter().enumerate() {
+        let context = || format!("books[{}]", i);
+        let publisher_id =
+            resolve(&fixture.publisher, &publishers, "Publisher").with_context(context)?;
+        let shop_id = fixture
+            .shop
+            .as_deref()
+            .map(|s| resolve(s, &shops, "Shop"))
+            .transpose()
+            .with_context(context)?;
+
+        let key = (fixture.title.clone(), fixture.author.clone(), publisher_id);
+        if books.contains(&key) {
+            report.skipped.books += 1;
+            continue;
+        }
+        state
+            .book_usecase
+            .create(usecase::book::CreateDto {
+                title: fixture.title,
+                author: fixture.author,
+                publisher_id,
+                shop_id,
+                format: fixture.format,
+                price: fixture.price,
+            })
+            .await
+            .with_context(context)?;
+        books.push(key);
+        report.created.books += 1;
+    }
+
+    Ok(report)
+}
+
+fn resolve(
+    reference: &str,
+    by_name: &HashMap<String, uuid::Uuid>,
+    kind: &str,
+) -> anyhow::Result<uuid::Uuid> {
+    if let Ok(id) = uuid::Uuid::parse_str(reference) {
+        return Ok(id);
+    }
+    by_name
+        .get(reference)
+        .copied()
+        .with_context(|| format!("{} '{}' not found", kind, reference))
+}
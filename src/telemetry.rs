@@ -0,0 +1,65 @@
+use crate::config::{LogConfig, TelemetryConfig};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// 終了時に送信待ちのスパンを送り切るため、main の最後まで保持する
+pub struct Guard(Option<SdkTracerProvider>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.0.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to shut down tracer provider: {}", e);
+        }
+    }
+}
+
+/// ログの出力と、設定されていれば OTLP へのスパンの送信を始める
+pub fn init(log: &LogConfig, telemetry: &TelemetryConfig) -> anyhow::Result<Guard> {
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    let provider = telemetry
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| tracer_provider(endpoint, telemetry))
+        .transpose()?;
+    let otel = provider.as_ref().map(|p| {
+        tracing_opentelemetry::layer().with_tracer(p.tracer(telemetry.service_name.clone()))
+    });
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_new(&log.filter)?)
+        .with(log.layer())
+        .with(otel)
+        .try_init()?;
+    Ok(Guard(provider))
+}
+
+/// スパンをまとめて {endpoint}/v1/traces に送る
+pub fn tracer_provider(
+    endpoint: &str,
+    telemetry: &TelemetryConfig,
+) -> anyhow::Result<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        // 呼び出し元がサンプリングしたトレースは必ず続ける
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            telemetry.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(telemetry.service_name.clone())
+                .build(),
+        )
+        .build())
+}
@@ -1,5 +1,2798 @@
+#[cfg(test)]
+mod harness;
+#[cfg(test)]
+mod routes;
+
 #[cfg(test)]
 mod tests {
-    // Integration tests were removed/refactored.
-    // UseCase unit tests are now located in `usecase` crate.
+    // UseCase unit tests are located in `usecase` crate.
+    // ここでは SQLite 上でマイグレーションと infra のリポジトリを通して検証する。
+    use migration::{Migrator, MigratorTrait};
+    use rstest::*;
+    use sea_orm::{
+        ColumnTrait, Database, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    };
+    use std::sync::Arc;
+
+    #[fixture]
+    async fn db() -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to SQLite");
+        Migrator::up(&db, None)
+            .await
+            .expect("Failed to run migrations");
+        db
+    }
+
+    fn services(
+        db: &DatabaseConnection,
+    ) -> (
+        usecase::book::Service,
+        usecase::publisher::Service,
+        usecase::shop::Service,
+    ) {
+        services_with_dispatcher(db, Arc::new(usecase::domain_event::Dispatcher::new()))
+    }
+
+    fn services_with_dispatcher(
+        db: &DatabaseConnection,
+        dispatcher: Arc<usecase::domain_event::Dispatcher>,
+    ) -> (
+        usecase::book::Service,
+        usecase::publisher::Service,
+        usecase::shop::Service,
+    ) {
+        let book_repo =
+            Arc::new(infra::book::SqlRepository::new(db.clone())) as Arc<dyn book::Repository>;
+        let publisher_repo = Arc::new(infra::publisher::SqlRepository::new(db.clone()))
+            as Arc<dyn publisher::Repository>;
+        let shop_repo =
+            Arc::new(infra::shop::SqlRepository::new(db.clone())) as Arc<dyn shop::Repository>;
+        let events = change_events(db);
+        (
+            usecase::book::Service::new(
+                book_repo,
+                publisher_repo.clone(),
+                shop_repo.clone(),
+                events.clone(),
+                dispatcher.clone(),
+            ),
+            usecase::publisher::Service::new(publisher_repo, events.clone(), dispatcher.clone()),
+            usecase::shop::Service::new(shop_repo, events, dispatcher),
+        )
+    }
+
+    fn change_events(db: &DatabaseConnection) -> Arc<usecase::change_event::Service> {
+        Arc::new(usecase::change_event::Service::new(
+            Arc::new(infra::change_event::SqlRepository::new(db.clone())),
+            chrono::Duration::days(7),
+        ))
+    }
+
+    fn outbox(
+        db: &DatabaseConnection,
+        sinks: Vec<Arc<dyn outbox::Sink>>,
+    ) -> usecase::outbox::Service {
+        usecase::outbox::Service::new(
+            Arc::new(infra::outbox::SqlRepository::new(db.clone())),
+            sinks,
+            outbox::vo::RetryPolicy::new(
+                3,
+                chrono::Duration::seconds(1),
+                chrono::Duration::seconds(60),
+            )
+            .unwrap(),
+            chrono::Duration::days(7),
+        )
+    }
+
+    fn webhooks_with_sender(
+        db: &DatabaseConnection,
+        sender: infra::webhook_sender::HttpSender,
+    ) -> Arc<usecase::webhook::Service> {
+        Arc::new(usecase::webhook::Service::new(
+            Arc::new(infra::webhook_subscription::SqlRepository::new(db.clone())),
+            Arc::new(infra::webhook_delivery::SqlRepository::new(db.clone())),
+            Arc::new(sender),
+            outbox::vo::RetryPolicy::new(
+                3,
+                chrono::Duration::milliseconds(1),
+                chrono::Duration::seconds(60),
+            )
+            .unwrap(),
+            chrono::Duration::days(7),
+        ))
+    }
+
+    fn api_keys(db: &DatabaseConnection) -> usecase::api_key::Service {
+        usecase::api_key::Service::new(Arc::new(infra::api_key::SqlRepository::new(db.clone())))
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_save_each_rolls_back_only_failed_changes(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let (_, publisher_usecase, _) = services(&db);
+        let publisher = publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let publisher = publisher::Repository::find_by_pub_id(
+            &infra::publisher::SqlRepository::new(db.clone()),
+            publisher.pub_id,
+            false,
+        )
+        .await
+        .expect("Failed to load publisher")
+        .expect("Publisher not found");
+        let new_book = |pub_id: uuid::Uuid, title: &str| {
+            book::Change::Create(book::Book::new(
+                pub_id,
+                book::vo::BookTitle::new(title.to_string()).unwrap(),
+                book::vo::BookAuthor::new("Author".to_string()).unwrap(),
+                publisher.clone(),
+                None,
+                book::vo::BookFormat::Real,
+                book::vo::BookPrice::new(1000).unwrap(),
+                "test".to_string(),
+            ))
+        };
+
+        // 2 件目は pub_id が重複して INSERT に失敗する
+        let duplicated = uuid::Uuid::new_v4();
+        let saved = book::Repository::save_each(
+            &infra::book::SqlRepository::new(db.clone()),
+            vec![
+                new_book(duplicated, "First"),
+                new_book(duplicated, "Second"),
+                new_book(uuid::Uuid::new_v4(), "Third"),
+            ],
+        )
+        .await
+        .expect("Failed to save books");
+        assert_eq!(
+            saved.iter().map(|r| r.is_ok()).collect::<Vec<_>>(),
+            [true, false, true]
+        );
+        let titles: Vec<String> = infra::book::Entity::find()
+            .order_by_asc(infra::book::Column::Id)
+            .all(&db)
+            .await
+            .expect("Failed to load books")
+            .into_iter()
+            .map(|b| b.title)
+            .collect();
+        assert_eq!(titles, ["First", "Third"]);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_book_history_is_recorded(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let (book_usecase, publisher_usecase, shop_usecase) = services(&db);
+
+        let publisher = publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let shop = shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: "Shop".to_string(),
+            })
+            .await
+            .expect("Failed to create shop");
+
+        let created = book_usecase
+            .create(usecase::book::CreateDto {
+                title: "Book".to_string(),
+                author: "Author".to_string(),
+                publisher_id: publisher.pub_id,
+                shop_id: Some(shop.pub_id),
+                format: Some("EBook".to_string()),
+                price: 1000,
+            })
+            .await
+            .expect("Failed to create book");
+        book_usecase
+            .update(
+                created.pub_id,
+                usecase::book::UpdateDto {
+                    title: "Book 2".to_string(),
+                    author: "Author".to_string(),
+                    publisher_id: publisher.pub_id,
+                    shop_id: Some(shop.pub_id),
+                    format: Some("EBook".to_string()),
+                    price: 1200,
+                },
+            )
+            .await
+            .expect("Failed to update book");
+        book_usecase
+            .delete(created.pub_id)
+            .await
+            .expect("Failed to delete book");
+
+        let history = infra::book_history::Entity::find()
+            .order_by_asc(infra::book_history::Column::HistoryId)
+            .all(&db)
+            .await
+            .expect("Failed to load history");
+        let operations: Vec<_> = history.iter().map(|h| h.operation_type.as_str()).collect();
+        // 削除は論理削除のため UPDATE として記録される
+        assert_eq!(operations, ["INSERT", "UPDATE", "UPDATE"]);
+        assert!(history[2].deleted_at.is_some());
+
+        let updated = &history[1];
+        assert_eq!(updated.pub_id, created.pub_id);
+        assert_eq!(updated.title, "Book 2");
+        assert_eq!(updated.price, 1200);
+        assert_eq!(updated.format, "EBook");
+        assert!(updated.shop_id.is_some());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_publisher_and_shop_history_is_recorded(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let (_, publisher_usecase, shop_usecase) = services(&db);
+
+        let publisher = publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Before".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        publisher_usecase
+            .update(
+                publisher.pub_id,
+                usecase::publisher::UpdateDto {
+                    name: "After".to_string(),
+                },
+            )
+            .await
+            .expect("Failed to update publisher");
+
+        let shop = shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: "Shop".to_string(),
+            })
+            .await
+            .expect("Failed to create shop");
+        shop_usecase
+            .delete(shop.pub_id)
+            .await
+            .expect("Failed to delete shop");
+
+        let publisher_history = infra::publisher_history::Entity::find()
+            .order_by_asc(infra::publisher_history::Column::HistoryId)
+            .all(&db)
+            .await
+            .expect("Failed to load publisher history");
+        let names: Vec<_> = publisher_history
+            .iter()
+            .map(|h| (h.operation_type.as_str(), h.name.as_str()))
+            .collect();
+        assert_eq!(names, [("INSERT", "Before"), ("UPDATE", "After")]);
+
+        let shop_history = infra::shop_history::Entity::find()
+            .order_by_asc(infra::shop_history::Column::HistoryId)
+            .all(&db)
+            .await
+            .expect("Failed to load shop history");
+        let operations: Vec<_> = shop_history
+            .iter()
+            .map(|h| h.operation_type.as_str())
+            .collect();
+        assert_eq!(operations, ["INSERT", "UPDATE"]);
+        assert!(shop_history.iter().all(|h| h.pub_id == shop.pub_id));
+        assert!(shop_history[1].deleted_at.is_some());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_soft_delete_and_purge(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let state = infra::test::state(&db);
+
+        let publisher = state
+            .publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let shop = state
+            .shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: "Shop".to_string(),
+            })
+            .await
+            .expect("Failed to create shop");
+        let book = state
+            .book_usecase
+            .create(usecase::book::CreateDto {
+                title: "Book".to_string(),
+                author: "Author".to_string(),
+                publisher_id: publisher.pub_id,
+                shop_id: Some(shop.pub_id),
+                format: None,
+                price: 100,
+            })
+            .await
+            .expect("Failed to create book");
+
+        state
+            .book_usecase
+            .delete(book.pub_id)
+            .await
+            .expect("Failed to delete book");
+        state
+            .publisher_usecase
+            .delete(publisher.pub_id)
+            .await
+            .expect("Failed to delete publisher");
+        state
+            .shop_usecase
+            .delete(shop.pub_id)
+            .await
+            .expect("Failed to delete shop");
+        assert!(
+            state
+                .publisher_usecase
+                .get_all(false)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            state.publisher_usecase.get_all(true).await.unwrap().len(),
+            1
+        );
+
+        assert!(state.book_usecase.get(book.pub_id, false).await.is_err());
+
+        // 保持期間内は削除されない
+        let purged = crate::purge::purge(&state, chrono::Duration::days(1))
+            .await
+            .expect("Failed to purge");
+        assert_eq!(purged, 0);
+        let purged = crate::purge::purge(&state, chrono::Duration::zero())
+            .await
+            .expect("Failed to purge");
+        assert_eq!(purged, 3);
+        assert!(state.book_usecase.get(book.pub_id, true).await.is_err());
+
+        let history = infra::book_history::Entity::find()
+            .order_by_asc(infra::book_history::Column::HistoryId)
+            .all(&db)
+            .await
+            .expect("Failed to load history");
+        let operations: Vec<_> = history.iter().map(|h| h.operation_type.as_str()).collect();
+        assert_eq!(operations, ["INSERT", "UPDATE", "DELETE"]);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_book_batch(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let (book_usecase, publisher_usecase, shop_usecase) = services(&db);
+
+        let publisher = publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let shop = shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: "Shop".to_string(),
+            })
+            .await
+            .expect("Failed to create shop");
+        let existing = book_usecase
+            .create(usecase::book::CreateDto {
+                title: "Existing".to_string(),
+                author: "Author".to_string(),
+                publisher_id: publisher.pub_id,
+                shop_id: None,
+                format: None,
+                price: 100,
+            })
+            .await
+            .expect("Failed to create book");
+
+        let result = book_usecase
+            .batch(usecase::book::BatchDto {
+                mode: usecase::book::BatchMode::AllOrNothing,
+                operations: vec![
+                    usecase::book::BatchOperation::Create {
+                        data: usecase::book::CreateDto {
+                            title: "New".to_string(),
+                            author: "Author".to_string(),
+                            publisher_id: publisher.pub_id,
+                            shop_id: Some(shop.pub_id),
+                            format: Some("EBook".to_string()),
+                            price: 500,
+                        },
+                    },
+                    usecase::book::BatchOperation::Update {
+                        pub_id: existing.pub_id,
+                        data: usecase::book::UpdateDto {
+                            title: "Existing 2".to_string(),
+                            author: "Author".to_string(),
+                            publisher_id: publisher.pub_id,
+                            shop_id: Some(shop.pub_id),
+                            format: None,
+                            price: 150,
+                        },
+                    },
+                ],
+            })
+            .await
+            .expect("Failed to run batch");
+        assert!(result.all_succeeded());
+
+        let books = book_usecase
+            .get_all(false)
+            .await
+            .expect("Failed to get all");
+        assert_eq!(books.len(), 2);
+        assert!(books.iter().all(|b| b.shop.is_some()));
+        let updated = book_usecase
+            .get(existing.pub_id, false)
+            .await
+            .expect("Failed to get book");
+        assert_eq!(updated.title, "Existing 2");
+        assert_eq!(updated.price, 150);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_book_import(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let (book_usecase, publisher_usecase, shop_usecase) = services(&db);
+
+        publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let shop = shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: "Shop".to_string(),
+            })
+            .await
+            .expect("Failed to create shop");
+
+        let csv = format!(
+            "title,author,publisher,shop,format,price\n\
+             Book 1,Author,Publisher,Shop,EBook,100\n\
+             Book 2,Author,Publisher,{},,200\n\
+             Book 3,Author,Missing,,,300\n",
+            shop.pub_id
+        );
+        let report = book_usecase
+            .import(csv.as_bytes(), false)
+            .await
+            .expect("Failed to import");
+        assert_eq!(report.imported_rows, 2);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 4);
+
+        let books = book_usecase
+            .get_all(false)
+            .await
+            .expect("Failed to get all");
+        assert_eq!(books.len(), 2);
+        assert!(books.iter().all(|b| b.shop.is_some()));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_book_stream_pages(#[future] db: DatabaseConnection) {
+        use futures::TryStreamExt;
+
+        let db = db.await;
+        let (book_usecase, publisher_usecase, _) = services(&db);
+        let publisher = publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let mut created = Vec::new();
+        for i in 0..5 {
+            let book = book_usecase
+                .create(usecase::book::CreateDto {
+                    title: format!("Book {}", i),
+                    author: "Author".to_string(),
+                    publisher_id: publisher.pub_id,
+                    shop_id: None,
+                    format: None,
+                    price: 100,
+                })
+                .await
+                .expect("Failed to create book");
+            created.push(book.pub_id);
+        }
+        book_usecase
+            .delete(created[2])
+            .await
+            .expect("Failed to delete book");
+
+        let repo = infra::book::SqlRepository::new(db.clone());
+        let streamed: Vec<_> = book::Repository::stream_all(&repo, false, 2)
+            .map_ok(|b| b.pub_id())
+            .try_collect()
+            .await
+            .expect("Failed to stream books");
+        assert_eq!(streamed, [created[0], created[1], created[3], created[4]]);
+
+        let streamed: Vec<_> = book::Repository::stream_all(&repo, true, 2)
+            .try_collect()
+            .await
+            .expect("Failed to stream books");
+        assert_eq!(streamed.len(), 5);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_book_export_formats(#[future] db: DatabaseConnection) {
+        use axum::body::Body;
+        use axum::http::{Request, header};
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let db = db.await;
+        let state = Arc::new(infra::test::state(&db));
+        let publisher = state
+            .publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        for i in 0..2 {
+            state
+                .book_usecase
+                .create(usecase::book::CreateDto {
+                    title: format!("Book {}", i),
+                    author: "Author".to_string(),
+                    publisher_id: publisher.pub_id,
+                    shop_id: None,
+                    format: None,
+                    price: 100,
+                })
+                .await
+                .expect("Failed to create book");
+        }
+        let router = api::create_router(state, &api::HttpConfig::default());
+
+        let get = |accept: &'static str| {
+            router.clone().oneshot(
+                Request::get("/books")
+                    .header(header::ACCEPT, accept)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("text/csv").await.unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<_> = body.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("pub_id,title,author,publisher,publisher_name,shop"));
+        assert!(lines[1].contains("Book 0"));
+
+        let response = get("application/x-ndjson").await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let books: Vec<usecase::book::ResponseDto> = body
+            .split(|b| *b == b'\n')
+            .filter(|l| !l.is_empty())
+            .map(|l| serde_json::from_slice(l).unwrap())
+            .collect();
+        assert_eq!(books.len(), 2);
+
+        let response = get("application/json").await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let books: Vec<usecase::book::ResponseDto> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(books.len(), 2);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_book_search(#[future] db: DatabaseConnection) {
+        let db = db.await;
+        let (book_usecase, publisher_usecase, shop_usecase) = services(&db);
+        let publisher = publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "猫出版".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let shop = shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: "100%書店".to_string(),
+            })
+            .await
+            .expect("Failed to create shop");
+        for (title, author, shop_id) in [
+            ("犬の本", "作者", Some(shop.pub_id)),
+            ("吾輩は猫である", "夏目漱石", None),
+            ("猫と暮らす", "猫好き", None),
+        ] {
+            book_usecase
+                .create(usecase::book::CreateDto {
+                    title: title.to_string(),
+                    author: author.to_string(),
+                    publisher_id: publisher.pub_id,
+                    shop_id,
+                    format: None,
+                    price: 100,
+                })
+                .await
+                .expect("Failed to create book");
+        }
+
+        // title と author の両方に一致するものが先、publisher 名だけの一致は最後
+        let result = book_usecase
+            .search("猫", 1, 20)
+            .await
+            .expect("Failed to search");
+        let titles: Vec<_> = result.items.iter().map(|h| h.book.title.as_str()).collect();
+        assert_eq!(titles, ["猫と暮らす", "吾輩は猫である", "犬の本"]);
+        assert_eq!(result.total, 3);
+        assert_eq!(result.items[2].highlight.publisher, "<mark>猫</mark>出版");
+
+        let result = book_usecase
+            .search("猫 漱石", 1, 20)
+            .await
+            .expect("Failed to search");
+        assert_eq!(result.total, 1);
+
+        let result = book_usecase
+            .search("猫", 2, 2)
+            .await
+            .expect("Failed to search");
+        assert_eq!(result.total, 3);
+        assert_eq!(result.items.len(), 1);
+
+        // LIKE の特殊文字はそのまま文字として扱う
+        let result = book_usecase
+            .search("100%", 1, 20)
+            .await
+            .expect("Failed to search");
+        assert_eq!(result.total, 1);
+        let result = book_usecase
+            .search("1_0", 1, 20)
+            .await
+            .expect("Failed to search");
+        assert_eq!(result.total, 0);
+    }
+
+    #[test]
+    // figment::Jail のクロージャの戻り値の型は変えられない
+    #[allow(clippy::result_large_err)]
+    fn test_config_layers() {
+        use crate::config::{Config, LogFormat, Overrides};
+
+        figment::Jail::expect_with(|jail| {
+            jail.create_file(
+                "config.toml",
+                r#"
+                [server]
+                bind = "127.0.0.1:8080"
+
+                [database]
+                url = "postgres://file"
+                max_connections = 5
+
+                [http]
+                cors_origins = ["https://example.com"]
+                "#,
+            )?;
+            jail.set_env("DATABASE_URL", "postgres://legacy");
+            jail.set_env("APP_DATABASE__MAX_CONNECTIONS", "20");
+            jail.set_env("APP_LOG__FORMAT", "json");
+            jail.set_env("OTEL_EXPORTER_OTLP_ENDPOINT", "http://collector:4318");
+
+            let config = Config::load(&Overrides {
+                bind: Some("127.0.0.1:9090".parse().unwrap()),
+                auto_migrate: Some(false),
+                ..Default::default()
+            })
+            .expect("Failed to load config");
+            // CLI > 環境変数 > ファイル > 既定値
+            assert_eq!(config.server.bind.port(), 9090);
+            assert_eq!(config.database.url, "postgres://legacy");
+            assert_eq!(config.database.max_connections, 20);
+            assert!(!config.database.auto_migrate);
+            assert_eq!(config.log.format, LogFormat::Json);
+            assert_eq!(config.http.cors_origins, ["https://example.com"]);
+            assert!(config.http.swagger_ui);
+            assert_eq!(config.purge.retention_days, 30);
+            assert_eq!(
+                config.telemetry.otlp_endpoint.as_deref(),
+                Some("http://collector:4318")
+            );
+            assert_eq!(config.telemetry.service_name, "rust-web-app");
+            Ok(())
+        });
+    }
+
+    #[test]
+    // figment::Jail のクロージャの戻り値の型は変えられない
+    #[allow(clippy::result_large_err)]
+    fn test_config_validation_reports_all_errors() {
+        use crate::config::{Config, Overrides};
+
+        figment::Jail::expect_with(|jail| {
+            jail.create_file(
+                "app.toml",
+                r#"
+                [database]
+                min_connections = 20
+                max_connections = 10
+
+                [http]
+                cors_origins = ["example.com"]
+
+                [purge]
+                interval_secs = 0
+
+                [outbox]
+                backoff_base_secs = 60
+                backoff_max_secs = 10
+
+                [[outbox.sinks]]
+                kind = "webhook"
+                url = "example.com/hooks"
+
+                [webhook]
+                timeout_secs = 0
+
+                [grpc]
+                bind = "0.0.0.0:3000"
+
+                [telemetry]
+                sample_ratio = 2.0
+                "#,
+            )?;
+            let error = Config::load(&Overrides {
+                config: Some("app.toml".into()),
+                ..Default::default()
+            })
+            .unwrap_err()
+            .to_string();
+            assert!(error.contains("database.url is required"));
+            assert!(error.contains("database.min_connections"));
+            assert!(error.contains("http.cors_origins"));
+            assert!(error.contains("purge.interval_secs"));
+            assert!(error.contains("outbox.max_attempts / backoff_base_secs"));
+            assert!(error.contains("outbox webhook url"));
+            assert!(error.contains("webhook.timeout_secs"));
+            assert!(error.contains("grpc.bind"));
+            assert!(error.contains("telemetry.sample_ratio"));
+
+            let error = Config::load(&Overrides {
+                config: Some("missing.toml".into()),
+                ..Default::default()
+            })
+            .unwrap_err()
+            .to_string();
+            assert!(error.contains("not found"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_openapi_json_is_up_to_date() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+        let checked_in = std::fs::read_to_string(&path).expect("Failed to read openapi.json");
+        let generated = api::openapi()
+            .to_pretty_json()
+            .expect("Failed to generate OpenAPI JSON");
+        assert!(
+            checked_in.trim_end() == generated.trim_end(),
+            "openapi.json is stale; run `cargo run -- openapi --output openapi.json`"
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_health_and_readiness(#[future] db: DatabaseConnection) {
+        use crate::health::{DatabaseCheck, MigrationCheck, WorkerStatus};
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let db = db.await;
+        let worker = Arc::new(WorkerStatus::new("purge_worker"));
+        let router = |db: &DatabaseConnection| {
+            api::create_router(
+                Arc::new(api::AppState {
+                    readiness_checks: vec![
+                        Arc::new(DatabaseCheck(db.clone())),
+                        Arc::new(MigrationCheck(db.clone())),
+                        worker.clone(),
+                    ],
+                    ..infra::test::state(db)
+                }),
+                &api::HttpConfig::default(),
+            )
+        };
+        let get = |router: axum::Router, path: &'static str| async move {
+            let response = router
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            (status, body)
+        };
+
+        let (status, _) = get(router(&db), "/healthz").await;
+        assert_eq!(status, StatusCode::OK);
+
+        // ワーカーが起動していないうちは準備できていない
+        let (status, body) = get(router(&db), "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["checks"]["database"]["status"], "ok");
+        assert_eq!(body["checks"]["migrations"]["status"], "ok");
+        assert_eq!(body["checks"]["purge_worker"]["status"], "degraded");
+
+        let alive = worker.start();
+        let (status, body) = get(router(&db), "/readyz").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+
+        worker.record(Err("boom".to_string()));
+        let (status, body) = get(router(&db), "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        // 理由は応答に載せない
+        assert_eq!(body["checks"]["purge_worker"]["error"], "unavailable");
+        assert!(!body.to_string().contains("boom"));
+        worker.record(Ok(()));
+
+        // マイグレーション前の DB
+        let fresh = sea_orm::Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to SQLite");
+        let (status, body) = get(router(&fresh), "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["checks"]["migrations"]["error"], "unavailable");
+        assert!(!body.to_string().contains("m20220101_000001_create_table"));
+        drop(alive);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_scrape() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode, header};
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let mut db = Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to SQLite");
+        infra::metrics::register(&mut db).expect("Failed to register metrics");
+        Migrator::up(&db, None)
+            .await
+            .expect("Failed to run migrations");
+        let state = Arc::new(infra::test::state(&db));
+        let publisher = state
+            .publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let router = api::create_router(state, &api::HttpConfig::default());
+        let send = |request: Request<Body>| router.clone().oneshot(request);
+        let create = |title: String| {
+            Request::post("/books")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    serde_json::json!({
+                        "title": title,
+                        "author": "Author",
+                        "publisher_id": publisher.pub_id,
+                        "price": 100,
+                    })
+                    .to_string(),
+                ))
+                .unwrap()
+        };
+
+        let response = send(create("Book".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = send(create("x".repeat(33))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let path = format!("/books/{}", uuid::Uuid::now_v7());
+        let response = send(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = send(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/plain")
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        // 他のテストと同じレジストリを共有するため、値ではなく系列の有無を確認する
+        for series in [
+            r#"http_requests_total{method="POST",route="/books",status="201"}"#,
+            r#"http_requests_total{method="GET",route="/books/{pub_id}",status="404"}"#,
+            r#"http_request_duration_seconds_bucket{method="POST",route="/books",status="400","#,
+            r#"validation_failures_total{rule="Title must be 32 chars or less"}"#,
+            "books_created_total ",
+            "books_published_total ",
+            r#"db_query_duration_seconds_count{operation="insert",status="ok"}"#,
+            r#"db_query_duration_seconds_count{operation="select",status="ok"}"#,
+            r#"db_pool_connections{state="in_use"}"#,
+            r#"db_pool_connections{state="idle"}"#,
+        ] {
+            assert!(body.contains(series), "{} not found in:\n{}", series, body);
+        }
+    }
+
+    #[rstest]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_spans_are_exported_with_incoming_trace_context(#[future] db: DatabaseConnection) {
+        use axum::body::{Body, Bytes};
+        use axum::http::{Request, StatusCode};
+        use opentelemetry::trace::TracerProvider;
+        use std::sync::Mutex;
+        use tower::ServiceExt;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        // OTLP/HTTP コレクタの代わりに受け取った内容を溜めておく
+        let received = Arc::new(Mutex::new(Vec::<Bytes>::new()));
+        let collector = axum::Router::new().route(
+            "/v1/traces",
+            axum::routing::post({
+                let received = received.clone();
+                move |body: Bytes| async move {
+                    received.lock().unwrap().push(body);
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, collector).await });
+
+        let telemetry = crate::config::TelemetryConfig {
+            otlp_endpoint: Some(endpoint.clone()),
+            sample_ratio: 0.0,
+            ..Default::default()
+        };
+        let provider = crate::telemetry::tracer_provider(&endpoint, &telemetry)
+            .expect("Failed to build tracer provider");
+        opentelemetry::global::set_text_map_propagator(
+            opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+        );
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let db = db.await;
+        let router = api::create_router(
+            Arc::new(infra::test::state(&db)),
+            &api::HttpConfig::default(),
+        );
+        let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let pub_id = uuid::Uuid::now_v7();
+        // 初めて通るコールサイトの登録と重なったスパンは記録されないことがあるので、
+        // サンプリングしないリクエストを先に 1 度通しておく
+        let warm_up = Request::get(format!("/publishers/{}", pub_id))
+            .body(Body::empty())
+            .unwrap();
+        router.clone().oneshot(warm_up).await.unwrap();
+        let response = router
+            .oneshot(
+                Request::get(format!("/publishers/{}", pub_id))
+                    .header(
+                        "traceparent",
+                        format!("00-{}-00f067aa0ba902b7-01", trace_id),
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        // レスポンスボディが破棄されるまでリクエストのスパンは閉じない
+        drop(response);
+
+        tokio::task::spawn_blocking(move || provider.force_flush())
+            .await
+            .unwrap()
+            .expect("Failed to flush spans");
+
+        // protobuf のまま、trace_id のバイト列とスパン名・属性の文字列が含まれるかを見る
+        let body: Vec<u8> = received.lock().unwrap().concat();
+        let contains = |needle: &[u8]| body.windows(needle.len()).any(|w| w == needle);
+        let trace_id: Vec<u8> = (0..trace_id.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&trace_id[i..i + 2], 16).unwrap())
+            .collect();
+        // sample_ratio が 0 でも呼び出し元がサンプリングしたトレースは送られる
+        assert!(contains(&trace_id), "trace_id not propagated");
+        for needle in ["request", "get", "find_by_pub_id"] {
+            assert!(contains(needle.as_bytes()), "span {} not exported", needle);
+        }
+        assert!(contains(pub_id.to_string().as_bytes()));
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_drains_in_flight_requests() {
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::sync::{Notify, oneshot};
+
+        let started = Arc::new(Notify::new());
+        let router = axum::Router::new().route(
+            "/slow",
+            axum::routing::get({
+                let started = started.clone();
+                move |axum::extract::Query(q): axum::extract::Query<
+                    std::collections::HashMap<String, u64>,
+                >| async move {
+                    started.notify_one();
+                    tokio::time::sleep(Duration::from_millis(q["ms"])).await;
+                    "done"
+                }
+            }),
+        );
+        let request = |addr: std::net::SocketAddr, ms: u64| async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(
+                    format!(
+                        "GET /slow?ms={} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                        ms
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response).await;
+            response
+        };
+        let start = |drain_timeout: Duration| {
+            let router = router.clone();
+            async move {
+                let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let addr = listener.local_addr().unwrap();
+                let (tx, rx) = oneshot::channel::<()>();
+                let server = tokio::spawn(crate::shutdown::serve(
+                    listener,
+                    router,
+                    async move {
+                        let _ = rx.await;
+                    },
+                    drain_timeout,
+                ));
+                (addr, tx, server)
+            }
+        };
+
+        // 終了シグナルの後も処理中のリクエストは最後まで返す
+        let (addr, tx, server) = start(Duration::from_secs(5)).await;
+        let client = tokio::spawn(request(addr, 300));
+        started.notified().await;
+        tx.send(()).unwrap();
+        let response = client.await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with("done"));
+        server.await.unwrap().expect("Server failed");
+        // 新しい接続は受け付けない
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+
+        // drain_timeout を過ぎたリクエストは打ち切る
+        let (addr, tx, server) = start(Duration::from_millis(100)).await;
+        let client = tokio::spawn(request(addr, 10_000));
+        started.notified().await;
+        tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("Server did not stop after drain timeout")
+            .unwrap()
+            .expect("Server failed");
+        client.abort();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_seed_is_idempotent(#[future] db: DatabaseConnection) {
+        use crate::seed::{Counts, Fixtures, seed};
+
+        let db = db.await;
+        let app = crate::app::App::new(db, &crate::config::Config::default()).unwrap();
+        let yaml = r#"
+publishers:
+  - name: Publisher
+shops:
+  - name: Shop
+books:
+  - title: Book
+    author: Author
+    publisher: Publisher
+    shop: Shop
+    format: EBook
+    price: 1000
+"#;
+        let report = seed(&app.state, serde_yaml::from_str(yaml).unwrap())
+            .await
+            .expect("Failed to seed");
+        assert_eq!(
+            report.created,
+            Counts {
+                publishers: 1,
+                shops: 1,
+                books: 1
+            }
+        );
+
+        // 2 回目は登録済みとして読み飛ばす
+        let report = seed(&app.state, serde_yaml::from_str(yaml).unwrap())
+            .await
+            .expect("Failed to seed");
+        assert_eq!(report.created, Counts::default());
+        assert_eq!(report.skipped.books, 1);
+
+        // JSON でも書け、ドメインのルールが適用される
+        let json = r#"{"books": [{"title": "Another", "author": "Author", "publisher": "Publisher", "price": -1}]}"#;
+        let fixtures: Fixtures = serde_json::from_str(json).unwrap();
+        let error = seed(&app.state, fixtures).await.unwrap_err();
+        assert_eq!(error.to_string(), "books[0]");
+        assert!(format!("{:#}", error).contains("Price must be 0 or more"));
+
+        let json = r#"{"books": [{"title": "Another", "author": "Author", "publisher": "Unknown", "price": 1}]}"#;
+        let error = seed(&app.state, serde_json::from_str(json).unwrap())
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("Publisher 'Unknown' not found"));
+
+        let books = app.state.book_usecase.get_all(false).await.unwrap();
+        assert_eq!(books.len(), 1);
+        assert_eq!(books[0].shop.as_ref().unwrap().name, "Shop");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_cli_export(#[future] db: DatabaseConnection) {
+        use crate::cli::{ExportFormat, export};
+
+        let db = db.await;
+        let app = crate::app::App::new(db, &crate::config::Config::default()).unwrap();
+        let fixtures = serde_json::from_str(
+            r#"{"publishers": [{"name": "Publisher"}],
+                "books": [
+                    {"title": "Book 1", "author": "Author", "publisher": "Publisher", "price": 1},
+                    {"title": "Book 2", "author": "Author", "publisher": "Publisher", "price": 2}
+                ]}"#,
+        )
+        .unwrap();
+        crate::seed::seed(&app.state, fixtures).await.unwrap();
+
+        let mut csv = Vec::new();
+        let count = export(&app.state.book_usecase, ExportFormat::Csv, false, &mut csv)
+            .await
+            .expect("Failed to export");
+        assert_eq!(count, 2);
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("pub_id,title,author,publisher,publisher_name"));
+
+        // CSV はそのまま取り込みに使える
+        let report = app
+            .state
+            .book_usecase
+            .import(csv.as_bytes(), true)
+            .await
+            .expect("Failed to import");
+        assert_eq!(report.valid_rows, 2);
+        assert!(report.errors.is_empty());
+
+        let mut ndjson = Vec::new();
+        export(
+            &app.state.book_usecase,
+            ExportFormat::Ndjson,
+            false,
+            &mut ndjson,
+        )
+        .await
+        .expect("Failed to export");
+        let books: Vec<usecase::book::ResponseDto> = ndjson
+            .split(|b| *b == b'\n')
+            .filter(|l| !l.is_empty())
+            .map(|l| serde_json::from_slice(l).unwrap())
+            .collect();
+        assert_eq!(books.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cli_migrate() {
+        use crate::cli::{MigrateAction, migrate};
+
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to SQLite");
+        let total = Migrator::migrations().len();
+        migrate(&db, MigrateAction::Up { steps: Some(1) })
+            .await
+            .expect("Failed to migrate up");
+        let pending = Migrator::get_pending_migrations(&db).await.unwrap();
+        assert_eq!(pending.len(), total - 1);
+
+        migrate(&db, MigrateAction::Up { steps: None })
+            .await
+            .expect("Failed to migrate up");
+        assert!(
+            Migrator::get_pending_migrations(&db)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        migrate(&db, MigrateAction::Down { steps: 2 })
+            .await
+            .expect("Failed to migrate down");
+        let pending = Migrator::get_pending_migrations(&db).await.unwrap();
+        assert_eq!(pending.len(), 2);
+        migrate(&db, MigrateAction::Status)
+            .await
+            .expect("Failed to show status");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_body_limit_does_not_apply_to_streamed_import(#[future] db: DatabaseConnection) {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode, header};
+        use tower::ServiceExt;
+
+        let db = db.await;
+        let app = crate::app::App::new(db, &crate::config::Config::default()).unwrap();
+        let router = api::create_router(
+            app.state.clone(),
+            &api::HttpConfig {
+                max_body_bytes: 64,
+                ..Default::default()
+            },
+        );
+        let json = serde_json::json!({ "name": "x".repeat(64) }).to_string();
+        let response = router
+            .clone()
+            .oneshot(
+                Request::post("/publishers")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(json))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let mut csv = "title,author,publisher,shop,format,price\n".to_string();
+        for i in 0..10 {
+            csv.push_str(&format!("Book {},Author,Unknown,,,100\n", i));
+        }
+        let response = router
+            .oneshot(
+                Request::post("/imports/books?dry_run=true")
+                    .header(header::CONTENT_TYPE, "text/csv")
+                    .body(Body::from(csv))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_idempotency_key_replays_create(#[future] db: DatabaseConnection) {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode, header};
+        use http_body_util::BodyExt;
+        use sea_orm::PaginatorTrait;
+        use tower::ServiceExt;
+
+        let db = db.await;
+        let router = api::create_router(
+            Arc::new(infra::test::state(&db)),
+            &api::HttpConfig::default(),
+        );
+        let create = |key: &str, name: &str| {
+            router.clone().oneshot(
+                Request::post("/publishers")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header("idempotency-key", key)
+                    .body(Body::from(serde_json::json!({ "name": name }).to_string()))
+                    .unwrap(),
+            )
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let first = create("key-1", "Publisher").await.unwrap();
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert!(!first.headers().contains_key("idempotent-replayed"));
+        let first = json(first).await;
+
+        // 再試行には同じレスポンスを返し、二重に作成しない
+        let retry = create("key-1", "Publisher").await.unwrap();
+        assert_eq!(retry.status(), StatusCode::CREATED);
+        assert_eq!(retry.headers()["idempotent-replayed"], "true");
+        assert_eq!(retry.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(json(retry).await, first);
+        let count = infra::publisher::Entity::find().count(&db).await.unwrap();
+        assert_eq!(count, 1);
+
+        let reused = create("key-1", "Other").await.unwrap();
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let invalid = create("has space", "Other").await.unwrap();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        // 検証エラーも保存し、同じ内容の再試行には同じエラーを返す
+        let rejected = create("key-2", &"x".repeat(33)).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+        let retry = create("key-2", &"x".repeat(33)).await.unwrap();
+        assert_eq!(retry.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(retry.headers()["idempotent-replayed"], "true");
+
+        // 別の API キーが同じキーを使っても、互いのレスポンスは返さない
+        let api_key_usecase = api_keys(&db);
+        let issue = |name: &str| {
+            api_key_usecase.issue(usecase::api_key::IssueDto {
+                name: name.to_string(),
+                owner: "Test".to_string(),
+                scopes: vec!["publishers:write".to_string()],
+                expires_at: None,
+            })
+        };
+        let a = format!("ApiKey {}", issue("A").await.unwrap().secret);
+        let b = format!("ApiKey {}", issue("B").await.unwrap().secret);
+        let create_as = |credentials: &str, name: &str| {
+            router.clone().oneshot(
+                Request::post("/publishers")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::AUTHORIZATION, credentials)
+                    .header("idempotency-key", "key-1")
+                    .body(Body::from(serde_json::json!({ "name": name }).to_string()))
+                    .unwrap(),
+            )
+        };
+        let response = create_as(&a, "Publisher A").await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(!response.headers().contains_key("idempotent-replayed"));
+        let created_by_a = json(response).await;
+        assert_ne!(created_by_a["pub_id"], first["pub_id"]);
+        let response = create_as(&b, "Publisher B").await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(!response.headers().contains_key("idempotent-replayed"));
+        assert_ne!(json(response).await["pub_id"], created_by_a["pub_id"]);
+        let retry = create_as(&a, "Publisher A").await.unwrap();
+        assert_eq!(retry.headers()["idempotent-replayed"], "true");
+        assert_eq!(json(retry).await, created_by_a);
+        let count = infra::publisher::Entity::find().count(&db).await.unwrap();
+        assert_eq!(count, 3);
+
+        // キーが無ければ毎回作成する
+        let response = router
+            .clone()
+            .oneshot(
+                Request::post("/publishers")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(r#"{"name":"No Key"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_idempotency_key_expires(#[future] db: DatabaseConnection) {
+        use idempotency::Repository;
+        use usecase::idempotency::{Outcome, ResponseDto};
+
+        let db = db.await;
+        let repo = Arc::new(infra::idempotency::SqlRepository::new(db.clone()));
+        let service = usecase::idempotency::Service::new(
+            repo.clone(),
+            chrono::Duration::hours(1),
+            chrono::Duration::minutes(1),
+        );
+        let begin = |key: &'static str, body: &'static [u8]| {
+            service.begin(key.to_string(), "POST /books".to_string(), body)
+        };
+
+        let Outcome::Proceed(claim) = begin("key-1", b"body").await.unwrap() else {
+            panic!("first request should proceed");
+        };
+        assert!(matches!(
+            begin("key-1", b"body").await.unwrap(),
+            Outcome::InProgress
+        ));
+        service
+            .complete(
+                claim,
+                ResponseDto {
+                    status: 201,
+                    content_type: None,
+                    body: b"created".to_vec(),
+                },
+            )
+            .await
+            .unwrap();
+        match begin("key-1", b"body").await.unwrap() {
+            Outcome::Replay(response) => assert_eq!(response.body, b"created"),
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+        assert!(matches!(
+            begin("key-1", b"other").await.unwrap(),
+            Outcome::Mismatch
+        ));
+
+        // 期限切れのレコードを直接作る
+        let now = chrono::Utc::now();
+        let expired = idempotency::Record::reconstruct(
+            idempotency::vo::IdempotencyKey::new("key-2".to_string()).unwrap(),
+            "POST /books".to_string(),
+            "hash".to_string(),
+            None,
+            now - chrono::Duration::hours(2),
+            now - chrono::Duration::hours(1),
+            now - chrono::Duration::hours(2),
+        );
+        assert!(repo.insert_or_get(expired).await.unwrap().is_none());
+        // 期限が切れたキーは別の内容でも新しいリクエストとして受け付ける
+        assert!(matches!(
+            begin("key-2", b"other").await.unwrap(),
+            Outcome::Proceed(_)
+        ));
+
+        let key_3 = idempotency::Record::reconstruct(
+            idempotency::vo::IdempotencyKey::new("key-3".to_string()).unwrap(),
+            "POST /books".to_string(),
+            "hash".to_string(),
+            None,
+            now - chrono::Duration::hours(2),
+            now - chrono::Duration::hours(1),
+            now - chrono::Duration::hours(2),
+        );
+        repo.insert_or_get(key_3).await.unwrap();
+        assert_eq!(service.purge_expired().await.unwrap(), 1);
+        assert!(matches!(
+            begin("key-1", b"body").await.unwrap(),
+            Outcome::Replay(_)
+        ));
+    }
+
+    // 処理中にタイムアウトや切断で中断されたキーは、lease を過ぎれば TTL を待たずに再試行できる
+    #[tokio::test]
+    async fn test_idempotency_key_is_taken_over_after_dropped_request() {
+        use axum::http::{Method, StatusCode};
+        use sea_orm::{ActiveModelTrait, Set};
+        use usecase::idempotency::Outcome;
+
+        let h = super::harness::Harness::new().await;
+        let body = serde_json::json!({ "name": "Retried" });
+        // ミドルウェアと同じ scope と内容でキーを押さえ、complete も abandon もせずに捨てる
+        let outcome = h
+            .state
+            .idempotency_usecase
+            .begin(
+                "key-1".to_string(),
+                "anonymous POST /publishers".to_string(),
+                format!("\n{}", body).as_bytes(),
+            )
+            .await
+            .unwrap();
+        let Outcome::Proceed(claim) = outcome else {
+            panic!("first request should proceed");
+        };
+        drop(claim);
+
+        let retry = || {
+            h.request(Method::POST, "/publishers")
+                .header("idempotency-key", "key-1")
+                .json(body.clone())
+                .send()
+        };
+        retry().await.expect(StatusCode::CONFLICT);
+
+        // lease が切れるまで時間を進める代わりに、期限を過去にする
+        infra::idempotency::ActiveModel {
+            key: Set("key-1".to_string()),
+            scope: Set("anonymous POST /publishers".to_string()),
+            locked_until: Set(Some(chrono::Utc::now() - chrono::Duration::seconds(1))),
+            ..Default::default()
+        }
+        .update(&h.db)
+        .await
+        .unwrap();
+        let created = retry().await.expect(StatusCode::CREATED);
+        assert!(!created.headers.contains_key("idempotent-replayed"));
+        let replayed = retry().await.expect(StatusCode::CREATED);
+        assert_eq!(replayed.headers["idempotent-replayed"], "true");
+        assert_eq!(replayed.body, created.body);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_api_key_authentication(#[future] db: DatabaseConnection) {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode, header};
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let db = db.await;
+        let router = |allow_anonymous: bool| {
+            api::create_router(
+                Arc::new(infra::test::state(&db)),
+                &api::HttpConfig {
+                    auth: api::config::AuthConfig { allow_anonymous },
+                    ..Default::default()
+                },
+            )
+        };
+        let send =
+            |router: &axum::Router, method: &str, path: &str, key: Option<&str>, body: &str| {
+                let mut request = Request::builder()
+                    .method(method)
+                    .uri(path)
+                    .header(header::CONTENT_TYPE, "application/json");
+                if let Some(key) = key {
+                    request = request.header(header::AUTHORIZATION, key);
+                }
+                router
+                    .clone()
+                    .oneshot(request.body(Body::from(body.to_string())).unwrap())
+            };
+        let json = |response: axum::response::Response| async move {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        // 最初の admin キーは CLI と同じく usecase から発行する
+        let admin = api_keys(&db)
+            .issue(usecase::api_key::IssueDto {
+                name: "Bootstrap".to_string(),
+                owner: "Ops".to_string(),
+                scopes: vec!["admin".to_string()],
+                expires_at: None,
+            })
+            .await
+            .expect("Failed to issue admin key");
+        let admin = format!("ApiKey {}", admin.secret);
+
+        let open = router(true);
+        let response = send(&open, "GET", "/books", None, "").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send(&open, "GET", "/admin/api-keys", None, "")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "ApiKey");
+
+        let response = send(
+            &open,
+            "POST",
+            "/admin/api-keys",
+            Some(&admin),
+            r#"{"name": "POS", "owner": "Store Ops", "scopes": ["books:read"]}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let issued = json(response).await;
+        let secret = issued["secret"].as_str().unwrap();
+        let pos = format!("ApiKey {}", secret);
+        let pub_id = issued["api_key"]["pub_id"].as_str().unwrap();
+
+        // 匿名を許可しない設定でも、スコープの範囲内なら使える
+        let closed = router(false);
+        let response = send(&closed, "GET", "/books", None, "").await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = send(&closed, "GET", "/healthz", None, "").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send(&closed, "GET", "/books", Some(&pos), "")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send(
+            &closed,
+            "POST",
+            "/publishers",
+            Some(&pos),
+            r#"{"name": "P"}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        for invalid in ["ApiKey wrong.secret", "Bearer token"] {
+            let response = send(&closed, "GET", "/books", Some(invalid), "")
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", invalid);
+        }
+
+        // 一覧には秘密の値を含めず、使った日時を記録する
+        let response = send(&closed, "GET", "/admin/api-keys", Some(&admin), "")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let listed = json(response).await;
+        let listed = listed.as_array().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert!(!listed.iter().any(|k| k.to_string().contains(secret)));
+        assert!(listed.iter().all(|k| !k["last_used_at"].is_null()));
+        let stored = infra::api_key::Entity::find()
+            .all(&db)
+            .await
+            .expect("Failed to load API keys");
+        assert!(!stored.iter().any(|k| k.key_hash.contains(secret)));
+
+        let response = send(
+            &closed,
+            "DELETE",
+            &format!("/admin/api-keys/{}", pub_id),
+            Some(&admin),
+            "",
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send(&closed, "GET", "/books", Some(&pos), "")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // 認証と同時に失効されても、使った記録で失効を取り消さない
+        let revoked = infra::api_key::Entity::find()
+            .filter(infra::api_key::Column::PubId.eq(uuid::Uuid::parse_str(pub_id).unwrap()))
+            .one(&db)
+            .await
+            .expect("Failed to load API key")
+            .expect("API key not found");
+        api_key::Repository::touch(
+            &infra::api_key::SqlRepository::new(db.clone()),
+            revoked.id,
+            chrono::Utc::now(),
+        )
+        .await
+        .expect("Failed to record API key usage");
+        let stored = infra::api_key::Entity::find_by_id(revoked.id)
+            .one(&db)
+            .await
+            .expect("Failed to load API key")
+            .expect("API key not found");
+        assert_eq!(stored.revoked_at, revoked.revoked_at);
+        assert_eq!(stored.last_used_at, revoked.last_used_at);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_change_event_feed(#[future] db: DatabaseConnection) {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode, header};
+        use http_body_util::BodyExt;
+        use std::time::Duration;
+        use tower::ServiceExt;
+
+        let db = db.await;
+        let app = crate::app::App::new(db, &crate::config::Config::default()).unwrap();
+        let router = api::create_router(app.state.clone(), &api::HttpConfig::default());
+        let subscribe = |query: &str, last_event_id: Option<&str>| {
+            let mut request = Request::get(format!("/events{}", query));
+            if let Some(id) = last_event_id {
+                request = request.header("last-event-id", id);
+            }
+            router.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        // 空行で区切られた SSE の 1 イベントから id / event / data を取り出す
+        async fn next_event(
+            body: &mut Body,
+            buffer: &mut String,
+        ) -> (i64, String, serde_json::Value) {
+            while !buffer.contains("\n\n") {
+                let frame = tokio::time::timeout(Duration::from_secs(2), body.frame())
+                    .await
+                    .expect("event should arrive")
+                    .expect("feed should not end")
+                    .unwrap();
+                if let Ok(data) = frame.into_data() {
+                    buffer.push_str(std::str::from_utf8(&data).unwrap());
+                }
+            }
+            let end = buffer.find("\n\n").unwrap();
+            let event: String = buffer.drain(..end + 2).collect();
+            let field = |name: &str| {
+                event
+                    .lines()
+                    .find_map(|l| {
+                        l.strip_prefix(&format!("{}: ", name))
+                            .or_else(|| l.strip_prefix(&format!("{}:", name)))
+                    })
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            (
+                field("id").parse().unwrap(),
+                field("event"),
+                serde_json::from_str(&field("data")).unwrap(),
+            )
+        }
+
+        let publisher = app
+            .state
+            .publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let shop = app
+            .state
+            .shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: "Shop".to_string(),
+            })
+            .await
+            .expect("Failed to create shop");
+
+        // Last-Event-ID より後のイベントをログから流し、続けて新しいイベントを流す
+        let response = subscribe("?types=publisher,book", Some("0")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let mut body = response.into_body();
+        let mut buffer = String::new();
+        let (id, event_type, data) = next_event(&mut body, &mut buffer).await;
+        assert_eq!(event_type, "publisher.created");
+        assert_eq!(data["resource_id"], publisher.pub_id.to_string());
+        assert_eq!(data["data"]["name"], "Publisher");
+
+        let created = router
+            .clone()
+            .oneshot(
+                Request::post("/books")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "title": "Book",
+                            "author": "Author",
+                            "publisher_id": publisher.pub_id,
+                            "shop_id": shop.pub_id,
+                            "price": 1000
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let (book_event_id, event_type, data) = next_event(&mut body, &mut buffer).await;
+        assert_eq!(event_type, "book.created");
+        assert_eq!(data["data"]["shop"]["name"], "Shop");
+        // shop のイベントは絞り込みで除かれる
+        assert_eq!(book_event_id, id + 2);
+
+        // 再接続すると続きから流す
+        let response = subscribe("", Some(&id.to_string())).await.unwrap();
+        let mut body = response.into_body();
+        let mut buffer = String::new();
+        assert_eq!(next_event(&mut body, &mut buffer).await.1, "shop.created");
+        assert_eq!(next_event(&mut body, &mut buffer).await.0, book_event_id);
+
+        for (query, last_event_id) in [("?types=author", None), ("", Some("abc"))] {
+            let response = subscribe(query, last_event_id).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+        }
+
+        // シャットダウン時はフィードを閉じる
+        app.state.change_event_usecase.close();
+        let end = tokio::time::timeout(Duration::from_secs(2), body.collect())
+            .await
+            .expect("feed should end on close");
+        assert!(end.is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_domain_events_reach_registered_handler(#[future] db: DatabaseConnection) {
+        use std::sync::Mutex;
+        use usecase::domain_event::{DomainEvent, Handler};
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<DomainEvent>>);
+
+        #[async_trait::async_trait]
+        impl Handler for Recorder {
+            async fn handle(&self, event: &DomainEvent) -> anyhow::Result<()> {
+                self.0.lock().unwrap().push(event.clone());
+                Ok(())
+            }
+        }
+
+        let db = db.await;
+        let dispatcher = Arc::new(usecase::domain_event::Dispatcher::new());
+        let recorder = Arc::new(Recorder::default());
+        dispatcher.register(recorder.clone());
+        let (book_usecase, publisher_usecase, _) = services_with_dispatcher(&db, dispatcher);
+
+        let publisher = publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let book_dto = |price| usecase::book::CreateDto {
+            title: "Book".to_string(),
+            author: "Author".to_string(),
+            publisher_id: publisher.pub_id,
+            shop_id: None,
+            format: None,
+            price,
+        };
+        let book = book_usecase
+            .create(book_dto(100))
+            .await
+            .expect("Failed to create book");
+        let dto = book_dto(120);
+        book_usecase
+            .update(
+                book.pub_id,
+                usecase::book::UpdateDto {
+                    title: dto.title,
+                    author: dto.author,
+                    publisher_id: dto.publisher_id,
+                    shop_id: dto.shop_id,
+                    format: dto.format,
+                    price: dto.price,
+                },
+            )
+            .await
+            .expect("Failed to update book");
+
+        let events = recorder.0.lock().unwrap();
+        let names: Vec<_> = events.iter().map(|e| e.name()).collect();
+        assert_eq!(
+            names,
+            ["PublisherCreated", "BookCreated", "BookPriceChanged"]
+        );
+        assert_eq!(
+            events[2],
+            DomainEvent::Book {
+                pub_id: book.pub_id,
+                event: book::event::Event::PriceChanged { old: 100, new: 120 },
+            }
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_outbox_is_written_with_aggregate_and_relayed(#[future] db: DatabaseConnection) {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode, header};
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let db = db.await;
+        let (book_usecase, publisher_usecase, shop_usecase) = services(&db);
+
+        let publisher = publisher_usecase
+            .create(usecase::publisher::CreateDto {
+                name: "Publisher".to_string(),
+            })
+            .await
+            .expect("Failed to create publisher");
+        let book = book_usecase
+            .create(usecase::book::CreateDto {
+                title: "Book".to_string(),
+                author: "Author".to_string(),
+                publisher_id: publisher.pub_id,
+                shop_id: None,
+                format: None,
+                price: 100,
+            })
+            .await
+            .expect("Failed to create book");
+
+        // 集約と同じトランザクションで書き込まれている
+        let pending = outbox(&db, Vec::new())
+            .get_all(Some("pending"), 100)
+            .await
+            .expect("Failed to list outbox");
+        let names: Vec<_> = pending.iter().map(|m| m.event_type.as_str()).collect();
+        assert_eq!(names, ["BookCreated", "PublisherCreated"]);
+        assert_eq!(pending[0].aggregate_id, book.pub_id);
+        assert_eq!(pending[0].payload["type"], "created");
+
+        let (sink, mut receiver) = infra::outbox_sink::ChannelSink::new(10);
+        let report = outbox(&db, vec![Arc::new(sink)])
+            .relay()
+            .await
+            .expect("Failed to relay");
+        assert_eq!(report.delivered, 2);
+        assert_eq!(
+            receiver.recv().await.unwrap().event_type(),
+            "PublisherCreated"
+        );
+        assert_eq!(receiver.recv().await.unwrap().event_type(), "BookCreated");
+
+        // 受信側が閉じていると配信に失敗し、再試行待ちになる
+        let (sink, receiver) = infra::outbox_sink::ChannelSink::new(10);
+        drop(receiver);
+        let closed = outbox(&db, vec![Arc::new(sink)]);
+        shop_usecase
+            .create(usecase::shop::CreateDto {
+                name: "Shop".to_string(),
+            })
+            .await
+            .expect("Failed to create shop");
+        assert_eq!(closed.relay().await.unwrap().retrying, 1);
+        let failed = &closed.get_all(Some("pending"), 100).await.unwrap()[0];
+        assert_eq!(failed.event_type, "ShopCreated");
+        assert_eq!(failed.attempts, 1);
+        assert!(failed.last_error.is_some());
+        assert!(failed.next_attempt_at > chrono::Utc::now());
+
+        let admin = api_keys(&db)
+            .issue(usecase::api_key::IssueDto {
+                name: "Ops".to_string(),
+                owner: "Ops".to_string(),
+                scopes: vec!["admin".to_string()],
+                expires_at: None,
+            })
+            .await
+            .expect("Failed to issue api key");
+        let router = api::create_router(
+            Arc::new(infra::test::state(&db)),
+            &api::HttpConfig::default(),
+        );
+        let send = |method: &str, path: String| {
+            router.clone().oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(path)
+                    .header(header::AUTHORIZATION, format!("ApiKey {}", admin.secret))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = send("GET", "/admin/outbox?status=delivered".to_string())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let delivered: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(delivered.as_array().unwrap().len(), 2);
+        let delivered_id = delivered[0]["id"].as_i64().unwrap();
+
+        let response = send("POST", format!("/admin/outbox/{}/retry", failed.id))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let retried: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(retried["status"], "pending");
+        assert_eq!(retried["attempts"], 0);
+
+        let response = send("POST", format!("/admin/outbox/{}/retry", delivered_id))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = send("GET", "/admin/outbox/0".to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = send("GET", "/admin/outbox?status=unknown".to_string())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_webhook_is_signed_and_retried_against_stub_receiver(
+        #[future] db: DatabaseConnection,
+    ) {
+        use axum::body::Body;
+        use axum::http::{HeaderMap, Request, StatusCode, header};
+        use http_body_util::BodyExt;
+        use std::sync::Mutex;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tower::ServiceExt;
+
+        // 最初の 1 回だけ 500 を返す受信側
+        #[derive(Default)]
+        struct Receiver {
+            calls: AtomicUsize,
+            received: Mutex<Vec<(HeaderMap, String)>>,
+        }
+        let receiver = Arc::new(Receiver::default());
+        let stub = axum::Router::new()
+            .route(
+                "/hooks",
+                axum::routing::post(
+                    |axum::extract::State(receiver): axum::extract::State<Arc<Receiver>>,
+                     headers: HeaderMap,
+                     body: String| async move {
+                        receiver.received.lock().unwrap().push((headers, body));
+                        if receiver.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                            StatusCode::INTERNAL_SERVER_ERROR
+                        } else {
+                            StatusCode::NO_CONTENT
+                        }
+                    },
+                ),
+            )
+            // 転送先を追うと内部のアドレスに誘導できるため、3xx はそのまま失敗として扱う
+            .route(
+                "/redirect",
+                axum::routing::post(|| async { axum::response::Redirect::temporary("/hooks") }),
+            )
+            .with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, stub).await });
+
+        // ループバックの受信側には直接配信できない。公開のホスト名をテストの中だけ向け直す
+        let timeout = std::time::Duration::from_secs(5);
+        let direct = infra::webhook_sender::HttpSender::new(timeout).unwrap();
+        let error = webhook::Sender::post(&direct, &format!("http://{}/hooks", addr), &[], "{}")
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("not a public address"),
+            "{}",
+            error
+        );
+        let host = "hooks.example.com";
+        let url = format!("http://{}:{}/hooks", host, addr.port());
+        let sender = infra::webhook_sender::HttpSender::with_override(timeout, host, addr).unwrap();
+        let status = webhook::Sender::post(
+            &sender,
+            &format!("http://{}:{}/redirect", host, addr.port()),
+            &[],
+            "{}",
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, 307);
+        assert_eq!(receiver.calls.load(Ordering::SeqCst), 0);
+
+        let db = db.await;
+        let api_key = api_keys(&db)
+            .issue(usecase::api_key::IssueDto {
+                name: "Webhook".to_string(),
+                owner: "Test".to_string(),
+                scopes: [
+                    "webhooks:read",
+                    "webhooks:write",
+                    "publishers:write",
+                    "books:write",
+                ]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+                expires_at: None,
+            })
+            .await
+            .unwrap();
+        let authorization = format!("ApiKey {}", api_key.secret);
+        let webhook_usecase = webhooks_with_sender(&db, sender);
+        let router = api::create_router(
+            Arc::new(api::AppState {
+                outbox_usecase: outbox(&db, vec![Arc::new(webhook_usecase.fanout())]),
+                webhook_usecase: webhook_usecase.clone(),
+                ..infra::test::state(&db)
+            }),
+            &api::HttpConfig::default(),
+        );
+        let send = |method: &str, path: String, body: String| {
+            router.clone().oneshot(
+                Request::builder()
+                    .method(method)
+                    .uri(path)
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .header(header::AUTHORIZATION, &authorization)
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+        let json = |response: axum::response::Response| async move {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let secret = "0123456789abcdef";
+        let response = send(
+            "POST",
+            "/webhooks".to_string(),
+            serde_json::json!({
+                "url": url,
+                "event_types": ["BookCreated", "BookPriceChanged"],
+                "secret": secret,
+            })
+            .to_string(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let subscription = json(response).await;
+        assert!(subscription.get("secret").is_none());
+        let pub_id = subscription["pub_id"].as_str().unwrap().to_string();
+
+        let response = send(
+            "POST",
+            "/webhooks".to_string(),
+            r#"{"url": "https://partner.example.com/hooks", "event_types": ["BookSold"], "secret": "0123456789abcdef"}"#
+                .to_string(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // 購読していない PublisherCreated は配信しない
+        let response = send(
+            "POST",
+            "/publishers".to_string(),
+            r#"{"name": "Publisher"}"#.to_string(),
+        )
+        .await
+        .unwrap();
+        let publisher_id = json(response).await["pub_id"].as_str().unwrap().to_string();
+        let response = send(
+            "POST",
+            "/books".to_string(),
+            serde_json::json!({
+                "title": "Book",
+                "author": "Author",
+                "publisher_id": publisher_id,
+                "price": 100,
+            })
+            .to_string(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let book_id = json(response).await["pub_id"].as_str().unwrap().to_string();
+
+        let relay = outbox(&db, vec![Arc::new(webhook_usecase.fanout())]);
+        assert_eq!(relay.relay().await.unwrap().delivered, 2);
+        let report = webhook_usecase.deliver().await.unwrap();
+        assert_eq!(report.retrying, 1);
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        let report = webhook_usecase.deliver().await.unwrap();
+        assert_eq!(report.delivered, 1);
+
+        {
+            let received = receiver.received.lock().unwrap();
+            assert_eq!(received.len(), 2);
+            let (headers, body) = &received[1];
+            let timestamp: i64 = headers[usecase::webhook::TIMESTAMP_HEADER]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert_eq!(
+                headers[usecase::webhook::SIGNATURE_HEADER],
+                usecase::webhook::sign(secret, timestamp, body)
+            );
+            assert_eq!(headers[usecase::webhook::EVENT_HEADER], "BookCreated");
+            assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+            let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+            assert_eq!(payload["event_type"], "BookCreated");
+            assert_eq!(payload["aggregate_id"], book_id.as_str());
+            assert_eq!(payload["data"]["type"], "created");
+        }
+
+        let response = send(
+            "GET",
+            format!("/webhooks/{}/deliveries", pub_id),
+            String::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let log = json(response).await;
+        assert_eq!(log.as_array().unwrap().len(), 1);
+        assert_eq!(log[0]["status"], "delivered");
+        assert_eq!(log[0]["attempts"], 2);
+        assert_eq!(log[0]["response_status"], 204);
+        let response = send(
+            "GET",
+            format!("/webhooks/{}/deliveries?status=sent", pub_id),
+            String::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = send("DELETE", format!("/webhooks/{}", pub_id), String::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = send("GET", format!("/webhooks/{}", pub_id), String::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    #[rstest]
+    #[tokio::test]
+    async fn test_graphql_resolves_book_with_publisher_shop_and_history(
+        #[future] db: DatabaseConnection,
+    ) {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode, header};
+        use http_body_util::BodyExt;
+        use serde_json::json;
+        use tower::ServiceExt;
+
+        let db = db.await;
+        let router = api::create_router(
+            Arc::new(infra::test::state(&db)),
+            &api::HttpConfig::default(),
+        );
+        let graphql = |query: &str, variables: serde_json::Value, key: Option<&str>| {
+            let mut request =
+                Request::post("/graphql").header(header::CONTENT_TYPE, "application/json");
+            if let Some(key) = key {
+                request = request.header(header::AUTHORIZATION, key);
+            }
+            let body = json!({ "query": query, "variables": variables }).to_string();
+            let router = router.clone();
+            async move {
+                let response = router
+                    .oneshot(request.body(Body::from(body)).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        let created = graphql(
+            r#"mutation {
+                publisher: createPublisher(input: { name: "GraphQL Press" }) { pubId }
+                shop: createShop(input: { name: "GraphQL Books" }) { pubId }
+            }"#,
+            json!({}),
+            None,
+        )
+        .await;
+        assert!(created["errors"].is_null(), "{}", created);
+        let publisher_id = created["data"]["publisher"]["pubId"].clone();
+        let shop_id = created["data"]["shop"]["pubId"].clone();
+
+        let create_book =
+            r#"mutation ($input: BookInput!) { createBook(input: $input) { pubId } }"#;
+        let mut book_ids = Vec::new();
+        for (title, shop) in [
+            ("First", shop_id.clone()),
+            ("Second", serde_json::Value::Null),
+        ] {
+            let created = graphql(
+                create_book,
+                json!({ "input": {
+                    "title": title,
+                    "author": "Author",
+                    "publisherId": publisher_id,
+                    "shopId": shop,
+                    "price": 1000,
+                }}),
+                None,
+            )
+            .await;
+            assert!(created["errors"].is_null(), "{}", created);
+            book_ids.push(created["data"]["createBook"]["pubId"].clone());
+        }
+        let updated = graphql(
+            r#"mutation ($pubId: UUID!, $input: BookInput!) {
+                updateBook(pubId: $pubId, input: $input) { price }
+            }"#,
+            json!({ "pubId": book_ids[0], "input": {
+                "title": "First",
+                "author": "Author",
+                "publisherId": publisher_id,
+                "shopId": shop_id,
+                "format": "EBook",
+                "price": 1200,
+            }}),
+            None,
+        )
+        .await;
+        assert_eq!(updated["data"]["updateBook"]["price"], 1200);
+
+        // Book が参照している publisher は削除できない
+        let deleted = graphql(
+            r#"mutation ($pubId: UUID!) { deletePublisher(pubId: $pubId) }"#,
+            json!({ "pubId": publisher_id }),
+            None,
+        )
+        .await;
+        assert_eq!(deleted["errors"][0]["extensions"]["code"], "BAD_REQUEST");
+
+        let result = graphql(
+            r#"{
+                books {
+                    title
+                    publisher { name deletedAt }
+                    shop { name }
+                    history { operation price format }
+                }
+            }"#,
+            json!({}),
+            None,
+        )
+        .await;
+        assert!(result["errors"].is_null(), "{}", result);
+        let books = result["data"]["books"].as_array().unwrap();
+        assert_eq!(books.len(), 2);
+        let first = books.iter().find(|b| b["title"] == "First").unwrap();
+        assert_eq!(first["publisher"]["name"], "GraphQL Press");
+        assert!(first["publisher"]["deletedAt"].is_null());
+        assert_eq!(first["shop"]["name"], "GraphQL Books");
+        assert_eq!(
+            first["history"],
+            json!([
+                { "operation": "INSERT", "price": 1000, "format": "Real" },
+                { "operation": "UPDATE", "price": 1200, "format": "EBook" },
+            ])
+        );
+        let second = books.iter().find(|b| b["title"] == "Second").unwrap();
+        assert!(second["shop"].is_null());
+        assert_eq!(second["history"].as_array().unwrap().len(), 1);
+
+        let result = graphql(
+            r#"query ($pubId: UUID!) {
+                book(pubId: $pubId) { title }
+                publisher(pubId: $pubId) { name }
+                shop(pubId: $pubId) { name }
+            }"#,
+            json!({ "pubId": uuid::Uuid::now_v7() }),
+            None,
+        )
+        .await;
+        assert_eq!(
+            result["data"],
+            json!({ "book": null, "publisher": null, "shop": null })
+        );
+
+        let result = graphql(
+            r#"mutation ($input: BookInput!) { createBook(input: $input) { pubId } }"#,
+            json!({ "input": {
+                "title": "x".repeat(33),
+                "author": "Author",
+                "publisherId": uuid::Uuid::now_v7(),
+                "price": 1000,
+            }}),
+            None,
+        )
+        .await;
+        assert_eq!(result["errors"][0]["extensions"]["code"], "BAD_REQUEST");
+
+        // スコープは REST と同じく操作ごとに確かめる
+        let key = api_keys(&db)
+            .issue(usecase::api_key::IssueDto {
+                name: "Frontend".to_string(),
+                owner: "Web".to_string(),
+                scopes: vec!["books:read".to_string()],
+                expires_at: None,
+            })
+            .await
+            .expect("Failed to issue key");
+        let key = format!("ApiKey {}", key.secret);
+        let result = graphql(
+            r#"mutation { createShop(input: { name: "Denied" }) { pubId } }"#,
+            json!({}),
+            Some(&key),
+        )
+        .await;
+        assert_eq!(result["errors"][0]["extensions"]["code"], "FORBIDDEN");
+        let result = graphql(r#"{ books { title } }"#, json!({}), Some(&key)).await;
+        assert_eq!(result["data"]["books"].as_array().unwrap().len(), 2);
+        // 論理削除済みを含めて読めるのは admin スコープだけ
+        let query = r#"{ books(includeDeleted: true) { title } }"#;
+        let result = graphql(query, json!({}), Some(&key)).await;
+        assert_eq!(result["errors"][0]["extensions"]["code"], "FORBIDDEN");
+        let result = graphql(query, json!({}), None).await;
+        assert_eq!(result["errors"][0]["extensions"]["code"], "UNAUTHENTICATED");
+
+        let response = router
+            .clone()
+            .oneshot(Request::get("/graphql").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    /// ルーターを実際のポートで起動し、そこに向けたクライアントを返す
+    async fn serve_client(router: axum::Router) -> client::Client {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+        client::Client::new(format!("http://{}", addr))
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_client_against_in_process_server(#[future] db: DatabaseConnection) {
+        use client::StatusCode;
+        use client::dto::*;
+        use futures::TryStreamExt;
+
+        let db = db.await;
+        let app = crate::app::App::new(db, &crate::config::Config::default()).unwrap();
+        let api = serve_client(api::create_router(
+            app.state.clone(),
+            &api::HttpConfig::default(),
+        ))
+        .await;
+
+        api.healthz().await.unwrap();
+        // ワーカーを起動していないので 503 になるが、エラーにせず内訳を返す
+        let readiness = api.readyz().await.unwrap();
+        assert_eq!(readiness.status, client::health::Status::Degraded);
+        assert_eq!(
+            readiness.checks["database"].status,
+            client::health::Status::Ok
+        );
+
+        let publisher = api
+            .create_publisher(&PublisherCreateDto {
+                name: "Client Press".to_string(),
+            })
+            .await
+            .unwrap();
+        let renamed = api
+            .update_publisher(
+                publisher.pub_id,
+                &PublisherUpdateDto {
+                    name: "Client Publishing".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(renamed.name, "Client Publishing");
+        let shop = api
+            .create_shop(&ShopCreateDto {
+                name: "Client Books".to_string(),
+            })
+            .await
+            .unwrap();
+        // shop の検証エラーも他のルートと同じ本文で返る
+        match api.restore_shop(shop.pub_id).await {
+            Err(client::Error::Api { status, body }) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert!(
+                    body.error.contains("Shop is not deleted."),
+                    "{}",
+                    body.error
+                );
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+        api.delete_shop(shop.pub_id).await.unwrap();
+        assert!(api.get_shops(false).await.unwrap().is_empty());
+        // 論理削除済みを含めて読めるのは admin スコープだけ
+        let error = api.get_shop(shop.pub_id, true).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::UNAUTHORIZED));
+        let admin = app
+            .state
+            .api_key_usecase
+            .issue(ApiKeyIssueDto {
+                name: "Admin".to_string(),
+                owner: "Ops".to_string(),
+                scopes: vec!["shops:read".to_string(), "admin".to_string()],
+                expires_at: None,
+            })
+            .await
+            .unwrap();
+        let admin = api.clone().with_api_key(admin.secret);
+        assert!(
+            admin
+                .get_shop(shop.pub_id, true)
+                .await
+                .unwrap()
+                .deleted_at
+                .is_some()
+        );
+        api.restore_shop(shop.pub_id).await.unwrap();
+        assert_eq!(api.get_publishers(false).await.unwrap().len(), 1);
+
+        // 同じ Idempotency-Key の再試行は最初のレスポンスを返す
+        let create = BookCreateDto {
+            title: "Typed Client".to_string(),
+            author: "Author".to_string(),
+            publisher_id: publisher.pub_id,
+            shop_id: Some(shop.pub_id),
+            format: None,
+            price: 1000,
+        };
+        let retrying = api.with_idempotency_key("client-book-1");
+        let book = retrying.create_book(&create).await.unwrap();
+        assert_eq!(
+            retrying.create_book(&create).await.unwrap().pub_id,
+            book.pub_id
+        );
+        assert_eq!(
+            api.get_book(book.pub_id, false)
+                .await
+                .unwrap()
+                .shop
+                .unwrap()
+                .name,
+            "Client Books"
+        );
+
+        let update = |price| BookUpdateDto {
+            title: "Typed Client".to_string(),
+            author: "Author".to_string(),
+            publisher_id: publisher.pub_id,
+            shop_id: None,
+            format: Some("EBook".to_string()),
+            price,
+        };
+        assert_eq!(
+            api.update_book(book.pub_id, &update(1200))
+                .await
+                .unwrap()
+                .price,
+            1200
+        );
+        api.change_book_applied_at(
+            book.pub_id,
+            &BookChangeAppliedAtDto {
+                applied_at: Some(chrono::Utc::now()),
+            },
+        )
+        .await
+        .unwrap();
+        // エラーは {"error": ...} の本文ごと返る
+        match api.update_book(book.pub_id, &update(1500)).await {
+            Err(client::Error::Api { status, body }) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert!(body.error.contains("already applied"), "{}", body.error);
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+        let missing = api.get_book(uuid::Uuid::now_v7(), false).await.unwrap_err();
+        assert_eq!(missing.status(), Some(StatusCode::NOT_FOUND));
+
+        let batch = api
+            .batch_books(&BookBatchDto {
+                mode: BookBatchMode::BestEffort,
+                operations: vec![
+                    BookBatchOperation::Create {
+                        data: BookCreateDto {
+                            title: "Batched".to_string(),
+                            price: 500,
+                            shop_id: None,
+                            ..create
+                        },
+                    },
+                    BookBatchOperation::Update {
+                        pub_id: book.pub_id,
+                        data: update(2000),
+                    },
+                ],
+            })
+            .await
+            .unwrap();
+        let statuses: Vec<_> = batch.results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [BookBatchItemStatus::Created, BookBatchItemStatus::Failed]
+        );
+
+        let report = api
+            .import_books(
+                "title,author,publisher,shop,format,price\nImported,Author,Client Publishing,,Real,300\n",
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!((report.imported_rows, report.errors.len()), (1, 0));
+        let found = api.search_books("Imported", 1, 20).await.unwrap();
+        assert_eq!(found.total, 1);
+        assert_eq!(found.items[0].book.title, "Imported");
+
+        let ndjson: Vec<_> = api
+            .export_books(client::book::ExportFormat::NdJson, false)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let ndjson = String::from_utf8(ndjson.concat()).unwrap();
+        assert_eq!(ndjson.lines().count(), 3);
+        assert_eq!(api.get_books(false).await.unwrap().len(), 3);
+
+        let batched = &batch.results[0].book.as_ref().unwrap().pub_id;
+        api.delete_book(*batched).await.unwrap();
+        api.restore_book(*batched).await.unwrap();
+
+        let resolved = api
+            .graphql(
+                "query ($id: UUID!) { book(pubId: $id) { title publisher { name } } }",
+                serde_json::json!({ "id": book.pub_id }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            resolved["data"]["book"]["publisher"]["name"],
+            "Client Publishing"
+        );
+        assert!(api.metrics().await.unwrap().contains("http_requests_total"));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_client_admin_webhook_and_event_routes(#[future] db: DatabaseConnection) {
+        use client::StatusCode;
+        use client::dto::*;
+        use futures::StreamExt;
+        use std::time::Duration;
+
+        let db = db.await;
+        let app = crate::app::App::new(db, &crate::config::Config::default()).unwrap();
+        let anonymous = serve_client(api::create_router(
+            app.state.clone(),
+            &api::HttpConfig::default(),
+        ))
+        .await;
+        let bootstrap = app
+            .state
+            .api_key_usecase
+            .issue(ApiKeyIssueDto {
+                name: "Bootstrap".to_string(),
+                owner: "Ops".to_string(),
+                scopes: vec!["admin".to_string()],
+                expires_at: None,
+            })
+            .await
+            .unwrap();
+        let admin = anonymous.clone().with_api_key(bootstrap.secret);
+
+        let error = anonymous.get_api_keys().await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::UNAUTHORIZED));
+        let issued = admin
+            .issue_api_key(&ApiKeyIssueDto {
+                name: "Reader".to_string(),
+                owner: "Backend".to_string(),
+                scopes: vec!["books:read".to_string()],
+                expires_at: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(admin.get_api_keys().await.unwrap().len(), 2);
+        let reader = anonymous.clone().with_api_key(issued.secret);
+        reader.get_books(false).await.unwrap();
+        let error = reader.get_outbox_messages(None, 100).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::FORBIDDEN));
+        let revoked = admin.revoke_api_key(issued.api_key.pub_id).await.unwrap();
+        assert!(revoked.revoked_at.is_some());
+
+        let subscription = WebhookCreateDto {
+            url: "https://partner.example.com/hooks".to_string(),
+            event_types: vec!["PublisherCreated".to_string()],
+            secret: "0123456789abcdef".to_string(),
+        };
+        let error = anonymous.create_webhook(&subscription).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::UNAUTHORIZED));
+        let issued = admin
+            .issue_api_key(&ApiKeyIssueDto {
+                name: "Partner".to_string(),
+                owner: "Backend".to_string(),
+                scopes: vec!["webhooks:read".to_string(), "webhooks:write".to_string()],
+                expires_at: None,
+            })
+            .await
+            .unwrap();
+        let partner = anonymous.clone().with_api_key(issued.secret);
+        let webhook = partner.create_webhook(&subscription).await.unwrap();
+        let error = anonymous.get_webhooks().await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::UNAUTHORIZED));
+        assert_eq!(partner.get_webhooks().await.unwrap().len(), 1);
+        assert_eq!(
+            partner.get_webhook(webhook.pub_id).await.unwrap().url,
+            webhook.url
+        );
+
+        let mut events = anonymous.events(&["publisher"], None).await.unwrap();
+        let publisher = anonymous
+            .create_publisher(&PublisherCreateDto {
+                name: "Evented".to_string(),
+            })
+            .await
+            .unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .expect("event should arrive")
+            .expect("feed should not end")
+            .unwrap();
+        assert_eq!(event.event_type, "publisher.created");
+        assert_eq!(event.resource_id, publisher.pub_id);
+        // 不正な種類はストリームを開く前にエラーになる
+        let Err(error) = anonymous.events(&["author"], None).await else {
+            panic!("unknown resource type should be rejected");
+        };
+        assert_eq!(error.status(), Some(StatusCode::BAD_REQUEST));
+
+        let messages = admin
+            .get_outbox_messages(Some("pending"), 100)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 1);
+        let message = admin.get_outbox_message(messages[0].id).await.unwrap();
+        assert_eq!(message.aggregate_id, publisher.pub_id);
+        assert_eq!(
+            admin
+                .retry_outbox_message(message.id)
+                .await
+                .unwrap()
+                .attempts,
+            0
+        );
+        let error = admin.get_outbox_message(i64::MAX).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+
+        // relay はこのテストでは動かしていないので、配信はまだ記録されていない
+        assert!(
+            partner
+                .get_webhook_deliveries(webhook.pub_id, None, 100)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let error = anonymous.delete_webhook(webhook.pub_id).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::UNAUTHORIZED));
+        partner.delete_webhook(webhook.pub_id).await.unwrap();
+        let error = partner.get_webhook(webhook.pub_id).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+    }
 }
@@ -0,0 +1,212 @@
+//! インメモリの SQLite に対して create_router をプロセス内で動かすテスト用のハーネス
+use crate::app::App;
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, Method, Request, StatusCode, header};
+use http_body_util::BodyExt;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::Database;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+pub(crate) struct Harness {
+    pub(crate) state: Arc<api::AppState>,
+    /// API を通さずにレコードを確かめたり書き換えたりする時に使う
+    pub(crate) db: sea_orm::DatabaseConnection,
+    router: axum::Router,
+}
+
+impl Harness {
+    /// 既定の設定 (匿名アクセスを許可) で起動する
+    pub(crate) async fn new() -> Self {
+        Self::with_config(&api::HttpConfig::default()).await
+    }
+
+    pub(crate) async fn with_config(config: &api::HttpConfig) -> Self {
+        let db = Database::connect("sqlite::memory:")
+            .await
+            .expect("Failed to connect to SQLite");
+        Migrator::up(&db, None)
+            .await
+            .expect("Failed to run migrations");
+        let app = App::new(db, &crate::config::Config::default()).expect("Failed to build app");
+        let router = api::create_router(app.state.clone(), config);
+        Self {
+            state: app.state,
+            db: app.db,
+            router,
+        }
+    }
+
+    /// 本文を読み切れないレスポンス (SSE など) を扱う時に使う
+    pub(crate) fn router(&self) -> axum::Router {
+        self.router.clone()
+    }
+
+    pub(crate) async fn send(&self, request: Request<Body>) -> Response {
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("Router should not fail");
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response
+            .into_body()
+            .collect()
+            .await
+            .expect("Failed to read body")
+            .to_bytes();
+        Response {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub(crate) fn request(&self, method: Method, path: &str) -> RequestBuilder<'_> {
+        RequestBuilder {
+            harness: self,
+            request: Request::builder().method(method).uri(path),
+            body: Body::empty(),
+        }
+    }
+
+    pub(crate) async fn get(&self, path: &str) -> Response {
+        self.request(Method::GET, path).send().await
+    }
+
+    pub(crate) async fn post(&self, path: &str, body: Value) -> Response {
+        self.request(Method::POST, path).json(body).send().await
+    }
+
+    pub(crate) async fn put(&self, path: &str, body: Value) -> Response {
+        self.request(Method::PUT, path).json(body).send().await
+    }
+
+    pub(crate) async fn delete(&self, path: &str) -> Response {
+        self.request(Method::DELETE, path).send().await
+    }
+
+    /// API を通して作る。失敗したらその場でテストを止める
+    pub(crate) async fn publisher(&self, name: &str) -> usecase::publisher::ResponseDto {
+        self.post("/publishers", json!({ "name": name }))
+            .await
+            .expect(StatusCode::CREATED)
+            .json()
+    }
+
+    pub(crate) async fn shop(&self, name: &str) -> usecase::shop::ResponseDto {
+        self.post("/shops", json!({ "name": name }))
+            .await
+            .expect(StatusCode::CREATED)
+            .json()
+    }
+
+    pub(crate) async fn book(
+        &self,
+        title: &str,
+        publisher: &usecase::publisher::ResponseDto,
+        shop: Option<&usecase::shop::ResponseDto>,
+    ) -> usecase::book::ResponseDto {
+        self.post(
+            "/books",
+            json!({
+                "title": title,
+                "author": "Author",
+                "publisher_id": publisher.pub_id,
+                "shop_id": shop.map(|s| s.pub_id),
+                "price": 1000,
+            }),
+        )
+        .await
+        .expect(StatusCode::CREATED)
+        .json()
+    }
+
+    /// CLI と同じく usecase から発行し、Authorization ヘッダーの値を返す
+    pub(crate) async fn api_key(&self, scopes: &[&str]) -> String {
+        let issued = self
+            .state
+            .api_key_usecase
+            .issue(usecase::api_key::IssueDto {
+                name: "Harness".to_string(),
+                owner: "Test".to_string(),
+                scopes: scopes.iter().map(|s| s.to_string()).collect(),
+                expires_at: None,
+            })
+            .await
+            .expect("Failed to issue API key");
+        format!("ApiKey {}", issued.secret)
+    }
+}
+
+pub(crate) struct RequestBuilder<'a> {
+    harness: &'a Harness,
+    request: axum::http::request::Builder,
+    body: Body,
+}
+
+impl RequestBuilder<'_> {
+    pub(crate) fn header(mut self, name: &str, value: &str) -> Self {
+        self.request = self.request.header(name, value);
+        self
+    }
+
+    pub(crate) fn json(mut self, body: Value) -> Self {
+        self.request = self
+            .request
+            .header(header::CONTENT_TYPE, "application/json");
+        self.body = Body::from(body.to_string());
+        self
+    }
+
+    pub(crate) fn body(mut self, content_type: &str, body: impl Into<Body>) -> Self {
+        self.request = self.request.header(header::CONTENT_TYPE, content_type);
+        self.body = body.into();
+        self
+    }
+
+    pub(crate) async fn send(self) -> Response {
+        let request = self.request.body(self.body).expect("Invalid request");
+        self.harness.send(request).await
+    }
+}
+
+/// 本文まで読み終えたレスポンス
+#[derive(Debug)]
+pub(crate) struct Response {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Bytes,
+}
+
+impl Response {
+    /// ステータスが違えば本文と一緒に表示して止める
+    #[track_caller]
+    pub(crate) fn expect(self, status: StatusCode) -> Self {
+        assert_eq!(self.status, status, "{}", self.text());
+        self
+    }
+
+    #[track_caller]
+    pub(crate) fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| panic!("{}: {}", e, self.text()))
+    }
+
+    pub(crate) fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// `{"error": ...}` のメッセージ
+    #[track_caller]
+    pub(crate) fn error(&self) -> String {
+        let body: Value = self.json();
+        body["error"]
+            .as_str()
+            .unwrap_or_else(|| panic!("No error message: {}", body))
+            .to_string()
+    }
+}
@@ -0,0 +1,887 @@
+//! 全ルートをハーネス越しに HTTP で呼び、成功とエラーの両方を確かめる
+use super::harness::Harness;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode, header};
+use rstest::*;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+#[fixture]
+async fn harness() -> Harness {
+    Harness::new().await
+}
+
+const MISSING: &str = "0190b6a4-0000-7000-8000-000000000000";
+
+#[rstest]
+#[tokio::test]
+async fn test_publisher_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let created = h
+        .post("/publishers", json!({ "name": "Press" }))
+        .await
+        .expect(StatusCode::CREATED)
+        .json::<Value>();
+    let path = format!("/publishers/{}", created["pub_id"].as_str().unwrap());
+
+    let fetched = h.get(&path).await.expect(StatusCode::OK).json::<Value>();
+    assert_eq!(fetched["name"], "Press");
+    let updated = h
+        .put(&path, json!({ "name": "Renamed Press" }))
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(updated["name"], "Renamed Press");
+    let listed = h
+        .get("/publishers")
+        .await
+        .expect(StatusCode::OK)
+        .json::<Vec<Value>>();
+    assert_eq!(listed.len(), 1);
+
+    let response = h
+        .post("/publishers", json!({ "name": "x".repeat(33) }))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("32 chars or less"),
+        "{}",
+        response.error()
+    );
+    h.put(&path, json!({ "name": "x".repeat(33) }))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    // 必須項目がなければ JSON の抽出で弾かれる
+    h.post("/publishers", json!({}))
+        .await
+        .expect(StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = h
+        .post(&format!("{}/restore", path), json!({}))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(response.error().contains("Publisher is not deleted."));
+    h.delete(&path).await.expect(StatusCode::NO_CONTENT);
+    h.get(&path).await.expect(StatusCode::NOT_FOUND);
+    h.delete(&path).await.expect(StatusCode::NOT_FOUND);
+    // 論理削除済みを含めて読めるのは admin スコープだけ
+    let with_deleted = format!("{}?include_deleted=true", path);
+    h.get(&with_deleted).await.expect(StatusCode::UNAUTHORIZED);
+    let reader = h.api_key(&["publishers:read"]).await;
+    let response = h
+        .request(Method::GET, &with_deleted)
+        .header("authorization", &reader)
+        .send()
+        .await
+        .expect(StatusCode::FORBIDDEN);
+    assert!(response.error().contains("admin"), "{}", response.error());
+    let admin = h.api_key(&["publishers:read", "admin"]).await;
+    let deleted = h
+        .request(Method::GET, &with_deleted)
+        .header("authorization", &admin)
+        .send()
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert!(!deleted["deleted_at"].is_null());
+    assert!(h.get("/publishers").await.json::<Vec<Value>>().is_empty());
+    h.post(&format!("{}/restore", path), json!({}))
+        .await
+        .expect(StatusCode::OK);
+    h.get(&path).await.expect(StatusCode::OK);
+
+    let missing = format!("/publishers/{}", MISSING);
+    let response = h.get(&missing).await.expect(StatusCode::NOT_FOUND);
+    assert!(response.error().contains(MISSING), "{}", response.error());
+    h.put(&missing, json!({ "name": "Nobody" }))
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    h.post(&format!("{}/restore", missing), json!({}))
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    h.get("/publishers/not-a-uuid")
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_shop_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let created = h.shop("Books").await;
+    let path = format!("/shops/{}", created.pub_id);
+
+    assert_eq!(
+        h.get(&path).await.expect(StatusCode::OK).json::<Value>()["name"],
+        "Books"
+    );
+    let updated = h
+        .put(&path, json!({ "name": "Bookstore" }))
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(updated["name"], "Bookstore");
+    assert_eq!(h.get("/shops").await.json::<Vec<Value>>().len(), 1);
+
+    let response = h
+        .post("/shops", json!({ "name": "x".repeat(33) }))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("32 chars or less"),
+        "{}",
+        response.error()
+    );
+    let response = h
+        .post(&format!("{}/restore", path), json!({}))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(response.error().contains("Shop is not deleted."));
+    h.delete(&path).await.expect(StatusCode::NO_CONTENT);
+    h.get(&path).await.expect(StatusCode::NOT_FOUND);
+    let admin = h.api_key(&["shops:read", "admin"]).await;
+    assert_eq!(
+        h.request(Method::GET, "/shops?include_deleted=true")
+            .header("authorization", &admin)
+            .send()
+            .await
+            .expect(StatusCode::OK)
+            .json::<Vec<Value>>()
+            .len(),
+        1
+    );
+    h.post(&format!("{}/restore", path), json!({}))
+        .await
+        .expect(StatusCode::OK);
+
+    let missing = format!("/shops/{}", MISSING);
+    h.get(&missing).await.expect(StatusCode::NOT_FOUND);
+    h.put(&missing, json!({ "name": "Nowhere" }))
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    h.delete(&missing).await.expect(StatusCode::NOT_FOUND);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_book_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let publisher = h.publisher("Press").await;
+    let shop = h.shop("Books").await;
+    let book = h.book("Title", &publisher, Some(&shop)).await;
+    let path = format!("/books/{}", book.pub_id);
+    assert_eq!(book.format, "Real");
+    assert_eq!(book.shop.as_ref().unwrap().name, "Books");
+
+    let fetched = h.get(&path).await.expect(StatusCode::OK).json::<Value>();
+    assert_eq!(fetched["publisher"]["name"], "Press");
+    let body = |price: i32| {
+        json!({
+            "title": "Title",
+            "author": "Author",
+            "publisher_id": publisher.pub_id,
+            "shop_id": null,
+            "format": "EBook",
+            "price": price,
+        })
+    };
+    let updated = h
+        .put(&path, body(1200))
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(
+        (updated["price"].as_i64(), updated["shop"].is_null()),
+        (Some(1200), true)
+    );
+
+    // 参照先や値の検証
+    let response = h
+        .post(
+            "/books",
+            json!({
+                "title": "Orphan",
+                "author": "Author",
+                "publisher_id": MISSING,
+                "price": 100,
+            }),
+        )
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    assert!(
+        response.error().contains("Publisher"),
+        "{}",
+        response.error()
+    );
+    let response = h.put(&path, body(-1)).await.expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("Price must be 0 or more"),
+        "{}",
+        response.error()
+    );
+    let mut long_title = body(100);
+    long_title["title"] = json!("x".repeat(33));
+    h.post("/books", long_title)
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    h.request(Method::POST, "/books")
+        .body("application/json", "{")
+        .send()
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+
+    h.post(&format!("{}/restore", path), json!({}))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    h.delete(&path).await.expect(StatusCode::NO_CONTENT);
+    h.get(&path).await.expect(StatusCode::NOT_FOUND);
+    assert!(h.get("/books").await.json::<Vec<Value>>().is_empty());
+    let admin = h.api_key(&["books:read", "admin"]).await;
+    assert_eq!(
+        h.request(Method::GET, "/books?include_deleted=true")
+            .header("authorization", &admin)
+            .send()
+            .await
+            .expect(StatusCode::OK)
+            .json::<Vec<Value>>()
+            .len(),
+        1
+    );
+    let restored = h
+        .post(&format!("{}/restore", path), json!({}))
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert!(restored["deleted_at"].is_null());
+
+    let missing = format!("/books/{}", MISSING);
+    h.get(&missing).await.expect(StatusCode::NOT_FOUND);
+    h.put(&missing, body(100))
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    h.delete(&missing).await.expect(StatusCode::NOT_FOUND);
+    h.put(
+        &format!("{}/applied_at", missing),
+        json!({ "applied_at": null }),
+    )
+    .await
+    .expect(StatusCode::NOT_FOUND);
+    h.get("/books/not-a-uuid")
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_deletes_keep_books_consistent(#[future] harness: Harness) {
+    let h = harness.await;
+    let publisher = h.publisher("Press").await;
+    let shop = h.shop("Books").await;
+    let book = h.book("Title", &publisher, Some(&shop)).await;
+    let publisher = format!("/publishers/{}", publisher.pub_id);
+    let shop = format!("/shops/{}", shop.pub_id);
+    let book = format!("/books/{}", book.pub_id);
+
+    // 削除されていない Book が参照している間は削除できない
+    for path in [&publisher, &shop] {
+        let response = h.delete(path).await.expect(StatusCode::BAD_REQUEST);
+        assert!(
+            response.error().contains("still has books"),
+            "{}",
+            response.error()
+        );
+    }
+    h.delete(&book).await.expect(StatusCode::NO_CONTENT);
+    h.delete(&publisher).await.expect(StatusCode::NO_CONTENT);
+    h.delete(&shop).await.expect(StatusCode::NO_CONTENT);
+
+    // 参照先が削除されたままの Book は復元できない
+    let response = h
+        .post(&format!("{}/restore", book), json!({}))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("publisher is deleted"),
+        "{}",
+        response.error()
+    );
+    h.post(&format!("{}/restore", publisher), json!({}))
+        .await
+        .expect(StatusCode::OK);
+    let response = h
+        .post(&format!("{}/restore", book), json!({}))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("shop is deleted"),
+        "{}",
+        response.error()
+    );
+    h.post(&format!("{}/restore", shop), json!({}))
+        .await
+        .expect(StatusCode::OK);
+    h.post(&format!("{}/restore", book), json!({}))
+        .await
+        .expect(StatusCode::OK);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_applied_book_is_locked(#[future] harness: Harness) {
+    let h = harness.await;
+    let publisher = h.publisher("Press").await;
+    let book = h.book("Title", &publisher, None).await;
+    let path = format!("/books/{}", book.pub_id);
+    let body = json!({
+        "title": "Changed",
+        "author": "Author",
+        "publisher_id": publisher.pub_id,
+        "price": 1000,
+    });
+
+    let applied = h
+        .put(
+            &format!("{}/applied_at", path),
+            json!({ "applied_at": "2026-01-01T00:00:00Z" }),
+        )
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(applied["applied_at"], "2026-01-01T00:00:00Z");
+
+    let response = h
+        .put(&path, body.clone())
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.error(),
+        "Domain rule violation: Cannot update a book that is already applied."
+    );
+    assert_eq!(h.get(&path).await.json::<Value>()["title"], "Title");
+    // バッチの更新も同じ規則で失敗する
+    let batch = h
+        .post(
+            "/books:batch",
+            json!({
+                "mode": "best_effort",
+                "operations": [{ "op": "update", "pub_id": book.pub_id, "data": body }],
+            }),
+        )
+        .await
+        .expect(StatusCode::MULTI_STATUS)
+        .json::<Value>();
+    assert_eq!(batch["results"][0]["status"], "failed");
+    assert!(
+        batch["results"][0]["error"]
+            .as_str()
+            .unwrap()
+            .contains("already applied")
+    );
+
+    // 適用を取り消せば再び更新できる
+    h.put(
+        &format!("{}/applied_at", path),
+        json!({ "applied_at": null }),
+    )
+    .await
+    .expect(StatusCode::OK);
+    let updated = h
+        .put(&path, body)
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(updated["title"], "Changed");
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_book_bulk_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let publisher = h.publisher("Press").await;
+    let existing = h.book("Existing", &publisher, None).await;
+    let create = |title: &str| {
+        json!({ "op": "create", "data": {
+            "title": title,
+            "author": "Author",
+            "publisher_id": publisher.pub_id,
+            "price": 100,
+        }})
+    };
+
+    let batch = h
+        .post(
+            "/books:batch",
+            json!({ "mode": "all_or_nothing", "operations": [create("Batched")] }),
+        )
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(batch["results"][0]["status"], "created");
+    let batch = h
+        .post(
+            "/books:batch",
+            json!({ "mode": "all_or_nothing", "operations": [create("Skipped"), create(&"x".repeat(33))] }),
+        )
+        .await
+        .expect(StatusCode::MULTI_STATUS)
+        .json::<Value>();
+    assert_eq!(batch["results"][0]["status"], "skipped");
+    assert_eq!(batch["results"][1]["status"], "failed");
+    let response = h
+        .post(
+            "/books:batch",
+            json!({ "mode": "best_effort", "operations": [] }),
+        )
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("between 1 and"),
+        "{}",
+        response.error()
+    );
+
+    let csv = "title,author,publisher,shop,format,price\n\
+               Imported,Author,Press,,Real,300\n\
+               Broken,Author,Unknown Press,,Real,300\n";
+    let report = h
+        .request(Method::POST, "/imports/books?dry_run=true")
+        .body("text/csv", csv)
+        .send()
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(
+        (
+            report["valid_rows"].as_u64(),
+            report["imported_rows"].as_u64()
+        ),
+        (Some(1), Some(0))
+    );
+    let report = h
+        .request(Method::POST, "/imports/books")
+        .body("text/csv", csv)
+        .send()
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(report["imported_rows"], 1);
+    assert_eq!(report["errors"][0]["line"], 3);
+    h.request(Method::POST, "/imports/books")
+        .body("application/json", "[]")
+        .send()
+        .await
+        .expect(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let found = h
+        .get("/search?q=Imported")
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(found["total"], 1);
+    assert_eq!(
+        found["items"][0]["highlight"]["title"],
+        "<mark>Imported</mark>"
+    );
+    let response = h.get("/search?q=%20").await.expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("must not be empty"),
+        "{}",
+        response.error()
+    );
+    h.get("/search?q=Imported&per_page=101")
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    h.get("/search").await.expect(StatusCode::BAD_REQUEST);
+
+    let csv = h
+        .request(Method::GET, "/books")
+        .header("accept", "text/csv")
+        .send()
+        .await
+        .expect(StatusCode::OK);
+    assert!(
+        csv.headers[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/csv")
+    );
+    assert_eq!(csv.text().lines().count(), 4);
+    let ndjson = h
+        .request(Method::GET, "/books")
+        .header("accept", "application/x-ndjson")
+        .send()
+        .await
+        .expect(StatusCode::OK);
+    let titles: Vec<String> = ndjson
+        .text()
+        .lines()
+        .map(|l| {
+            serde_json::from_str::<Value>(l).unwrap()["title"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert!(titles.contains(&existing.title));
+    assert_eq!(titles.len(), 3);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_event_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let response = h
+        .get("/events?types=book,author")
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("Unknown resource type: author"),
+        "{}",
+        response.error()
+    );
+    let response = h
+        .request(Method::GET, "/events")
+        .header("last-event-id", "latest")
+        .send()
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert_eq!(response.error(), "Last-Event-ID must be an integer");
+
+    // ストリームは終わらないので、本文は読まずにヘッダーだけ確かめる
+    let response = h
+        .router()
+        .oneshot(
+            Request::get("/events?types=book")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_webhook_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let writer = h.api_key(&["webhooks:write"]).await;
+    let reader = h.api_key(&["webhooks:read"]).await;
+    let read = |path: &str| {
+        h.request(Method::GET, path)
+            .header("authorization", &reader)
+            .send()
+    };
+    let register = |body: Value| {
+        h.request(Method::POST, "/webhooks")
+            .header("authorization", &writer)
+            .json(body)
+            .send()
+    };
+    let subscription = json!({
+        "url": "https://partner.example.com/hooks",
+        "event_types": ["BookCreated"],
+        "secret": "0123456789abcdef",
+    });
+
+    // 匿名のアクセスを許可していても、登録には API キーが必要
+    h.post("/webhooks", subscription.clone())
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+    let created = register(subscription)
+        .await
+        .expect(StatusCode::CREATED)
+        .json::<Value>();
+    assert!(created.get("secret").is_none());
+    let path = format!("/webhooks/{}", created["pub_id"].as_str().unwrap());
+
+    // 送り先と配信の記録が見えるので、参照にも API キーが必要
+    for anonymous in [
+        "/webhooks".to_string(),
+        path.clone(),
+        format!("{}/deliveries", path),
+    ] {
+        h.get(&anonymous).await.expect(StatusCode::UNAUTHORIZED);
+    }
+    h.request(Method::GET, "/webhooks")
+        .header("authorization", &writer)
+        .send()
+        .await
+        .expect(StatusCode::FORBIDDEN);
+    assert_eq!(read("/webhooks").await.json::<Vec<Value>>().len(), 1);
+    assert_eq!(
+        read(&path).await.expect(StatusCode::OK).json::<Value>()["url"],
+        "https://partner.example.com/hooks"
+    );
+    let deliveries = read(&format!("{}/deliveries?status=dead&limit=10", path))
+        .await
+        .expect(StatusCode::OK)
+        .json::<Vec<Value>>();
+    assert!(deliveries.is_empty());
+    let response = read(&format!("{}/deliveries?status=lost", path))
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("Unknown delivery status"),
+        "{}",
+        response.error()
+    );
+
+    let response = register(
+        json!({ "url": "ftp://example.com", "event_types": ["BookCreated"], "secret": "0123456789abcdef" }),
+    )
+    .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("http:// or https://"),
+        "{}",
+        response.error()
+    );
+    let response = register(
+        json!({ "url": "http://169.254.169.254/latest", "event_types": ["BookCreated"], "secret": "0123456789abcdef" }),
+    )
+    .await
+    .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("private or link-local"),
+        "{}",
+        response.error()
+    );
+    let response = register(
+        json!({ "url": "https://example.com", "event_types": ["BookCreated"], "secret": "short" }),
+    )
+    .await
+    .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("between 16 and 128"),
+        "{}",
+        response.error()
+    );
+
+    h.delete(&path).await.expect(StatusCode::UNAUTHORIZED);
+    let delete = || {
+        h.request(Method::DELETE, &path)
+            .header("authorization", &writer)
+            .send()
+    };
+    delete().await.expect(StatusCode::NO_CONTENT);
+    read(&path).await.expect(StatusCode::NOT_FOUND);
+    delete().await.expect(StatusCode::NOT_FOUND);
+    read(&format!("{}/deliveries", path))
+        .await
+        .expect(StatusCode::NOT_FOUND);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_admin_api_key_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let admin = h.api_key(&["admin"]).await;
+    let reader = h.api_key(&["books:read"]).await;
+    let as_admin =
+        |method: Method, path: &str| h.request(method, path).header("authorization", &admin);
+
+    h.get("/admin/api-keys")
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+    let response = h
+        .request(Method::GET, "/admin/api-keys")
+        .header("authorization", &reader)
+        .send()
+        .await
+        .expect(StatusCode::FORBIDDEN);
+    assert!(response.error().contains("admin"), "{}", response.error());
+
+    let issued = as_admin(Method::POST, "/admin/api-keys")
+        .json(json!({ "name": "CI", "owner": "Platform", "scopes": ["books:write"] }))
+        .send()
+        .await
+        .expect(StatusCode::CREATED)
+        .json::<Value>();
+    assert!(issued["secret"].as_str().is_some());
+    let listed = as_admin(Method::GET, "/admin/api-keys")
+        .send()
+        .await
+        .expect(StatusCode::OK)
+        .json::<Vec<Value>>();
+    assert_eq!(listed.len(), 3);
+    assert!(listed.iter().all(|k| k.get("secret").is_none()));
+
+    let response = as_admin(Method::POST, "/admin/api-keys")
+        .json(json!({ "name": "CI", "owner": "Platform", "scopes": ["books:delete"] }))
+        .send()
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("Unknown scope: books:delete"),
+        "{}",
+        response.error()
+    );
+    as_admin(Method::POST, "/admin/api-keys")
+        .json(json!({ "name": "", "owner": "Platform", "scopes": ["admin"] }))
+        .send()
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+
+    let path = format!(
+        "/admin/api-keys/{}",
+        issued["api_key"]["pub_id"].as_str().unwrap()
+    );
+    let revoked = as_admin(Method::DELETE, &path)
+        .send()
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert!(!revoked["revoked_at"].is_null());
+    let response = as_admin(Method::DELETE, &path)
+        .send()
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    assert!(
+        response.error().contains("already revoked"),
+        "{}",
+        response.error()
+    );
+    as_admin(Method::DELETE, &format!("/admin/api-keys/{}", MISSING))
+        .send()
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    // 失効したキーでは認証できない
+    h.request(Method::GET, "/books")
+        .header(
+            "authorization",
+            &format!("ApiKey {}", issued["secret"].as_str().unwrap()),
+        )
+        .send()
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_admin_outbox_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let admin = h.api_key(&["admin"]).await;
+    let as_admin =
+        |method: Method, path: &str| h.request(method, path).header("authorization", &admin);
+    let publisher = h.publisher("Press").await;
+
+    h.get("/admin/outbox")
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+    // relay を動かしていないので pending のまま残っている
+    let pending = as_admin(Method::GET, "/admin/outbox?status=pending&limit=10")
+        .send()
+        .await
+        .expect(StatusCode::OK)
+        .json::<Vec<Value>>();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0]["aggregate_id"], json!(publisher.pub_id));
+    let path = format!("/admin/outbox/{}", pending[0]["id"]);
+    let message = as_admin(Method::GET, &path)
+        .send()
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(message["event_type"], "PublisherCreated");
+    let retried = as_admin(Method::POST, &format!("{}/retry", path))
+        .send()
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(retried["status"], "pending");
+
+    as_admin(Method::GET, "/admin/outbox?status=lost")
+        .send()
+        .await
+        .expect(StatusCode::BAD_REQUEST);
+    as_admin(Method::GET, "/admin/outbox/999")
+        .send()
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    as_admin(Method::POST, "/admin/outbox/999/retry")
+        .send()
+        .await
+        .expect(StatusCode::NOT_FOUND);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_operational_routes(#[future] harness: Harness) {
+    let h = harness.await;
+    let health = h
+        .get("/healthz")
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(health["status"], "ok");
+    // バックグラウンドのワーカーを起動していないので劣化と報告する
+    let ready = h
+        .get("/readyz")
+        .await
+        .expect(StatusCode::SERVICE_UNAVAILABLE)
+        .json::<Value>();
+    assert_eq!(ready["checks"]["database"]["status"], "ok");
+    assert_eq!(ready["checks"]["migrations"]["status"], "ok");
+
+    h.get("/publishers").await.expect(StatusCode::OK);
+    let metrics = h.get("/metrics").await.expect(StatusCode::OK).text();
+    assert!(metrics.contains("http_requests_total"));
+
+    let publisher = h.publisher("Press").await;
+    let resolved = h
+        .post(
+            "/graphql",
+            json!({
+                "query": "query ($id: UUID!) { publisher(pubId: $id) { name } }",
+                "variables": { "id": publisher.pub_id },
+            }),
+        )
+        .await
+        .expect(StatusCode::OK)
+        .json::<Value>();
+    assert_eq!(resolved["data"]["publisher"]["name"], "Press");
+
+    h.get("/openapi.json").await.expect(StatusCode::NOT_FOUND);
+    h.delete("/healthz")
+        .await
+        .expect(StatusCode::METHOD_NOT_ALLOWED);
+}
+
+// 資格情報を毎回変えても、検証に失敗すれば同じ IP として数える
+#[tokio::test]
+async fn test_rate_limit_is_keyed_on_principal() {
+    let mut config = api::HttpConfig::default();
+    config.rate_limit.read = api::config::RateLimit {
+        burst: 2,
+        per_minute: 1,
+    };
+    let h = Harness::with_config(&config).await;
+    let reader = h.api_key(&["publishers:read"]).await;
+    let with_key = |key: &str| {
+        h.request(Method::GET, "/publishers")
+            .header("authorization", key)
+            .send()
+    };
+
+    with_key("ApiKey bogus-1")
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+    with_key("ApiKey bogus-2")
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+    let response = with_key("ApiKey bogus-3")
+        .await
+        .expect(StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.error(), "Too many requests");
+
+    // 認証済みの利用者は別のバケットで数える
+    with_key(&reader).await.expect(StatusCode::OK);
+    with_key(&reader).await.expect(StatusCode::OK);
+    with_key(&reader)
+        .await
+        .expect(StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,39 @@
+use crate::health::WorkerStatus;
+use api::AppState;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+/// 購読された webhook への配信を一定間隔で行う
+pub async fn run(
+    state: Arc<AppState>,
+    interval: std::time::Duration,
+    status: Arc<WorkerStatus>,
+    shutdown: CancellationToken,
+) {
+    let _alive = status.start();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.cancelled() => break,
+        }
+        // 受信側の失敗は配信の記録に残るため、ここでは DB の失敗だけを扱う
+        match state.webhook_usecase.deliver().await {
+            Ok(report) => {
+                if report.delivered + report.retrying + report.dead > 0 {
+                    tracing::info!(
+                        delivered = report.delivered,
+                        retrying = report.retrying,
+                        dead = report.dead,
+                        "delivered webhooks"
+                    );
+                }
+                status.record(Ok(()));
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "failed to deliver webhooks");
+                status.record(Err(e.to_string()));
+            }
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use crate::error::UseCaseError;
+use api_key::vo::{ApiKeyName, Owner, Scope};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// 最後に使った日時はこの間隔でしか更新しない (リクエストごとに書き込まないため)
+const TOUCH_INTERVAL_SECS: i64 = 60;
+
+pub struct Service {
+    repo: Arc<dyn api_key::Repository>,
+}
+
+impl Service {
+    pub fn new(repo: Arc<dyn api_key::Repository>) -> Self {
+        Self { repo }
+    }
+
+    /// 秘密の値は戻り値でしか返さない
+    #[tracing::instrument(skip_all, fields(pub_id = tracing::field::Empty))]
+    pub async fn issue(&self, dto: IssueDto) -> Result<IssuedDto, UseCaseError> {
+        let name = ApiKeyName::new(dto.name)?;
+        let owner = Owner::new(dto.owner)?;
+        let scopes = dto
+            .scopes
+            .iter()
+            .map(|s| s.parse())
+            .collect::<Result<Vec<Scope>, _>>()?;
+
+        let prefix = uuid::Uuid::new_v4().simple().to_string()[..12].to_string();
+        let secret = format!(
+            "{}.{}{}",
+            prefix,
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let key = api_key::ApiKey::new(
+            uuid::Uuid::now_v7(),
+            name,
+            owner,
+            scopes,
+            prefix,
+            hash(&secret),
+            dto.expires_at,
+        )?;
+        tracing::Span::current().record("pub_id", tracing::field::display(key.pub_id()));
+
+        let created = self
+            .repo
+            .create(key)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(IssuedDto {
+            api_key: created.into(),
+            secret,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all(&self) -> Result<Vec<ResponseDto>, UseCaseError> {
+        let keys = self
+            .repo
+            .find_all()
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(keys.into_iter().map(ResponseDto::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn revoke(&self, pub_id: uuid::Uuid) -> Result<ResponseDto, UseCaseError> {
+        let mut key = self
+            .repo
+            .find_by_pub_id(pub_id)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .ok_or(UseCaseError::NotFound(format!(
+                "API key with pub_id = {} not found",
+                pub_id
+            )))?;
+
+        key.revoke()
+            .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
+
+        let updated = self
+            .repo
+            .update(key)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(updated.into())
+    }
+
+    /// `{prefix}.{secret}` 形式のキーを検証し、持っているスコープを返す
+    #[tracing::instrument(skip_all, fields(pub_id = tracing::field::Empty))]
+    pub async fn authenticate(&self, secret: &str) -> Result<PrincipalDto, UseCaseError> {
+        let invalid = || UseCaseError::Unauthorized("Invalid API key".to_string());
+        let (prefix, _) = secret.split_once('.').ok_or_else(invalid)?;
+        let mut key = self
+            .repo
+            .find_by_prefix(prefix)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .ok_or_else(invalid)?;
+        let now = chrono::Utc::now();
+        if !constant_time_eq(hash(secret).as_bytes(), key.key_hash().as_bytes())
+            || !key.is_active(now)
+        {
+            return Err(invalid());
+        }
+        tracing::Span::current().record("pub_id", tracing::field::display(key.pub_id()));
+
+        let stale = key
+            .last_used_at()
+            .is_none_or(|at| now - at >= chrono::Duration::seconds(TOUCH_INTERVAL_SECS));
+        if stale {
+            key.touch(now);
+            // 記録できなくても認証自体は通す
+            if let Err(e) = self.repo.touch(key.id(), now).await {
+                tracing::warn!(error = %e, "failed to record API key usage");
+            }
+        }
+        Ok(key.into())
+    }
+}
+
+fn hash(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = ApiKeyIssueDto)]
+pub struct IssueDto {
+    pub name: String,
+    /// キーを管理する担当者やチーム
+    pub owner: String,
+    #[schema(example = json!(["books:read", "books:write"]))]
+    pub scopes: Vec<String>,
+    /// 省略すると無期限
+    #[schema(value_type = Option<String>, example = "2027-01-01T00:00:00Z")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = ApiKeyIssuedDto)]
+pub struct IssuedDto {
+    pub api_key: ResponseDto,
+    /// `Authorization: ApiKey {secret}` で使う。再表示はできない
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = ApiKeyResponseDto)]
+pub struct ResponseDto {
+    pub pub_id: uuid::Uuid,
+    pub name: String,
+    pub owner: String,
+    pub scopes: Vec<String>,
+    /// キーの先頭部分。どのキーかを見分けるために使う
+    pub prefix: String,
+    #[schema(value_type = String, example = "2024-01-01T00:00:00Z")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[schema(value_type = Option<String>, example = "2024-01-01T00:00:00Z")]
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[schema(value_type = Option<String>, example = "2024-01-01T00:00:00Z")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[schema(value_type = Option<String>, example = "2024-01-01T00:00:00Z")]
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<api_key::ApiKey> for ResponseDto {
+    fn from(k: api_key::ApiKey) -> Self {
+        Self {
+            pub_id: k.pub_id(),
+            name: k.name(),
+            owner: k.owner(),
+            scopes: k.scopes().iter().map(|s| s.to_string()).collect(),
+            prefix: k.prefix().to_string(),
+            created_at: k.created_at(),
+            last_used_at: k.last_used_at(),
+            expires_at: k.expires_at(),
+            revoked_at: k.revoked_at(),
+        }
+    }
+}
+
+/// 認証できた API キー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrincipalDto {
+    pub pub_id: uuid::Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+}
+
+impl From<api_key::ApiKey> for PrincipalDto {
+    fn from(k: api_key::ApiKey) -> Self {
+        Self {
+            pub_id: k.pub_id(),
+            name: k.name(),
+            scopes: k.scopes().iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rstest::*;
+    use std::sync::Mutex;
+
+    struct FakeRepository {
+        store: Arc<Mutex<Vec<api_key::ApiKey>>>,
+    }
+
+    impl FakeRepository {
+        fn new() -> Self {
+            Self {
+                store: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl api_key::Repository for FakeRepository {
+        async fn find_all(&self) -> anyhow::Result<Vec<api_key::ApiKey>> {
+            Ok(self.store.lock().unwrap().clone())
+        }
+        async fn find_by_pub_id(
+            &self,
+            pub_id: uuid::Uuid,
+        ) -> anyhow::Result<Option<api_key::ApiKey>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|k| k.pub_id() == pub_id)
+                .cloned())
+        }
+        async fn find_by_prefix(&self, prefix: &str) -> anyhow::Result<Option<api_key::ApiKey>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|k| k.prefix() == prefix)
+                .cloned())
+        }
+        async fn create(&self, item: api_key::ApiKey) -> anyhow::Result<api_key::ApiKey> {
+            self.store.lock().unwrap().push(item.clone());
+            Ok(item)
+        }
+        async fn update(&self, item: api_key::ApiKey) -> anyhow::Result<api_key::ApiKey> {
+            let mut store = self.store.lock().unwrap();
+            let existing = store
+                .iter_mut()
+                .find(|k| k.pub_id() == item.pub_id())
+                .ok_or(anyhow::anyhow!("not found"))?;
+            *existing = item.clone();
+            Ok(item)
+        }
+        async fn touch(&self, id: i32, at: chrono::DateTime<chrono::Utc>) -> anyhow::Result<()> {
+            if let Some(key) = self
+                .store
+                .lock()
+                .unwrap()
+                .iter_mut()
+                .find(|k| k.id() == id && !k.is_revoked())
+            {
+                key.touch(at);
+            }
+            Ok(())
+        }
+    }
+
+    #[fixture]
+    fn service() -> Service {
+        Service::new(Arc::new(FakeRepository::new()))
+    }
+
+    fn issue_dto(scopes: &[&str]) -> IssueDto {
+        IssueDto {
+            name: "POS".to_string(),
+            owner: "Store Ops".to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            expires_at: None,
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_issue_and_authenticate(service: Service) {
+        let issued = service
+            .issue(issue_dto(&["books:write", "books:read", "books:read"]))
+            .await
+            .expect("Failed to issue");
+        assert!(
+            issued
+                .secret
+                .starts_with(&format!("{}.", issued.api_key.prefix))
+        );
+        assert_eq!(issued.api_key.scopes, ["books:read", "books:write"]);
+        assert!(issued.api_key.last_used_at.is_none());
+
+        let principal = service
+            .authenticate(&issued.secret)
+            .await
+            .expect("Failed to authenticate");
+        assert_eq!(principal.pub_id, issued.api_key.pub_id);
+        assert_eq!(principal.scopes, ["books:read", "books:write"]);
+
+        let listed = service.get_all().await.unwrap();
+        assert!(listed[0].last_used_at.is_some());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_authenticate_rejects_wrong_and_revoked_keys(service: Service) {
+        let issued = service.issue(issue_dto(&["admin"])).await.unwrap();
+
+        for secret in [
+            "no-separator".to_string(),
+            format!("{}.wrong", issued.api_key.prefix),
+            "unknownprefx.secret".to_string(),
+        ] {
+            assert!(matches!(
+                service.authenticate(&secret).await,
+                Err(UseCaseError::Unauthorized(_))
+            ));
+        }
+
+        service.revoke(issued.api_key.pub_id).await.unwrap();
+        assert!(matches!(
+            service.authenticate(&issued.secret).await,
+            Err(UseCaseError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            service.revoke(issued.api_key.pub_id).await,
+            Err(UseCaseError::DomainRuleViolation(_))
+        ));
+    }
+
+    #[rstest]
+    #[case(&[], "At least one scope is required.")]
+    #[case(&["books:delete"], "Unknown scope: books:delete")]
+    #[tokio::test]
+    async fn test_issue_validates_scopes(
+        service: Service,
+        #[case] scopes: &[&str],
+        #[case] message: &str,
+    ) {
+        let err = service.issue(issue_dto(scopes)).await.unwrap_err();
+        assert!(err.to_string().contains(message), "{}", err);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_expired_key_is_rejected(service: Service) {
+        let mut dto = issue_dto(&["books:read"]);
+        dto.expires_at = Some(chrono::Utc::now() - chrono::Duration::minutes(1));
+        assert!(service.issue(dto).await.is_err());
+
+        let issued = service.issue(issue_dto(&["books:read"])).await.unwrap();
+        // 期限切れの状態を直接作る
+        {
+            let repo = &service.repo;
+            let key = repo
+                .find_by_pub_id(issued.api_key.pub_id)
+                .await
+                .unwrap()
+                .unwrap();
+            let expired = api_key::ApiKey::reconstruct(
+                key.id(),
+                key.pub_id(),
+                ApiKeyName::new(key.name()).unwrap(),
+                Owner::new(key.owner()).unwrap(),
+                key.scopes().to_vec(),
+                key.prefix().to_string(),
+                key.key_hash().to_string(),
+                key.created_at(),
+                None,
+                Some(chrono::Utc::now() - chrono::Duration::seconds(1)),
+                None,
+            );
+            repo.update(expired).await.unwrap();
+        }
+        assert!(matches!(
+            service.authenticate(&issued.secret).await,
+            Err(UseCaseError::Unauthorized(_))
+        ));
+    }
+}
@@ -1,12 +1,29 @@
+use crate::domain_event::{Dispatcher, DomainEvent};
 use crate::error::UseCaseError;
+use crate::metrics;
+use change_event::vo::{Action, ResourceType};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use utoipa::ToSchema;
 
+mod import;
+mod search;
+pub use import::{ImportLineErrorDto, ImportReportDto};
+pub use search::{HighlightDto, MAX_PER_PAGE, SearchHitDto, SearchResponseDto};
+
+/// 1 リクエストで受け付ける一括操作の上限
+pub const MAX_BATCH_SIZE: usize = 500;
+/// エクスポート時に 1 度に読み込む件数
+const EXPORT_PAGE_SIZE: u64 = 500;
+
 pub struct Service {
     repo: Arc<dyn book::Repository>,
     publisher_repo: Arc<dyn publisher::Repository>,
     shop_repo: Arc<dyn shop::Repository>,
+    events: Arc<crate::change_event::Service>,
+    dispatcher: Arc<Dispatcher>,
 }
 
 impl Service {
@@ -14,18 +31,38 @@ impl Service {
         repo: Arc<dyn book::Repository>,
         publisher_repo: Arc<dyn publisher::Repository>,
         shop_repo: Arc<dyn shop::Repository>,
+        events: Arc<crate::change_event::Service>,
+        dispatcher: Arc<Dispatcher>,
     ) -> Self {
         Self {
             repo,
             publisher_repo,
             shop_repo,
+            events,
+            dispatcher,
         }
     }
 
-    pub async fn get_all(&self) -> Result<Vec<ResponseDto>, UseCaseError> {
+    /// 保存に成功した変更をイベントとして記録し、ドメインイベントを配信してレスポンスに変換する
+    async fn saved(
+        &self,
+        action: Action,
+        book: book::Book,
+        domain_events: Vec<DomainEvent>,
+    ) -> ResponseDto {
+        let response = ResponseDto::from(book);
+        self.events
+            .record(ResourceType::Book, action, response.pub_id, &response)
+            .await;
+        self.dispatcher.dispatch(domain_events).await;
+        response
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all(&self, include_deleted: bool) -> Result<Vec<ResponseDto>, UseCaseError> {
         let books = self
             .repo
-            .find_all()
+            .find_all(include_deleted)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
 
@@ -33,10 +70,29 @@ impl Service {
         Ok(response_dtos)
     }
 
-    pub async fn get(&self, pub_id: uuid::Uuid) -> Result<ResponseDto, UseCaseError> {
+    /// 全件をメモリに載せずに 1 件ずつ返す (エクスポート用)
+    pub fn stream_all(
+        &self,
+        include_deleted: bool,
+    ) -> futures::stream::BoxStream<'static, Result<ResponseDto, UseCaseError>> {
+        self.repo
+            .stream_all(include_deleted, EXPORT_PAGE_SIZE)
+            .map(|book| {
+                book.map(ResponseDto::from)
+                    .map_err(|_| UseCaseError::DatabaseError)
+            })
+            .boxed()
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get(
+        &self,
+        pub_id: uuid::Uuid,
+        include_deleted: bool,
+    ) -> Result<ResponseDto, UseCaseError> {
         let book = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, include_deleted)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
@@ -47,6 +103,7 @@ impl Service {
         Ok(book.into())
     }
 
+    #[tracing::instrument(skip_all, fields(pub_id = tracing::field::Empty))]
     pub async fn create(&self, dto: CreateDto) -> Result<ResponseDto, UseCaseError> {
         let title = book::vo::BookTitle::new(dto.title)?;
         let author = book::vo::BookAuthor::new(dto.author)?;
@@ -58,7 +115,7 @@ impl Service {
 
         let publisher = self
             .publisher_repo
-            .find_by_pub_id(dto.publisher_id)
+            .find_by_pub_id(dto.publisher_id, false)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
@@ -69,7 +126,7 @@ impl Service {
         let shop = if let Some(shop_id) = dto.shop_id {
             Some(
                 self.shop_repo
-                    .find_by_pub_id(shop_id)
+                    .find_by_pub_id(shop_id, false)
                     .await
                     .map_err(|_| UseCaseError::DatabaseError)?
                     .ok_or(UseCaseError::NotFound(format!(
@@ -91,14 +148,18 @@ impl Service {
             price,
             "test player".to_string(),
         );
+        tracing::Span::current().record("pub_id", tracing::field::display(book.pub_id()));
+        let domain_events = DomainEvent::from_book(&book);
         self.repo
             .create(book.clone())
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
+        metrics::books_created(1);
 
-        Ok(book.into())
+        Ok(self.saved(Action::Created, book, domain_events).await)
     }
 
+    #[tracing::instrument(skip(self, dto))]
     pub async fn update(
         &self,
         pub_id: uuid::Uuid,
@@ -114,7 +175,7 @@ impl Service {
 
         let mut book = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, false)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound("Book not found".to_string()))?;
@@ -122,7 +183,7 @@ impl Service {
         // Resolve Publisher
         let publisher = if book.publisher().pub_id() != dto.publisher_id {
             self.publisher_repo
-                .find_by_pub_id(dto.publisher_id)
+                .find_by_pub_id(dto.publisher_id, false)
                 .await
                 .map_err(|_| UseCaseError::DatabaseError)?
                 .ok_or(UseCaseError::NotFound(format!(
@@ -137,7 +198,7 @@ impl Service {
         let shop = if let Some(shop_id) = dto.shop_id {
             Some(
                 self.shop_repo
-                    .find_by_pub_id(shop_id)
+                    .find_by_pub_id(shop_id, false)
                     .await
                     .map_err(|_| UseCaseError::DatabaseError)?
                     .ok_or(UseCaseError::NotFound(format!(
@@ -160,18 +221,20 @@ impl Service {
         )
         .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
 
+        let domain_events = DomainEvent::from_book(&book);
         self.repo
             .update(book.clone())
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
 
-        Ok(book.into())
+        Ok(self.saved(Action::Updated, book, domain_events).await)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn delete(&self, pub_id: uuid::Uuid) -> Result<(), UseCaseError> {
-        let book = self
+        let mut book = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, false)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
@@ -179,12 +242,68 @@ impl Service {
                 pub_id
             )))?;
 
+        book.delete("test player".to_string())
+            .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
+
+        let domain_events = DomainEvent::from_book(&book);
         self.repo
-            .delete(book)
+            .update(book.clone())
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
+        self.saved(Action::Deleted, book, domain_events).await;
         Ok(())
     }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn restore(&self, pub_id: uuid::Uuid) -> Result<ResponseDto, UseCaseError> {
+        let mut book = self
+            .repo
+            .find_by_pub_id(pub_id, true)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .ok_or(UseCaseError::NotFound(format!(
+                "Book with pub_id = {} not found",
+                pub_id
+            )))?;
+
+        book.restore("test player".to_string())
+            .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
+
+        let domain_events = DomainEvent::from_book(&book);
+        self.repo
+            .update(book.clone())
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+
+        Ok(self.saved(Action::Restored, book, domain_events).await)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_deleted(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, UseCaseError> {
+        self.repo
+            .purge_deleted(deleted_before)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
+
+    /// 複数の Book の変更履歴をまとめて読む。論理削除済みの Book の履歴も返す
+    #[tracing::instrument(skip_all, fields(count = pub_ids.len()))]
+    pub async fn get_history(
+        &self,
+        pub_ids: &[uuid::Uuid],
+    ) -> Result<Vec<HistoryDto>, UseCaseError> {
+        let history = self
+            .repo
+            .find_history(pub_ids)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(history.into_iter().map(HistoryDto::from).collect())
+    }
+
+    #[tracing::instrument(skip(self, dto))]
     pub async fn change_applied_at(
         &self,
         pub_id: uuid::Uuid,
@@ -192,7 +311,7 @@ impl Service {
     ) -> Result<ResponseDto, UseCaseError> {
         let mut book = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, false)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
@@ -200,15 +319,283 @@ impl Service {
                 pub_id
             )))?;
 
+        let action = match (book.applied_at(), dto.applied_at) {
+            (None, Some(_)) => Action::Published,
+            (Some(_), None) => Action::Unpublished,
+            _ => Action::Updated,
+        };
         book.change_applied_at(dto.applied_at, "test player".to_string())
             .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
 
+        let domain_events = DomainEvent::from_book(&book);
         self.repo
             .update(book.clone())
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
+        if action == Action::Published {
+            metrics::book_published();
+        }
 
-        Ok(book.into())
+        Ok(self.saved(action, book, domain_events).await)
+    }
+
+    #[tracing::instrument(skip_all, fields(operations = dto.operations.len()))]
+    pub async fn batch(&self, dto: BatchDto) -> Result<BatchResponseDto, UseCaseError> {
+        if dto.operations.is_empty() || dto.operations.len() > MAX_BATCH_SIZE {
+            return Err(UseCaseError::DomainRuleViolation(format!(
+                "Batch must contain between 1 and {} operations.",
+                MAX_BATCH_SIZE
+            )));
+        }
+
+        // Publisher / Shop / Book はそれぞれ 1 クエリでまとめて解決する
+        let mut publisher_ids = HashSet::new();
+        let mut shop_ids = HashSet::new();
+        let mut book_ids = HashSet::new();
+        for op in &dto.operations {
+            let (publisher_id, shop_id) = match op {
+                BatchOperation::Create { data } => (data.publisher_id, data.shop_id),
+                BatchOperation::Update { pub_id, data } => {
+                    book_ids.insert(*pub_id);
+                    (data.publisher_id, data.shop_id)
+                }
+            };
+            publisher_ids.insert(publisher_id);
+            shop_ids.extend(shop_id);
+        }
+
+        let publishers: HashMap<_, _> = self
+            .publisher_repo
+            .find_by_pub_ids(&publisher_ids.into_iter().collect::<Vec<_>>(), false)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .into_iter()
+            .map(|p| (p.pub_id(), p))
+            .collect();
+        let shops: HashMap<_, _> = self
+            .shop_repo
+            .find_by_pub_ids(&shop_ids.into_iter().collect::<Vec<_>>(), false)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .into_iter()
+            .map(|s| (s.pub_id(), s))
+            .collect();
+        let books: HashMap<_, _> = self
+            .repo
+            .find_by_pub_ids(&book_ids.into_iter().collect::<Vec<_>>())
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .into_iter()
+            .map(|b| (b.pub_id(), b))
+            .collect();
+
+        // 同じ Book への更新が複数含まれる場合は後続を失敗扱いにする
+        let mut seen = HashSet::new();
+        let prepared: Vec<Result<book::Change, UseCaseError>> = dto
+            .operations
+            .into_iter()
+            .map(|op| {
+                if let BatchOperation::Update { pub_id, .. } = &op
+                    && !seen.insert(*pub_id)
+                {
+                    return Err(UseCaseError::DomainRuleViolation(format!(
+                        "Book with pub_id = {} appears more than once in the batch.",
+                        pub_id
+                    )));
+                }
+                prepare(op, &publishers, &shops, &books)
+            })
+            .inspect(|p| {
+                if let Err(e) = p {
+                    metrics::validation_failed(e);
+                }
+            })
+            .collect();
+
+        let results = match dto.mode {
+            BatchMode::AllOrNothing => {
+                if prepared.iter().any(|p| p.is_err()) {
+                    prepared
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, p)| match p {
+                            Ok(_) => BatchItemResultDto::skipped(index),
+                            Err(e) => BatchItemResultDto::failed(index, &e),
+                        })
+                        .collect()
+                } else {
+                    let changes: Vec<book::Change> =
+                        prepared.into_iter().map(Result::unwrap).collect();
+                    let statuses: Vec<BatchItemStatus> =
+                        changes.iter().map(BatchItemStatus::from).collect();
+                    let domain_events: Vec<Vec<DomainEvent>> =
+                        changes.iter().map(domain_events_of).collect();
+                    let saved = self
+                        .repo
+                        .save_all(changes)
+                        .await
+                        .map_err(|_| UseCaseError::DatabaseError)?;
+                    metrics::books_created(
+                        statuses
+                            .iter()
+                            .filter(|s| **s == BatchItemStatus::Created)
+                            .count() as u64,
+                    );
+                    let mut results = Vec::with_capacity(saved.len());
+                    for (index, ((book, status), domain_events)) in saved
+                        .into_iter()
+                        .zip(statuses)
+                        .zip(domain_events)
+                        .enumerate()
+                    {
+                        results.push(BatchItemResultDto {
+                            index,
+                            status,
+                            book: Some(self.saved(status.action(), book, domain_events).await),
+                            error: None,
+                        });
+                    }
+                    results
+                }
+            }
+            BatchMode::BestEffort => {
+                let mut results: Vec<Option<BatchItemResultDto>> =
+                    Vec::with_capacity(prepared.len());
+                let mut pending = Vec::new();
+                let mut changes = Vec::new();
+                for (index, p) in prepared.into_iter().enumerate() {
+                    match p {
+                        Ok(change) => {
+                            pending.push((
+                                index,
+                                BatchItemStatus::from(&change),
+                                domain_events_of(&change),
+                            ));
+                            changes.push(change);
+                            results.push(None);
+                        }
+                        Err(e) => results.push(Some(BatchItemResultDto::failed(index, &e))),
+                    }
+                }
+                let saved = self
+                    .repo
+                    .save_each(changes)
+                    .await
+                    .map_err(|_| UseCaseError::DatabaseError)?;
+                for ((index, status, domain_events), book) in pending.into_iter().zip(saved) {
+                    results[index] = Some(match book {
+                        Ok(book) => {
+                            if status == BatchItemStatus::Created {
+                                metrics::books_created(1);
+                            }
+                            BatchItemResultDto {
+                                index,
+                                status,
+                                book: Some(self.saved(status.action(), book, domain_events).await),
+                                error: None,
+                            }
+                        }
+                        Err(_) => BatchItemResultDto::failed(index, &UseCaseError::DatabaseError),
+                    });
+                }
+                results.into_iter().flatten().collect()
+            }
+        };
+
+        Ok(BatchResponseDto { results })
+    }
+}
+
+/// 保存する変更から、配信するドメインイベントを作る
+fn domain_events_of(change: &book::Change) -> Vec<DomainEvent> {
+    match change {
+        book::Change::Create(book) | book::Change::Update(book) => DomainEvent::from_book(book),
+    }
+}
+
+/// 1 件ごとの結果に載せるエラーメッセージ
+fn error_message(e: &UseCaseError) -> String {
+    match e {
+        UseCaseError::NotFound(msg) | UseCaseError::DomainRuleViolation(msg) => msg.clone(),
+        e => e.to_string(),
+    }
+}
+
+/// 一括操作の 1 件を検証し、保存する変更に変換する
+fn prepare(
+    op: BatchOperation,
+    publishers: &HashMap<uuid::Uuid, publisher::Publisher>,
+    shops: &HashMap<uuid::Uuid, shop::Shop>,
+    books: &HashMap<uuid::Uuid, book::Book>,
+) -> Result<book::Change, UseCaseError> {
+    let (pub_id, dto) = match op {
+        BatchOperation::Create { data } => (None, data),
+        // 更新も作成と同じ項目を持つため、検証は CreateDto の形に揃えて行う
+        BatchOperation::Update { pub_id, data } => (
+            Some(pub_id),
+            CreateDto {
+                title: data.title,
+                author: data.author,
+                publisher_id: data.publisher_id,
+                shop_id: data.shop_id,
+                format: data.format,
+                price: data.price,
+            },
+        ),
+    };
+    let title = book::vo::BookTitle::new(dto.title)?;
+    let author = book::vo::BookAuthor::new(dto.author)?;
+    let price = book::vo::BookPrice::new(dto.price)?;
+    let format = match dto.format.as_deref() {
+        Some("EBook") => book::vo::BookFormat::EBook,
+        _ => book::vo::BookFormat::Real,
+    };
+
+    let publisher = publishers
+        .get(&dto.publisher_id)
+        .cloned()
+        .ok_or(UseCaseError::NotFound(format!(
+            "Publisher with pub_id = {} not found",
+            dto.publisher_id
+        )))?;
+    let shop = match dto.shop_id {
+        Some(shop_id) => Some(shops.get(&shop_id).cloned().ok_or(UseCaseError::NotFound(
+            format!("Shop with pub_id = {} not found", shop_id),
+        ))?),
+        None => None,
+    };
+
+    match pub_id {
+        None => Ok(book::Change::Create(book::Book::new(
+            uuid::Uuid::now_v7(),
+            title,
+            author,
+            publisher,
+            shop,
+            format,
+            price,
+            "test player".to_string(),
+        ))),
+        Some(pub_id) => {
+            let mut book = books
+                .get(&pub_id)
+                .cloned()
+                .ok_or(UseCaseError::NotFound(format!(
+                    "Book with pub_id = {} not found",
+                    pub_id
+                )))?;
+            book.update(
+                title,
+                author,
+                publisher,
+                shop,
+                format,
+                price,
+                "test player".to_string(),
+            )
+            .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
+            Ok(book::Change::Update(book))
+        }
     }
 }
 
@@ -243,6 +630,107 @@ pub struct ChangeAppliedAtDto {
     pub applied_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = BookBatchMode)]
+pub enum BatchMode {
+    /// 1 件でも失敗したら何も保存しない
+    AllOrNothing,
+    /// 成功した操作だけを保存する
+    BestEffort,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+#[schema(as = BookBatchOperation)]
+pub enum BatchOperation {
+    Create { data: CreateDto },
+    Update { pub_id: uuid::Uuid, data: UpdateDto },
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = BookBatchDto)]
+pub struct BatchDto {
+    pub mode: BatchMode,
+    pub operations: Vec<BatchOperation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = BookBatchItemStatus)]
+pub enum BatchItemStatus {
+    Created,
+    Updated,
+    Failed,
+    /// all_or_nothing で他の操作が失敗したため保存されなかった
+    Skipped,
+}
+
+impl BatchItemStatus {
+    /// 保存した操作に対応するイベント
+    fn action(&self) -> Action {
+        match self {
+            BatchItemStatus::Created => Action::Created,
+            _ => Action::Updated,
+        }
+    }
+}
+
+impl From<&book::Change> for BatchItemStatus {
+    fn from(change: &book::Change) -> Self {
+        match change {
+            book::Change::Create(_) => BatchItemStatus::Created,
+            book::Change::Update(_) => BatchItemStatus::Updated,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = BookBatchItemResultDto)]
+pub struct BatchItemResultDto {
+    pub index: usize,
+    pub status: BatchItemStatus,
+    pub book: Option<ResponseDto>,
+    pub error: Option<String>,
+}
+
+impl BatchItemResultDto {
+    fn failed(index: usize, e: &UseCaseError) -> Self {
+        Self {
+            index,
+            status: BatchItemStatus::Failed,
+            book: None,
+            error: Some(error_message(e)),
+        }
+    }
+
+    fn skipped(index: usize) -> Self {
+        Self {
+            index,
+            status: BatchItemStatus::Skipped,
+            book: None,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = BookBatchResponseDto)]
+pub struct BatchResponseDto {
+    pub results: Vec<BatchItemResultDto>,
+}
+
+impl BatchResponseDto {
+    pub fn all_succeeded(&self) -> bool {
+        self.results.iter().all(|r| {
+            matches!(
+                r.status,
+                BatchItemStatus::Created | BatchItemStatus::Updated
+            )
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[schema(as = BookResponseDto)]
 pub struct ResponseDto {
@@ -256,6 +744,8 @@ pub struct ResponseDto {
     #[schema(value_type = String, example = "Real")]
     pub format: String,
     pub price: i32,
+    #[schema(value_type = Option<String>, example = "2024-01-01T00:00:00Z")]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl From<book::Book> for ResponseDto {
@@ -275,6 +765,38 @@ impl From<book::Book> for ResponseDto {
             applied_at: book.applied_at(),
             format: book.format().to_string(),
             price: book.price(),
+            deleted_at: book.deleted_at(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryDto {
+    pub pub_id: uuid::Uuid,
+    pub operation: String,
+    pub operation_at: chrono::DateTime<chrono::Utc>,
+    pub title: String,
+    pub author: String,
+    pub format: String,
+    pub price: i32,
+    pub applied_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub updated_by: String,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<book::History> for HistoryDto {
+    fn from(history: book::History) -> Self {
+        Self {
+            pub_id: history.pub_id,
+            operation: history.operation,
+            operation_at: history.operation_at,
+            title: history.title,
+            author: history.author,
+            format: history.format,
+            price: history.price,
+            applied_at: history.applied_at,
+            updated_by: history.updated_by,
+            deleted_at: history.deleted_at,
         }
     }
 }
@@ -302,26 +824,49 @@ mod tests {
 
     struct FakeRepository {
         store: Arc<Mutex<Vec<book::Book>>>,
+        /// 保存できる件数。超える save_all は失敗する
+        capacity: Option<usize>,
     }
 
     impl FakeRepository {
         fn new() -> Self {
             Self {
                 store: Arc::new(Mutex::new(Vec::new())),
+                capacity: None,
             }
         }
     }
 
     #[async_trait]
     impl book::Repository for FakeRepository {
-        async fn find_all(&self) -> anyhow::Result<Vec<book::Book>> {
+        async fn find_all(&self, include_deleted: bool) -> anyhow::Result<Vec<book::Book>> {
+            let store = self.store.lock().unwrap();
+            Ok(store
+                .iter()
+                .filter(|b| include_deleted || !b.is_deleted())
+                .cloned()
+                .collect())
+        }
+
+        async fn find_by_pub_id(
+            &self,
+            pub_id: uuid::Uuid,
+            include_deleted: bool,
+        ) -> anyhow::Result<Option<book::Book>> {
             let store = self.store.lock().unwrap();
-            Ok(store.clone())
+            Ok(store
+                .iter()
+                .find(|b| b.pub_id() == pub_id && (include_deleted || !b.is_deleted()))
+                .cloned())
         }
 
-        async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<book::Book>> {
+        async fn find_by_pub_ids(&self, pub_ids: &[uuid::Uuid]) -> anyhow::Result<Vec<book::Book>> {
             let store = self.store.lock().unwrap();
-            Ok(store.iter().find(|b| b.pub_id() == pub_id).cloned())
+            Ok(store
+                .iter()
+                .filter(|b| pub_ids.contains(&b.pub_id()) && !b.is_deleted())
+                .cloned()
+                .collect())
         }
 
         async fn create(&self, item: book::Book) -> anyhow::Result<book::Book> {
@@ -342,13 +887,17 @@ mod tests {
                 item.updated_at(),
                 item.created_by(),
                 item.updated_by(),
+                item.deleted_at(),
+                item.deleted_by(),
             );
 
             store.push(new_book.clone());
             Ok(new_book)
         }
 
-        async fn update(&self, item: book::Book) -> anyhow::Result<book::Book> {
+        async fn update(&self, mut item: book::Book) -> anyhow::Result<book::Book> {
+            // SQL の実装と同じく、保存したイベントは集約から取り除く
+            item.take_events();
             let mut store = self.store.lock().unwrap();
             if let Some(index) = store.iter().position(|b| b.id() == item.id()) {
                 store[index] = item.clone();
@@ -358,10 +907,93 @@ mod tests {
             }
         }
 
-        async fn delete(&self, item: book::Book) -> anyhow::Result<()> {
+        fn stream_all(
+            &self,
+            include_deleted: bool,
+            _page_size: u64,
+        ) -> futures::stream::BoxStream<'static, anyhow::Result<book::Book>> {
+            let books: Vec<_> = self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|b| include_deleted || !b.is_deleted())
+                .cloned()
+                .map(Ok)
+                .collect();
+            futures::stream::iter(books).boxed()
+        }
+
+        async fn search(&self, query: &book::SearchQuery) -> anyhow::Result<book::SearchPage> {
+            let store = self.store.lock().unwrap();
+            let hits: Vec<_> = store
+                .iter()
+                .filter(|b| !b.is_deleted())
+                .filter(|b| {
+                    let text = format!(
+                        "{} {} {} {}",
+                        b.title(),
+                        b.author(),
+                        b.publisher().name(),
+                        b.shop().map(|s| s.name()).unwrap_or_default()
+                    )
+                    .to_lowercase();
+                    query.terms.iter().all(|t| text.contains(&t.to_lowercase()))
+                })
+                .cloned()
+                .collect();
+            Ok(book::SearchPage {
+                total: hits.len() as u64,
+                books: hits
+                    .into_iter()
+                    .skip(query.offset as usize)
+                    .take(query.limit as usize)
+                    .collect(),
+            })
+        }
+
+        async fn find_history(
+            &self,
+            _pub_ids: &[uuid::Uuid],
+        ) -> anyhow::Result<Vec<book::History>> {
+            Ok(Vec::new())
+        }
+
+        async fn save_all(&self, changes: Vec<book::Change>) -> anyhow::Result<Vec<book::Book>> {
+            if let Some(capacity) = self.capacity
+                && self.store.lock().unwrap().len() + changes.len() > capacity
+            {
+                anyhow::bail!("Store is full");
+            }
+            let mut saved = Vec::with_capacity(changes.len());
+            for change in changes {
+                saved.push(match change {
+                    book::Change::Create(b) => book::Repository::create(self, b).await?,
+                    book::Change::Update(b) => book::Repository::update(self, b).await?,
+                });
+            }
+            Ok(saved)
+        }
+
+        async fn save_each(
+            &self,
+            changes: Vec<book::Change>,
+        ) -> anyhow::Result<Vec<anyhow::Result<book::Book>>> {
+            let mut saved = Vec::with_capacity(changes.len());
+            for change in changes {
+                saved.push(self.save_all(vec![change]).await.map(|mut b| b.remove(0)));
+            }
+            Ok(saved)
+        }
+
+        async fn purge_deleted(
+            &self,
+            deleted_before: chrono::DateTime<chrono::Utc>,
+        ) -> anyhow::Result<u64> {
             let mut store = self.store.lock().unwrap();
-            store.retain(|b| b.pub_id() != item.pub_id());
-            Ok(())
+            let before = store.len();
+            store.retain(|b| b.deleted_at().is_none_or(|at| at >= deleted_before));
+            Ok((before - store.len()) as u64)
         }
     }
 
@@ -382,21 +1014,59 @@ mod tests {
 
     #[async_trait]
     impl publisher::Repository for FakePublisherRepository {
-        async fn find_all(&self) -> anyhow::Result<Vec<publisher::Publisher>> {
-            Ok(self.store.lock().unwrap().clone())
+        async fn find_all(
+            &self,
+            include_deleted: bool,
+        ) -> anyhow::Result<Vec<publisher::Publisher>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| include_deleted || !p.is_deleted())
+                .cloned()
+                .collect())
         }
         async fn find_by_pub_id(
             &self,
             pub_id: uuid::Uuid,
+            include_deleted: bool,
         ) -> anyhow::Result<Option<publisher::Publisher>> {
             Ok(self
                 .store
                 .lock()
                 .unwrap()
                 .iter()
-                .find(|p| p.pub_id() == pub_id)
+                .find(|p| p.pub_id() == pub_id && (include_deleted || !p.is_deleted()))
                 .cloned())
         }
+        async fn find_by_pub_ids(
+            &self,
+            pub_ids: &[uuid::Uuid],
+            include_deleted: bool,
+        ) -> anyhow::Result<Vec<publisher::Publisher>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| pub_ids.contains(&p.pub_id()) && (include_deleted || !p.is_deleted()))
+                .cloned()
+                .collect())
+        }
+        async fn find_by_names(
+            &self,
+            names: &[String],
+        ) -> anyhow::Result<Vec<publisher::Publisher>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| names.contains(&p.name()) && !p.is_deleted())
+                .cloned()
+                .collect())
+        }
         async fn create(&self, item: publisher::Publisher) -> anyhow::Result<publisher::Publisher> {
             self.store.lock().unwrap().push(item.clone());
             Ok(item)
@@ -407,7 +1077,13 @@ mod tests {
         ) -> anyhow::Result<publisher::Publisher> {
             panic!("Not implemented")
         }
-        async fn delete(&self, _item: publisher::Publisher) -> anyhow::Result<()> {
+        async fn has_books(&self, _id: i32) -> anyhow::Result<bool> {
+            panic!("Not implemented")
+        }
+        async fn purge_deleted(
+            &self,
+            _deleted_before: chrono::DateTime<chrono::Utc>,
+        ) -> anyhow::Result<u64> {
             panic!("Not implemented")
         }
     }
@@ -429,18 +1105,53 @@ mod tests {
 
     #[async_trait]
     impl shop::Repository for FakeShopRepository {
-        async fn find_all(&self) -> anyhow::Result<Vec<shop::Shop>> {
-            Ok(self.store.lock().unwrap().clone())
+        async fn find_all(&self, include_deleted: bool) -> anyhow::Result<Vec<shop::Shop>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| include_deleted || !s.is_deleted())
+                .cloned()
+                .collect())
         }
-        async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<shop::Shop>> {
+        async fn find_by_pub_id(
+            &self,
+            pub_id: uuid::Uuid,
+            include_deleted: bool,
+        ) -> anyhow::Result<Option<shop::Shop>> {
             Ok(self
                 .store
                 .lock()
                 .unwrap()
                 .iter()
-                .find(|s| s.pub_id() == pub_id)
+                .find(|s| s.pub_id() == pub_id && (include_deleted || !s.is_deleted()))
                 .cloned())
         }
+        async fn find_by_pub_ids(
+            &self,
+            pub_ids: &[uuid::Uuid],
+            include_deleted: bool,
+        ) -> anyhow::Result<Vec<shop::Shop>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| pub_ids.contains(&s.pub_id()) && (include_deleted || !s.is_deleted()))
+                .cloned()
+                .collect())
+        }
+        async fn find_by_names(&self, names: &[String]) -> anyhow::Result<Vec<shop::Shop>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| names.contains(&s.name()) && !s.is_deleted())
+                .cloned()
+                .collect())
+        }
         async fn create(&self, item: shop::Shop) -> anyhow::Result<shop::Shop> {
             self.store.lock().unwrap().push(item.clone());
             Ok(item)
@@ -448,7 +1159,13 @@ mod tests {
         async fn update(&self, _item: shop::Shop) -> anyhow::Result<shop::Shop> {
             panic!("Not implemented")
         }
-        async fn delete(&self, _item: shop::Shop) -> anyhow::Result<()> {
+        async fn has_books(&self, _id: i32) -> anyhow::Result<bool> {
+            panic!("Not implemented")
+        }
+        async fn purge_deleted(
+            &self,
+            _deleted_before: chrono::DateTime<chrono::Utc>,
+        ) -> anyhow::Result<u64> {
             panic!("Not implemented")
         }
     }
@@ -463,7 +1180,13 @@ mod tests {
         let pub_repo = Arc::new(FakePublisherRepository::new());
         let shop_repo = Arc::new(FakeShopRepository::new());
         (
-            Service::new(Arc::new(repo), pub_repo.clone(), shop_repo.clone()),
+            Service::new(
+                Arc::new(repo),
+                pub_repo.clone(),
+                shop_repo.clone(),
+                crate::change_event::tests::fake().0,
+                Arc::new(Dispatcher::new()),
+            ),
             pub_repo,
             shop_repo,
         )
@@ -519,7 +1242,7 @@ mod tests {
 
         // Get
         let fetched = service
-            .get(created.pub_id)
+            .get(created.pub_id, false)
             .await
             .expect("Failed to get book");
         assert_eq!(fetched.pub_id, created.pub_id);
@@ -549,7 +1272,7 @@ mod tests {
         };
         service.create(dto).await.expect("Failed to create");
 
-        let all = service.get_all().await.expect("Failed to get all");
+        let all = service.get_all(false).await.expect("Failed to get all");
         assert_eq!(all.len(), 1);
     }
 
@@ -580,6 +1303,412 @@ mod tests {
             .delete(created.pub_id)
             .await
             .expect("Failed to delete");
-        assert!(service.get(created.pub_id).await.is_err());
+        assert!(service.get(created.pub_id, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_change_events_are_recorded() {
+        let pub_repo = Arc::new(FakePublisherRepository::new());
+        let (events, event_repo) = crate::change_event::tests::fake();
+        let service = Service::new(
+            Arc::new(FakeRepository::new()),
+            pub_repo.clone(),
+            Arc::new(FakeShopRepository::new()),
+            events,
+            Arc::new(Dispatcher::new()),
+        );
+        let pub_id = uuid::Uuid::new_v4();
+        pub_repo.add(create_dummy_publisher(pub_id));
+
+        let created = service
+            .create(CreateDto {
+                title: "Book".to_string(),
+                author: "Author".to_string(),
+                publisher_id: pub_id,
+                shop_id: None,
+                format: None,
+                price: 100,
+            })
+            .await
+            .expect("Failed to create book");
+        for applied_at in [Some(chrono::Utc::now()), None] {
+            service
+                .change_applied_at(created.pub_id, ChangeAppliedAtDto { applied_at })
+                .await
+                .expect("Failed to change applied_at");
+        }
+        service
+            .delete(created.pub_id)
+            .await
+            .expect("Failed to delete");
+        // 失敗した操作は記録しない
+        assert!(service.delete(created.pub_id).await.is_err());
+
+        assert_eq!(
+            event_repo.event_types(),
+            [
+                "book.created",
+                "book.published",
+                "book.unpublished",
+                "book.deleted"
+            ]
+        );
+        let stored = event_repo.store.lock().unwrap();
+        assert!(stored.iter().all(|e| e.resource_id() == created.pub_id));
+    }
+
+    #[tokio::test]
+    async fn test_domain_events_are_dispatched_after_save() {
+        let pub_repo = Arc::new(FakePublisherRepository::new());
+        let (dispatcher, handler) = crate::domain_event::tests::recording();
+        let service = Service::new(
+            Arc::new(FakeRepository::new()),
+            pub_repo.clone(),
+            Arc::new(FakeShopRepository::new()),
+            crate::change_event::tests::fake().0,
+            dispatcher,
+        );
+        let pub_id = uuid::Uuid::new_v4();
+        pub_repo.add(create_dummy_publisher(pub_id));
+
+        let created = service
+            .create(CreateDto {
+                title: "Book".to_string(),
+                author: "Author".to_string(),
+                publisher_id: pub_id,
+                shop_id: None,
+                format: None,
+                price: 100,
+            })
+            .await
+            .expect("Failed to create book");
+        let update = |price| UpdateDto {
+            title: "Book".to_string(),
+            author: "Author".to_string(),
+            publisher_id: pub_id,
+            shop_id: None,
+            format: None,
+            price,
+        };
+        service
+            .update(created.pub_id, update(200))
+            .await
+            .expect("Failed to update book");
+        // 何も変わらない更新はイベントを記録しない
+        service
+            .update(created.pub_id, update(200))
+            .await
+            .expect("Failed to update book");
+        service
+            .change_applied_at(
+                created.pub_id,
+                ChangeAppliedAtDto {
+                    applied_at: Some(chrono::Utc::now()),
+                },
+            )
+            .await
+            .expect("Failed to change applied_at");
+        // 適用済みの Book は更新できず、イベントも配信されない
+        assert!(service.update(created.pub_id, update(300)).await.is_err());
+
+        assert_eq!(
+            handler.names(),
+            ["BookCreated", "BookPriceChanged", "BookApplied"]
+        );
+        let events = handler.events.lock().unwrap();
+        assert!(events.iter().all(|e| e.pub_id() == created.pub_id));
+        assert_eq!(
+            events[1],
+            DomainEvent::Book {
+                pub_id: created.pub_id,
+                event: book::event::Event::PriceChanged { old: 100, new: 200 },
+            }
+        );
+    }
+
+    fn create_op(title: &str, publisher_id: uuid::Uuid) -> BatchOperation {
+        BatchOperation::Create {
+            data: CreateDto {
+                title: title.to_string(),
+                author: "Author".to_string(),
+                publisher_id,
+                shop_id: None,
+                format: None,
+                price: 100,
+            },
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_batch_all_or_nothing(
+        #[future] service: (
+            Service,
+            Arc<FakePublisherRepository>,
+            Arc<FakeShopRepository>,
+        ),
+    ) {
+        let (service, pub_repo, _) = service.await;
+        let pub_id = uuid::Uuid::new_v4();
+        pub_repo.add(create_dummy_publisher(pub_id));
+
+        // 存在しない Publisher を含むので何も保存されない
+        let result = service
+            .batch(BatchDto {
+                mode: BatchMode::AllOrNothing,
+                operations: vec![
+                    create_op("Book 1", pub_id),
+                    create_op("Book 2", uuid::Uuid::new_v4()),
+                ],
+            })
+            .await
+            .expect("Failed to run batch");
+        assert!(!result.all_succeeded());
+        assert_eq!(result.results[0].status, BatchItemStatus::Skipped);
+        assert_eq!(result.results[1].status, BatchItemStatus::Failed);
+        assert!(service.get_all(false).await.unwrap().is_empty());
+
+        let result = service
+            .batch(BatchDto {
+                mode: BatchMode::AllOrNothing,
+                operations: vec![create_op("Book 1", pub_id), create_op("Book 2", pub_id)],
+            })
+            .await
+            .expect("Failed to run batch");
+        assert!(result.all_succeeded());
+        let created = result.results[0].book.as_ref().unwrap();
+
+        let result = service
+            .batch(BatchDto {
+                mode: BatchMode::AllOrNothing,
+                operations: vec![BatchOperation::Update {
+                    pub_id: created.pub_id,
+                    data: UpdateDto {
+                        title: "Book 1 Updated".to_string(),
+                        author: "Author".to_string(),
+                        publisher_id: pub_id,
+                        shop_id: None,
+                        format: None,
+                        price: 200,
+                    },
+                }],
+            })
+            .await
+            .expect("Failed to run batch");
+        assert_eq!(result.results[0].status, BatchItemStatus::Updated);
+        let fetched = service.get(created.pub_id, false).await.unwrap();
+        assert_eq!(fetched.title, "Book 1 Updated");
+        assert_eq!(service.get_all(false).await.unwrap().len(), 2);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_batch_best_effort(
+        #[future] service: (
+            Service,
+            Arc<FakePublisherRepository>,
+            Arc<FakeShopRepository>,
+        ),
+    ) {
+        let (service, pub_repo, _) = service.await;
+        let pub_id = uuid::Uuid::new_v4();
+        pub_repo.add(create_dummy_publisher(pub_id));
+
+        let result = service
+            .batch(BatchDto {
+                mode: BatchMode::BestEffort,
+                operations: vec![
+                    create_op("Book 1", pub_id),
+                    create_op(&"x".repeat(33), pub_id),
+                    BatchOperation::Update {
+                        pub_id: uuid::Uuid::new_v4(),
+                        data: UpdateDto {
+                            title: "Missing".to_string(),
+                            author: "Author".to_string(),
+                            publisher_id: pub_id,
+                            shop_id: None,
+                            format: None,
+                            price: 100,
+                        },
+                    },
+                ],
+            })
+            .await
+            .expect("Failed to run batch");
+        let statuses: Vec<_> = result.results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                BatchItemStatus::Created,
+                BatchItemStatus::Failed,
+                BatchItemStatus::Failed
+            ]
+        );
+        assert!(
+            result.results[2]
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("not found")
+        );
+        assert_eq!(service.get_all(false).await.unwrap().len(), 1);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_batch_size_limit(
+        #[future] service: (
+            Service,
+            Arc<FakePublisherRepository>,
+            Arc<FakeShopRepository>,
+        ),
+    ) {
+        let (service, _, _) = service.await;
+        let pub_id = uuid::Uuid::new_v4();
+        let operations = (0..=MAX_BATCH_SIZE)
+            .map(|i| create_op(&format!("Book {}", i), pub_id))
+            .collect();
+
+        let result = service
+            .batch(BatchDto {
+                mode: BatchMode::BestEffort,
+                operations,
+            })
+            .await;
+        assert!(matches!(result, Err(UseCaseError::DomainRuleViolation(_))));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_import(
+        #[future] service: (
+            Service,
+            Arc<FakePublisherRepository>,
+            Arc<FakeShopRepository>,
+        ),
+    ) {
+        let (service, pub_repo, shop_repo) = service.await;
+        let pub_id = uuid::Uuid::new_v4();
+        let shop_id = uuid::Uuid::new_v4();
+        pub_repo.add(create_dummy_publisher(pub_id));
+        shop_repo.add(create_dummy_shop(shop_id));
+
+        let csv = format!(
+            "title,author,publisher,shop,format,price\n\
+             Book 1,Author,Test Publisher,,EBook,100\n\
+             Book 2,Author,{},Test Shop,,200\n\
+             Book 3,Author,Unknown,,,300\n\
+             Book 4,Author,Test Publisher,,,abc\n\
+             {},Author,{},{},,100\n",
+            pub_id,
+            "x".repeat(33),
+            pub_id,
+            shop_id
+        );
+
+        // dry_run では書き込まない
+        let report = service
+            .import(csv.as_bytes(), true)
+            .await
+            .expect("Failed to import");
+        assert_eq!(report.total_rows, 5);
+        assert_eq!(report.valid_rows, 2);
+        assert_eq!(report.imported_rows, 0);
+        let lines: Vec<_> = report.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [4, 5, 6]);
+        assert!(report.errors[0].error.contains("not found"));
+        assert!(service.get_all(false).await.unwrap().is_empty());
+
+        let report = service
+            .import(csv.as_bytes(), false)
+            .await
+            .expect("Failed to import");
+        assert_eq!(report.imported_rows, 2);
+        let books = service.get_all(false).await.unwrap();
+        assert_eq!(books.len(), 2);
+        assert!(books.iter().any(|b| b.shop.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_import_reports_where_it_stopped() {
+        let pub_repo = Arc::new(FakePublisherRepository::new());
+        let pub_id = uuid::Uuid::new_v4();
+        pub_repo.add(create_dummy_publisher(pub_id));
+        // 最初のまとまりだけ保存できる
+        let service = Service::new(
+            Arc::new(FakeRepository {
+                capacity: Some(MAX_BATCH_SIZE),
+                ..FakeRepository::new()
+            }),
+            pub_repo,
+            Arc::new(FakeShopRepository::new()),
+            crate::change_event::tests::fake().0,
+            Arc::new(Dispatcher::new()),
+        );
+        let mut csv = "title,author,publisher,shop,format,price\n".to_string();
+        for i in 0..MAX_BATCH_SIZE + 10 {
+            csv.push_str(&format!("Book {},Author,{},,,100\n", i, pub_id));
+        }
+
+        let report = service
+            .import(csv.as_bytes(), false)
+            .await
+            .expect("Failed to import");
+        assert_eq!(report.imported_rows, MAX_BATCH_SIZE as u64);
+        assert_eq!(report.errors.len(), 1);
+        // ヘッダーが 1 行目なので、次のまとまりは MAX_BATCH_SIZE + 2 行目から
+        assert_eq!(report.errors[0].line, MAX_BATCH_SIZE as u64 + 2);
+        assert!(report.errors[0].error.contains("Import stopped"));
+        assert_eq!(service.get_all(false).await.unwrap().len(), MAX_BATCH_SIZE);
+
+        // 何もコミットしていなければエラーのまま返す
+        let report = service.import(csv.as_bytes(), false).await;
+        assert!(matches!(report, Err(UseCaseError::DatabaseError)));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_search(
+        #[future] service: (
+            Service,
+            Arc<FakePublisherRepository>,
+            Arc<FakeShopRepository>,
+        ),
+    ) {
+        let (service, pub_repo, _) = service.await;
+        let pub_id = uuid::Uuid::new_v4();
+        pub_repo.add(create_dummy_publisher(pub_id));
+        for title in ["吾輩は猫である", "猫の本", "犬の本"] {
+            service
+                .create(CreateDto {
+                    title: title.to_string(),
+                    author: "夏目漱石".to_string(),
+                    publisher_id: pub_id,
+                    shop_id: None,
+                    format: None,
+                    price: 100,
+                })
+                .await
+                .expect("Failed to create book");
+        }
+
+        // 全角スペース区切りの語はすべて含む必要がある
+        let result = service
+            .search("猫\u{3000}漱石", 1, 1)
+            .await
+            .expect("Failed to search");
+        assert_eq!(result.total, 2);
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(
+            result.items[0].highlight.title,
+            "吾輩は<mark>猫</mark>である"
+        );
+        assert_eq!(result.items[0].highlight.author, "夏目<mark>漱石</mark>");
+
+        let result = service.search("猫", 2, 1).await.expect("Failed to search");
+        assert_eq!(result.items[0].book.title, "猫の本");
+
+        assert!(service.search("  ", 1, 20).await.is_err());
+        assert!(service.search("猫", 1, MAX_PER_PAGE + 1).await.is_err());
     }
 }
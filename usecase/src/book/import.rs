@@ -0,0 +1,280 @@
+use super::{
+    BatchOperation, CreateDto, MAX_BATCH_SIZE, Service, domain_events_of, error_message, prepare,
+};
+use crate::error::UseCaseError;
+use crate::metrics;
+use change_event::vo::Action;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// CSV の 1 行。publisher / shop には名前か pub_id を指定する
+#[derive(Debug, Deserialize)]
+struct Row {
+    title: String,
+    author: String,
+    publisher: String,
+    shop: Option<String>,
+    format: Option<String>,
+    price: i32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+#[schema(as = BookImportReportDto)]
+pub struct ImportReportDto {
+    pub dry_run: bool,
+    pub total_rows: u64,
+    pub valid_rows: u64,
+    pub imported_rows: u64,
+    pub errors: Vec<ImportLineErrorDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = BookImportLineErrorDto)]
+pub struct ImportLineErrorDto {
+    pub line: u64,
+    pub error: String,
+}
+
+impl Service {
+    /// CSV から Book を取り込む。
+    /// ファイル全体は読み込まず MAX_BATCH_SIZE 行ずつ処理し、不正な行は報告して読み飛ばす。
+    /// 全体では atomic ではなく、まとまりごとにコミットする。途中で続けられなくなった場合は、
+    /// 既にコミットしたまとまりがあれば止まった行を errors の最後に載せて報告を返す
+    #[tracing::instrument(skip(self, reader))]
+    pub async fn import<R>(&self, reader: R, dry_run: bool) -> Result<ImportReportDto, UseCaseError>
+    where
+        R: tokio::io::AsyncRead + Unpin + Send,
+    {
+        let mut csv = csv_async::AsyncReaderBuilder::new()
+            .flexible(true)
+            .trim(csv_async::Trim::All)
+            .create_reader(reader);
+        let headers = csv
+            .headers()
+            .await
+            .map_err(|e| UseCaseError::DomainRuleViolation(format!("Invalid CSV header: {}", e)))?
+            .clone();
+
+        let mut report = ImportReportDto {
+            dry_run,
+            ..Default::default()
+        };
+        let mut chunk: Vec<(u64, Row)> = Vec::with_capacity(MAX_BATCH_SIZE);
+        let mut last_line = 1;
+        let mut records = csv.records();
+        while let Some(record) = records.next().await {
+            report.total_rows += 1;
+            let line = match &record {
+                Ok(r) => r.position().map(|p| p.line()),
+                Err(e) => e.position().map(|p| p.line()),
+            };
+            let Some(line) = line else {
+                // 読み込み途中のまとまりは取り込まずに止める
+                let line = chunk
+                    .first()
+                    .map(|(line, _)| *line)
+                    .unwrap_or(last_line + 1);
+                let e = UseCaseError::DomainRuleViolation(format!(
+                    "Invalid CSV: {}",
+                    record.err().map(|e| e.to_string()).unwrap_or_default()
+                ));
+                return stopped(report, line, e);
+            };
+            last_line = line;
+            match record.and_then(|r| r.deserialize::<Row>(Some(&headers))) {
+                Ok(row) => chunk.push((line, row)),
+                Err(e) => report.errors.push(ImportLineErrorDto {
+                    line,
+                    error: e.to_string(),
+                }),
+            }
+
+            if chunk.len() == MAX_BATCH_SIZE {
+                let first = chunk[0].0;
+                if let Err(e) = self
+                    .import_chunk(std::mem::take(&mut chunk), &mut report)
+                    .await
+                {
+                    return stopped(report, first, e);
+                }
+            }
+        }
+        if let Some(&(first, _)) = chunk.first()
+            && let Err(e) = self.import_chunk(chunk, &mut report).await
+        {
+            return stopped(report, first, e);
+        }
+        // 解析エラーと検証エラーは検出タイミングが異なるため行番号順に揃える
+        report.errors.sort_by_key(|e| e.line);
+
+        Ok(report)
+    }
+
+    async fn import_chunk(
+        &self,
+        rows: Vec<(u64, Row)>,
+        report: &mut ImportReportDto,
+    ) -> Result<(), UseCaseError> {
+        // pub_id として解釈できない値は名前として扱う
+        let (publisher_ids, publisher_names) =
+            split_references(rows.iter().map(|(_, r)| &r.publisher));
+        let (shop_ids, shop_names) =
+            split_references(rows.iter().filter_map(|(_, r)| r.shop.as_ref()));
+
+        let mut publishers = HashMap::new();
+        let mut publishers_by_name: HashMap<String, Vec<uuid::Uuid>> = HashMap::new();
+        for p in self
+            .publisher_repo
+            .find_by_pub_ids(&publisher_ids, false)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .into_iter()
+            .chain(
+                self.publisher_repo
+                    .find_by_names(&publisher_names)
+                    .await
+                    .map_err(|_| UseCaseError::DatabaseError)?,
+            )
+        {
+            let ids = publishers_by_name.entry(p.name()).or_default();
+            if !ids.contains(&p.pub_id()) {
+                ids.push(p.pub_id());
+            }
+            publishers.insert(p.pub_id(), p);
+        }
+
+        let mut shops = HashMap::new();
+        let mut shops_by_name: HashMap<String, Vec<uuid::Uuid>> = HashMap::new();
+        for s in self
+            .shop_repo
+            .find_by_pub_ids(&shop_ids, false)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .into_iter()
+            .chain(
+                self.shop_repo
+                    .find_by_names(&shop_names)
+                    .await
+                    .map_err(|_| UseCaseError::DatabaseError)?,
+            )
+        {
+            let ids = shops_by_name.entry(s.name()).or_default();
+            if !ids.contains(&s.pub_id()) {
+                ids.push(s.pub_id());
+            }
+            shops.insert(s.pub_id(), s);
+        }
+
+        let mut changes = Vec::with_capacity(rows.len());
+        for (line, row) in rows {
+            let change = resolve(&row.publisher, &publishers_by_name, "Publisher")
+                .and_then(|publisher_id| {
+                    let shop_id = row
+                        .shop
+                        .as_deref()
+                        .map(|s| resolve(s, &shops_by_name, "Shop"))
+                        .transpose()?;
+                    Ok(CreateDto {
+                        title: row.title,
+                        author: row.author,
+                        publisher_id,
+                        shop_id,
+                        format: row.format,
+                        price: row.price,
+                    })
+                })
+                .and_then(|data| {
+                    prepare(
+                        BatchOperation::Create { data },
+                        &publishers,
+                        &shops,
+                        &HashMap::new(),
+                    )
+                });
+            match change {
+                Ok(change) => changes.push(change),
+                Err(e) => {
+                    metrics::validation_failed(&e);
+                    report.errors.push(ImportLineErrorDto {
+                        line,
+                        error: error_message(&e),
+                    })
+                }
+            }
+        }
+
+        report.valid_rows += changes.len() as u64;
+        if !report.dry_run && !changes.is_empty() {
+            let domain_events: Vec<_> = changes.iter().map(domain_events_of).collect();
+            let saved = self
+                .repo
+                .save_all(changes)
+                .await
+                .map_err(|_| UseCaseError::DatabaseError)?;
+            report.imported_rows += saved.len() as u64;
+            metrics::books_created(saved.len() as u64);
+            for (book, domain_events) in saved.into_iter().zip(domain_events) {
+                self.saved(Action::Created, book, domain_events).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// line から先を取り込めなかった時の結果。何もコミットしていなければエラーをそのまま返す
+fn stopped(
+    mut report: ImportReportDto,
+    line: u64,
+    e: UseCaseError,
+) -> Result<ImportReportDto, UseCaseError> {
+    if report.imported_rows == 0 {
+        return Err(e);
+    }
+    tracing::warn!(line, error = %e, "book import stopped after partial commit");
+    report.errors.sort_by_key(|e| e.line);
+    report.errors.push(ImportLineErrorDto {
+        line,
+        error: format!(
+            "Import stopped, this and later lines were not imported: {}",
+            error_message(&e)
+        ),
+    });
+    Ok(report)
+}
+
+fn split_references<'a>(
+    references: impl Iterator<Item = &'a String>,
+) -> (Vec<uuid::Uuid>, Vec<String>) {
+    let mut ids = Vec::new();
+    let mut names = Vec::new();
+    for r in references {
+        match uuid::Uuid::parse_str(r) {
+            Ok(id) => ids.push(id),
+            Err(_) => names.push(r.clone()),
+        }
+    }
+    (ids, names)
+}
+
+fn resolve(
+    reference: &str,
+    by_name: &HashMap<String, Vec<uuid::Uuid>>,
+    kind: &str,
+) -> Result<uuid::Uuid, UseCaseError> {
+    if let Ok(id) = uuid::Uuid::parse_str(reference) {
+        return Ok(id);
+    }
+    match by_name.get(reference).map(Vec::as_slice) {
+        Some([id]) => Ok(*id),
+        Some([_, _, ..]) => Err(UseCaseError::DomainRuleViolation(format!(
+            "{} name '{}' is ambiguous, use pub_id instead",
+            kind, reference
+        ))),
+        _ => Err(UseCaseError::NotFound(format!(
+            "{} '{}' not found",
+            kind, reference
+        ))),
+    }
+}
@@ -0,0 +1,168 @@
+use super::{ResponseDto, Service};
+use crate::error::UseCaseError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 1 ページの最大件数
+pub const MAX_PER_PAGE: u64 = 100;
+/// 検索語の最大数 (それ以降は無視する)
+const MAX_TERMS: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = BookSearchResponseDto)]
+pub struct SearchResponseDto {
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub items: Vec<SearchHitDto>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = BookSearchHitDto)]
+pub struct SearchHitDto {
+    pub book: ResponseDto,
+    pub highlight: HighlightDto,
+}
+
+/// 一致した箇所を `<mark>` で囲んだ文字列。それ以外の部分は HTML エスケープ済み
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = BookHighlightDto)]
+pub struct HighlightDto {
+    pub title: String,
+    pub author: String,
+    pub publisher: String,
+    pub shop: Option<String>,
+}
+
+impl Service {
+    #[tracing::instrument(skip(self))]
+    pub async fn search(
+        &self,
+        q: &str,
+        page: u64,
+        per_page: u64,
+    ) -> Result<SearchResponseDto, UseCaseError> {
+        // 全角スペースも区切りとして扱う
+        let mut terms: Vec<String> = Vec::new();
+        for term in q.split_whitespace() {
+            if !terms.iter().any(|t| t == term) {
+                terms.push(term.to_string());
+            }
+        }
+        terms.truncate(MAX_TERMS);
+        if terms.is_empty() {
+            return Err(UseCaseError::DomainRuleViolation(
+                "Search query must not be empty.".to_string(),
+            ));
+        }
+        if page == 0 || per_page == 0 || per_page > MAX_PER_PAGE {
+            return Err(UseCaseError::DomainRuleViolation(format!(
+                "page must be 1 or greater and per_page must be between 1 and {}.",
+                MAX_PER_PAGE
+            )));
+        }
+
+        let result = self
+            .repo
+            .search(&book::SearchQuery {
+                terms: terms.clone(),
+                offset: (page - 1) * per_page,
+                limit: per_page,
+            })
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+
+        let items = result
+            .books
+            .into_iter()
+            .map(|book| {
+                let highlight = HighlightDto {
+                    title: highlight(&book.title(), &terms),
+                    author: highlight(&book.author(), &terms),
+                    publisher: highlight(&book.publisher().name(), &terms),
+                    shop: book.shop().map(|s| highlight(&s.name(), &terms)),
+                };
+                SearchHitDto {
+                    book: book.into(),
+                    highlight,
+                }
+            })
+            .collect();
+
+        Ok(SearchResponseDto {
+            total: result.total,
+            page,
+            per_page,
+            items,
+        })
+    }
+}
+
+/// 大文字小文字を区別せず、語に一致した範囲を `<mark>` で囲む
+fn highlight(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let folded: Vec<char> = chars.iter().map(|c| fold(*c)).collect();
+    let mut marked = vec![false; chars.len()];
+    for term in terms {
+        let term: Vec<char> = term.chars().map(fold).collect();
+        if term.is_empty() || term.len() > folded.len() {
+            continue;
+        }
+        for start in 0..=folded.len() - term.len() {
+            if folded[start..start + term.len()] == term[..] {
+                marked[start..start + term.len()].fill(true);
+            }
+        }
+    }
+
+    let mut highlighted = String::with_capacity(text.len());
+    let mut open = false;
+    for (c, m) in chars.iter().zip(marked) {
+        if m != open {
+            highlighted.push_str(if m { "<mark>" } else { "</mark>" });
+            open = m;
+        }
+        match c {
+            '&' => highlighted.push_str("&amp;"),
+            '<' => highlighted.push_str("&lt;"),
+            '>' => highlighted.push_str("&gt;"),
+            '"' => highlighted.push_str("&quot;"),
+            '\'' => highlighted.push_str("&#39;"),
+            c => highlighted.push(*c),
+        }
+    }
+    if open {
+        highlighted.push_str("</mark>");
+    }
+    highlighted
+}
+
+/// 文字数を変えずに小文字へ揃える
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let terms = vec!["猫".to_string(), "rust".to_string()];
+        assert_eq!(
+            highlight("吾輩は猫である", &terms),
+            "吾輩は<mark>猫</mark>である"
+        );
+        assert_eq!(
+            highlight("Rust <入門>", &terms),
+            "<mark>Rust</mark> &lt;入門&gt;"
+        );
+        assert_eq!(highlight("犬", &terms), "犬");
+    }
+
+    #[test]
+    fn test_highlight_merges_overlapping_terms() {
+        let terms = vec!["ab".to_string(), "bc".to_string()];
+        assert_eq!(highlight("abcd", &terms), "<mark>abc</mark>d");
+    }
+}
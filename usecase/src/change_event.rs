@@ -0,0 +1,384 @@
+use crate::error::UseCaseError;
+use change_event::vo::{Action, ResourceType};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+/// フィードが 1 度に読み込む件数
+const FEED_PAGE_SIZE: u64 = 100;
+/// 他のプロセスが記録したイベントも拾うため、通知が無くてもこの間隔で読み直す
+const FEED_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// カタログの変更をイベントログに記録し、購読者に流す
+pub struct Service {
+    repo: Arc<dyn change_event::Repository>,
+    retention: chrono::Duration,
+    /// 最後に記録したイベントの id。購読中のフィードを起こすために使う
+    latest: watch::Sender<i64>,
+    closing: CancellationToken,
+}
+
+impl Service {
+    /// retention より古いイベントは purge_expired で削除する
+    pub fn new(repo: Arc<dyn change_event::Repository>, retention: chrono::Duration) -> Self {
+        Self {
+            repo,
+            retention,
+            latest: watch::channel(0).0,
+            closing: CancellationToken::new(),
+        }
+    }
+
+    /// 変更の保存に成功した後に呼ぶ。記録に失敗しても変更自体は取り消さない
+    #[tracing::instrument(skip_all, fields(%resource_type, %action, %resource_id))]
+    pub(crate) async fn record<T: Serialize>(
+        &self,
+        resource_type: ResourceType,
+        action: Action,
+        resource_id: uuid::Uuid,
+        data: &T,
+    ) {
+        let data = match serde_json::to_string(data) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to serialize change event");
+                return;
+            }
+        };
+        let event = change_event::ChangeEvent::new(resource_type, action, resource_id, data);
+        match self.repo.append(event).await {
+            Ok(event) => {
+                self.latest.send_replace(event.id());
+            }
+            Err(e) => tracing::warn!(error = %e, "failed to record change event"),
+        }
+    }
+
+    /// last_event_id より後のイベントを流し、追いついたら新しいイベントを待って流し続ける。
+    /// last_event_id が無ければ購読を始めた時点以降のイベントだけを流す。
+    /// resource_types が空なら全ての種類を流す。
+    /// 記録は id の順にコミットされるので、id 順に読み進めても取りこぼさず、Last-Event-ID から再開できる
+    #[tracing::instrument(skip(self))]
+    pub async fn subscribe(
+        &self,
+        last_event_id: Option<i64>,
+        resource_types: &[String],
+    ) -> Result<futures::stream::BoxStream<'static, Result<EventDto, UseCaseError>>, UseCaseError>
+    {
+        let resource_types = resource_types
+            .iter()
+            .map(|t| t.parse())
+            .collect::<Result<Vec<ResourceType>, _>>()?;
+        let after = match last_event_id {
+            Some(id) => id,
+            None => self
+                .repo
+                .last_id()
+                .await
+                .map_err(|_| UseCaseError::DatabaseError)?,
+        };
+        let feed = Feed {
+            repo: self.repo.clone(),
+            resource_types,
+            after,
+            pending: VecDeque::new(),
+            stale: true,
+            latest: self.latest.subscribe(),
+            closing: self.closing.clone(),
+        };
+        // 読み込みに失敗したらフィードを終える (クライアントは Last-Event-ID で再開できる)
+        let stream = futures::stream::unfold(Some(feed), |feed| async move {
+            let mut feed = feed?;
+            match feed.next().await? {
+                Ok(event) => Some((Ok(event), Some(feed))),
+                Err(e) => Some((Err(e), None)),
+            }
+        });
+        Ok(stream.boxed())
+    }
+
+    /// 購読中の全てのフィードを終える (シャットダウン時に呼ぶ)
+    pub fn close(&self) {
+        self.closing.cancel();
+    }
+
+    /// 保持期間を過ぎたイベントを削除する
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_expired(&self) -> Result<u64, UseCaseError> {
+        self.repo
+            .purge_before(chrono::Utc::now() - self.retention)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
+}
+
+/// 1 つの購読の読み込み位置
+struct Feed {
+    repo: Arc<dyn change_event::Repository>,
+    resource_types: Vec<ResourceType>,
+    after: i64,
+    pending: VecDeque<change_event::ChangeEvent>,
+    /// まだ読み込んでいないイベントがあるかもしれない
+    stale: bool,
+    latest: watch::Receiver<i64>,
+    closing: CancellationToken,
+}
+
+impl Feed {
+    async fn next(&mut self) -> Option<Result<EventDto, UseCaseError>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                self.after = event.id();
+                return Some(Ok(event.into()));
+            }
+            if self.stale {
+                // 読み込み中に記録されたイベントの通知を取りこぼさないよう、先に既読にする
+                self.latest.mark_unchanged();
+                let events = match self
+                    .repo
+                    .find_after(self.after, &self.resource_types, FEED_PAGE_SIZE)
+                    .await
+                {
+                    Ok(events) => events,
+                    Err(_) => return Some(Err(UseCaseError::DatabaseError)),
+                };
+                // 1 ページ分埋まっていれば続きがある
+                self.stale = events.len() as u64 == FEED_PAGE_SIZE;
+                self.pending.extend(events);
+                continue;
+            }
+            tokio::select! {
+                _ = self.closing.cancelled() => return None,
+                changed = self.latest.changed() => {
+                    if changed.is_err() {
+                        return None;
+                    }
+                }
+                _ = tokio::time::sleep(FEED_POLL_INTERVAL) => {}
+            }
+            self.stale = true;
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = ChangeEventDto)]
+pub struct EventDto {
+    /// 記録順に増える。再接続時に Last-Event-ID として送る
+    pub id: i64,
+    #[schema(example = "book.created")]
+    pub event_type: String,
+    #[schema(example = "book")]
+    pub resource_type: String,
+    pub resource_id: uuid::Uuid,
+    #[schema(value_type = String, example = "2024-01-01T00:00:00Z")]
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
+    /// 変更後のリソース (BookResponseDto / PublisherResponseDto / ShopResponseDto)
+    #[schema(value_type = Object)]
+    pub data: serde_json::Value,
+}
+
+impl From<change_event::ChangeEvent> for EventDto {
+    fn from(event: change_event::ChangeEvent) -> Self {
+        Self {
+            id: event.id(),
+            event_type: event.event_type(),
+            resource_type: event.resource_type().to_string(),
+            resource_id: event.resource_id(),
+            occurred_at: event.occurred_at(),
+            data: serde_json::from_str(event.data()).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rstest::*;
+    use std::sync::Mutex;
+
+    /// 他のサービスのテストからも記録されたイベントを確認できるようにする
+    pub(crate) struct FakeRepository {
+        pub(crate) store: Arc<Mutex<Vec<change_event::ChangeEvent>>>,
+    }
+
+    impl FakeRepository {
+        pub(crate) fn new() -> Self {
+            Self {
+                store: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl change_event::Repository for FakeRepository {
+        async fn append(
+            &self,
+            event: change_event::ChangeEvent,
+        ) -> anyhow::Result<change_event::ChangeEvent> {
+            let mut store = self.store.lock().unwrap();
+            let event = change_event::ChangeEvent::reconstruct(
+                store.len() as i64 + 1,
+                event.resource_type(),
+                event.action(),
+                event.resource_id(),
+                event.data().to_string(),
+                event.occurred_at(),
+            );
+            store.push(event.clone());
+            Ok(event)
+        }
+
+        async fn find_after(
+            &self,
+            after: i64,
+            resource_types: &[ResourceType],
+            limit: u64,
+        ) -> anyhow::Result<Vec<change_event::ChangeEvent>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|e| e.id() > after)
+                .filter(|e| {
+                    resource_types.is_empty() || resource_types.contains(&e.resource_type())
+                })
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+
+        async fn last_id(&self) -> anyhow::Result<i64> {
+            Ok(self.store.lock().unwrap().len() as i64)
+        }
+
+        async fn purge_before(&self, before: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64> {
+            let mut store = self.store.lock().unwrap();
+            let count = store.iter().filter(|e| e.occurred_at() < before).count();
+            store.retain(|e| e.occurred_at() >= before);
+            Ok(count as u64)
+        }
+    }
+
+    impl FakeRepository {
+        /// 記録された event_type を順に返す
+        pub(crate) fn event_types(&self) -> Vec<String> {
+            self.store
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|e| e.event_type())
+                .collect()
+        }
+    }
+
+    /// 他のサービスに渡すイベントの記録先と、記録された内容を確認するためのリポジトリ
+    pub(crate) fn fake() -> (Arc<Service>, Arc<FakeRepository>) {
+        let repo = Arc::new(FakeRepository::new());
+        let service = Service::new(repo.clone(), chrono::Duration::days(7));
+        (Arc::new(service), repo)
+    }
+
+    #[fixture]
+    fn service() -> Service {
+        Service::new(Arc::new(FakeRepository::new()), chrono::Duration::days(7))
+    }
+
+    async fn record(service: &Service, resource_type: ResourceType, action: Action) {
+        service
+            .record(
+                resource_type,
+                action,
+                uuid::Uuid::now_v7(),
+                &serde_json::json!({ "name": "x" }),
+            )
+            .await;
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_subscribe_resumes_after_last_event_id(service: Service) {
+        record(&service, ResourceType::Publisher, Action::Created).await;
+        record(&service, ResourceType::Book, Action::Created).await;
+        record(&service, ResourceType::Book, Action::Published).await;
+
+        let mut feed = service.subscribe(Some(1), &[]).await.unwrap();
+        let event = feed.next().await.unwrap().unwrap();
+        assert_eq!((event.id, event.event_type.as_str()), (2, "book.created"));
+        assert_eq!(event.data["name"], "x");
+        let event = feed.next().await.unwrap().unwrap();
+        assert_eq!((event.id, event.event_type.as_str()), (3, "book.published"));
+
+        // 追いついた後は新しく記録されたイベントを流す
+        record(&service, ResourceType::Shop, Action::Deleted).await;
+        let event = tokio::time::timeout(Duration::from_secs(1), feed.next())
+            .await
+            .expect("live event should be delivered")
+            .unwrap()
+            .unwrap();
+        assert_eq!((event.id, event.event_type.as_str()), (4, "shop.deleted"));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_subscribe_without_last_event_id_starts_from_now(service: Service) {
+        record(&service, ResourceType::Book, Action::Created).await;
+
+        let mut feed = service.subscribe(None, &[]).await.unwrap();
+        record(&service, ResourceType::Book, Action::Updated).await;
+        let event = feed.next().await.unwrap().unwrap();
+        assert_eq!((event.id, event.event_type.as_str()), (2, "book.updated"));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_subscribe_filters_by_resource_type(service: Service) {
+        record(&service, ResourceType::Book, Action::Created).await;
+        record(&service, ResourceType::Publisher, Action::Updated).await;
+        record(&service, ResourceType::Shop, Action::Created).await;
+
+        let mut feed = service
+            .subscribe(Some(0), &["publisher".to_string(), "shop".to_string()])
+            .await
+            .unwrap();
+        let types: Vec<_> = [
+            feed.next().await.unwrap().unwrap(),
+            feed.next().await.unwrap().unwrap(),
+        ]
+        .into_iter()
+        .map(|e| e.event_type)
+        .collect();
+        assert_eq!(types, ["publisher.updated", "shop.created"]);
+
+        match service.subscribe(None, &["author".to_string()]).await {
+            Err(UseCaseError::ChangeEventDomainError(_)) => (),
+            _ => panic!("Expected ChangeEventDomainError"),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_close_ends_feeds(service: Service) {
+        let mut feed = service.subscribe(None, &[]).await.unwrap();
+        service.close();
+        assert!(feed.next().await.is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_purge_expired() {
+        let repo = Arc::new(FakeRepository::new());
+        let service = Service::new(repo.clone(), chrono::Duration::zero());
+        record(&service, ResourceType::Book, Action::Created).await;
+
+        assert_eq!(service.purge_expired().await.unwrap(), 1);
+        assert!(repo.event_types().is_empty());
+    }
+}
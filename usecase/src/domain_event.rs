@@ -0,0 +1,190 @@
+use async_trait::async_trait;
+use std::sync::{Arc, RwLock};
+
+/// 集約が記録したイベントに、発生元の集約を添えたもの
+#[derive(Debug, Clone, PartialEq)]
+pub enum DomainEvent {
+    Book {
+        pub_id: uuid::Uuid,
+        event: book::event::Event,
+    },
+    Publisher {
+        pub_id: uuid::Uuid,
+        event: publisher::event::Event,
+    },
+    Shop {
+        pub_id: uuid::Uuid,
+        event: shop::event::Event,
+    },
+}
+
+impl DomainEvent {
+    /// BookPriceChanged のような、集約名を含むイベント名
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::Book { event, .. } => event.name(),
+            DomainEvent::Publisher { event, .. } => event.name(),
+            DomainEvent::Shop { event, .. } => event.name(),
+        }
+    }
+
+    pub fn pub_id(&self) -> uuid::Uuid {
+        match self {
+            DomainEvent::Book { pub_id, .. }
+            | DomainEvent::Publisher { pub_id, .. }
+            | DomainEvent::Shop { pub_id, .. } => *pub_id,
+        }
+    }
+
+    /// 全ての集約のイベント名
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        book::event::Event::NAMES
+            .into_iter()
+            .chain(publisher::event::Event::NAMES)
+            .chain(shop::event::Event::NAMES)
+    }
+
+    /// 保存前の集約から、保存に成功した後に配信するイベントを作る
+    pub(crate) fn from_book(book: &book::Book) -> Vec<Self> {
+        let pub_id = book.pub_id();
+        book.events()
+            .iter()
+            .map(|event| DomainEvent::Book {
+                pub_id,
+                event: event.clone(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn from_publisher(publisher: &publisher::Publisher) -> Vec<Self> {
+        let pub_id = publisher.pub_id();
+        publisher
+            .events()
+            .iter()
+            .map(|event| DomainEvent::Publisher {
+                pub_id,
+                event: event.clone(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn from_shop(shop: &shop::Shop) -> Vec<Self> {
+        let pub_id = shop.pub_id();
+        shop.events()
+            .iter()
+            .map(|event| DomainEvent::Shop {
+                pub_id,
+                event: event.clone(),
+            })
+            .collect()
+    }
+}
+
+/// ドメインイベントを受け取る処理 (通知、検索インデックスの更新など)
+#[async_trait]
+pub trait Handler: Send + Sync {
+    async fn handle(&self, event: &DomainEvent) -> anyhow::Result<()>;
+}
+
+/// 登録された Handler に、永続化に成功した変更のイベントを配る
+#[derive(Default)]
+pub struct Dispatcher {
+    handlers: RwLock<Vec<Arc<dyn Handler>>>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, handler: Arc<dyn Handler>) {
+        self.handlers.write().unwrap().push(handler);
+    }
+
+    /// 変更は保存済みのため、Handler が失敗しても記録するだけで呼び出し元には返さない
+    pub(crate) async fn dispatch(&self, events: Vec<DomainEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        let handlers = self.handlers.read().unwrap().clone();
+        for event in &events {
+            for handler in &handlers {
+                if let Err(e) = handler.handle(event).await {
+                    tracing::warn!(error = %e, event = event.name(), "domain event handler failed");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// 受け取ったイベントを貯めておく Handler
+    #[derive(Default)]
+    pub(crate) struct RecordingHandler {
+        pub(crate) events: Mutex<Vec<DomainEvent>>,
+    }
+
+    impl RecordingHandler {
+        pub(crate) fn names(&self) -> Vec<&'static str> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|e| e.name())
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl Handler for RecordingHandler {
+        async fn handle(&self, event: &DomainEvent) -> anyhow::Result<()> {
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    struct FailingHandler;
+
+    #[async_trait]
+    impl Handler for FailingHandler {
+        async fn handle(&self, _event: &DomainEvent) -> anyhow::Result<()> {
+            Err(anyhow::anyhow!("handler failed"))
+        }
+    }
+
+    /// RecordingHandler を登録済みの Dispatcher
+    pub(crate) fn recording() -> (Arc<Dispatcher>, Arc<RecordingHandler>) {
+        let dispatcher = Arc::new(Dispatcher::new());
+        let handler = Arc::new(RecordingHandler::default());
+        dispatcher.register(handler.clone());
+        (dispatcher, handler)
+    }
+
+    #[tokio::test]
+    async fn test_failing_handler_does_not_stop_others() {
+        let dispatcher = Dispatcher::new();
+        let handler = Arc::new(RecordingHandler::default());
+        dispatcher.register(Arc::new(FailingHandler));
+        dispatcher.register(handler.clone());
+
+        let pub_id = uuid::Uuid::now_v7();
+        dispatcher
+            .dispatch(vec![
+                DomainEvent::Shop {
+                    pub_id,
+                    event: shop::event::Event::Created,
+                },
+                DomainEvent::Shop {
+                    pub_id,
+                    event: shop::event::Event::Deleted,
+                },
+            ])
+            .await;
+
+        assert_eq!(handler.names(), vec!["ShopCreated", "ShopDeleted"]);
+    }
+}
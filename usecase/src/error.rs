@@ -4,6 +4,8 @@ use thiserror::Error;
 pub enum UseCaseError {
     #[error("Entity not found")]
     NotFound(String),
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
     #[error("Internal server error")]
     InternalServerError,
     #[error("Database execution failed")]
@@ -16,4 +18,14 @@ pub enum UseCaseError {
     PublisherDomainError(#[from] publisher::DomainError),
     #[error("Domain error occurred: {0}")]
     ShopDomainError(#[from] shop::DomainError),
+    #[error("Domain error occurred: {0}")]
+    IdempotencyDomainError(#[from] idempotency::DomainError),
+    #[error("Domain error occurred: {0}")]
+    ApiKeyDomainError(#[from] api_key::DomainError),
+    #[error("Domain error occurred: {0}")]
+    ChangeEventDomainError(#[from] change_event::DomainError),
+    #[error("Domain error occurred: {0}")]
+    OutboxDomainError(#[from] outbox::DomainError),
+    #[error("Domain error occurred: {0}")]
+    WebhookDomainError(#[from] webhook::DomainError),
 }
@@ -0,0 +1,347 @@
+use crate::error::UseCaseError;
+use idempotency::vo::IdempotencyKey;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+pub struct Service {
+    repo: Arc<dyn idempotency::Repository>,
+    ttl: chrono::Duration,
+    lease: chrono::Duration,
+}
+
+/// Idempotency-Key 付きリクエストを処理してよいか
+#[derive(Debug)]
+pub enum Outcome {
+    /// 初めてのリクエスト。処理後に complete か abandon を呼ぶ
+    Proceed(Claim),
+    /// 同じリクエストの再試行。保存したレスポンスを返す
+    Replay(ResponseDto),
+    /// 同じキーで内容の違うリクエスト
+    Mismatch,
+    /// 同じキーの最初のリクエストがまだ処理中。lease を過ぎれば再試行が引き継ぐ
+    InProgress,
+}
+
+/// 処理中のキー
+#[derive(Debug)]
+pub struct Claim {
+    key: IdempotencyKey,
+    scope: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseDto {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Service {
+    /// lease は処理中のキーを押さえておく時間。リクエストのタイムアウトより長くする
+    pub fn new(
+        repo: Arc<dyn idempotency::Repository>,
+        ttl: chrono::Duration,
+        lease: chrono::Duration,
+    ) -> Self {
+        Self { repo, ttl, lease }
+    }
+
+    /// scope はキーを使い回せる範囲、request は同じリクエストかを比べるための内容
+    #[tracing::instrument(skip(self, key, request))]
+    pub async fn begin(
+        &self,
+        key: String,
+        scope: String,
+        request: &[u8],
+    ) -> Result<Outcome, UseCaseError> {
+        let key = IdempotencyKey::new(key)?;
+        let request_hash = format!("{:x}", Sha256::digest(request));
+        let record = idempotency::Record::new(
+            key.clone(),
+            scope.clone(),
+            request_hash.clone(),
+            self.ttl,
+            self.lease,
+        );
+
+        let existing = self
+            .repo
+            .insert_or_get(record)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        let outcome = match existing {
+            None => Outcome::Proceed(Claim { key, scope }),
+            Some(r) if r.request_hash() != request_hash => Outcome::Mismatch,
+            Some(r) => match r.response() {
+                Some(response) => Outcome::Replay(response.clone().into()),
+                None => Outcome::InProgress,
+            },
+        };
+        Ok(outcome)
+    }
+
+    /// レスポンスを保存し、以降の再試行に返す
+    #[tracing::instrument(skip(self, response), fields(status = response.status))]
+    pub async fn complete(&self, claim: Claim, response: ResponseDto) -> Result<(), UseCaseError> {
+        self.repo
+            .save_response(
+                &claim.key,
+                &claim.scope,
+                idempotency::StoredResponse {
+                    status: response.status,
+                    content_type: response.content_type,
+                    body: response.body,
+                },
+            )
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
+
+    /// 処理に失敗したキーを解放し、同じキーで再試行できるようにする
+    #[tracing::instrument(skip(self))]
+    pub async fn abandon(&self, claim: Claim) -> Result<(), UseCaseError> {
+        self.repo
+            .delete(&claim.key, &claim.scope)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
+
+    /// 期限切れのキーを削除する
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_expired(&self) -> Result<u64, UseCaseError> {
+        self.repo
+            .purge_expired(chrono::Utc::now())
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
+}
+
+impl From<idempotency::StoredResponse> for ResponseDto {
+    fn from(response: idempotency::StoredResponse) -> Self {
+        Self {
+            status: response.status,
+            content_type: response.content_type,
+            body: response.body,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use rstest::*;
+    use std::sync::Mutex;
+
+    struct FakeRepository {
+        store: Arc<Mutex<Vec<idempotency::Record>>>,
+    }
+
+    impl FakeRepository {
+        fn new() -> Self {
+            Self {
+                store: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl idempotency::Repository for FakeRepository {
+        async fn insert_or_get(
+            &self,
+            record: idempotency::Record,
+        ) -> anyhow::Result<Option<idempotency::Record>> {
+            let mut store = self.store.lock().unwrap();
+            store.retain(|r| {
+                r.key() != record.key()
+                    || r.scope() != record.scope()
+                    || !r.is_stale(record.created_at())
+            });
+            let existing = store
+                .iter()
+                .find(|r| r.key() == record.key() && r.scope() == record.scope())
+                .cloned();
+            if existing.is_none() {
+                store.push(record);
+            }
+            Ok(existing)
+        }
+        async fn save_response(
+            &self,
+            key: &IdempotencyKey,
+            scope: &str,
+            response: idempotency::StoredResponse,
+        ) -> anyhow::Result<()> {
+            let mut store = self.store.lock().unwrap();
+            let record = store
+                .iter_mut()
+                .find(|r| r.key() == key && r.scope() == scope)
+                .ok_or(anyhow::anyhow!("not found"))?;
+            *record = idempotency::Record::reconstruct(
+                record.key().clone(),
+                record.scope().to_string(),
+                record.request_hash().to_string(),
+                Some(response),
+                record.created_at(),
+                record.expires_at(),
+                record.locked_until(),
+            );
+            Ok(())
+        }
+        async fn delete(&self, key: &IdempotencyKey, scope: &str) -> anyhow::Result<()> {
+            self.store
+                .lock()
+                .unwrap()
+                .retain(|r| r.key() != key || r.scope() != scope);
+            Ok(())
+        }
+        async fn purge_expired(&self, now: chrono::DateTime<chrono::Utc>) -> anyhow::Result<u64> {
+            let mut store = self.store.lock().unwrap();
+            let before = store.len();
+            store.retain(|r| r.expires_at() > now);
+            Ok((before - store.len()) as u64)
+        }
+    }
+
+    #[fixture]
+    fn service() -> Service {
+        Service::new(
+            Arc::new(FakeRepository::new()),
+            chrono::Duration::hours(1),
+            chrono::Duration::minutes(1),
+        )
+    }
+
+    fn response() -> ResponseDto {
+        ResponseDto {
+            status: 201,
+            content_type: Some("application/json".to_string()),
+            body: b"{\"id\":1}".to_vec(),
+        }
+    }
+
+    async fn begin(service: &Service, key: &str, request: &[u8]) -> Outcome {
+        service
+            .begin(key.to_string(), "POST /books".to_string(), request)
+            .await
+            .expect("Failed to begin")
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_replay_after_complete(service: Service) {
+        let Outcome::Proceed(claim) = begin(&service, "key-1", b"body").await else {
+            panic!("first request should proceed");
+        };
+        assert!(matches!(
+            begin(&service, "key-1", b"body").await,
+            Outcome::InProgress
+        ));
+
+        service.complete(claim, response()).await.unwrap();
+        match begin(&service, "key-1", b"body").await {
+            Outcome::Replay(replayed) => assert_eq!(replayed, response()),
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_mismatch_on_different_request(service: Service) {
+        let Outcome::Proceed(claim) = begin(&service, "key-1", b"body").await else {
+            panic!("first request should proceed");
+        };
+        service.complete(claim, response()).await.unwrap();
+
+        assert!(matches!(
+            begin(&service, "key-1", b"other").await,
+            Outcome::Mismatch
+        ));
+        // scope が違えば別のキーとして扱う
+        assert!(matches!(
+            service
+                .begin("key-1".to_string(), "POST /shops".to_string(), b"other")
+                .await
+                .unwrap(),
+            Outcome::Proceed(_)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_abandon_allows_retry(service: Service) {
+        let Outcome::Proceed(claim) = begin(&service, "key-1", b"body").await else {
+            panic!("first request should proceed");
+        };
+        service.abandon(claim).await.unwrap();
+
+        assert!(matches!(
+            begin(&service, "key-1", b"body").await,
+            Outcome::Proceed(_)
+        ));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("has space")]
+    #[case("日本語")]
+    #[tokio::test]
+    async fn test_invalid_key(service: Service, #[case] key: &str) {
+        let result = service
+            .begin(key.to_string(), "POST /books".to_string(), b"body")
+            .await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::IdempotencyDomainError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_key_is_reused() {
+        let service = Service::new(
+            Arc::new(FakeRepository::new()),
+            chrono::Duration::zero(),
+            chrono::Duration::zero(),
+        );
+        let Outcome::Proceed(claim) = begin(&service, "key-1", b"body").await else {
+            panic!("first request should proceed");
+        };
+        service.complete(claim, response()).await.unwrap();
+
+        assert!(matches!(
+            begin(&service, "key-1", b"other").await,
+            Outcome::Proceed(_)
+        ));
+        assert_eq!(service.purge_expired().await.unwrap(), 1);
+    }
+
+    // タイムアウトや切断で complete も abandon も呼ばれなかったキーは、lease を過ぎれば引き継げる
+    #[tokio::test]
+    async fn test_dropped_claim_is_taken_over_after_lease() {
+        let service = Service::new(
+            Arc::new(FakeRepository::new()),
+            chrono::Duration::hours(24),
+            chrono::Duration::milliseconds(50),
+        );
+        let Outcome::Proceed(claim) = begin(&service, "key-1", b"body").await else {
+            panic!("first request should proceed");
+        };
+        drop(claim);
+        assert!(matches!(
+            begin(&service, "key-1", b"body").await,
+            Outcome::InProgress
+        ));
+
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        let Outcome::Proceed(claim) = begin(&service, "key-1", b"body").await else {
+            panic!("retry should take over the abandoned key");
+        };
+        service.complete(claim, response()).await.unwrap();
+        // 保存したレスポンスは lease を過ぎても TTL の間は返す
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        assert!(matches!(
+            begin(&service, "key-1", b"body").await,
+            Outcome::Replay(_)
+        ));
+    }
+}
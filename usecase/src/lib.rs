@@ -1,4 +1,11 @@
+pub mod api_key;
 pub mod book;
+pub mod change_event;
+pub mod domain_event;
 pub mod error;
+pub mod idempotency;
+pub mod metrics;
+pub mod outbox;
 pub mod publisher;
 pub mod shop;
+pub mod webhook;
@@ -0,0 +1,141 @@
+use crate::domain_event::{DomainEvent, Handler};
+use crate::error::UseCaseError;
+use async_trait::async_trait;
+use prometheus::{IntCounter, IntCounterVec, register_int_counter, register_int_counter_vec};
+use std::sync::LazyLock;
+
+static BOOKS_CREATED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("books_created_total", "Number of books created").unwrap()
+});
+
+static BOOKS_PUBLISHED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "books_published_total",
+        "Number of books whose applied_at was set"
+    )
+    .unwrap()
+});
+
+static VALIDATION_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "validation_failures_total",
+        "Number of requests rejected by validation, by rule",
+        &["rule"]
+    )
+    .unwrap()
+});
+
+static DOMAIN_EVENTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "domain_events_total",
+        "Number of domain events dispatched, by event",
+        &["event"]
+    )
+    .unwrap()
+});
+
+/// 一度も発生していないカウンタも 0 として出力されるよう登録しておく
+pub fn init() {
+    LazyLock::force(&BOOKS_CREATED);
+    LazyLock::force(&BOOKS_PUBLISHED);
+    LazyLock::force(&VALIDATION_FAILURES);
+    LazyLock::force(&DOMAIN_EVENTS);
+}
+
+pub(crate) fn books_created(count: u64) {
+    BOOKS_CREATED.inc_by(count);
+}
+
+pub(crate) fn book_published() {
+    BOOKS_PUBLISHED.inc();
+}
+
+/// 配信されたドメインイベントを種類ごとに数える
+pub struct DomainEventCounter;
+
+#[async_trait]
+impl Handler for DomainEventCounter {
+    async fn handle(&self, event: &DomainEvent) -> anyhow::Result<()> {
+        DOMAIN_EVENTS.with_label_values(&[event.name()]).inc();
+        Ok(())
+    }
+}
+
+/// 入力の誤りによる失敗を規則ごとに数える。NotFound や DB エラーは数えない
+pub fn validation_failed(error: &UseCaseError) {
+    if let Some(rule) = rule(error) {
+        VALIDATION_FAILURES.with_label_values(&[rule]).inc();
+    }
+}
+
+/// ラベルの値が増え続けないよう、ドメイン層の固定メッセージだけを規則名に使う。
+/// ユースケース層で組み立てたメッセージは ID などを含むため invalid_request にまとめる
+fn rule(error: &UseCaseError) -> Option<&str> {
+    let message = match error {
+        UseCaseError::BookDomainError(
+            book::DomainError::InvalidFormat(m) | book::DomainError::DomainRuleViolation(m),
+        )
+        | UseCaseError::PublisherDomainError(
+            publisher::DomainError::InvalidFormat(m)
+            | publisher::DomainError::DomainRuleViolation(m),
+        )
+        | UseCaseError::ShopDomainError(
+            shop::DomainError::InvalidFormat(m) | shop::DomainError::DomainRuleViolation(m),
+        )
+        | UseCaseError::IdempotencyDomainError(idempotency::DomainError::InvalidFormat(m))
+        | UseCaseError::ApiKeyDomainError(
+            api_key::DomainError::InvalidFormat(m) | api_key::DomainError::DomainRuleViolation(m),
+        )
+        | UseCaseError::OutboxDomainError(outbox::DomainError::DomainRuleViolation(m)) => {
+            m.as_str()
+        }
+        // 未知の配信状況だけは入力を含むため、他の webhook の規則と分ける
+        UseCaseError::WebhookDomainError(
+            webhook::DomainError::InvalidFormat(m) | webhook::DomainError::DomainRuleViolation(m),
+        ) if !m.starts_with("Unknown delivery status") => m.as_str(),
+        // 未知のリソース種別や状態はメッセージに入力をそのまま含む
+        UseCaseError::ChangeEventDomainError(_)
+        | UseCaseError::OutboxDomainError(outbox::DomainError::InvalidFormat(_))
+        | UseCaseError::WebhookDomainError(_) => "invalid_request",
+        // ドメインエラーを to_string() して詰め替えたもの
+        UseCaseError::DomainRuleViolation(m) => m
+            .strip_prefix("Domain rule violation: ")
+            .or_else(|| m.strip_prefix("Invalid format: "))
+            .unwrap_or("invalid_request"),
+        _ => return None,
+    };
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        UseCaseError::BookDomainError(book::DomainError::InvalidFormat("Title must be 32 chars or less".to_string())),
+        Some("Title must be 32 chars or less")
+    )]
+    #[case(
+        UseCaseError::DomainRuleViolation("Domain rule violation: Book is already deleted.".to_string()),
+        Some("Book is already deleted.")
+    )]
+    #[case(
+        UseCaseError::DomainRuleViolation(format!("Book with pub_id = {} appears more than once in the batch.", uuid::Uuid::now_v7())),
+        Some("invalid_request")
+    )]
+    #[case(
+        UseCaseError::WebhookDomainError(webhook::DomainError::InvalidFormat("Secret must be between 16 and 128 chars".to_string())),
+        Some("Secret must be between 16 and 128 chars")
+    )]
+    #[case(
+        UseCaseError::WebhookDomainError(webhook::DomainError::InvalidFormat("Unknown delivery status: sent".to_string())),
+        Some("invalid_request")
+    )]
+    #[case(UseCaseError::NotFound("Book".to_string()), None)]
+    #[case(UseCaseError::DatabaseError, None)]
+    fn test_rule(#[case] error: UseCaseError, #[case] expected: Option<&str>) {
+        assert_eq!(rule(&error), expected);
+    }
+}
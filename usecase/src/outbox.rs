@@ -0,0 +1,404 @@
+use crate::error::UseCaseError;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// 1 回の中継で配信するメッセージの上限
+const RELAY_BATCH_SIZE: u64 = 100;
+/// 一覧で返す件数の上限
+pub const MAX_LIST_SIZE: u64 = 500;
+
+/// 集約の変更と一緒に保存したメッセージを Sink へ中継する。
+/// 配信に成功したと記録する前に落ちると次の中継で再送するため、少なくとも 1 回は届く
+pub struct Service {
+    repo: Arc<dyn outbox::Repository>,
+    sinks: Vec<Arc<dyn outbox::Sink>>,
+    policy: outbox::vo::RetryPolicy,
+    retention: chrono::Duration,
+}
+
+/// 1 回の中継の結果
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RelayReport {
+    pub delivered: u64,
+    /// 失敗したが、後で再試行するもの
+    pub retrying: u64,
+    /// 再試行の上限に達したもの
+    pub dead: u64,
+}
+
+impl Service {
+    /// 配信済みのメッセージは retention 経過後に purge_delivered で削除する
+    pub fn new(
+        repo: Arc<dyn outbox::Repository>,
+        sinks: Vec<Arc<dyn outbox::Sink>>,
+        policy: outbox::vo::RetryPolicy,
+        retention: chrono::Duration,
+    ) -> Self {
+        Self {
+            repo,
+            sinks,
+            policy,
+            retention,
+        }
+    }
+
+    /// 配信予定を過ぎたメッセージを古い順に全ての Sink へ送る。
+    /// 1 つでも失敗したら全ての Sink へ送り直すため、成功した Sink には重ねて届く
+    #[tracing::instrument(skip(self))]
+    pub async fn relay(&self) -> Result<RelayReport, UseCaseError> {
+        let now = chrono::Utc::now();
+        let messages = self
+            .repo
+            .find_due(now, RELAY_BATCH_SIZE)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+
+        let mut report = RelayReport::default();
+        for mut message in messages {
+            match self.send(&message).await {
+                Ok(()) => {
+                    message.delivered(chrono::Utc::now());
+                    report.delivered += 1;
+                }
+                Err(error) => {
+                    tracing::warn!(id = message.id(), %error, "failed to deliver outbox message");
+                    message.failed(error, chrono::Utc::now(), &self.policy);
+                    if message.status() == outbox::vo::Status::Dead {
+                        report.dead += 1;
+                    } else {
+                        report.retrying += 1;
+                    }
+                }
+            }
+            self.repo
+                .update(message)
+                .await
+                .map_err(|_| UseCaseError::DatabaseError)?;
+        }
+        Ok(report)
+    }
+
+    async fn send(&self, message: &outbox::Message) -> Result<(), String> {
+        for sink in &self.sinks {
+            sink.send(message)
+                .await
+                .map_err(|e| format!("{}: {}", sink.name(), e))?;
+        }
+        Ok(())
+    }
+
+    /// 新しい順に返す。status を指定するとその状態のものだけを返す
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all(
+        &self,
+        status: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<MessageDto>, UseCaseError> {
+        let status = status.map(str::parse).transpose()?;
+        let messages = self
+            .repo
+            .find_all(status, limit.clamp(1, MAX_LIST_SIZE))
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(messages.into_iter().map(MessageDto::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get(&self, id: i64) -> Result<MessageDto, UseCaseError> {
+        let message = self.find(id).await?;
+        Ok(message.into())
+    }
+
+    /// dead や再試行待ちのメッセージを、回数を数え直してすぐに配信し直す
+    #[tracing::instrument(skip(self))]
+    pub async fn retry(&self, id: i64) -> Result<MessageDto, UseCaseError> {
+        let mut message = self.find(id).await?;
+        message.retry(chrono::Utc::now())?;
+        let result = self
+            .repo
+            .update(message)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(result.into())
+    }
+
+    /// 保持期間を過ぎた配信済みのメッセージを削除する
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_delivered(&self) -> Result<u64, UseCaseError> {
+        self.repo
+            .purge_delivered(chrono::Utc::now() - self.retention)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
+
+    async fn find(&self, id: i64) -> Result<outbox::Message, UseCaseError> {
+        self.repo
+            .find_by_id(id)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .ok_or(UseCaseError::NotFound(format!(
+                "Outbox message with id = {} not found",
+                id
+            )))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = OutboxMessageDto)]
+pub struct MessageDto {
+    pub id: i64,
+    #[schema(example = "BookPriceChanged")]
+    pub event_type: String,
+    #[schema(example = "book")]
+    pub aggregate_type: String,
+    pub aggregate_id: uuid::Uuid,
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    #[schema(example = "pending")]
+    pub status: String,
+    pub attempts: u32,
+    #[schema(value_type = String, example = "2024-01-01T00:00:00Z")]
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    pub last_error: Option<String>,
+    #[schema(value_type = String, example = "2024-01-01T00:00:00Z")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[schema(value_type = Option<String>, example = "2024-01-01T00:00:00Z")]
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<outbox::Message> for MessageDto {
+    fn from(message: outbox::Message) -> Self {
+        Self {
+            id: message.id(),
+            event_type: message.event_type().to_string(),
+            aggregate_type: message.aggregate_type().to_string(),
+            aggregate_id: message.aggregate_id(),
+            payload: serde_json::from_str(message.payload()).unwrap_or_default(),
+            status: message.status().to_string(),
+            attempts: message.attempts(),
+            next_attempt_at: message.next_attempt_at(),
+            last_error: message.last_error().map(str::to_string),
+            created_at: message.created_at(),
+            delivered_at: message.delivered_at(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use outbox::vo::Status;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Default)]
+    struct FakeRepository {
+        store: Mutex<Vec<outbox::Message>>,
+    }
+
+    impl FakeRepository {
+        fn add(&self, event_type: &str) -> i64 {
+            let mut store = self.store.lock().unwrap();
+            let id = store.len() as i64 + 1;
+            let message = outbox::Message::new(
+                "book".to_string(),
+                uuid::Uuid::now_v7(),
+                event_type.to_string(),
+                "{}".to_string(),
+            );
+            store.push(outbox::Message::reconstruct(
+                id,
+                message.aggregate_type().to_string(),
+                message.aggregate_id(),
+                message.event_type().to_string(),
+                message.payload().to_string(),
+                message.status(),
+                message.attempts(),
+                message.next_attempt_at(),
+                None,
+                message.created_at(),
+                None,
+            ));
+            id
+        }
+
+        fn get(&self, id: i64) -> outbox::Message {
+            self.store.lock().unwrap()[id as usize - 1].clone()
+        }
+    }
+
+    #[async_trait]
+    impl outbox::Repository for FakeRepository {
+        async fn find_all(
+            &self,
+            status: Option<Status>,
+            limit: u64,
+        ) -> anyhow::Result<Vec<outbox::Message>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .rev()
+                .filter(|m| status.is_none_or(|s| m.status() == s))
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+
+        async fn find_by_id(&self, id: i64) -> anyhow::Result<Option<outbox::Message>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|m| m.id() == id)
+                .cloned())
+        }
+
+        async fn find_due(
+            &self,
+            now: chrono::DateTime<chrono::Utc>,
+            limit: u64,
+        ) -> anyhow::Result<Vec<outbox::Message>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|m| m.status() == Status::Pending && m.next_attempt_at() <= now)
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+
+        async fn update(&self, item: outbox::Message) -> anyhow::Result<outbox::Message> {
+            let mut store = self.store.lock().unwrap();
+            let index = store
+                .iter()
+                .position(|m| m.id() == item.id())
+                .ok_or(anyhow::anyhow!("Message not found"))?;
+            store[index] = item.clone();
+            Ok(item)
+        }
+
+        async fn purge_delivered(
+            &self,
+            before: chrono::DateTime<chrono::Utc>,
+        ) -> anyhow::Result<u64> {
+            let mut store = self.store.lock().unwrap();
+            let count = store.len();
+            store.retain(|m| m.delivered_at().is_none_or(|at| at >= before));
+            Ok((count - store.len()) as u64)
+        }
+    }
+
+    /// 最初の failures 回だけ失敗する Sink
+    struct FlakySink {
+        failures: AtomicU32,
+        received: Mutex<Vec<i64>>,
+    }
+
+    impl FlakySink {
+        fn new(failures: u32) -> Arc<Self> {
+            Arc::new(Self {
+                failures: AtomicU32::new(failures),
+                received: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl outbox::Sink for FlakySink {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn send(&self, message: &outbox::Message) -> anyhow::Result<()> {
+            let remaining = self.failures.load(Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::SeqCst);
+                anyhow::bail!("unavailable");
+            }
+            self.received.lock().unwrap().push(message.id());
+            Ok(())
+        }
+    }
+
+    fn service(
+        sink: Arc<FlakySink>,
+        max_attempts: u32,
+        base: chrono::Duration,
+    ) -> (Service, Arc<FakeRepository>) {
+        let repo = Arc::new(FakeRepository::default());
+        let policy =
+            outbox::vo::RetryPolicy::new(max_attempts, base, chrono::Duration::hours(1)).unwrap();
+        let service = Service::new(repo.clone(), vec![sink], policy, chrono::Duration::days(1));
+        (service, repo)
+    }
+
+    #[tokio::test]
+    async fn test_relay_retries_with_backoff() {
+        let sink = FlakySink::new(1);
+        let (service, repo) = service(sink.clone(), 5, chrono::Duration::minutes(1));
+        let id = repo.add("BookCreated");
+
+        let report = service.relay().await.expect("Failed to relay");
+        assert_eq!(
+            report,
+            RelayReport {
+                retrying: 1,
+                ..Default::default()
+            }
+        );
+        let message = repo.get(id);
+        assert_eq!(message.status(), Status::Pending);
+        assert_eq!(message.attempts(), 1);
+        assert_eq!(message.last_error(), Some("flaky: unavailable"));
+        assert!(message.next_attempt_at() > chrono::Utc::now() + chrono::Duration::seconds(50));
+
+        // 再試行の時刻までは送らない
+        assert_eq!(service.relay().await.unwrap(), RelayReport::default());
+        assert!(sink.received.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_relay_marks_dead_and_retry_resets() {
+        let sink = FlakySink::new(2);
+        let (service, repo) = service(sink.clone(), 2, chrono::Duration::milliseconds(1));
+        let id = repo.add("BookCreated");
+
+        service.relay().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let report = service.relay().await.unwrap();
+        assert_eq!(report.dead, 1);
+        assert_eq!(repo.get(id).status(), Status::Dead);
+
+        let dead = service.get_all(Some("dead"), 10).await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].attempts, 2);
+
+        let retried = service.retry(id).await.expect("Failed to retry");
+        assert_eq!(retried.status, "pending");
+        assert_eq!(retried.attempts, 0);
+        let report = service.relay().await.unwrap();
+        assert_eq!(report.delivered, 1);
+        assert_eq!(*sink.received.lock().unwrap(), [id]);
+
+        // 配信済みは再試行できない
+        assert!(matches!(
+            service.retry(id).await,
+            Err(UseCaseError::OutboxDomainError(_))
+        ));
+        assert!(matches!(
+            service.get_all(Some("unknown"), 10).await,
+            Err(UseCaseError::OutboxDomainError(_))
+        ));
+        assert!(matches!(
+            service.retry(999).await,
+            Err(UseCaseError::NotFound(_))
+        ));
+    }
+}
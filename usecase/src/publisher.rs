@@ -1,30 +1,63 @@
+use crate::domain_event::{Dispatcher, DomainEvent};
 use crate::error::UseCaseError;
+use change_event::vo::{Action, ResourceType};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
 
 pub struct Service {
     repo: Arc<dyn publisher::Repository>,
+    events: Arc<crate::change_event::Service>,
+    dispatcher: Arc<Dispatcher>,
 }
 
 impl Service {
-    pub fn new(repo: Arc<dyn publisher::Repository>) -> Self {
-        Self { repo }
+    pub fn new(
+        repo: Arc<dyn publisher::Repository>,
+        events: Arc<crate::change_event::Service>,
+        dispatcher: Arc<Dispatcher>,
+    ) -> Self {
+        Self {
+            repo,
+            events,
+            dispatcher,
+        }
     }
 
-    pub async fn get_all(&self) -> Result<Vec<ResponseDto>, UseCaseError> {
+    /// 保存に成功した変更をイベントとして記録し、ドメインイベントを配信してレスポンスに変換する
+    async fn saved(
+        &self,
+        action: Action,
+        publisher: publisher::Publisher,
+        domain_events: Vec<DomainEvent>,
+    ) -> ResponseDto {
+        let response = ResponseDto::from(publisher);
+        self.events
+            .record(ResourceType::Publisher, action, response.pub_id, &response)
+            .await;
+        self.dispatcher.dispatch(domain_events).await;
+        response
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all(&self, include_deleted: bool) -> Result<Vec<ResponseDto>, UseCaseError> {
         let publishers = self
             .repo
-            .find_all()
+            .find_all(include_deleted)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
         Ok(publishers.into_iter().map(ResponseDto::from).collect())
     }
 
-    pub async fn get(&self, pub_id: uuid::Uuid) -> Result<ResponseDto, UseCaseError> {
+    #[tracing::instrument(skip(self))]
+    pub async fn get(
+        &self,
+        pub_id: uuid::Uuid,
+        include_deleted: bool,
+    ) -> Result<ResponseDto, UseCaseError> {
         let publisher = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, include_deleted)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
@@ -34,18 +67,37 @@ impl Service {
         Ok(publisher.into())
     }
 
+    /// 見つかったものだけを返す。並び順は pub_ids と揃えない
+    #[tracing::instrument(skip_all, fields(count = pub_ids.len()))]
+    pub async fn get_by_pub_ids(
+        &self,
+        pub_ids: &[uuid::Uuid],
+        include_deleted: bool,
+    ) -> Result<Vec<ResponseDto>, UseCaseError> {
+        let publishers = self
+            .repo
+            .find_by_pub_ids(pub_ids, include_deleted)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(publishers.into_iter().map(ResponseDto::from).collect())
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = tracing::field::Empty))]
     pub async fn create(&self, dto: CreateDto) -> Result<ResponseDto, UseCaseError> {
         let name = publisher::vo::PublisherName::new(dto.name)?;
         let publisher =
             publisher::Publisher::new(uuid::Uuid::now_v7(), name, "test player".to_string());
+        tracing::Span::current().record("pub_id", tracing::field::display(publisher.pub_id()));
+        let domain_events = DomainEvent::from_publisher(&publisher);
         let result = self
             .repo
             .create(publisher)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
-        Ok(result.into())
+        Ok(self.saved(Action::Created, result, domain_events).await)
     }
 
+    #[tracing::instrument(skip(self, dto))]
     pub async fn update(
         &self,
         pub_id: uuid::Uuid,
@@ -54,7 +106,7 @@ impl Service {
         let name = publisher::vo::PublisherName::new(dto.name)?;
         let mut publisher = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, false)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
@@ -66,31 +118,86 @@ impl Service {
             .update(name, "test player".to_string())
             .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
 
+        let domain_events = DomainEvent::from_publisher(&publisher);
         let result = self
             .repo
             .update(publisher)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
-        Ok(result.into())
+        Ok(self.saved(Action::Updated, result, domain_events).await)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn delete(&self, pub_id: uuid::Uuid) -> Result<(), UseCaseError> {
-        let publisher = self
+        let mut publisher = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, false)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
                 "Publisher with pub_id = {} not found",
                 pub_id
             )))?;
+        if self
+            .repo
+            .has_books(publisher.id())
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+        {
+            return Err(UseCaseError::DomainRuleViolation(
+                "Cannot delete a publisher that still has books.".to_string(),
+            ));
+        }
 
-        self.repo
-            .delete(publisher)
+        publisher
+            .delete("test player".to_string())
+            .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
+
+        let domain_events = DomainEvent::from_publisher(&publisher);
+        let result = self
+            .repo
+            .update(publisher)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
+        self.saved(Action::Deleted, result, domain_events).await;
         Ok(())
     }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn restore(&self, pub_id: uuid::Uuid) -> Result<ResponseDto, UseCaseError> {
+        let mut publisher = self
+            .repo
+            .find_by_pub_id(pub_id, true)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .ok_or(UseCaseError::NotFound(format!(
+                "Publisher with pub_id = {} not found",
+                pub_id
+            )))?;
+
+        publisher
+            .restore("test player".to_string())
+            .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
+
+        let domain_events = DomainEvent::from_publisher(&publisher);
+        let result = self
+            .repo
+            .update(publisher)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(self.saved(Action::Restored, result, domain_events).await)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_deleted(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, UseCaseError> {
+        self.repo
+            .purge_deleted(deleted_before)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -110,6 +217,8 @@ pub struct UpdateDto {
 pub struct ResponseDto {
     pub pub_id: uuid::Uuid,
     pub name: String,
+    #[schema(value_type = Option<String>, example = "2024-01-01T00:00:00Z")]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl From<publisher::Publisher> for ResponseDto {
@@ -117,6 +226,7 @@ impl From<publisher::Publisher> for ResponseDto {
         Self {
             pub_id: publisher.pub_id(),
             name: publisher.name(),
+            deleted_at: publisher.deleted_at(),
         }
     }
 }
@@ -142,19 +252,58 @@ mod tests {
 
     #[async_trait]
     impl publisher::Repository for FakeRepository {
-        async fn find_all(&self) -> anyhow::Result<Vec<publisher::Publisher>> {
+        async fn find_all(
+            &self,
+            include_deleted: bool,
+        ) -> anyhow::Result<Vec<publisher::Publisher>> {
             let store = self.store.lock().unwrap();
-            Ok(store.clone())
+            Ok(store
+                .iter()
+                .filter(|p| include_deleted || !p.is_deleted())
+                .cloned()
+                .collect())
         }
 
         async fn find_by_pub_id(
             &self,
             pub_id: uuid::Uuid,
+            include_deleted: bool,
         ) -> anyhow::Result<Option<publisher::Publisher>> {
             let store = self.store.lock().unwrap();
-            Ok(store.iter().find(|p| p.pub_id() == pub_id).cloned())
+            Ok(store
+                .iter()
+                .find(|p| p.pub_id() == pub_id && (include_deleted || !p.is_deleted()))
+                .cloned())
+        }
+
+        async fn find_by_pub_ids(
+            &self,
+            pub_ids: &[uuid::Uuid],
+            include_deleted: bool,
+        ) -> anyhow::Result<Vec<publisher::Publisher>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| pub_ids.contains(&p.pub_id()) && (include_deleted || !p.is_deleted()))
+                .cloned()
+                .collect())
         }
 
+        async fn find_by_names(
+            &self,
+            names: &[String],
+        ) -> anyhow::Result<Vec<publisher::Publisher>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| names.contains(&p.name()) && !p.is_deleted())
+                .cloned()
+                .collect())
+        }
         async fn create(&self, item: publisher::Publisher) -> anyhow::Result<publisher::Publisher> {
             let mut store = self.store.lock().unwrap();
             let new_id = store.iter().map(|p| p.id()).max().unwrap_or(0) + 1;
@@ -167,13 +316,20 @@ mod tests {
                 item.updated_at(),
                 item.created_by(),
                 item.updated_by(),
+                item.deleted_at(),
+                item.deleted_by(),
             );
 
             store.push(new_publisher.clone());
             Ok(new_publisher)
         }
 
-        async fn update(&self, item: publisher::Publisher) -> anyhow::Result<publisher::Publisher> {
+        async fn update(
+            &self,
+            mut item: publisher::Publisher,
+        ) -> anyhow::Result<publisher::Publisher> {
+            // SQL の実装と同じく、保存したイベントは集約から取り除く
+            item.take_events();
             let mut store = self.store.lock().unwrap();
             if let Some(index) = store.iter().position(|p| p.id() == item.id()) {
                 store[index] = item.clone();
@@ -183,17 +339,29 @@ mod tests {
             }
         }
 
-        async fn delete(&self, item: publisher::Publisher) -> anyhow::Result<()> {
+        async fn has_books(&self, _id: i32) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+
+        async fn purge_deleted(
+            &self,
+            deleted_before: chrono::DateTime<chrono::Utc>,
+        ) -> anyhow::Result<u64> {
             let mut store = self.store.lock().unwrap();
-            store.retain(|p| p.pub_id() != item.pub_id());
-            Ok(())
+            let before = store.len();
+            store.retain(|p| p.deleted_at().is_none_or(|at| at >= deleted_before));
+            Ok((before - store.len()) as u64)
         }
     }
 
     #[fixture]
     async fn service() -> Service {
         let repo = FakeRepository::new();
-        Service::new(Arc::new(repo))
+        Service::new(
+            Arc::new(repo),
+            crate::change_event::tests::fake().0,
+            Arc::new(Dispatcher::new()),
+        )
     }
 
     #[rstest]
@@ -207,7 +375,10 @@ mod tests {
         let created = service.create(dto).await.expect("Failed to create");
         assert_eq!(created.name, "Test Publisher");
 
-        let fetched = service.get(created.pub_id).await.expect("Failed to get");
+        let fetched = service
+            .get(created.pub_id, false)
+            .await
+            .expect("Failed to get");
         assert_eq!(fetched.name, "Test Publisher");
         assert_eq!(fetched.pub_id, created.pub_id);
     }
@@ -226,7 +397,7 @@ mod tests {
         service.create(dto1).await.expect("Failed to create 1");
         service.create(dto2).await.expect("Failed to create 2");
 
-        let all = service.get_all().await.expect("Failed to get all");
+        let all = service.get_all(false).await.expect("Failed to get all");
         assert_eq!(all.len(), 2);
     }
 
@@ -249,10 +420,53 @@ mod tests {
             .expect("Failed to update");
         assert_eq!(updated.name, "Updated Name");
 
-        let fetched = service.get(created.pub_id).await.expect("Failed to get");
+        let fetched = service
+            .get(created.pub_id, false)
+            .await
+            .expect("Failed to get");
         assert_eq!(fetched.name, "Updated Name");
     }
 
+    #[tokio::test]
+    async fn test_rename_dispatches_domain_event() {
+        let (dispatcher, handler) = crate::domain_event::tests::recording();
+        let service = Service::new(
+            Arc::new(FakeRepository::new()),
+            crate::change_event::tests::fake().0,
+            dispatcher,
+        );
+        let created = service
+            .create(CreateDto {
+                name: "Original Name".to_string(),
+            })
+            .await
+            .expect("Failed to create");
+        for name in ["Updated Name", "Updated Name"] {
+            service
+                .update(
+                    created.pub_id,
+                    UpdateDto {
+                        name: name.to_string(),
+                    },
+                )
+                .await
+                .expect("Failed to update");
+        }
+
+        // 同じ名前への更新は PublisherRenamed を記録しない
+        assert_eq!(handler.names(), ["PublisherCreated", "PublisherRenamed"]);
+        assert_eq!(
+            handler.events.lock().unwrap()[1],
+            DomainEvent::Publisher {
+                pub_id: created.pub_id,
+                event: publisher::event::Event::Renamed {
+                    old: "Original Name".to_string(),
+                    new: "Updated Name".to_string(),
+                },
+            }
+        );
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_delete(#[future] service: Service) {
@@ -267,11 +481,72 @@ mod tests {
             .await
             .expect("Failed to delete");
 
-        let result = service.get(created.pub_id).await;
+        let result = service.get(created.pub_id, false).await;
         assert!(result.is_err());
         match result {
             Err(UseCaseError::NotFound(_)) => (),
             _ => panic!("Expected NotFound error"),
         }
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_restore(#[future] service: Service) {
+        let service = service.await;
+        let dto = CreateDto {
+            name: "To Restore".to_string(),
+        };
+        let created = service.create(dto).await.expect("Failed to create");
+        service
+            .delete(created.pub_id)
+            .await
+            .expect("Failed to delete");
+
+        let deleted = service
+            .get(created.pub_id, true)
+            .await
+            .expect("Failed to get deleted");
+        assert!(deleted.deleted_at.is_some());
+        assert!(service.get_all(false).await.unwrap().is_empty());
+        assert_eq!(service.get_all(true).await.unwrap().len(), 1);
+
+        let restored = service
+            .restore(created.pub_id)
+            .await
+            .expect("Failed to restore");
+        assert!(restored.deleted_at.is_none());
+        assert!(service.get(created.pub_id, false).await.is_ok());
+
+        match service.restore(created.pub_id).await {
+            Err(UseCaseError::DomainRuleViolation(_)) => (),
+            _ => panic!("Expected DomainRuleViolation error"),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_purge_deleted(#[future] service: Service) {
+        let service = service.await;
+        let dto = CreateDto {
+            name: "To Purge".to_string(),
+        };
+        let created = service.create(dto).await.expect("Failed to create");
+        service
+            .delete(created.pub_id)
+            .await
+            .expect("Failed to delete");
+
+        let purged = service
+            .purge_deleted(chrono::Utc::now() - chrono::Duration::days(1))
+            .await
+            .expect("Failed to purge");
+        assert_eq!(purged, 0);
+
+        let purged = service
+            .purge_deleted(chrono::Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .expect("Failed to purge");
+        assert_eq!(purged, 1);
+        assert!(service.get(created.pub_id, true).await.is_err());
+    }
 }
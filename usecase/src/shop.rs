@@ -1,31 +1,64 @@
+use crate::domain_event::{Dispatcher, DomainEvent};
 use crate::error::UseCaseError;
+use change_event::vo::{Action, ResourceType};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
 
 pub struct Service {
     repo: Arc<dyn shop::Repository>,
+    events: Arc<crate::change_event::Service>,
+    dispatcher: Arc<Dispatcher>,
 }
 
 impl Service {
-    pub fn new(repo: Arc<dyn shop::Repository>) -> Self {
-        Self { repo }
+    pub fn new(
+        repo: Arc<dyn shop::Repository>,
+        events: Arc<crate::change_event::Service>,
+        dispatcher: Arc<Dispatcher>,
+    ) -> Self {
+        Self {
+            repo,
+            events,
+            dispatcher,
+        }
+    }
+
+    /// 保存に成功した変更をイベントとして記録し、ドメインイベントを配信してレスポンスに変換する
+    async fn saved(
+        &self,
+        action: Action,
+        shop: shop::Shop,
+        domain_events: Vec<DomainEvent>,
+    ) -> ResponseDto {
+        let response = ResponseDto::from(shop);
+        self.events
+            .record(ResourceType::Shop, action, response.pub_id, &response)
+            .await;
+        self.dispatcher.dispatch(domain_events).await;
+        response
     }
 
-    pub async fn get_all(&self) -> Result<Vec<ResponseDto>, UseCaseError> {
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all(&self, include_deleted: bool) -> Result<Vec<ResponseDto>, UseCaseError> {
         let shops = self
             .repo
-            .find_all()
+            .find_all(include_deleted)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
         let dtos = shops.into_iter().map(ResponseDto::from).collect();
         Ok(dtos)
     }
 
-    pub async fn get(&self, pub_id: uuid::Uuid) -> Result<ResponseDto, UseCaseError> {
+    #[tracing::instrument(skip(self))]
+    pub async fn get(
+        &self,
+        pub_id: uuid::Uuid,
+        include_deleted: bool,
+    ) -> Result<ResponseDto, UseCaseError> {
         let shop = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, include_deleted)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
@@ -35,19 +68,38 @@ impl Service {
         Ok(shop.into())
     }
 
+    /// 見つかったものだけを返す。並び順は pub_ids と揃えない
+    #[tracing::instrument(skip_all, fields(count = pub_ids.len()))]
+    pub async fn get_by_pub_ids(
+        &self,
+        pub_ids: &[uuid::Uuid],
+        include_deleted: bool,
+    ) -> Result<Vec<ResponseDto>, UseCaseError> {
+        let shops = self
+            .repo
+            .find_by_pub_ids(pub_ids, include_deleted)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(shops.into_iter().map(ResponseDto::from).collect())
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = tracing::field::Empty))]
     pub async fn create(&self, dto: CreateDto) -> Result<ResponseDto, UseCaseError> {
         let name = shop::vo::ShopName::new(dto.name)?;
 
         let shop = shop::Shop::new(uuid::Uuid::now_v7(), name, "test player".to_string());
+        tracing::Span::current().record("pub_id", tracing::field::display(shop.pub_id()));
 
+        let domain_events = DomainEvent::from_shop(&shop);
         let created = self
             .repo
             .create(shop)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
 
-        Ok(created.into())
+        Ok(self.saved(Action::Created, created, domain_events).await)
     }
+    #[tracing::instrument(skip(self, dto))]
     pub async fn update(
         &self,
         pub_id: uuid::Uuid,
@@ -55,7 +107,7 @@ impl Service {
     ) -> Result<ResponseDto, UseCaseError> {
         let mut shop = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, false)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
@@ -68,33 +120,85 @@ impl Service {
         shop.update(name, "test player".to_string())
             .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
 
+        let domain_events = DomainEvent::from_shop(&shop);
         let updated = self
             .repo
             .update(shop)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
 
-        Ok(updated.into())
+        Ok(self.saved(Action::Updated, updated, domain_events).await)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn delete(&self, pub_id: uuid::Uuid) -> Result<(), UseCaseError> {
-        let shop = self
+        let mut shop = self
             .repo
-            .find_by_pub_id(pub_id)
+            .find_by_pub_id(pub_id, false)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?
             .ok_or(UseCaseError::NotFound(format!(
                 "Shop with pub_id = {} not found",
                 pub_id
             )))?;
+        if self
+            .repo
+            .has_books(shop.id())
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+        {
+            return Err(UseCaseError::DomainRuleViolation(
+                "Cannot delete a shop that still has books.".to_string(),
+            ));
+        }
 
-        self.repo
-            .delete(shop)
+        shop.delete("test player".to_string())
+            .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
+
+        let domain_events = DomainEvent::from_shop(&shop);
+        let deleted = self
+            .repo
+            .update(shop)
             .await
             .map_err(|_| UseCaseError::DatabaseError)?;
-
+        self.saved(Action::Deleted, deleted, domain_events).await;
         Ok(())
     }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn restore(&self, pub_id: uuid::Uuid) -> Result<ResponseDto, UseCaseError> {
+        let mut shop = self
+            .repo
+            .find_by_pub_id(pub_id, true)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .ok_or(UseCaseError::NotFound(format!(
+                "Shop with pub_id = {} not found",
+                pub_id
+            )))?;
+
+        shop.restore("test player".to_string())
+            .map_err(|e| UseCaseError::DomainRuleViolation(e.to_string()))?;
+
+        let domain_events = DomainEvent::from_shop(&shop);
+        let result = self
+            .repo
+            .update(shop)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(self.saved(Action::Restored, result, domain_events).await)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_deleted(
+        &self,
+        deleted_before: chrono::DateTime<chrono::Utc>,
+    ) -> Result<u64, UseCaseError> {
+        self.repo
+            .purge_deleted(deleted_before)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -114,6 +218,8 @@ pub struct UpdateDto {
 pub struct ResponseDto {
     pub pub_id: uuid::Uuid,
     pub name: String,
+    #[schema(value_type = Option<String>, example = "2024-01-01T00:00:00Z")]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl From<shop::Shop> for ResponseDto {
@@ -121,6 +227,7 @@ impl From<shop::Shop> for ResponseDto {
         Self {
             pub_id: s.pub_id(),
             name: s.name(),
+            deleted_at: s.deleted_at(),
         }
     }
 }
@@ -146,18 +253,53 @@ mod tests {
 
     #[async_trait]
     impl shop::Repository for FakeRepository {
-        async fn find_all(&self) -> anyhow::Result<Vec<shop::Shop>> {
-            Ok(self.store.lock().unwrap().clone())
+        async fn find_all(&self, include_deleted: bool) -> anyhow::Result<Vec<shop::Shop>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| include_deleted || !s.is_deleted())
+                .cloned()
+                .collect())
         }
-        async fn find_by_pub_id(&self, pub_id: uuid::Uuid) -> anyhow::Result<Option<shop::Shop>> {
+        async fn find_by_pub_id(
+            &self,
+            pub_id: uuid::Uuid,
+            include_deleted: bool,
+        ) -> anyhow::Result<Option<shop::Shop>> {
             Ok(self
                 .store
                 .lock()
                 .unwrap()
                 .iter()
-                .find(|s| s.pub_id() == pub_id)
+                .find(|s| s.pub_id() == pub_id && (include_deleted || !s.is_deleted()))
                 .cloned())
         }
+        async fn find_by_pub_ids(
+            &self,
+            pub_ids: &[uuid::Uuid],
+            include_deleted: bool,
+        ) -> anyhow::Result<Vec<shop::Shop>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| pub_ids.contains(&s.pub_id()) && (include_deleted || !s.is_deleted()))
+                .cloned()
+                .collect())
+        }
+        async fn find_by_names(&self, names: &[String]) -> anyhow::Result<Vec<shop::Shop>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|s| names.contains(&s.name()) && !s.is_deleted())
+                .cloned()
+                .collect())
+        }
         async fn create(&self, item: shop::Shop) -> anyhow::Result<shop::Shop> {
             self.store.lock().unwrap().push(item.clone());
             Ok(item)
@@ -165,7 +307,13 @@ mod tests {
         async fn update(&self, _item: shop::Shop) -> anyhow::Result<shop::Shop> {
             panic!("Not implemented")
         }
-        async fn delete(&self, _item: shop::Shop) -> anyhow::Result<()> {
+        async fn has_books(&self, _id: i32) -> anyhow::Result<bool> {
+            panic!("Not implemented")
+        }
+        async fn purge_deleted(
+            &self,
+            _deleted_before: chrono::DateTime<chrono::Utc>,
+        ) -> anyhow::Result<u64> {
             panic!("Not implemented")
         }
     }
@@ -173,7 +321,11 @@ mod tests {
     #[fixture]
     fn service() -> Service {
         let repo = FakeRepository::new();
-        Service::new(Arc::new(repo))
+        Service::new(
+            Arc::new(repo),
+            crate::change_event::tests::fake().0,
+            Arc::new(Dispatcher::new()),
+        )
     }
 
     #[rstest]
@@ -186,7 +338,10 @@ mod tests {
         let created = service.create(dto).await.expect("Failed to create");
         assert_eq!(created.name, "Test Shop");
 
-        let fetched = service.get(created.pub_id).await.expect("Failed to get");
+        let fetched = service
+            .get(created.pub_id, false)
+            .await
+            .expect("Failed to get");
         assert_eq!(fetched.pub_id, created.pub_id);
     }
 }
@@ -0,0 +1,770 @@
+use crate::domain_event::DomainEvent;
+use crate::error::UseCaseError;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use webhook::vo::{Secret, WebhookUrl};
+
+/// 1 回の配信処理で送る件数の上限
+const DELIVERY_BATCH_SIZE: u64 = 100;
+/// 配信履歴で返す件数の上限
+pub const MAX_LIST_SIZE: u64 = 500;
+
+/// `sha256={hex}`。`{timestamp}.{body}` を購読の secret で HMAC-SHA256 したもの
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// 署名した時刻 (UNIX 秒)。受信側は古すぎるものを捨てて再送攻撃を防ぐ
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+/// 配信の ID。再試行しても変わらない
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+/// 受信側が署名を検証するときも同じ計算をする
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// 外部システムへの webhook の購読と、署名付きの配信を扱う。
+/// 配信の記録は outbox の中継で作るため、集約の変更が保存されたイベントだけが届く
+pub struct Service {
+    subscriptions: Arc<dyn webhook::SubscriptionRepository>,
+    deliveries: Arc<dyn webhook::DeliveryRepository>,
+    sender: Arc<dyn webhook::Sender>,
+    policy: outbox::vo::RetryPolicy,
+    retention: chrono::Duration,
+}
+
+/// 1 回の配信処理の結果
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    pub delivered: u64,
+    /// 失敗したが、後で再試行するもの
+    pub retrying: u64,
+    /// 再試行の上限に達したもの
+    pub dead: u64,
+}
+
+impl Service {
+    /// 配信済みの記録は retention 経過後に purge_delivered で削除する
+    pub fn new(
+        subscriptions: Arc<dyn webhook::SubscriptionRepository>,
+        deliveries: Arc<dyn webhook::DeliveryRepository>,
+        sender: Arc<dyn webhook::Sender>,
+        policy: outbox::vo::RetryPolicy,
+        retention: chrono::Duration,
+    ) -> Self {
+        Self {
+            subscriptions,
+            deliveries,
+            sender,
+            policy,
+            retention,
+        }
+    }
+
+    /// outbox のメッセージを購読ごとの配信に振り分ける Sink
+    pub fn fanout(&self) -> Fanout {
+        Fanout {
+            subscriptions: self.subscriptions.clone(),
+            deliveries: self.deliveries.clone(),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(pub_id = tracing::field::Empty))]
+    pub async fn create(&self, dto: CreateDto) -> Result<ResponseDto, UseCaseError> {
+        let url = WebhookUrl::new(dto.url)?;
+        let secret = Secret::new(dto.secret)?;
+        if let Some(unknown) = dto
+            .event_types
+            .iter()
+            .find(|t| !DomainEvent::names().any(|name| name == t.as_str()))
+        {
+            return Err(UseCaseError::DomainRuleViolation(format!(
+                "Unknown event type: {}",
+                unknown
+            )));
+        }
+        let subscription =
+            webhook::Subscription::new(uuid::Uuid::now_v7(), url, dto.event_types, secret)?;
+        tracing::Span::current().record("pub_id", tracing::field::display(subscription.pub_id()));
+
+        let created = self
+            .subscriptions
+            .create(subscription)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(created.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all(&self) -> Result<Vec<ResponseDto>, UseCaseError> {
+        let subscriptions = self
+            .subscriptions
+            .find_all()
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(subscriptions.into_iter().map(ResponseDto::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn get(&self, pub_id: uuid::Uuid) -> Result<ResponseDto, UseCaseError> {
+        Ok(self.find(pub_id).await?.into())
+    }
+
+    /// 配信待ちのものも含めて配信の記録ごと削除する
+    #[tracing::instrument(skip(self))]
+    pub async fn delete(&self, pub_id: uuid::Uuid) -> Result<(), UseCaseError> {
+        let subscription = self.find(pub_id).await?;
+        self.subscriptions
+            .delete(subscription)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
+
+    /// 購読の配信履歴を新しい順に返す。status を指定するとその状態のものだけを返す
+    #[tracing::instrument(skip(self))]
+    pub async fn deliveries(
+        &self,
+        pub_id: uuid::Uuid,
+        status: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<DeliveryDto>, UseCaseError> {
+        let status = status.map(str::parse).transpose()?;
+        let subscription = self.find(pub_id).await?;
+        let deliveries = self
+            .deliveries
+            .find_by_subscription(subscription.id(), status, limit.clamp(1, MAX_LIST_SIZE))
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        Ok(deliveries.into_iter().map(DeliveryDto::from).collect())
+    }
+
+    /// 配信予定を過ぎたものを古い順に送る。2xx 以外の応答は失敗として後で送り直す
+    #[tracing::instrument(skip(self))]
+    pub async fn deliver(&self) -> Result<DeliveryReport, UseCaseError> {
+        let deliveries = self
+            .deliveries
+            .find_due(chrono::Utc::now(), DELIVERY_BATCH_SIZE)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?;
+        if deliveries.is_empty() {
+            return Ok(DeliveryReport::default());
+        }
+        let subscriptions: HashMap<_, _> = self
+            .subscriptions
+            .find_all()
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .into_iter()
+            .map(|s| (s.id(), s))
+            .collect();
+
+        let mut report = DeliveryReport::default();
+        for mut delivery in deliveries {
+            // 読み込んだ後に購読が削除された
+            let Some(subscription) = subscriptions.get(&delivery.subscription_id()) else {
+                continue;
+            };
+            match self.send(subscription, &delivery).await {
+                Ok(status) if (200..300).contains(&status) => {
+                    delivery.delivered(status, chrono::Utc::now());
+                    report.delivered += 1;
+                }
+                result => {
+                    let (error, status) = match result {
+                        Ok(status) => (format!("HTTP {}", status), Some(status)),
+                        Err(e) => (e.to_string(), None),
+                    };
+                    tracing::warn!(id = delivery.id(), %error, "failed to deliver webhook");
+                    delivery.failed(error, status, chrono::Utc::now(), &self.policy);
+                    if delivery.status() == webhook::vo::DeliveryStatus::Dead {
+                        report.dead += 1;
+                    } else {
+                        report.retrying += 1;
+                    }
+                }
+            }
+            self.deliveries
+                .update(delivery)
+                .await
+                .map_err(|_| UseCaseError::DatabaseError)?;
+        }
+        Ok(report)
+    }
+
+    async fn send(
+        &self,
+        subscription: &webhook::Subscription,
+        delivery: &webhook::Delivery,
+    ) -> anyhow::Result<u16> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let headers = [
+            (
+                SIGNATURE_HEADER,
+                sign(subscription.secret(), timestamp, delivery.payload()),
+            ),
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+            (EVENT_HEADER, delivery.event_type().to_string()),
+            (DELIVERY_HEADER, delivery.id().to_string()),
+        ];
+        self.sender
+            .post(subscription.url(), &headers, delivery.payload())
+            .await
+    }
+
+    /// 保持期間を過ぎた配信済みの記録を削除する
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_delivered(&self) -> Result<u64, UseCaseError> {
+        self.deliveries
+            .purge_delivered(chrono::Utc::now() - self.retention)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)
+    }
+
+    async fn find(&self, pub_id: uuid::Uuid) -> Result<webhook::Subscription, UseCaseError> {
+        self.subscriptions
+            .find_by_pub_id(pub_id)
+            .await
+            .map_err(|_| UseCaseError::DatabaseError)?
+            .ok_or(UseCaseError::NotFound(format!(
+                "Webhook with pub_id = {} not found",
+                pub_id
+            )))
+    }
+}
+
+/// outbox の中継先として登録し、イベントを購読している webhook ごとに配信を積む
+pub struct Fanout {
+    subscriptions: Arc<dyn webhook::SubscriptionRepository>,
+    deliveries: Arc<dyn webhook::DeliveryRepository>,
+}
+
+#[async_trait]
+impl outbox::Sink for Fanout {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    /// 中継が再送しても、同じメッセージの配信は 1 つの購読に 1 つしか積まない
+    async fn send(&self, message: &outbox::Message) -> anyhow::Result<()> {
+        let subscriptions = self.subscriptions.find_all().await?;
+        let targets: Vec<_> = subscriptions
+            .iter()
+            .filter(|s| s.subscribes(message.event_type()))
+            .collect();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        let payload = serde_json::to_string(&Payload {
+            id: message.id(),
+            event_type: message.event_type(),
+            aggregate_type: message.aggregate_type(),
+            aggregate_id: message.aggregate_id(),
+            occurred_at: message.created_at(),
+            data: serde_json::from_str(message.payload())?,
+        })?;
+        let deliveries = targets
+            .into_iter()
+            .map(|s| {
+                webhook::Delivery::new(
+                    s.id(),
+                    message.id(),
+                    message.event_type().to_string(),
+                    payload.clone(),
+                )
+            })
+            .collect();
+        self.deliveries.enqueue(deliveries).await?;
+        Ok(())
+    }
+}
+
+/// webhook で POST する本文。受信側は id で重複を除く
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    id: i64,
+    event_type: &'a str,
+    aggregate_type: &'a str,
+    aggregate_id: uuid::Uuid,
+    occurred_at: chrono::DateTime<chrono::Utc>,
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = WebhookCreateDto)]
+pub struct CreateDto {
+    #[schema(example = "https://partner.example.com/hooks/books")]
+    pub url: String,
+    #[schema(example = json!(["BookCreated", "BookPriceChanged"]))]
+    pub event_types: Vec<String>,
+    /// 署名に使う 16 文字以上の値。レスポンスには含めない
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = WebhookResponseDto)]
+pub struct ResponseDto {
+    pub pub_id: uuid::Uuid,
+    pub url: String,
+    pub event_types: Vec<String>,
+    #[schema(value_type = String, example = "2024-01-01T00:00:00Z")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<webhook::Subscription> for ResponseDto {
+    fn from(s: webhook::Subscription) -> Self {
+        Self {
+            pub_id: s.pub_id(),
+            url: s.url().to_string(),
+            event_types: s.event_types().to_vec(),
+            created_at: s.created_at(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[schema(as = WebhookDeliveryDto)]
+pub struct DeliveryDto {
+    pub id: i64,
+    #[schema(example = "BookPriceChanged")]
+    pub event_type: String,
+    /// 送った本文
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    #[schema(example = "delivered")]
+    pub status: String,
+    pub attempts: u32,
+    /// 最後に受け取った応答のステータスコード
+    #[schema(example = 200)]
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    #[schema(value_type = String, example = "2024-01-01T00:00:00Z")]
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    #[schema(value_type = String, example = "2024-01-01T00:00:00Z")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[schema(value_type = Option<String>, example = "2024-01-01T00:00:00Z")]
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<webhook::Delivery> for DeliveryDto {
+    fn from(d: webhook::Delivery) -> Self {
+        Self {
+            id: d.id(),
+            event_type: d.event_type().to_string(),
+            payload: serde_json::from_str(d.payload()).unwrap_or_default(),
+            status: d.status().to_string(),
+            attempts: d.attempts(),
+            response_status: d.response_status(),
+            last_error: d.last_error().map(str::to_string),
+            next_attempt_at: d.next_attempt_at(),
+            created_at: d.created_at(),
+            delivered_at: d.delivered_at(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outbox::Sink;
+    use rstest::rstest;
+    use std::sync::Mutex;
+    use webhook::vo::DeliveryStatus;
+
+    #[derive(Default)]
+    struct FakeSubscriptionRepository {
+        store: Mutex<Vec<webhook::Subscription>>,
+    }
+
+    #[async_trait]
+    impl webhook::SubscriptionRepository for FakeSubscriptionRepository {
+        async fn find_all(&self) -> anyhow::Result<Vec<webhook::Subscription>> {
+            Ok(self.store.lock().unwrap().clone())
+        }
+        async fn find_by_pub_id(
+            &self,
+            pub_id: uuid::Uuid,
+        ) -> anyhow::Result<Option<webhook::Subscription>> {
+            Ok(self
+                .store
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|s| s.pub_id() == pub_id)
+                .cloned())
+        }
+        async fn create(
+            &self,
+            item: webhook::Subscription,
+        ) -> anyhow::Result<webhook::Subscription> {
+            let mut store = self.store.lock().unwrap();
+            let created = webhook::Subscription::reconstruct(
+                store.len() as i32 + 1,
+                item.pub_id(),
+                WebhookUrl::new(item.url().to_string()).unwrap(),
+                item.event_types().to_vec(),
+                Secret::new(item.secret().to_string()).unwrap(),
+                item.created_at(),
+            );
+            store.push(created.clone());
+            Ok(created)
+        }
+        async fn delete(&self, item: webhook::Subscription) -> anyhow::Result<()> {
+            self.store
+                .lock()
+                .unwrap()
+                .retain(|s| s.pub_id() != item.pub_id());
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeDeliveryRepository {
+        store: Mutex<Vec<webhook::Delivery>>,
+    }
+
+    impl FakeDeliveryRepository {
+        fn all(&self) -> Vec<webhook::Delivery> {
+            self.store.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl webhook::DeliveryRepository for FakeDeliveryRepository {
+        async fn enqueue(&self, items: Vec<webhook::Delivery>) -> anyhow::Result<u64> {
+            let mut store = self.store.lock().unwrap();
+            let mut count = 0;
+            for item in items {
+                if store.iter().any(|d| {
+                    d.subscription_id() == item.subscription_id()
+                        && d.message_id() == item.message_id()
+                }) {
+                    continue;
+                }
+                let id = store.len() as i64 + 1;
+                store.push(webhook::Delivery::reconstruct(
+                    id,
+                    item.subscription_id(),
+                    item.message_id(),
+                    item.event_type().to_string(),
+                    item.payload().to_string(),
+                    item.status(),
+                    item.attempts(),
+                    item.next_attempt_at(),
+                    None,
+                    None,
+                    item.created_at(),
+                    None,
+                ));
+                count += 1;
+            }
+            Ok(count)
+        }
+        async fn find_due(
+            &self,
+            now: chrono::DateTime<chrono::Utc>,
+            limit: u64,
+        ) -> anyhow::Result<Vec<webhook::Delivery>> {
+            Ok(self
+                .all()
+                .into_iter()
+                .filter(|d| d.status() == DeliveryStatus::Pending && d.next_attempt_at() <= now)
+                .take(limit as usize)
+                .collect())
+        }
+        async fn find_by_subscription(
+            &self,
+            subscription_id: i32,
+            status: Option<DeliveryStatus>,
+            limit: u64,
+        ) -> anyhow::Result<Vec<webhook::Delivery>> {
+            Ok(self
+                .all()
+                .into_iter()
+                .rev()
+                .filter(|d| d.subscription_id() == subscription_id)
+                .filter(|d| status.is_none_or(|s| d.status() == s))
+                .take(limit as usize)
+                .collect())
+        }
+        async fn update(&self, item: webhook::Delivery) -> anyhow::Result<webhook::Delivery> {
+            let mut store = self.store.lock().unwrap();
+            let index = store
+                .iter()
+                .position(|d| d.id() == item.id())
+                .ok_or(anyhow::anyhow!("Delivery not found"))?;
+            store[index] = item.clone();
+            Ok(item)
+        }
+        async fn purge_delivered(
+            &self,
+            before: chrono::DateTime<chrono::Utc>,
+        ) -> anyhow::Result<u64> {
+            let mut store = self.store.lock().unwrap();
+            let count = store.len();
+            store.retain(|d| d.delivered_at().is_none_or(|at| at >= before));
+            Ok((count - store.len()) as u64)
+        }
+    }
+
+    #[derive(Clone)]
+    struct SentRequest {
+        url: String,
+        headers: HashMap<&'static str, String>,
+        body: String,
+    }
+
+    /// 用意した応答を順に返し、受け取ったリクエストを貯めておく
+    #[derive(Default)]
+    struct FakeSender {
+        responses: Mutex<Vec<u16>>,
+        requests: Mutex<Vec<SentRequest>>,
+    }
+
+    #[async_trait]
+    impl webhook::Sender for FakeSender {
+        async fn post(
+            &self,
+            url: &str,
+            headers: &[(&'static str, String)],
+            body: &str,
+        ) -> anyhow::Result<u16> {
+            self.requests.lock().unwrap().push(SentRequest {
+                url: url.to_string(),
+                headers: headers.iter().cloned().collect(),
+                body: body.to_string(),
+            });
+            let mut responses = self.responses.lock().unwrap();
+            if responses.is_empty() {
+                anyhow::bail!("connection refused");
+            }
+            Ok(responses.remove(0))
+        }
+    }
+
+    struct Fixture {
+        service: Service,
+        deliveries: Arc<FakeDeliveryRepository>,
+        sender: Arc<FakeSender>,
+    }
+
+    fn fixture(responses: Vec<u16>, max_attempts: u32) -> Fixture {
+        let deliveries = Arc::new(FakeDeliveryRepository::default());
+        let sender = Arc::new(FakeSender {
+            responses: Mutex::new(responses),
+            ..Default::default()
+        });
+        let policy = outbox::vo::RetryPolicy::new(
+            max_attempts,
+            chrono::Duration::milliseconds(1),
+            chrono::Duration::hours(1),
+        )
+        .unwrap();
+        let service = Service::new(
+            Arc::new(FakeSubscriptionRepository::default()),
+            deliveries.clone(),
+            sender.clone(),
+            policy,
+            chrono::Duration::days(1),
+        );
+        Fixture {
+            service,
+            deliveries,
+            sender,
+        }
+    }
+
+    fn create_dto(event_types: &[&str]) -> CreateDto {
+        CreateDto {
+            url: "https://partner.example.com/hooks".to_string(),
+            event_types: event_types.iter().map(|t| t.to_string()).collect(),
+            secret: "0123456789abcdef".to_string(),
+        }
+    }
+
+    fn message(id: i64, event_type: &str) -> outbox::Message {
+        outbox::Message::reconstruct(
+            id,
+            "book".to_string(),
+            uuid::Uuid::now_v7(),
+            event_type.to_string(),
+            r#"{"type":"created"}"#.to_string(),
+            outbox::vo::Status::Pending,
+            0,
+            chrono::Utc::now(),
+            None,
+            chrono::Utc::now(),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_create_rejects_invalid_subscription() {
+        let f = fixture(Vec::new(), 3);
+        assert!(matches!(
+            f.service.create(create_dto(&["BookSold"])).await,
+            Err(UseCaseError::DomainRuleViolation(_))
+        ));
+        assert!(matches!(
+            f.service.create(create_dto(&[])).await,
+            Err(UseCaseError::WebhookDomainError(_))
+        ));
+        let mut dto = create_dto(&["BookCreated"]);
+        dto.secret = "short".to_string();
+        assert!(matches!(
+            f.service.create(dto).await,
+            Err(UseCaseError::WebhookDomainError(_))
+        ));
+        let mut dto = create_dto(&["BookCreated"]);
+        dto.url = "ftp://partner.example.com".to_string();
+        assert!(matches!(
+            f.service.create(dto).await,
+            Err(UseCaseError::WebhookDomainError(_))
+        ));
+    }
+
+    // 内部のネットワークへ配信させない
+    #[rstest]
+    #[case("http://127.0.0.1:8080/hooks", false)]
+    #[case("http://localhost/hooks", false)]
+    #[case("http://api.localhost/hooks", false)]
+    #[case("http://10.0.0.1/hooks", false)]
+    #[case("http://192.168.1.10/hooks", false)]
+    #[case("http://169.254.169.254/latest/meta-data", false)]
+    #[case("http://0.0.0.0/hooks", false)]
+    #[case("http://[::1]:8080/hooks", false)]
+    #[case("http://[fe80::1]/hooks", false)]
+    #[case("http://[::ffff:127.0.0.1]/hooks", false)]
+    #[case("http://user@127.0.0.1/hooks", false)]
+    #[case("https://203.0.113.10/hooks", true)]
+    #[case("https://partner.example.com:8443/hooks", true)]
+    #[tokio::test]
+    async fn test_create_rejects_internal_url(#[case] url: &str, #[case] allowed: bool) {
+        let f = fixture(Vec::new(), 3);
+        let mut dto = create_dto(&["BookCreated"]);
+        dto.url = url.to_string();
+        let result = f.service.create(dto).await;
+        assert_eq!(result.is_ok(), allowed, "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_fanout_enqueues_once_per_matching_subscription() {
+        let f = fixture(Vec::new(), 3);
+        let prices = f
+            .service
+            .create(create_dto(&["BookPriceChanged", "BookCreated"]))
+            .await
+            .unwrap();
+        f.service
+            .create(create_dto(&["PublisherCreated"]))
+            .await
+            .unwrap();
+        assert_eq!(prices.event_types, ["BookCreated", "BookPriceChanged"]);
+
+        let fanout = f.service.fanout();
+        fanout.send(&message(1, "BookCreated")).await.unwrap();
+        // 中継が再送しても重ねて積まない
+        fanout.send(&message(1, "BookCreated")).await.unwrap();
+        fanout.send(&message(2, "ShopCreated")).await.unwrap();
+
+        let deliveries = f.deliveries.all();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].subscription_id(), 1);
+        let payload: serde_json::Value = serde_json::from_str(deliveries[0].payload()).unwrap();
+        assert_eq!(payload["id"], 1);
+        assert_eq!(payload["event_type"], "BookCreated");
+        assert_eq!(payload["data"]["type"], "created");
+    }
+
+    #[tokio::test]
+    async fn test_deliver_signs_and_retries_until_success() {
+        let f = fixture(vec![500, 204], 3);
+        let subscription = f
+            .service
+            .create(create_dto(&["BookCreated"]))
+            .await
+            .unwrap();
+        f.service
+            .fanout()
+            .send(&message(1, "BookCreated"))
+            .await
+            .unwrap();
+
+        let report = f.service.deliver().await.unwrap();
+        assert_eq!(report.retrying, 1);
+        let failed = &f.deliveries.all()[0];
+        assert_eq!(failed.response_status(), Some(500));
+        assert_eq!(failed.last_error(), Some("HTTP 500"));
+
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let report = f.service.deliver().await.unwrap();
+        assert_eq!(report.delivered, 1);
+
+        let requests = f.sender.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        let sent = &requests[1];
+        assert_eq!(sent.url, "https://partner.example.com/hooks");
+        let timestamp: i64 = sent.headers[TIMESTAMP_HEADER].parse().unwrap();
+        assert_eq!(
+            sent.headers[SIGNATURE_HEADER],
+            sign("0123456789abcdef", timestamp, &sent.body)
+        );
+        assert_eq!(sent.headers[EVENT_HEADER], "BookCreated");
+        // 再試行しても本文と配信 ID は変わらない
+        assert_eq!(sent.body, requests[0].body);
+        assert_eq!(
+            sent.headers[DELIVERY_HEADER],
+            requests[0].headers[DELIVERY_HEADER]
+        );
+
+        let log = f
+            .service
+            .deliveries(subscription.pub_id, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].status, "delivered");
+        assert_eq!(log[0].attempts, 2);
+        assert_eq!(log[0].response_status, Some(204));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_marks_dead_after_max_attempts() {
+        let f = fixture(Vec::new(), 2);
+        let subscription = f
+            .service
+            .create(create_dto(&["BookCreated"]))
+            .await
+            .unwrap();
+        f.service
+            .fanout()
+            .send(&message(1, "BookCreated"))
+            .await
+            .unwrap();
+
+        f.service.deliver().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let report = f.service.deliver().await.unwrap();
+        assert_eq!(report.dead, 1);
+
+        let dead = f
+            .service
+            .deliveries(subscription.pub_id, Some("dead"), 10)
+            .await
+            .unwrap();
+        assert_eq!(dead[0].last_error.as_deref(), Some("connection refused"));
+        assert_eq!(dead[0].response_status, None);
+        assert!(matches!(
+            f.service
+                .deliveries(subscription.pub_id, Some("unknown"), 10)
+                .await,
+            Err(UseCaseError::WebhookDomainError(_))
+        ));
+    }
+}